@@ -22,6 +22,7 @@ async fn main() {
             v.iter()
                 .fold(String::from("\n"), |a, b| format!("\t{} \n\t{:?}", a, b)),
         );
+        println!(">> Group Leader: {:?}", group.leader().await);
         println!(">>>>>>><<<<<<<<<");
     }
 }
@@ -3,16 +3,17 @@ use async_std::task::JoinHandle;
 use flume::{Receiver, Sender};
 use futures::prelude::*;
 use futures::select;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::ops::Add;
-use std::time::{Duration, Instant};
 use janu::net::queryable::EVAL;
 use janu::net::{
-    CongestionControl, ConsolidationMode, QueryConsolidation, QueryTarget, Reliability, ResKey,
+    Channel, CongestionControl, ConsolidationMode, QueryConsolidation, QueryTarget, Reliability, ResKey,
     Sample, Session, SubInfo, SubMode, ZFuture,
 };
 use janu_util::sync::Condition;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Add;
+use std::time::{Duration, Instant};
 
 const GROUP_PREFIX: &str = "/janu/ext/net/group";
 const EVENT_POSTFIX: &str = "evt";
@@ -39,7 +40,7 @@ pub struct NewGroupViewEvent {
     pub source: String,
     pub members: Vec<Member>,
 }
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct NewLeaderEvent {
     pub mid: String,
 }
@@ -49,12 +50,52 @@ struct KeepAliveEvent {
     pub mid: String,
 }
 
+// The members running a previous version are not aware of the member priorities nor of the
+// leaders. To remain compatible with them, the priorities are not part of the members on the
+// wire but appended at the end of the messages, followed by the leader of the sender: the
+// previous decoders ignore these trailing bytes, while the priorities default to 0 and the
+// leader to `None` when decoding the messages of a previous member.
+#[derive(Serialize, Deserialize, Debug)]
+struct WireMember {
+    mid: String,
+    info: Option<String>,
+    liveliness: MemberLiveliness,
+    lease: Duration,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct WireGroupView {
+    source: String,
+    members: Vec<WireMember>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 enum GroupNetEvent {
-    Join(JoinEvent),
+    Join(WireMember),
     Leave(LeaveEvent),
     KeepAlive(KeepAliveEvent),
-    NewGroupView(NewGroupViewEvent),
+    NewGroupView(WireGroupView),
+}
+
+fn encode<T: Serialize>(msg: &T, priorities: &[u32], leader: Option<&str>) -> Vec<u8> {
+    let mut buf = bincode::serialize(msg).unwrap();
+    buf.extend(bincode::serialize(priorities).unwrap());
+    buf.extend(bincode::serialize(&leader).unwrap());
+    buf
+}
+
+type Decoded<T> = (T, Vec<u32>, Option<String>);
+
+fn decode<T: DeserializeOwned>(buf: &[u8]) -> bincode::Result<Decoded<T>> {
+    use bincode::Options;
+    let options = bincode::options()
+        .with_fixint_encoding()
+        .allow_trailing_bytes();
+    let mut de = bincode::Deserializer::from_slice(buf, options);
+    let msg = T::deserialize(&mut de)?;
+    let priorities = Vec::<u32>::deserialize(&mut de).unwrap_or_default();
+    let leader = Option::<String>::deserialize(&mut de).unwrap_or_default();
+    Ok((msg, priorities, leader))
 }
 
 /// Events exposed to the user to be informed for relevant
@@ -80,6 +121,8 @@ pub struct Member {
     info: Option<String>,
     liveliness: MemberLiveliness,
     lease: Duration,
+    #[serde(default)]
+    priority: u32,
 }
 
 impl Member {
@@ -89,6 +132,7 @@ impl Member {
             info: None,
             liveliness: MemberLiveliness::Auto,
             lease: DEFAULT_LEASE,
+            priority: 0,
         }
     }
 
@@ -109,6 +153,36 @@ impl Member {
         self.liveliness = l;
         self
     }
+
+    /// Sets the priority of this member for leader election. The member with the
+    /// highest priority is elected, ties are broken by the lowest member id.
+    pub fn priority(mut self, p: u32) -> Self {
+        self.priority = p;
+        self
+    }
+
+    fn outranks(&self, other: &Member) -> bool {
+        self.priority > other.priority || (self.priority == other.priority && self.mid < other.mid)
+    }
+
+    fn to_wire(&self) -> WireMember {
+        WireMember {
+            mid: self.mid.clone(),
+            info: self.info.clone(),
+            liveliness: self.liveliness.clone(),
+            lease: self.lease,
+        }
+    }
+
+    fn from_wire(m: WireMember, priority: u32) -> Member {
+        Member {
+            mid: m.mid,
+            info: m.info,
+            liveliness: m.liveliness,
+            lease: m.lease,
+            priority,
+        }
+    }
 }
 
 struct GroupState {
@@ -120,6 +194,14 @@ struct GroupState {
     event_resource: ResKey,
     user_events_tx: Mutex<Option<Sender<GroupEvent>>>,
     cond: Condition,
+    view_changed_at: Mutex<Instant>,
+    leader: Mutex<Option<String>>,
+}
+
+impl GroupState {
+    async fn view_changed(&self) {
+        *self.view_changed_at.lock().await = Instant::now();
+    }
 }
 
 pub struct Group {
@@ -129,15 +211,17 @@ pub struct Group {
 async fn keep_alive_task(z: Arc<Session>, state: Arc<GroupState>) {
     let mid = state.local_member.mid.clone();
     let evt = GroupNetEvent::KeepAlive(KeepAliveEvent { mid });
-    let buf = bincode::serialize(&evt).unwrap();
     let period = state.local_member.lease.mul_f32(VIEW_REFRESH_LEASE_RATIO);
     loop {
         async_std::task::sleep(period).await;
         log::debug!("Sending Keep Alive for: {}", &state.local_member.mid);
+        // The keep-alives let the other members learn the current leader
+        let leader = state.leader.lock().await.clone();
+        let buf = encode(&evt, &[], leader.as_deref());
         let _ = z
             .write_ext(
                 &state.event_resource,
-                (buf.clone()).into(),
+                buf.into(),
                 0,
                 0,
                 CongestionControl::Drop,
@@ -162,13 +246,19 @@ fn spawn_watchdog(s: Arc<GroupState>, period: Duration) -> JoinHandle<()> {
                 ms.remove(e);
             }
             drop(ms);
-            let u_evt = &*s.user_events_tx.lock().await;
+            if !expired_members.is_empty() {
+                s.view_changed().await;
+            }
+            let u_evt = s.user_events_tx.lock().await;
             for e in expired_members {
-                if let Some(tx) = u_evt {
+                if let Some(tx) = &*u_evt {
                     tx.send(GroupEvent::LeaseExpired(LeaseExpiredEvent { mid: e }))
                         .unwrap()
                 }
             }
+            // The election notifies the user on its own
+            drop(u_evt);
+            elect_leader(&s, None).await;
         }
     };
     async_std::task::spawn(watch_dog)
@@ -180,7 +270,11 @@ async fn query_handler(z: Arc<Session>, state: Arc<GroupState>) {
         GROUP_PREFIX, &state.gid, &state.local_member.mid
     );
     log::debug!("Started query handler for: {}", &qres);
-    let buf = bincode::serialize(&state.local_member).unwrap();
+    let buf = encode(
+        &state.local_member.to_wire(),
+        &[state.local_member.priority],
+        None,
+    );
     let mut queryable = z
         .declare_queryable(&qres.clone().into(), EVAL)
         .await
@@ -192,10 +286,77 @@ async fn query_handler(z: Arc<Session>, state: Arc<GroupState>) {
             res_name: qres.clone(),
             payload: buf.clone().into(),
             data_info: None,
+            channel: Channel::default(),
+            congestion_control: CongestionControl::default(),
         })
     }
 }
 
+/// Elects the leader out of the current view and notifies the user if it changed.
+/// The leadership is sticky: the incumbent leader remains the leader for as long as it is
+/// part of the view, i.e. until it leaves or its lease expires, hence a member joining with
+/// a higher priority does not preempt it. The members learn the incumbent leader from the
+/// keep-alives of the other members, passed as `learned`. A member only elects a leader on
+/// its own once its view has been stable for the longest lease of the local member and of the
+/// members asserting their liveliness automatically, i.e. once it had the time to receive a
+/// keep-alive from each of them. Members sharing the same view then elect the same leader.
+///
+/// The election does not require a quorum: during a partition, each side elects its own
+/// leader once the lease of the leader on the other side expired, hence the group may have
+/// two leaders. When the partitions merge back, the members learn both leaders from the
+/// keep-alives and retain the one that outranks the other: the outranked leader steps down
+/// and receives a `NewLeader` event.
+async fn elect_leader(state: &Arc<GroupState>, learned: Option<String>) {
+    let ms = state.members.lock().await;
+    let local = &state.local_member;
+    let member = |mid: &str| -> Option<&Member> {
+        if mid == local.mid {
+            Some(local)
+        } else {
+            ms.get(mid).map(|(m, _)| m)
+        }
+    };
+
+    let mut current = state.leader.lock().await;
+    let incumbent = current.as_deref().and_then(member);
+    let leader = match (incumbent, learned.as_deref().and_then(member)) {
+        (Some(i), Some(l)) if l.outranks(i) => l,
+        (Some(i), _) => i,
+        (None, Some(l)) => l,
+        (None, None) => {
+            let stable = ms
+                .values()
+                .filter(|(m, _)| matches!(m.liveliness, MemberLiveliness::Auto))
+                .fold(local.lease, |lease, (m, _)| lease.max(m.lease));
+            if state.view_changed_at.lock().await.elapsed() < stable {
+                // The previous leader is no longer part of the view
+                *current = None;
+                return;
+            }
+            let mut leader = local;
+            for (m, _) in ms.values() {
+                if m.outranks(leader) {
+                    leader = m;
+                }
+            }
+            leader
+        }
+    };
+    let mid = leader.mid.clone();
+    drop(ms);
+
+    if current.as_ref() != Some(&mid) {
+        log::debug!("New leader elected for group {}: {}", &state.gid, &mid);
+        *current = Some(mid.clone());
+        drop(current);
+        let u_evt = &*state.user_events_tx.lock().await;
+        if let Some(tx) = u_evt {
+            tx.send(GroupEvent::NewLeader(NewLeaderEvent { mid }))
+                .unwrap()
+        }
+    }
+}
+
 async fn advertise_view(z: &Arc<Session>, state: &Arc<GroupState>) {
     log::debug!("Maybe Advertising NewGroupView....");
     let mut min: String = state.local_member.mid.clone();
@@ -214,18 +375,56 @@ async fn advertise_view(z: &Arc<Session>, state: &Arc<GroupState>) {
         .collect();
     members.push(state.local_member.clone());
     if min == *sid {
-        let evt = GroupNetEvent::NewGroupView(NewGroupViewEvent {
+        let priorities: Vec<u32> = members.iter().map(|m| m.priority).collect();
+        let evt = GroupNetEvent::NewGroupView(WireGroupView {
             source: sid.to_string(),
-            members,
+            members: members.iter().map(|m| m.to_wire()).collect(),
         });
         log::debug!("Advertising NewGroupView: {:?}", &evt);
-        let buf = bincode::serialize(&evt).unwrap();
-        let res = format!("{}/{}/{}", GROUP_PREFIX, &state.gid, EVENT_POSTFIX);
-        let _ = z.write(&res.into(), buf.into()).wait();
+        let buf = encode(&evt, &priorities, None);
+        let _ = z.write(&state.event_resource, buf.into()).wait();
+    }
+}
+
+// Queries the info of the member `mid`, or of all the members if `mid` is `*`, and adds
+// the members unknown so far to the view.
+async fn query_member(z: Arc<Session>, state: Arc<GroupState>, mid: String) {
+    let qres = format!("{}/{}/{}", GROUP_PREFIX, &state.gid, mid);
+    // @TODO: we could also send this member info
+    let qc = QueryConsolidation {
+        first_routers: ConsolidationMode::None,
+        last_router: ConsolidationMode::None,
+        reception: ConsolidationMode::None,
+    };
+    log::debug!("Issuing Query for {}", &qres);
+    let mut receiver = z
+        .query(&qres.into(), "", QueryTarget::default(), qc)
+        .await
+        .unwrap();
+
+    while let Some(sample) = receiver.next().await {
+        match decode::<WireMember>(&sample.data.payload.to_vec()) {
+            Ok((m, priorities, _)) => {
+                let priority = priorities.first().copied().unwrap_or_default();
+                let m = Member::from_wire(m, priority);
+                let expiry = Instant::now().add(m.lease);
+                log::debug!("Received member information: {:?}", &m);
+                let mut ms = state.members.lock().await;
+                if m.mid != state.local_member.mid && !ms.contains_key(&m.mid) {
+                    ms.insert(m.mid.clone(), (m, expiry));
+                    state.view_changed().await;
+                }
+                drop(ms);
+                state.cond.notify_all();
+            }
+            Err(e) => {
+                log::debug!("Unable to deserialize the Member info received:\n {}", e);
+            }
+        }
     }
 }
 
-async fn net_event_handler(z: Arc<Session>, state: Arc<GroupState>) {
+async fn net_event_handler(z: Arc<Session>, state: Arc<GroupState>, ready: Sender<()>) {
     let sub_info = SubInfo {
         period: None,
         mode: SubMode::Push,
@@ -235,17 +434,31 @@ async fn net_event_handler(z: Arc<Session>, state: Arc<GroupState>) {
         .declare_subscriber(&state.event_resource, &sub_info)
         .await
         .unwrap();
+    let _ = ready.send(());
     let stream = sub.receiver();
     while let Some(s) = stream.next().await {
         log::debug!("Handling Network Event...");
-        match bincode::deserialize::<GroupNetEvent>(&(s.payload.to_vec())) {
-            Ok(evt) => match evt {
-                GroupNetEvent::Join(je) => {
+        let mut learned = None;
+        match decode::<GroupNetEvent>(&(s.payload.to_vec())) {
+            Ok((evt, priorities, leader)) => match evt {
+                GroupNetEvent::Join(m) if m.mid == state.local_member.mid => {
+                    log::debug!("Join from Local Participant -- Ignoring");
+                }
+                GroupNetEvent::Join(m) => {
+                    let priority = priorities.first().copied().unwrap_or_default();
+                    let je = JoinEvent {
+                        member: Member::from_wire(m, priority),
+                    };
                     advertise_view(&z, &state).await;
                     log::debug!("Member joining the group:\n{:?}", &je.member);
                     let alive_till = Instant::now().add(je.member.lease);
                     let mut ms = state.members.lock().await;
-                    ms.insert(je.member.mid.clone(), (je.member.clone(), alive_till));
+                    if ms
+                        .insert(je.member.mid.clone(), (je.member.clone(), alive_till))
+                        .is_none()
+                    {
+                        state.view_changed().await;
+                    }
                     state.cond.notify_all();
                     drop(ms);
                     let u_evt = &*state.user_events_tx.lock().await;
@@ -255,7 +468,9 @@ async fn net_event_handler(z: Arc<Session>, state: Arc<GroupState>) {
                 }
                 GroupNetEvent::Leave(le) => {
                     log::debug!("Member leaving:\n{:?}", &le.mid);
-                    state.members.lock().await.remove(&le.mid);
+                    if state.members.lock().await.remove(&le.mid).is_some() {
+                        state.view_changed().await;
+                    }
                     let u_evt = &*state.user_events_tx.lock().await;
                     if let Some(tx) = u_evt {
                         tx.send(GroupEvent::Leave(le)).unwrap()
@@ -269,6 +484,7 @@ async fn net_event_handler(z: Arc<Session>, state: Arc<GroupState>) {
                         kae.mid.ne(&state.local_member.mid)
                     );
                     if kae.mid.ne(&state.local_member.mid) {
+                        learned = leader;
                         let mut mm = state.members.lock().await;
                         log::debug!("Members: \n{:?}", &mm);
                         let v = mm.remove(&kae.mid);
@@ -283,36 +499,13 @@ async fn net_event_handler(z: Arc<Session>, state: Arc<GroupState>) {
                                     "Received Keep Alive from unknown member: {}",
                                     &kae.mid
                                 );
-                                let qres = format!("{}/{}/{}", GROUP_PREFIX, &state.gid, kae.mid);
-                                // @TODO: we could also send this member info
-                                let qc = QueryConsolidation {
-                                    first_routers: ConsolidationMode::None,
-                                    last_router: ConsolidationMode::None,
-                                    reception: ConsolidationMode::None,
-                                };
-                                log::debug!("Issuing Query for {}", &qres);
-                                let mut receiver = z
-                                    .query(&qres.into(), "", QueryTarget::default(), qc)
-                                    .await
-                                    .unwrap();
-
-                                while let Some(sample) = receiver.next().await {
-                                    match bincode::deserialize::<Member>(
-                                        &sample.data.payload.to_vec(),
-                                    ) {
-                                        Ok(m) => {
-                                            let mut expiry = Instant::now();
-                                            expiry = expiry.add(m.lease);
-                                            log::debug!("Received member information: {:?}", &m);
-                                            mm.insert(kae.mid.clone(), (m, expiry));
-                                        }
-                                        Err(e) => {
-                                            log::debug!(
-                                                "Unable to deserialize the Member info received:\n {}", e);
-                                        }
-                                    }
-                                }
-                                state.cond.notify_all();
+                                // The query is issued from a separate task as the replies
+                                // are delivered by the same session that feeds this handler.
+                                async_std::task::spawn(query_member(
+                                    z.clone(),
+                                    state.clone(),
+                                    kae.mid,
+                                ));
                             }
                         }
                     } else {
@@ -321,10 +514,13 @@ async fn net_event_handler(z: Arc<Session>, state: Arc<GroupState>) {
                 }
                 GroupNetEvent::NewGroupView(ngve) => {
                     let mut ms = state.members.lock().await;
-                    for m in ngve.members {
-                        if ms.get(&m.mid).is_none() {
+                    for (i, m) in ngve.members.into_iter().enumerate() {
+                        let priority = priorities.get(i).copied().unwrap_or_default();
+                        let m = Member::from_wire(m, priority);
+                        if m.mid != state.local_member.mid && ms.get(&m.mid).is_none() {
                             let alive_till = Instant::now().add(m.lease);
                             ms.insert(m.mid.clone(), (m, alive_till));
+                            state.view_changed().await;
                         }
                     }
                 }
@@ -333,6 +529,7 @@ async fn net_event_handler(z: Arc<Session>, state: Arc<GroupState>) {
                 log::warn!("Failed decoding net-event due to:\n{:?}", e);
             }
         }
+        elect_leader(&state, learned).await;
     }
 }
 
@@ -353,20 +550,30 @@ impl Group {
             event_resource: event_resource.clone(),
             user_events_tx: Mutex::new(Default::default()),
             cond: Condition::new(),
+            view_changed_at: Mutex::new(Instant::now()),
+            leader: Mutex::new(None),
         });
         let is_auto_liveliness = matches!(with.liveliness, MemberLiveliness::Auto);
 
+        // Listen to the group events before announcing the member, so that the views
+        // advertised in response to the announcement are not missed.
+        let (ready_tx, ready_rx) = flume::bounded(1);
+        async_std::task::spawn(net_event_handler(z.clone(), state.clone(), ready_tx));
+        let _ = ready_rx.recv_async().await;
+
         // announce the member:
         log::debug!("Sending Join Message for local member:\n{:?}", &with);
-        let join_evt = GroupNetEvent::Join(JoinEvent { member: with });
-        let buf = bincode::serialize(&join_evt).unwrap();
+        let join_evt = GroupNetEvent::Join(with.to_wire());
+        let buf = encode(&join_evt, &[with.priority], None);
         let _ = z.write(&event_resource, buf.into()).await;
 
+        // Learn the members that joined before, their announcements have not been received.
+        async_std::task::spawn(query_member(z.clone(), state.clone(), "*".to_string()));
+
         // If the liveliness is manual it is the user who has to assert it.
         if is_auto_liveliness {
             async_std::task::spawn(keep_alive_task(z.clone(), state.clone()));
         }
-        let _ = async_std::task::spawn(net_event_handler(z.clone(), state.clone()));
         let _ = async_std::task::spawn(query_handler(z.clone(), state.clone()));
         let _ = spawn_watchdog(state.clone(), Duration::from_secs(1));
         Group { state }
@@ -434,4 +641,140 @@ impl Group {
         let ms = self.state.members.lock().await;
         ms.len() + 1 // with +1 being the local member
     }
+
+    /// Returns the identifier of the current group leader, or `None` if no
+    /// leader has been elected yet.
+    pub async fn leader(&self) -> Option<String> {
+        self.state.leader.lock().await.clone()
+    }
+
+    /// Returns whether the local member is the current group leader.
+    pub async fn is_leader(&self) -> bool {
+        self.state.leader.lock().await.as_deref() == Some(self.local_member_id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The wire format of the members running a previous version
+    mod legacy {
+        use super::*;
+
+        #[derive(Serialize, Deserialize, Debug)]
+        pub struct Member {
+            pub mid: String,
+            pub info: Option<String>,
+            pub liveliness: MemberLiveliness,
+            pub lease: Duration,
+        }
+
+        #[derive(Serialize, Deserialize, Debug)]
+        pub struct JoinEvent {
+            pub member: Member,
+        }
+
+        #[derive(Serialize, Deserialize, Debug)]
+        pub struct NewGroupViewEvent {
+            pub source: String,
+            pub members: Vec<Member>,
+        }
+
+        #[derive(Serialize, Deserialize, Debug)]
+        pub enum GroupNetEvent {
+            Join(JoinEvent),
+            Leave(LeaveEvent),
+            KeepAlive(KeepAliveEvent),
+            NewGroupView(NewGroupViewEvent),
+        }
+    }
+
+    #[test]
+    fn group_member_outranks() {
+        let a = Member::new("a");
+        let b = Member::new("b");
+        // Ties are broken by the lowest member id
+        assert!(a.outranks(&b));
+        assert!(!b.outranks(&a));
+        assert!(!a.outranks(&a));
+        // The highest priority is elected first
+        let b = b.priority(1);
+        assert!(b.outranks(&a));
+        assert!(!a.outranks(&b));
+    }
+
+    #[test]
+    fn group_wire_compatibility() {
+        let member = Member::new("a").info("info").priority(7);
+
+        // A previous member decodes the join of a new member
+        let buf = encode(
+            &GroupNetEvent::Join(member.to_wire()),
+            &[member.priority],
+            None,
+        );
+        match bincode::deserialize::<legacy::GroupNetEvent>(&buf).unwrap() {
+            legacy::GroupNetEvent::Join(je) => assert_eq!(je.member.mid, "a"),
+            _ => panic!(),
+        }
+
+        // A new member decodes the join of a previous member
+        let buf = bincode::serialize(&legacy::GroupNetEvent::Join(legacy::JoinEvent {
+            member: legacy::Member {
+                mid: "b".to_string(),
+                info: None,
+                liveliness: MemberLiveliness::Auto,
+                lease: DEFAULT_LEASE,
+            },
+        }))
+        .unwrap();
+        let (evt, priorities, leader) = decode::<GroupNetEvent>(&buf).unwrap();
+        match evt {
+            GroupNetEvent::Join(m) => assert_eq!(m.mid, "b"),
+            _ => panic!(),
+        }
+        assert!(priorities.is_empty());
+        assert_eq!(leader, None);
+
+        // A previous member decodes the view of a new member
+        let members = [member.clone(), Member::new("c").priority(3)];
+        let priorities: Vec<u32> = members.iter().map(|m| m.priority).collect();
+        let view = GroupNetEvent::NewGroupView(WireGroupView {
+            source: "a".to_string(),
+            members: members.iter().map(|m| m.to_wire()).collect(),
+        });
+        let buf = encode(&view, &priorities, None);
+        match bincode::deserialize::<legacy::GroupNetEvent>(&buf).unwrap() {
+            legacy::GroupNetEvent::NewGroupView(ngve) => {
+                let mids: Vec<&str> = ngve.members.iter().map(|m| m.mid.as_str()).collect();
+                assert_eq!(mids, ["a", "c"]);
+            }
+            _ => panic!(),
+        }
+
+        // A new member decodes the priorities of the view
+        let (evt, priorities, _) = decode::<GroupNetEvent>(&buf).unwrap();
+        assert!(matches!(evt, GroupNetEvent::NewGroupView(_)));
+        assert_eq!(priorities, [7, 3]);
+
+        // A previous member decodes the keep-alive of a new member carrying its leader
+        let kae = GroupNetEvent::KeepAlive(KeepAliveEvent {
+            mid: "a".to_string(),
+        });
+        let buf = encode(&kae, &[], Some("c"));
+        match bincode::deserialize::<legacy::GroupNetEvent>(&buf).unwrap() {
+            legacy::GroupNetEvent::KeepAlive(kae) => assert_eq!(kae.mid, "a"),
+            _ => panic!(),
+        }
+
+        // A new member decodes the leader of the keep-alive
+        let (_, _, leader) = decode::<GroupNetEvent>(&buf).unwrap();
+        assert_eq!(leader.as_deref(), Some("c"));
+
+        // A previous member decodes the info of a new member
+        let buf = encode(&member.to_wire(), &[member.priority], None);
+        let m = bincode::deserialize::<legacy::Member>(&buf).unwrap();
+        assert_eq!(m.info.as_deref(), Some("info"));
+    }
 }
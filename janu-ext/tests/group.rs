@@ -0,0 +1,133 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::sync::Arc;
+use async_std::task;
+use janu::net::*;
+use janu_ext::net::group::*;
+use janu_util::properties::config::*;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(20);
+const SLEEP: Duration = Duration::from_millis(100);
+const LOCATOR: &str = "tcp/127.0.0.1:19447";
+
+async fn session(listener: Option<&str>, peer: Option<&str>) -> Arc<Session> {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "peer".to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    if let Some(listener) = listener {
+        config.insert(ZN_LISTENER_KEY, listener.to_string());
+    }
+    if let Some(peer) = peer {
+        config.insert(ZN_PEER_KEY, peer.to_string());
+    }
+    Arc::new(open(config).await.unwrap())
+}
+
+// Waits for the declarations of `to` to be known by `from`: once a sample written by `from`
+// is received by a subscriber declared by `to`, the declarations made before by `to` are known.
+async fn wait_declarations(from: &Session, to: &Session) {
+    let sub_info = SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        period: None,
+    };
+    let mut sub = to
+        .declare_subscriber(&"/probe".into(), &sub_info)
+        .await
+        .unwrap();
+    let check = async {
+        loop {
+            from.write(&"/probe".into(), vec![0u8].into())
+                .await
+                .unwrap();
+            if let Ok(Some(_)) = sub.receiver().next().timeout(SLEEP).await {
+                break;
+            }
+        }
+    };
+    check.timeout(TIMEOUT).await.unwrap();
+    sub.undeclare().await.unwrap();
+}
+
+async fn wait_leader(groups: &[&Group], leader: &str) {
+    let check = async {
+        loop {
+            let mut elected = true;
+            for g in groups.iter() {
+                elected &= g.leader().await.as_deref() == Some(leader);
+                elected &= g.is_leader().await == (g.local_member_id() == leader);
+            }
+            if elected {
+                break;
+            }
+            task::sleep(SLEEP).await;
+        }
+    };
+    check.timeout(TIMEOUT).await.unwrap();
+}
+
+async fn wait_size(group: &Group, size: usize) {
+    let check = async {
+        while group.size().await != size {
+            task::sleep(SLEEP).await;
+        }
+    };
+    check.timeout(TIMEOUT).await.unwrap();
+}
+
+#[test]
+fn group_leader_election() {
+    task::block_on(async {
+        let za = session(Some(LOCATOR), None).await;
+        let zb = session(None, Some(LOCATOR)).await;
+        let zc = session(None, Some(LOCATOR)).await;
+
+        // "a" outranks "c" but does not assert its liveliness,
+        // hence it leaves the group once its lease expired
+        let a = Member::new("a")
+            .lease(Duration::from_secs(6))
+            .liveliness(MemberLiveliness::Manual)
+            .priority(2);
+        let c = Member::new("c").lease(Duration::from_secs(1)).priority(1);
+        // "b" has the highest priority but joins once "a" has been elected
+        let b = Member::new("b").lease(Duration::from_secs(2)).priority(9);
+
+        // Let the subscription of "a" propagate before "c" joins, as "a" never sends
+        // keep-alives that would let "c" learn it.
+        let ga = Group::join(za.clone(), "election", a).await;
+        wait_declarations(&zc, &za).await;
+        let gc = Group::join(zc.clone(), "election", c).await;
+        assert!(ga.wait_for_view_size(2, TIMEOUT).await);
+        assert!(gc.wait_for_view_size(2, TIMEOUT).await);
+
+        // The member with the highest priority is elected once the view is stable
+        wait_leader(&[&ga, &gc], "a").await;
+
+        // A late joiner with a higher priority learns the incumbent leader and does not preempt it
+        wait_declarations(&zb, &za).await;
+        wait_declarations(&zb, &zc).await;
+        let gb = Group::join(zb, "election", b).await;
+        assert!(ga.wait_for_view_size(3, TIMEOUT).await);
+        assert!(gb.wait_for_view_size(3, TIMEOUT).await);
+        assert!(gc.wait_for_view_size(3, TIMEOUT).await);
+        wait_leader(&[&ga, &gb, &gc], "a").await;
+
+        // The leader departs: the member with the highest priority is elected
+        wait_size(&gb, 2).await;
+        wait_size(&gc, 2).await;
+        wait_leader(&[&gb, &gc], "b").await;
+    });
+}
@@ -109,7 +109,10 @@ impl<T> Drop for RecyclingObject<T> {
     fn drop(&mut self) {
         if let Some(pool) = self.pool.upgrade() {
             if let Some(obj) = self.object.take() {
-                task::block_on(pool.push(obj));
+                // Only block the current thread if the pool is contended
+                if let Some(obj) = pool.try_push(obj) {
+                    task::block_on(pool.push(obj));
+                }
             }
         }
     }
@@ -13,8 +13,8 @@
 //
 use crate::core::{ZError, ZErrorKind, ZResult};
 use crate::{zconfigurable, zerror};
-use async_std::net::TcpStream;
-use std::net::IpAddr;
+use async_std::net::{TcpStream, UdpSocket};
+use std::net::{IpAddr, SocketAddr};
 use std::time::Duration;
 
 zconfigurable! {
@@ -94,6 +94,157 @@ pub fn set_linger(socket: &TcpStream, dur: Option<Duration>) -> ZResult<()> {
     }
 }
 
+pub fn set_tos(socket: &TcpStream, tos: u8) -> ZResult<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        let raw_socket = socket.as_raw_fd();
+        // The TOS byte is the traffic class for IPv6 sockets
+        let (level, name) = match socket.local_addr() {
+            Ok(addr) if addr.is_ipv6() => (libc::IPPROTO_IPV6, libc::IPV6_TCLASS),
+            Ok(_) => (libc::IPPROTO_IP, libc::IP_TOS),
+            Err(e) => {
+                return zerror!(ZErrorKind::IoError {
+                    descr: e.to_string()
+                })
+            }
+        };
+        let value = tos as libc::c_int;
+
+        // Set the IP_TOS or IPV6_TCLASS option
+        unsafe {
+            let ret = libc::setsockopt(
+                raw_socket,
+                level,
+                name,
+                &value as *const libc::c_int as *const libc::c_void,
+                std::mem::size_of_val(&value) as libc::socklen_t,
+            );
+            match ret {
+                0 => Ok(()),
+                _ => {
+                    let e = std::io::Error::last_os_error();
+                    zerror!(ZErrorKind::IoError {
+                        descr: format!("setsockopt failed: {}", e)
+                    })
+                }
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        // Windows ignores the TOS set by the applications, use the QoS policies instead
+        let _ = (socket, tos);
+        zerror!(ZErrorKind::IoError {
+            descr: "Setting the TOS of a socket is not supported on Windows".to_string()
+        })
+    }
+}
+
+/// Receive the datagrams already queued on the socket in a single system call, one datagram per
+/// buffer, without waiting for new datagrams. Returns the size and the source address of each
+/// received datagram, or no datagram if none is queued or if batched reads are not supported
+/// by the platform (i.e. anything but Linux).
+pub fn try_recv_batch_from(
+    socket: &UdpSocket,
+    buffers: &mut [&mut [u8]],
+) -> ZResult<Vec<(usize, SocketAddr)>> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        let raw_socket = socket.as_raw_fd();
+        let mut addrs: Vec<libc::sockaddr_storage> =
+            vec![unsafe { std::mem::zeroed() }; buffers.len()];
+        let mut iovecs: Vec<libc::iovec> = buffers
+            .iter_mut()
+            .map(|b| libc::iovec {
+                iov_base: b.as_mut_ptr() as *mut libc::c_void,
+                iov_len: b.len(),
+            })
+            .collect();
+        let mut msgs: Vec<libc::mmsghdr> = iovecs
+            .iter_mut()
+            .zip(addrs.iter_mut())
+            .map(|(iovec, addr)| {
+                let mut msg_hdr: libc::msghdr = unsafe { std::mem::zeroed() };
+                msg_hdr.msg_name = addr as *mut libc::sockaddr_storage as *mut libc::c_void;
+                msg_hdr.msg_namelen =
+                    std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+                msg_hdr.msg_iov = iovec as *mut libc::iovec;
+                msg_hdr.msg_iovlen = 1;
+                libc::mmsghdr {
+                    msg_hdr,
+                    msg_len: 0,
+                }
+            })
+            .collect();
+
+        // Receive the queued datagrams without blocking
+        let ret = unsafe {
+            libc::recvmmsg(
+                raw_socket,
+                msgs.as_mut_ptr(),
+                msgs.len() as libc::c_uint,
+                libc::MSG_DONTWAIT as _,
+                std::ptr::null_mut(),
+            )
+        };
+        if ret < 0 {
+            let e = std::io::Error::last_os_error();
+            return match e.kind() {
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::Interrupted => Ok(vec![]),
+                _ => zerror!(ZErrorKind::IoError {
+                    descr: format!("recvmmsg failed: {}", e)
+                }),
+            };
+        }
+
+        msgs[..ret as usize]
+            .iter()
+            .zip(addrs.iter())
+            .map(|(msg, addr)| Ok((msg.msg_len as usize, sockaddr_to_addr(addr)?)))
+            .collect()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (socket, buffers);
+        Ok(vec![])
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn sockaddr_to_addr(addr: &libc::sockaddr_storage) -> ZResult<SocketAddr> {
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+    match addr.ss_family as libc::c_int {
+        libc::AF_INET => {
+            let addr = unsafe { &*(addr as *const _ as *const libc::sockaddr_in) };
+            let ip = Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr));
+            Ok(SocketAddr::V4(SocketAddrV4::new(
+                ip,
+                u16::from_be(addr.sin_port),
+            )))
+        }
+        libc::AF_INET6 => {
+            let addr = unsafe { &*(addr as *const _ as *const libc::sockaddr_in6) };
+            let ip = Ipv6Addr::from(addr.sin6_addr.s6_addr);
+            Ok(SocketAddr::V6(SocketAddrV6::new(
+                ip,
+                u16::from_be(addr.sin6_port),
+                addr.sin6_flowinfo,
+                addr.sin6_scope_id,
+            )))
+        }
+        family => zerror!(ZErrorKind::IoError {
+            descr: format!("Unsupported address family: {}", family)
+        }),
+    }
+}
+
 pub fn get_interface(name: &str) -> ZResult<Option<IpAddr>> {
     #[cfg(unix)]
     {
@@ -370,3 +521,46 @@ pub fn get_unicast_addresses_of_interface(name: &str) -> ZResult<Vec<IpAddr>> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn recv_batch_from() {
+        use super::try_recv_batch_from;
+        use async_std::net::UdpSocket;
+        use async_std::task;
+        use std::time::Duration;
+
+        task::block_on(async {
+            let rx = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let tx = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let mut buffers = vec![vec![0u8; 64]; 4];
+
+            // Nothing is queued on the socket
+            let mut slices: Vec<&mut [u8]> = buffers.iter_mut().map(|b| &mut b[..]).collect();
+            assert!(try_recv_batch_from(&rx, &mut slices).unwrap().is_empty());
+
+            for i in 1..=3u8 {
+                let payload = vec![i; i as usize];
+                tx.send_to(&payload, rx.local_addr().unwrap())
+                    .await
+                    .unwrap();
+            }
+            task::sleep(Duration::from_millis(100)).await;
+
+            let mut slices: Vec<&mut [u8]> = buffers.iter_mut().map(|b| &mut b[..]).collect();
+            let res = try_recv_batch_from(&rx, &mut slices).unwrap();
+            if cfg!(target_os = "linux") {
+                // All the queued datagrams are received at once
+                assert_eq!(res.len(), 3);
+                for (i, (n, addr)) in res.iter().enumerate() {
+                    assert_eq!(*n, i + 1);
+                    assert_eq!(*addr, tx.local_addr().unwrap());
+                    assert_eq!(&buffers[i][..*n], vec![i as u8 + 1; *n].as_slice());
+                }
+            } else {
+                assert!(res.is_empty());
+            }
+        });
+    }
+}
@@ -238,7 +238,9 @@ mod consts {
 
     /// Configures the batch size.
     /// String key : `"batch_size"`.
-    /// Accepted values : `<unsigned 16-bit integer>`.
+    /// Accepted values : `<unsigned 32-bit integer>` between 1 and 16777216.
+    /// Values larger than 65535 are only used on stream-oriented links (e.g. TCP)
+    /// with peers supporting large batches, 65535 is used otherwise.
     /// Default value : `65535`.
     pub const ZN_BATCH_SIZE_KEY: u64 = 0x69;
     pub const ZN_BATCH_SIZE_STR: &str = "batch_size";
@@ -292,7 +294,8 @@ mod consts {
     pub const ZN_DEFRAG_BUFF_SIZE_STR: &str = "defrag_buff_size";
     pub const ZN_DEFRAG_BUFF_SIZE_DEFAULT: &str = "1073741824";
 
-    /// Configures the buffer size in bytes at receiving side for each link.
+    /// Configures the buffer size in bytes at receiving side for each link. The idle
+    /// buffers of a link may be lent to the other links reading batches of the same size.
     /// String key : `"link_rx_buff_size"`.
     /// Accepted values : `<unsigned integer>`.
     /// Default value : `16777216` (16MiB).
@@ -307,6 +310,104 @@ mod consts {
     pub const ZN_MULTICAST_IPV6_ADDRESS_KEY: u64 = 0x77;
     pub const ZN_MULTICAST_IPV6_ADDRESS_STR: &str = "multicast_ipv6_address";
     pub const ZN_MULTICAST_IPV6_ADDRESS_DEFAULT: &str = "[ff24::224]:7447";
+
+    /// Static link-state routing costs of the links established over some given locators.
+    /// Those links are not assigned a cost derived from their measured round-trip time anymore.
+    /// A locator matches a link if it is either its source or destination locator.
+    /// String key : `"link_costs"`.
+    /// Accepted values : `<locator>=<unsigned integer>[,<locator>=<unsigned integer>]*`.
+    pub const ZN_LINK_COSTS_KEY: u64 = 0x78;
+    pub const ZN_LINK_COSTS_STR: &str = "link_costs";
+
+    /// Egress policies limiting the rate or the bandwidth of the data routed to some faces
+    /// on some key expressions. A rate limit keeps the latest message of each key and drops
+    /// the others, a bandwidth limit drops the messages exceeding it.
+    /// A face selector matches a face if it is equal to its peer id, its kind (`router`,
+    /// `peer` or `client`) or is a prefix of one of its locators (e.g. `udp/192.168.1.`).
+    /// String key : `"egress_policies"`.
+    /// Accepted values : `<key expression>=<limit>[@<face selector>][,<key expression>=<limit>[@<face selector>]]*`
+    /// where `<limit>` is either `<number>msg/s` or `<unsigned integer>B/s`.
+    pub const ZN_EGRESS_POLICIES_KEY: u64 = 0x79;
+    pub const ZN_EGRESS_POLICIES_STR: &str = "egress_policies";
+
+    /// The strategy used by the routers of a router mesh that are also part of a peer mesh
+    /// to elect the router in charge of routing some data or query between both meshes.
+    /// `hash` elects a router from a hash of the resource name and the routers ids,
+    /// `closest` elects the router closest (by link cost) to the source of the data or query,
+    /// `home` elects the router configured in `router_homes` for the resource name.
+    /// All the routers must be configured with the same strategy.
+    /// String key : `"router_election"`.
+    /// Accepted values : `"hash"`, `"closest"`, `"home"`.
+    /// Default value : `"hash"`.
+    pub const ZN_ROUTER_ELECTION_KEY: u64 = 0x7a;
+    pub const ZN_ROUTER_ELECTION_STR: &str = "router_election";
+    pub const ZN_ROUTER_ELECTION_DEFAULT: &str = "hash";
+
+    /// The home routers of some key expressions, used by the `home` router election strategy.
+    /// The first home router matching a resource name is elected if it is reachable,
+    /// otherwise the router is elected as with the `hash` strategy.
+    /// String key : `"router_homes"`.
+    /// Accepted values : `<key expression>=<router id>[,<key expression>=<router id>]*`.
+    pub const ZN_ROUTER_HOMES_KEY: u64 = 0x7b;
+    pub const ZN_ROUTER_HOMES_STR: &str = "router_homes";
+
+    /// Key expressions covering the subscriptions that routers and peers advertise to the
+    /// other routers and peers as a single subscription on the covering key expression
+    /// (e.g. `/fleet/**`). The data is still only routed to the matching subscribers.
+    /// String key : `"subscriptions_aggregation"`.
+    /// Accepted values : `<key expression>[,<key expression>]*`.
+    pub const ZN_SUBSCRIPTIONS_AGGREGATION_KEY: u64 = 0x7c;
+    pub const ZN_SUBSCRIPTIONS_AGGREGATION_STR: &str = "subscriptions_aggregation";
+
+    /// The kinds of nodes a node automatically connects to when it discovers them
+    /// through multicast scouting or gossip discovery.
+    /// String key : `"autoconnect_whatami"`.
+    /// Accepted values : `<whatami>[,<whatami>]*` where `<whatami>` is `router` or `peer`.
+    /// Default value : `"router,peer"` for peers and `"router"` for routers.
+    pub const ZN_AUTOCONNECT_WHATAMI_KEY: u64 = 0x7d;
+    pub const ZN_AUTOCONNECT_WHATAMI_STR: &str = "autoconnect_whatami";
+
+    /// The protocols of the locators a node automatically connects to when it discovers
+    /// other nodes through multicast scouting or gossip discovery.
+    /// String key : `"autoconnect_protocols"`.
+    /// Accepted values : `<protocol>[,<protocol>]*` (e.g. `tcp,quic`).
+    /// Default value : all the protocols.
+    pub const ZN_AUTOCONNECT_PROTOCOLS_KEY: u64 = 0x7e;
+    pub const ZN_AUTOCONNECT_PROTOCOLS_STR: &str = "autoconnect_protocols";
+
+    /// The locators advertised for a listener in scouting replies and link-state messages
+    /// instead of the locators it is bound to (e.g. the external address behind a NAT).
+    /// Repeat a listener to advertise several locators for it.
+    /// String key : `"advertised_locators"`.
+    /// Accepted values : `<listener locator>=<advertised locator>[,<listener locator>=<advertised locator>]*`.
+    pub const ZN_ADVERTISED_LOCATORS_KEY: u64 = 0x7f;
+    pub const ZN_ADVERTISED_LOCATORS_STR: &str = "advertised_locators";
+
+    /// The interfaces and address ranges whose locators are not advertised
+    /// in scouting replies and link-state messages.
+    /// String key : `"advertised_locators_exclude"`.
+    /// Accepted values : `<interface name|address|address/prefix length>[,...]` (e.g. `docker0,172.16.0.0/12`).
+    pub const ZN_ADVERTISED_LOCATORS_EXCLUDE_KEY: u64 = 0x80;
+    pub const ZN_ADVERTISED_LOCATORS_EXCLUDE_STR: &str = "advertised_locators_exclude";
+
+    /// The kinds of nodes the locators of each family are advertised to.
+    /// The locators of the families that are not listed are advertised to all nodes.
+    /// Scouting replies only contain the locators advertised to all kinds of nodes.
+    /// String key : `"advertised_locators_families"`.
+    /// Accepted values : `<family>=<whatami>[|<whatami>]*[,<family>=<whatami>[|<whatami>]*]*`
+    /// where `<family>` is `ipv4`, `ipv6` or `unixsock` (e.g. `ipv6=router,unixsock=`).
+    pub const ZN_ADVERTISED_LOCATORS_FAMILIES_KEY: u64 = 0x81;
+    pub const ZN_ADVERTISED_LOCATORS_FAMILIES_STR: &str = "advertised_locators_families";
+
+    /// Indicates if the zero-copy features should be used over multicast. The datagrams of a
+    /// multicast group are received by any host joining it, hence it should only be enabled
+    /// if all the members of the group run on the same host.
+    /// String key : `"shm_multicast"`.
+    /// Accepted values : `"true"`, `"false"`.
+    /// Default value : `"false"`.
+    pub const ZN_SHM_MULTICAST_KEY: u64 = 0x82;
+    pub const ZN_SHM_MULTICAST_STR: &str = "shm_multicast";
+    pub const ZN_SHM_MULTICAST_DEFAULT: &str = ZN_FALSE;
 }
 
 pub use consts::*;
@@ -356,6 +457,17 @@ impl KeyTranscoder for ConfigTranscoder {
             ZN_DEFRAG_BUFF_SIZE_STR => Some(ZN_DEFRAG_BUFF_SIZE_KEY),
             ZN_LINK_RX_BUFF_SIZE_STR => Some(ZN_LINK_RX_BUFF_SIZE_KEY),
             ZN_MULTICAST_IPV6_ADDRESS_STR => Some(ZN_MULTICAST_IPV6_ADDRESS_KEY),
+            ZN_LINK_COSTS_STR => Some(ZN_LINK_COSTS_KEY),
+            ZN_EGRESS_POLICIES_STR => Some(ZN_EGRESS_POLICIES_KEY),
+            ZN_ROUTER_ELECTION_STR => Some(ZN_ROUTER_ELECTION_KEY),
+            ZN_ROUTER_HOMES_STR => Some(ZN_ROUTER_HOMES_KEY),
+            ZN_SUBSCRIPTIONS_AGGREGATION_STR => Some(ZN_SUBSCRIPTIONS_AGGREGATION_KEY),
+            ZN_AUTOCONNECT_WHATAMI_STR => Some(ZN_AUTOCONNECT_WHATAMI_KEY),
+            ZN_AUTOCONNECT_PROTOCOLS_STR => Some(ZN_AUTOCONNECT_PROTOCOLS_KEY),
+            ZN_ADVERTISED_LOCATORS_STR => Some(ZN_ADVERTISED_LOCATORS_KEY),
+            ZN_ADVERTISED_LOCATORS_EXCLUDE_STR => Some(ZN_ADVERTISED_LOCATORS_EXCLUDE_KEY),
+            ZN_ADVERTISED_LOCATORS_FAMILIES_STR => Some(ZN_ADVERTISED_LOCATORS_FAMILIES_KEY),
+            ZN_SHM_MULTICAST_STR => Some(ZN_SHM_MULTICAST_KEY),
             _ => None,
         }
     }
@@ -403,6 +515,21 @@ impl KeyTranscoder for ConfigTranscoder {
             ZN_DEFRAG_BUFF_SIZE_KEY => Some(ZN_DEFRAG_BUFF_SIZE_STR.to_string()),
             ZN_LINK_RX_BUFF_SIZE_KEY => Some(ZN_LINK_RX_BUFF_SIZE_STR.to_string()),
             ZN_MULTICAST_IPV6_ADDRESS_KEY => Some(ZN_MULTICAST_IPV6_ADDRESS_STR.to_string()),
+            ZN_LINK_COSTS_KEY => Some(ZN_LINK_COSTS_STR.to_string()),
+            ZN_EGRESS_POLICIES_KEY => Some(ZN_EGRESS_POLICIES_STR.to_string()),
+            ZN_ROUTER_ELECTION_KEY => Some(ZN_ROUTER_ELECTION_STR.to_string()),
+            ZN_ROUTER_HOMES_KEY => Some(ZN_ROUTER_HOMES_STR.to_string()),
+            ZN_SUBSCRIPTIONS_AGGREGATION_KEY => Some(ZN_SUBSCRIPTIONS_AGGREGATION_STR.to_string()),
+            ZN_AUTOCONNECT_WHATAMI_KEY => Some(ZN_AUTOCONNECT_WHATAMI_STR.to_string()),
+            ZN_AUTOCONNECT_PROTOCOLS_KEY => Some(ZN_AUTOCONNECT_PROTOCOLS_STR.to_string()),
+            ZN_ADVERTISED_LOCATORS_KEY => Some(ZN_ADVERTISED_LOCATORS_STR.to_string()),
+            ZN_ADVERTISED_LOCATORS_EXCLUDE_KEY => {
+                Some(ZN_ADVERTISED_LOCATORS_EXCLUDE_STR.to_string())
+            }
+            ZN_ADVERTISED_LOCATORS_FAMILIES_KEY => {
+                Some(ZN_ADVERTISED_LOCATORS_FAMILIES_STR.to_string())
+            }
+            ZN_SHM_MULTICAST_KEY => Some(ZN_SHM_MULTICAST_STR.to_string()),
             _ => None,
         }
     }
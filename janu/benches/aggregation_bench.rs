@@ -0,0 +1,99 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+#[macro_use]
+extern crate criterion;
+use async_std::sync::Arc;
+use criterion::{BenchmarkId, Criterion};
+use janu::net::protocol::core::{whatami, PeerId, Reliability, SubInfo, SubMode};
+use janu::net::routing::pubsub::*;
+use janu::net::routing::router::Router;
+use janu::net::runtime::Runtime;
+use janu::net::transport::{DummyPrimitives, Primitives};
+use janu_util::properties::config::*;
+use janu_util::zwrite;
+
+// The subscriptions of a router are propagated through its link state networks,
+// that are only available to the router of a runtime.
+fn router(aggregation: Option<&str>) -> (Runtime, Arc<Router>) {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "router".to_string());
+    config.insert(ZN_LISTENER_KEY, "".to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    if let Some(aggregation) = aggregation {
+        config.insert(ZN_SUBSCRIPTIONS_AGGREGATION_KEY, aggregation.to_string());
+    }
+    let runtime = async_std::task::block_on(Runtime::new(0, config, None)).unwrap();
+    let router = runtime.router.clone();
+    (runtime, router)
+}
+
+fn aggregation_bench(c: &mut Criterion) {
+    let mut aggregation_bench = c.benchmark_group("aggregation_bench");
+    let sub_info = SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        period: None,
+    };
+
+    for (id, aggregation) in [("exact", None), ("aggregated", Some("/bench/fleet/**"))].iter() {
+        let (_runtime, router) = router(*aggregation);
+        let face = zwrite!(router.tables).open_face(
+            PeerId::new(0, [0; 16]),
+            whatami::CLIENT,
+            Arc::new(DummyPrimitives {}),
+        );
+        let mut face = face.upgrade().unwrap();
+
+        let mut declared = 0;
+        for p in [8, 32, 256, 1024, 8192].iter() {
+            let mut tables = zwrite!(router.tables);
+            for i in declared..*p {
+                let name = ["/bench/fleet/r", &i.to_string(), "/x"].concat();
+                declare_client_subscription(&mut tables, &mut face, 0, &name, &sub_info);
+            }
+            declared = *p;
+
+            // Declares and forgets a subscription next to p subscriptions
+            aggregation_bench.bench_function(BenchmarkId::new([id, "_churn"].concat(), p), |b| {
+                b.iter(|| {
+                    declare_client_subscription(
+                        &mut tables,
+                        &mut face,
+                        0,
+                        "/bench/fleet/new/x",
+                        &sub_info,
+                    );
+                    forget_client_subscription(&mut tables, &mut face, 0, "/bench/fleet/new/x");
+                })
+            });
+            drop(tables);
+
+            // Connects a client to which the subscriptions are advertised
+            aggregation_bench.bench_function(
+                BenchmarkId::new([id, "_new_client"].concat(), p),
+                |b| {
+                    b.iter(|| {
+                        router
+                            .new_primitives(Arc::new(DummyPrimitives {}))
+                            .send_close();
+                    })
+                },
+            );
+        }
+    }
+    aggregation_bench.finish();
+}
+
+criterion_group!(benches, aggregation_bench);
+criterion_main!(benches);
@@ -0,0 +1,98 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+#[macro_use]
+extern crate criterion;
+
+use async_std::net::UdpSocket;
+use criterion::{black_box, Criterion};
+use janu::net::protocol::io::{ZBuf, ZSlice};
+use janu_util::collections::RecyclingObjectPool;
+use std::net::UdpSocket as StdUdpSocket;
+
+const BUFFER_SIZE: usize = 65_535;
+const DATAGRAM_SIZE: usize = 1_024;
+const DATAGRAM_NUM: usize = 16;
+
+fn bench_rx_buffers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rx_buffer");
+
+    // A new buffer is allocated for each batch read from a link
+    group.bench_function("alloc", |b| {
+        b.iter(|| {
+            let buffer = vec![0u8; BUFFER_SIZE];
+            let mut zbuf = ZBuf::new();
+            zbuf.add_zslice(ZSlice::from(buffer));
+            black_box(&zbuf);
+        })
+    });
+
+    // The buffers are taken from a pool and reinserted into it once dropped
+    let pool = RecyclingObjectPool::new(16, || vec![0u8; BUFFER_SIZE].into_boxed_slice());
+    group.bench_function("pool", |b| {
+        b.iter(|| {
+            let buffer = pool.try_take().unwrap_or_else(|| pool.alloc());
+            let mut zbuf = ZBuf::new();
+            zbuf.add_zslice(ZSlice::from(buffer));
+            black_box(&zbuf);
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_udp_recv(c: &mut Criterion) {
+    let mut group = c.benchmark_group("udp_recv");
+
+    let rx = StdUdpSocket::bind("127.0.0.1:0").unwrap();
+    let tx = StdUdpSocket::bind("127.0.0.1:0").unwrap();
+    tx.connect(rx.local_addr().unwrap()).unwrap();
+    let rx_async = UdpSocket::from(rx.try_clone().unwrap());
+    let payload = vec![0u8; DATAGRAM_SIZE];
+    let mut buffers = vec![vec![0u8; BUFFER_SIZE]; DATAGRAM_NUM];
+
+    // One system call per datagram
+    group.bench_function("recv_from", |b| {
+        b.iter(|| {
+            for _ in 0..DATAGRAM_NUM {
+                tx.send(&payload).unwrap();
+            }
+            for buffer in buffers.iter_mut() {
+                black_box(rx.recv_from(buffer).unwrap());
+            }
+        })
+    });
+
+    // One system call for all the queued datagrams
+    if cfg!(target_os = "linux") {
+        group.bench_function("recv_batch_from", |b| {
+            b.iter(|| {
+                for _ in 0..DATAGRAM_NUM {
+                    tx.send(&payload).unwrap();
+                }
+                let mut read = 0;
+                while read < DATAGRAM_NUM {
+                    let mut slices: Vec<&mut [u8]> =
+                        buffers[read..].iter_mut().map(|b| &mut b[..]).collect();
+                    let res = janu_util::net::try_recv_batch_from(&rx_async, &mut slices).unwrap();
+                    read += black_box(res).len();
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_rx_buffers, bench_udp_recv);
+criterion_main!(benches);
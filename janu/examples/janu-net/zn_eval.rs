@@ -45,6 +45,8 @@ async fn main() {
                     res_name: path.clone(),
                     payload: value.as_bytes().into(),
                     data_info: None,
+                    channel: Channel::default(),
+                    congestion_control: CongestionControl::default(),
                 });
             },
 
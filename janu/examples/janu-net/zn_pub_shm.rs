@@ -12,8 +12,6 @@
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
 #[cfg(feature = "zero-copy")]
-use async_std::task::sleep;
-#[cfg(feature = "zero-copy")]
 use clap::{App, Arg};
 #[cfg(feature = "zero-copy")]
 use std::time::Duration;
@@ -45,21 +43,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Allocating a Shared Memory Buffer...");
 
     for idx in 0..(K * N as u32) {
-        let mut sbuf = match shm.alloc(1024) {
-            Some(buf) => buf,
-            None => {
-                sleep(Duration::from_millis(100)).await;
-                println!(
-                    "Afer failing allocation the GC collected: {} bytes -- retrying",
-                    shm.garbage_collect()
-                );
-                println!(
-                    "Trying to de-fragment memory... De-fragmented {} bytes",
-                    shm.defragment()
-                );
-                shm.alloc(1024).unwrap()
-            }
-        };
+        // Wait for previously written buffers to be released if the segment is full.
+        // Released buffers are garbage collected automatically by the manager.
+        let mut sbuf = shm.alloc_wait(1024, Duration::from_secs(1)).await.unwrap();
 
         // We reserve a small space at the beginning of the buffer to include the iteration index
         // of the write. This is simply to have the same format as zn_pub.
@@ -91,10 +77,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .write(&path.clone().into(), sbuf.clone().into())
             .await?;
         if idx % K == 0 {
-            let freed = shm.garbage_collect();
-            println!("The Gargabe collector freed {} bytes", freed);
-            let defrag = shm.defragment();
-            println!("De-framented {} bytes", defrag);
+            println!("Shared Memory segments: {:?}", shm.stats());
         }
         // sleep(Duration::from_millis(100)).await;
     }
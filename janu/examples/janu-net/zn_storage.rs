@@ -72,6 +72,8 @@ async fn main() {
                             res_name: stored_name.clone(),
                             payload: data.clone(),
                             data_info: data_info.clone(),
+                            channel: Channel::default(),
+                            congestion_control: CongestionControl::default(),
                         });
                     }
                 }
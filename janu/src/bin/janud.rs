@@ -100,6 +100,24 @@ fn main() {
             WARNING: this identifier must be unique in the system! \
             If not set, a random UUIDv4 will be used.'",
             ))
+            .arg(Arg::from_usage(
+                "--router-election=[STRATEGY] \
+            'The strategy used to elect the router in charge of routing some data or query \
+            between the router mesh and the peer mesh: \
+            hash (default), closest (by link cost to the source) or home (see --router-home). \
+            WARNING: all the routers must use the same strategy!'",
+            ).possible_values(&["hash", "closest", "home"]))
+            .arg(Arg::from_usage(
+                "--router-home=[KEY_EXPR=ID]... \
+            'The id of the home router of a key expression, elected by the home router election strategy. \
+            Repeat this option to configure several homes. The first matching home is used.'",
+            ))
+            .arg(Arg::from_usage(
+                "--subscriptions-aggregation=[KEY_EXPR]... \
+            'A key expression under which the subscriptions are advertised to the other routers \
+            and peers as a single subscription on this key expression. \
+            Repeat this option to configure several key expressions.'",
+            ))
             .arg(Arg::from_usage(
                 "-P, --plugin=[PATH_TO_PLUGIN_LIB]... \
              'A plugin that must be loaded. Repeat this option to load several plugins.'",
@@ -176,6 +194,28 @@ fn main() {
         }
         config.insert(ZN_LISTENER_KEY, listener);
 
+        if let Some(strategy) = args.value_of("router-election") {
+            config.insert(ZN_ROUTER_ELECTION_KEY, strategy.to_string());
+        }
+
+        if let Some(values) = args.values_of("router-home") {
+            let mut homes = values.collect::<Vec<&str>>().join(",");
+            if let Some(val) = config.get(&ZN_ROUTER_HOMES_KEY) {
+                homes.push(',');
+                homes.push_str(val);
+            }
+            config.insert(ZN_ROUTER_HOMES_KEY, homes);
+        }
+
+        if let Some(values) = args.values_of("subscriptions-aggregation") {
+            let mut exprs = values.collect::<Vec<&str>>().join(",");
+            if let Some(val) = config.get(&ZN_SUBSCRIPTIONS_AGGREGATION_KEY) {
+                exprs.push(',');
+                exprs.push_str(val);
+            }
+            config.insert(ZN_SUBSCRIPTIONS_AGGREGATION_KEY, exprs);
+        }
+
         config.insert(
             ZN_ADD_TIMESTAMP_KEY,
             if args.is_present("no-timestamp") {
@@ -11,16 +11,30 @@
 // Contributors:
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
+#[cfg(feature = "transport_mem")]
+use super::mem::{LocatorConfigMem, LocatorMem};
 #[cfg(feature = "transport_quic")]
 use super::quic::{LocatorConfigQuic, LocatorQuic};
+#[cfg(all(feature = "transport_serial", target_family = "unix"))]
+use super::serial::{LocatorConfigSerial, LocatorSerial};
 #[cfg(feature = "transport_tcp")]
 use super::tcp::{LocatorConfigTcp, LocatorTcp};
 #[cfg(feature = "transport_tls")]
 use super::tls::{LocatorConfigTls, LocatorTls};
 #[cfg(feature = "transport_udp")]
 use super::udp::{LocatorConfigUdp, LocatorUdp};
+#[cfg(all(
+    any(
+        feature = "transport_unixsock-dgram",
+        feature = "transport_unixsock-seqpacket"
+    ),
+    target_os = "linux"
+))]
+use super::unixsock_packet::{LocatorConfigUnixSocketPacket, LocatorUnixSocketPacket};
 #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
 use super::unixsock_stream::{LocatorConfigUnixSocketStream, LocatorUnixSocketStream};
+#[cfg(feature = "transport_ws")]
+use super::ws::{LocatorConfigWs, LocatorConfigWss, LocatorWs};
 use std::cmp::PartialEq;
 use std::collections::HashMap;
 use std::fmt;
@@ -46,6 +60,33 @@ pub const STR_TLS: &str = "tls";
 pub const STR_QUIC: &str = "quic";
 #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
 pub const STR_UNIXSOCK_STREAM: &str = "unixsock-stream";
+#[cfg(all(feature = "transport_unixsock-dgram", target_os = "linux"))]
+pub const STR_UNIXSOCK_DGRAM: &str = "unixsock-dgram";
+#[cfg(all(feature = "transport_unixsock-seqpacket", target_os = "linux"))]
+pub const STR_UNIXSOCK_SEQPACKET: &str = "unixsock-seqpacket";
+#[cfg(feature = "transport_ws")]
+pub const STR_WS: &str = "ws";
+#[cfg(feature = "transport_ws")]
+pub const STR_WSS: &str = "wss";
+#[cfg(all(feature = "transport_serial", target_family = "unix"))]
+pub const STR_SERIAL: &str = "serial";
+#[cfg(feature = "transport_mem")]
+pub const STR_MEM: &str = "mem";
+
+// The protocols built in janu and the features enabling them
+const BUILTIN_PROTOCOLS: [(&str, &str); 11] = [
+    ("tcp", "transport_tcp"),
+    ("udp", "transport_udp"),
+    ("tls", "transport_tls"),
+    ("quic", "transport_quic"),
+    ("unixsock-stream", "transport_unixsock-stream"),
+    ("unixsock-dgram", "transport_unixsock-dgram"),
+    ("unixsock-seqpacket", "transport_unixsock-seqpacket"),
+    ("ws", "transport_ws"),
+    ("wss", "transport_ws"),
+    ("serial", "transport_serial"),
+    ("mem", "transport_mem"),
+];
 
 // Parsing chars
 pub const PROTO_SEPARATOR: char = '/';
@@ -175,6 +216,20 @@ pub enum LocatorProtocol {
     Quic,
     #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
     UnixSocketStream,
+    #[cfg(all(feature = "transport_unixsock-dgram", target_os = "linux"))]
+    UnixSocketDgram,
+    #[cfg(all(feature = "transport_unixsock-seqpacket", target_os = "linux"))]
+    UnixSocketSeqpacket,
+    #[cfg(feature = "transport_ws")]
+    Ws,
+    #[cfg(feature = "transport_ws")]
+    Wss,
+    #[cfg(all(feature = "transport_serial", target_family = "unix"))]
+    Serial,
+    #[cfg(feature = "transport_mem")]
+    Mem,
+    // A protocol registered on the transport manager by the application
+    Custom(String),
 }
 
 impl fmt::Display for LocatorProtocol {
@@ -190,6 +245,19 @@ impl fmt::Display for LocatorProtocol {
             LocatorProtocol::Quic => write!(f, "{}", STR_QUIC)?,
             #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
             LocatorProtocol::UnixSocketStream => write!(f, "{}", STR_UNIXSOCK_STREAM)?,
+            #[cfg(all(feature = "transport_unixsock-dgram", target_os = "linux"))]
+            LocatorProtocol::UnixSocketDgram => write!(f, "{}", STR_UNIXSOCK_DGRAM)?,
+            #[cfg(all(feature = "transport_unixsock-seqpacket", target_os = "linux"))]
+            LocatorProtocol::UnixSocketSeqpacket => write!(f, "{}", STR_UNIXSOCK_SEQPACKET)?,
+            #[cfg(feature = "transport_ws")]
+            LocatorProtocol::Ws => write!(f, "{}", STR_WS)?,
+            #[cfg(feature = "transport_ws")]
+            LocatorProtocol::Wss => write!(f, "{}", STR_WSS)?,
+            #[cfg(all(feature = "transport_serial", target_family = "unix"))]
+            LocatorProtocol::Serial => write!(f, "{}", STR_SERIAL)?,
+            #[cfg(feature = "transport_mem")]
+            LocatorProtocol::Mem => write!(f, "{}", STR_MEM)?,
+            LocatorProtocol::Custom(protocol) => write!(f, "{}", protocol)?,
         }
         Ok(())
     }
@@ -238,6 +306,45 @@ impl LocatorConfig {
                 ps.insert(LocatorProtocol::UnixSocketStream, p);
             }
         }
+        #[cfg(all(feature = "transport_unixsock-dgram", target_os = "linux"))]
+        {
+            let mut res = LocatorConfigUnixSocketPacket::from_config(config)?;
+            if let Some(p) = res.take() {
+                ps.insert(LocatorProtocol::UnixSocketDgram, p);
+            }
+        }
+        #[cfg(all(feature = "transport_unixsock-seqpacket", target_os = "linux"))]
+        {
+            let mut res = LocatorConfigUnixSocketPacket::from_config(config)?;
+            if let Some(p) = res.take() {
+                ps.insert(LocatorProtocol::UnixSocketSeqpacket, p);
+            }
+        }
+        #[cfg(feature = "transport_ws")]
+        {
+            let mut res = LocatorConfigWs::from_config(config)?;
+            if let Some(p) = res.take() {
+                ps.insert(LocatorProtocol::Ws, p);
+            }
+            let mut res = LocatorConfigWss::from_config(config)?;
+            if let Some(p) = res.take() {
+                ps.insert(LocatorProtocol::Wss, p);
+            }
+        }
+        #[cfg(all(feature = "transport_serial", target_family = "unix"))]
+        {
+            let mut res = LocatorConfigSerial::from_config(config)?;
+            if let Some(p) = res.take() {
+                ps.insert(LocatorProtocol::Serial, p);
+            }
+        }
+        #[cfg(feature = "transport_mem")]
+        {
+            let mut res = LocatorConfigMem::from_config(config)?;
+            if let Some(p) = res.take() {
+                ps.insert(LocatorProtocol::Mem, p);
+            }
+        }
         Ok(ps)
     }
 }
@@ -325,6 +432,19 @@ pub enum LocatorAddress {
     Quic(LocatorQuic),
     #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
     UnixSocketStream(LocatorUnixSocketStream),
+    #[cfg(all(feature = "transport_unixsock-dgram", target_os = "linux"))]
+    UnixSocketDgram(LocatorUnixSocketPacket),
+    #[cfg(all(feature = "transport_unixsock-seqpacket", target_os = "linux"))]
+    UnixSocketSeqpacket(LocatorUnixSocketPacket),
+    #[cfg(feature = "transport_ws")]
+    Ws(LocatorWs),
+    #[cfg(feature = "transport_ws")]
+    Wss(LocatorWs),
+    #[cfg(all(feature = "transport_serial", target_family = "unix"))]
+    Serial(LocatorSerial),
+    #[cfg(feature = "transport_mem")]
+    Mem(LocatorMem),
+    Custom(LocatorCustom),
 }
 
 impl FromStr for LocatorAddress {
@@ -349,6 +469,32 @@ impl FromStr for LocatorAddress {
             STR_QUIC => addr.parse().map(LocatorAddress::Quic),
             #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
             STR_UNIXSOCK_STREAM => addr.parse().map(LocatorAddress::UnixSocketStream),
+            #[cfg(all(feature = "transport_unixsock-dgram", target_os = "linux"))]
+            STR_UNIXSOCK_DGRAM => addr.parse().map(LocatorAddress::UnixSocketDgram),
+            #[cfg(all(feature = "transport_unixsock-seqpacket", target_os = "linux"))]
+            STR_UNIXSOCK_SEQPACKET => addr.parse().map(LocatorAddress::UnixSocketSeqpacket),
+            #[cfg(feature = "transport_ws")]
+            STR_WS => addr.parse().map(LocatorAddress::Ws),
+            #[cfg(feature = "transport_ws")]
+            STR_WSS => addr.parse().map(LocatorAddress::Wss),
+            #[cfg(all(feature = "transport_serial", target_family = "unix"))]
+            STR_SERIAL => addr.parse().map(LocatorAddress::Serial),
+            #[cfg(feature = "transport_mem")]
+            STR_MEM => addr.parse().map(LocatorAddress::Mem),
+            custom if LocatorCustom::is_valid_protocol(custom) => {
+                match BUILTIN_PROTOCOLS.iter().find(|(p, _)| *p == custom) {
+                    // A built-in protocol whose feature is disabled (or that is not
+                    // supported on this platform) can not be a custom one
+                    Some((_, feature)) => {
+                        let e = format!(
+                            "Invalid locator address: {}. Protocol {} requires the {} feature.",
+                            s, custom, feature
+                        );
+                        zerror!(ZErrorKind::InvalidLocator { descr: e })
+                    }
+                    None => Ok(LocatorAddress::Custom(LocatorCustom::new(custom, addr))),
+                }
+            }
             unknown => {
                 let e = format!(
                     "Invalid locator address: {}. Unknown protocol: {}.",
@@ -373,6 +519,19 @@ impl LocatorAddress {
             LocatorAddress::Quic(..) => LocatorProtocol::Quic,
             #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
             LocatorAddress::UnixSocketStream(..) => LocatorProtocol::UnixSocketStream,
+            #[cfg(all(feature = "transport_unixsock-dgram", target_os = "linux"))]
+            LocatorAddress::UnixSocketDgram(..) => LocatorProtocol::UnixSocketDgram,
+            #[cfg(all(feature = "transport_unixsock-seqpacket", target_os = "linux"))]
+            LocatorAddress::UnixSocketSeqpacket(..) => LocatorProtocol::UnixSocketSeqpacket,
+            #[cfg(feature = "transport_ws")]
+            LocatorAddress::Ws(..) => LocatorProtocol::Ws,
+            #[cfg(feature = "transport_ws")]
+            LocatorAddress::Wss(..) => LocatorProtocol::Wss,
+            #[cfg(all(feature = "transport_serial", target_family = "unix"))]
+            LocatorAddress::Serial(..) => LocatorProtocol::Serial,
+            #[cfg(feature = "transport_mem")]
+            LocatorAddress::Mem(..) => LocatorProtocol::Mem,
+            LocatorAddress::Custom(l) => LocatorProtocol::Custom(l.protocol.clone()),
         }
     }
 
@@ -388,6 +547,19 @@ impl LocatorAddress {
             LocatorAddress::Quic(l) => l.is_multicast(),
             #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
             LocatorAddress::UnixSocketStream(l) => l.is_multicast(),
+            #[cfg(all(feature = "transport_unixsock-dgram", target_os = "linux"))]
+            LocatorAddress::UnixSocketDgram(l) => l.is_multicast(),
+            #[cfg(all(feature = "transport_unixsock-seqpacket", target_os = "linux"))]
+            LocatorAddress::UnixSocketSeqpacket(l) => l.is_multicast(),
+            #[cfg(feature = "transport_ws")]
+            LocatorAddress::Ws(l) => l.is_multicast(),
+            #[cfg(feature = "transport_ws")]
+            LocatorAddress::Wss(l) => l.is_multicast(),
+            #[cfg(all(feature = "transport_serial", target_family = "unix"))]
+            LocatorAddress::Serial(l) => l.is_multicast(),
+            #[cfg(feature = "transport_mem")]
+            LocatorAddress::Mem(l) => l.is_multicast(),
+            LocatorAddress::Custom(..) => false,
         }
     }
 }
@@ -407,7 +579,75 @@ impl fmt::Display for LocatorAddress {
             LocatorAddress::UnixSocketStream(addr) => {
                 write!(f, "{}{}{}", STR_UNIXSOCK_STREAM, PROTO_SEPARATOR, addr)?
             }
+            #[cfg(all(feature = "transport_unixsock-dgram", target_os = "linux"))]
+            LocatorAddress::UnixSocketDgram(addr) => {
+                write!(f, "{}{}{}", STR_UNIXSOCK_DGRAM, PROTO_SEPARATOR, addr)?
+            }
+            #[cfg(all(feature = "transport_unixsock-seqpacket", target_os = "linux"))]
+            LocatorAddress::UnixSocketSeqpacket(addr) => {
+                write!(f, "{}{}{}", STR_UNIXSOCK_SEQPACKET, PROTO_SEPARATOR, addr)?
+            }
+            #[cfg(feature = "transport_ws")]
+            LocatorAddress::Ws(addr) => write!(f, "{}{}{}", STR_WS, PROTO_SEPARATOR, addr)?,
+            #[cfg(feature = "transport_ws")]
+            LocatorAddress::Wss(addr) => write!(f, "{}{}{}", STR_WSS, PROTO_SEPARATOR, addr)?,
+            #[cfg(all(feature = "transport_serial", target_family = "unix"))]
+            LocatorAddress::Serial(addr) => write!(f, "{}{}{}", STR_SERIAL, PROTO_SEPARATOR, addr)?,
+            #[cfg(feature = "transport_mem")]
+            LocatorAddress::Mem(addr) => write!(f, "{}{}{}", STR_MEM, PROTO_SEPARATOR, addr)?,
+            LocatorAddress::Custom(addr) => write!(f, "{}", addr)?,
         }
         Ok(())
     }
 }
+
+/*************************************/
+/*          CUSTOM LOCATOR           */
+/*************************************/
+/// The address of a locator of a protocol that is not built in janu,
+/// i.e. a protocol registered on the transport manager with
+/// [`register_link_protocol_unicast`](crate::net::transport::TransportManager::register_link_protocol_unicast).
+/// The address is kept as a string to be parsed by the link manager of the protocol.
+/// Any well-formed protocol name that is not the one of a built-in protocol is parsed,
+/// the endpoints of a protocol that is not registered are rejected when adding a
+/// listener or opening a transport, and the locators of such a protocol are rejected
+/// in the configuration of a runtime.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LocatorCustom {
+    protocol: String,
+    address: String,
+}
+
+impl LocatorCustom {
+    pub fn new(protocol: &str, address: &str) -> LocatorCustom {
+        LocatorCustom {
+            protocol: protocol.to_string(),
+            address: address.to_string(),
+        }
+    }
+
+    pub fn protocol(&self) -> &str {
+        &self.protocol
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    // A protocol name starts with a letter followed by letters, digits, '+', '-', '_' or '.'
+    pub(crate) fn is_valid_protocol(protocol: &str) -> bool {
+        let mut chars = protocol.chars();
+        match chars.next() {
+            Some(c) if c.is_ascii_alphabetic() => {
+                chars.all(|c| c.is_ascii_alphanumeric() || "+-_.".contains(c))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for LocatorCustom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.protocol, PROTO_SEPARATOR, self.address)
+    }
+}
@@ -11,29 +11,45 @@
 // Contributors:
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
+#[cfg(feature = "transport_mem")]
+use super::mem::LinkManagerUnicastMem;
 #[cfg(feature = "transport_quic")]
 use super::quic::LinkManagerUnicastQuic;
+#[cfg(all(feature = "transport_serial", target_family = "unix"))]
+use super::serial::LinkManagerUnicastSerial;
 #[cfg(feature = "transport_tcp")]
 use super::tcp::LinkManagerUnicastTcp;
 #[cfg(feature = "transport_tls")]
 use super::tls::LinkManagerUnicastTls;
 #[cfg(feature = "transport_udp")]
 use super::udp::{LinkManagerMulticastUdp, LinkManagerUnicastUdp};
+#[cfg(all(feature = "transport_unixsock-dgram", target_os = "linux"))]
+use super::unixsock_packet::LinkManagerUnicastUnixSocketDgram;
+#[cfg(all(feature = "transport_unixsock-seqpacket", target_os = "linux"))]
+use super::unixsock_packet::LinkManagerUnicastUnixSocketSeqpacket;
 #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
 use super::unixsock_stream::LinkManagerUnicastUnixSocketStream;
+#[cfg(feature = "transport_ws")]
+use super::ws::LinkManagerUnicastWs;
 use super::{EndPoint, LinkMulticast, LinkUnicast, Locator, LocatorProtocol};
 use crate::net::transport::TransportManager;
 use async_std::sync::Arc;
 use async_trait::async_trait;
 use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::properties::Properties;
 use janu_util::zerror;
 
 /*************************************/
 /*             UNICAST               */
 /*************************************/
-pub(crate) type LinkManagerUnicast = Arc<dyn LinkManagerUnicastTrait>;
+pub type LinkManagerUnicast = Arc<dyn LinkManagerUnicastTrait>;
+
+/// The manager of the unicast links of a link protocol. It opens the links
+/// towards remote endpoints and accepts the links on its listeners. The
+/// accepted links are handed over to the transport manager with
+/// [`handle_new_link_unicast`](TransportManager::handle_new_link_unicast).
 #[async_trait]
-pub(crate) trait LinkManagerUnicastTrait: Send + Sync {
+pub trait LinkManagerUnicastTrait: Send + Sync {
     async fn new_link(&self, endpoint: EndPoint) -> ZResult<LinkUnicast>;
     async fn new_listener(&self, endpoint: EndPoint) -> ZResult<Locator>;
     async fn del_listener(&self, endpoint: &EndPoint) -> ZResult<()>;
@@ -41,10 +57,30 @@ pub(crate) trait LinkManagerUnicastTrait: Send + Sync {
     fn get_locators(&self) -> Vec<Locator>;
 }
 
-pub(crate) struct LinkManagerBuilderUnicast;
+/// A link protocol that is not built in janu. Once registered on a transport
+/// manager with [`register_link_protocol_unicast`](TransportManager::register_link_protocol_unicast),
+/// the locators having its name as protocol (e.g. `myproto/<address>`) can be
+/// used for listeners and peers like the ones of the built-in protocols.
+pub trait LinkProtocolUnicastTrait: Send + Sync {
+    /// The name of the protocol used in its locators (e.g. `myproto`).
+    fn name(&self) -> &str;
+    /// Checks the address of a locator of the protocol (e.g. `<address>` in `myproto/<address>`).
+    fn check_address(&self, address: &str) -> ZResult<()>;
+    /// The configuration passed to all the endpoints of the protocol, if any.
+    /// The configuration of an endpoint takes precedence over it.
+    fn config(&self) -> Option<Properties> {
+        None
+    }
+    /// Creates the link manager of the protocol for the given transport manager.
+    fn new_link_manager(&self, manager: TransportManager) -> LinkManagerUnicast;
+}
+
+pub type LinkProtocolUnicast = Arc<dyn LinkProtocolUnicastTrait>;
+
+pub struct LinkManagerBuilderUnicast;
 
 impl LinkManagerBuilderUnicast {
-    pub(crate) fn make(
+    pub fn make(
         manager: TransportManager,
         protocol: &LocatorProtocol,
     ) -> ZResult<LinkManagerUnicast> {
@@ -61,6 +97,26 @@ impl LinkManagerBuilderUnicast {
             LocatorProtocol::UnixSocketStream => {
                 Ok(Arc::new(LinkManagerUnicastUnixSocketStream::new(manager)))
             }
+            #[cfg(all(feature = "transport_unixsock-dgram", target_os = "linux"))]
+            LocatorProtocol::UnixSocketDgram => {
+                Ok(Arc::new(LinkManagerUnicastUnixSocketDgram::new(manager)))
+            }
+            #[cfg(all(feature = "transport_unixsock-seqpacket", target_os = "linux"))]
+            LocatorProtocol::UnixSocketSeqpacket => {
+                Ok(Arc::new(LinkManagerUnicastUnixSocketSeqpacket::new(manager)))
+            }
+            #[cfg(feature = "transport_ws")]
+            LocatorProtocol::Ws => Ok(Arc::new(LinkManagerUnicastWs::new(manager, false))),
+            #[cfg(feature = "transport_ws")]
+            LocatorProtocol::Wss => Ok(Arc::new(LinkManagerUnicastWs::new(manager, true))),
+            #[cfg(all(feature = "transport_serial", target_family = "unix"))]
+            LocatorProtocol::Serial => Ok(Arc::new(LinkManagerUnicastSerial::new(manager))),
+            #[cfg(feature = "transport_mem")]
+            LocatorProtocol::Mem => Ok(Arc::new(LinkManagerUnicastMem::new(manager))),
+            LocatorProtocol::Custom(name) => {
+                let protocol = manager.get_link_protocol_unicast(name)?;
+                Ok(protocol.new_link_manager(manager))
+            }
         }
     }
 }
@@ -0,0 +1,124 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::config::*;
+use super::*;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::properties::config::ConfigProperties;
+use janu_util::properties::Properties;
+use janu_util::{zerror, zerror2};
+
+#[allow(unreachable_patterns)]
+pub(super) fn get_mem_name(locator: &Locator) -> ZResult<String> {
+    match &locator.address {
+        LocatorAddress::Mem(addr) => Ok(addr.name.clone()),
+        _ => {
+            let e = format!("Not a Mem locator: {:?}", locator);
+            log::debug!("{}", e);
+            zerror!(ZErrorKind::InvalidLocator { descr: e })
+        }
+    }
+}
+
+// The emulated characteristics of the links created from an endpoint.
+// They apply to the batches sent on the links.
+#[derive(Clone, Debug)]
+pub(super) struct MemConfig {
+    pub(super) mtu: u16,
+    pub(super) latency: Duration,
+    pub(super) loss: f64,
+    pub(super) bandwidth: Option<u64>,
+    pub(super) seed: u64,
+}
+
+impl MemConfig {
+    pub(super) fn from_endpoint(endpoint: &EndPoint) -> ZResult<MemConfig> {
+        fn parse<T: FromStr>(endpoint: &EndPoint, key: &str) -> ZResult<Option<T>> {
+            match endpoint.config.as_ref().and_then(|c| c.get(key)) {
+                Some(value) => value.parse().map(Some).map_err(|_| {
+                    let e = format!("Invalid Mem {} in {}: {}", key, endpoint, value);
+                    log::debug!("{}", e);
+                    zerror2!(ZErrorKind::InvalidLocator { descr: e })
+                }),
+                None => Ok(None),
+            }
+        }
+
+        let mtu = parse::<u16>(endpoint, MEM_MTU)?.unwrap_or(*MEM_DEFAULT_MTU);
+        let latency = parse::<u64>(endpoint, MEM_LATENCY)?.unwrap_or(0);
+        let loss = parse::<f64>(endpoint, MEM_LOSS)?.unwrap_or(0.0);
+        let bandwidth = parse::<u64>(endpoint, MEM_BANDWIDTH)?;
+        let seed = parse::<u64>(endpoint, MEM_SEED)?.unwrap_or(0);
+
+        if mtu == 0 || !(0.0..=100.0).contains(&loss) || bandwidth == Some(0) {
+            let e = format!("Invalid Mem configuration: {}", endpoint);
+            log::debug!("{}", e);
+            return zerror!(ZErrorKind::InvalidLocator { descr: e });
+        }
+
+        Ok(MemConfig {
+            mtu,
+            latency: Duration::from_millis(latency),
+            loss,
+            bandwidth,
+            seed,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LocatorMem {
+    pub(super) name: String,
+}
+
+impl LocatorMem {
+    pub fn is_multicast(&self) -> bool {
+        false
+    }
+}
+
+impl FromStr for LocatorMem {
+    type Err = ZError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            let e = format!("Invalid Mem locator: {:?}", s);
+            return zerror!(ZErrorKind::InvalidLocator { descr: e });
+        }
+        Ok(LocatorMem {
+            name: s.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for LocatorMem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        Ok(())
+    }
+}
+
+/*************************************/
+/*          LOCATOR CONFIG           */
+/*************************************/
+#[derive(Clone)]
+pub struct LocatorConfigMem;
+
+impl LocatorConfigMem {
+    pub fn from_config(_config: &ConfigProperties) -> ZResult<Option<Properties>> {
+        Ok(None)
+    }
+}
@@ -0,0 +1,47 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+mod endpoint;
+mod unicast;
+
+use super::*;
+pub use endpoint::*;
+pub use unicast::*;
+
+// Maximum MTU (Mem batch) in bytes.
+// NOTE: Batches are exchanged as they are between the two ends of an
+//       in-process link, hence the MTU is only constrained by the 16 bits
+//       used in Janu to encode the batch size.
+const MEM_MAX_MTU: u16 = u16::MAX;
+
+zconfigurable! {
+    // Default MTU (Mem batch) in bytes.
+    static ref MEM_DEFAULT_MTU: u16 = MEM_MAX_MTU;
+    // The number of batches that can be queued on each direction of a link.
+    static ref MEM_QUEUE_SIZE: usize = 16;
+    // The number of links that can wait to be accepted by a listener.
+    static ref MEM_ACCEPT_QUEUE_SIZE: usize = 16;
+}
+
+pub mod config {
+    // The MTU of the links in bytes. The smallest MTU of the two endpoints applies.
+    pub const MEM_MTU: &str = "mtu";
+    // The latency in milliseconds added to each batch sent on the links.
+    pub const MEM_LATENCY: &str = "latency";
+    // The percentage of the batches sent on the links to be dropped.
+    pub const MEM_LOSS: &str = "loss";
+    // The bandwidth in bytes per second of the links.
+    pub const MEM_BANDWIDTH: &str = "bandwidth";
+    // The seed of the pseudo-random generator deciding which batches are dropped.
+    pub const MEM_SEED: &str = "seed";
+}
@@ -0,0 +1,459 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::*;
+use crate::net::transport::TransportManager;
+use async_std::channel::{bounded, Receiver, Sender};
+use async_std::prelude::*;
+use async_std::sync::Mutex as AsyncMutex;
+use async_std::task;
+use async_std::task::JoinHandle;
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::sync::Signal;
+use janu_util::{zasynclock, zerror, zerror2, zlock, zread, zwrite};
+
+// The listeners of the process, indexed by name, where the new links
+// rendezvous with the listeners.
+struct MemRendezvous {
+    sender: Sender<MemConnection>,
+    mtu: u16,
+}
+
+// The listener end of a new link
+struct MemConnection {
+    src: LocatorMem,
+    dst: LocatorMem,
+    mtu: u16,
+    tx: Sender<MemBatch>,
+    rx: Receiver<MemBatch>,
+}
+
+lazy_static! {
+    static ref MEM_LISTENERS: Mutex<HashMap<String, MemRendezvous>> = Mutex::new(HashMap::new());
+}
+
+struct MemBatch {
+    data: Vec<u8>,
+    // The instant at which the batch is received by the other end
+    deliver_at: Instant,
+}
+
+struct MemShaper {
+    rng: StdRng,
+    // The instant at which the last batch has been transmitted
+    next_free: Instant,
+}
+
+pub struct LinkUnicastMem {
+    src: LocatorMem,
+    dst: LocatorMem,
+    config: MemConfig,
+    tx: Sender<MemBatch>,
+    rx: Receiver<MemBatch>,
+    // Emulate the latency, loss and bandwidth of the batches being sent
+    shaper: AsyncMutex<MemShaper>,
+}
+
+impl LinkUnicastMem {
+    fn new(
+        src: LocatorMem,
+        dst: LocatorMem,
+        config: MemConfig,
+        tx: Sender<MemBatch>,
+        rx: Receiver<MemBatch>,
+    ) -> LinkUnicastMem {
+        let shaper = MemShaper {
+            rng: StdRng::seed_from_u64(config.seed),
+            next_free: Instant::now(),
+        };
+        LinkUnicastMem {
+            src,
+            dst,
+            config,
+            tx,
+            rx,
+            shaper: AsyncMutex::new(shaper),
+        }
+    }
+}
+
+#[async_trait]
+impl LinkUnicastTrait for LinkUnicastMem {
+    async fn close(&self) -> ZResult<()> {
+        log::trace!("Closing Mem link: {}", self);
+        // Closing the channels wakes up the pending operations on both ends
+        self.tx.close();
+        self.rx.close();
+        Ok(())
+    }
+
+    async fn write(&self, buffer: &[u8]) -> ZResult<usize> {
+        self.write_all(buffer).await?;
+        Ok(buffer.len())
+    }
+
+    async fn write_all(&self, buffer: &[u8]) -> ZResult<()> {
+        if buffer.len() > self.config.mtu as usize {
+            let e = format!(
+                "Write error on Mem link {}: {} bytes exceed the MTU of {} bytes",
+                self,
+                buffer.len(),
+                self.config.mtu
+            );
+            log::trace!("{}", e);
+            return zerror!(ZErrorKind::IoError { descr: e });
+        }
+
+        // Keep the lock while sending to preserve the order of the batches
+        let mut shaper = zasynclock!(self.shaper);
+        let now = Instant::now();
+        let mut transmitted = now.max(shaper.next_free);
+        if let Some(bandwidth) = self.config.bandwidth {
+            transmitted += Duration::from_secs_f64(buffer.len() as f64 / bandwidth as f64);
+        }
+        shaper.next_free = transmitted;
+
+        let is_lost = self.config.loss > 0.0 && shaper.rng.gen::<f64>() * 100.0 < self.config.loss;
+        if is_lost {
+            log::trace!("Dropping {} bytes on Mem link {}", buffer.len(), self);
+        } else {
+            let batch = MemBatch {
+                data: buffer.to_vec(),
+                deliver_at: transmitted + self.config.latency,
+            };
+            self.tx.send(batch).await.map_err(|_| {
+                let e = format!("Write error on Mem link {}: link closed", self);
+                log::trace!("{}", e);
+                zerror2!(ZErrorKind::IoError { descr: e })
+            })?;
+        }
+
+        // Wait for the batch to be transmitted
+        task::sleep(transmitted.saturating_duration_since(Instant::now())).await;
+        Ok(())
+    }
+
+    async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
+        let batch = self.rx.recv().await.map_err(|_| {
+            let e = format!("Read error on Mem link {}: link closed", self);
+            log::trace!("{}", e);
+            zerror2!(ZErrorKind::IoError { descr: e })
+        })?;
+
+        // Wait for the batch to be delivered
+        task::sleep(batch.deliver_at.saturating_duration_since(Instant::now())).await;
+        if batch.data.len() > buffer.len() {
+            let e = format!(
+                "Read error on Mem link {}: {} bytes do not fit in a buffer of {} bytes",
+                self,
+                batch.data.len(),
+                buffer.len()
+            );
+            log::trace!("{}", e);
+            return zerror!(ZErrorKind::IoError { descr: e });
+        }
+        buffer[..batch.data.len()].copy_from_slice(&batch.data);
+        Ok(batch.data.len())
+    }
+
+    async fn read_exact(&self, buffer: &mut [u8]) -> ZResult<()> {
+        let n = self.read(buffer).await?;
+        if n != buffer.len() {
+            let e = format!(
+                "Read error on Mem link {}: expected {} bytes, received {} bytes",
+                self,
+                buffer.len(),
+                n
+            );
+            log::trace!("{}", e);
+            return zerror!(ZErrorKind::IoError { descr: e });
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn get_src(&self) -> Locator {
+        Locator {
+            address: LocatorAddress::Mem(self.src.clone()),
+            metadata: None,
+        }
+    }
+
+    #[inline(always)]
+    fn get_dst(&self) -> Locator {
+        Locator {
+            address: LocatorAddress::Mem(self.dst.clone()),
+            metadata: None,
+        }
+    }
+
+    #[inline(always)]
+    fn get_mtu(&self) -> u16 {
+        self.config.mtu
+    }
+
+    #[inline(always)]
+    fn is_reliable(&self) -> bool {
+        self.config.loss == 0.0
+    }
+
+    #[inline(always)]
+    fn is_streamed(&self) -> bool {
+        false
+    }
+}
+
+impl fmt::Display for LinkUnicastMem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} => {}", self.src, self.dst)?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for LinkUnicastMem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mem")
+            .field("src", &self.src)
+            .field("dst", &self.dst)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/*************************************/
+/*          LISTENER                 */
+/*************************************/
+struct ListenerMem {
+    endpoint: EndPoint,
+    active: Arc<AtomicBool>,
+    signal: Signal,
+    handle: JoinHandle<ZResult<()>>,
+}
+
+impl ListenerMem {
+    fn new(
+        endpoint: EndPoint,
+        active: Arc<AtomicBool>,
+        signal: Signal,
+        handle: JoinHandle<ZResult<()>>,
+    ) -> ListenerMem {
+        ListenerMem {
+            endpoint,
+            active,
+            signal,
+            handle,
+        }
+    }
+}
+
+pub struct LinkManagerUnicastMem {
+    manager: TransportManager,
+    listeners: Arc<RwLock<HashMap<String, ListenerMem>>>,
+}
+
+impl LinkManagerUnicastMem {
+    pub(crate) fn new(manager: TransportManager) -> Self {
+        Self {
+            manager,
+            listeners: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl LinkManagerUnicastTrait for LinkManagerUnicastMem {
+    async fn new_link(&self, endpoint: EndPoint) -> ZResult<LinkUnicast> {
+        let name = get_mem_name(&endpoint.locator)?;
+        let config = MemConfig::from_endpoint(&endpoint)?;
+
+        let (sender, mtu) = match zlock!(MEM_LISTENERS).get(&name) {
+            Some(rendezvous) => (rendezvous.sender.clone(), rendezvous.mtu.min(config.mtu)),
+            None => {
+                let e = format!(
+                    "Can not create a new Mem link to {}: no listener found",
+                    name
+                );
+                log::warn!("{}", e);
+                return zerror!(ZErrorKind::InvalidLink { descr: e });
+            }
+        };
+
+        // Create the channels between the two ends of the link
+        let local = LocatorMem {
+            name: format!("{}", Uuid::new_v4()),
+        };
+        let remote = LocatorMem { name: name.clone() };
+        let (tx_local, rx_remote) = bounded(*MEM_QUEUE_SIZE);
+        let (tx_remote, rx_local) = bounded(*MEM_QUEUE_SIZE);
+        let connection = MemConnection {
+            src: remote.clone(),
+            dst: local.clone(),
+            mtu,
+            tx: tx_remote,
+            rx: rx_remote,
+        };
+        sender.send(connection).await.map_err(|_| {
+            let e = format!("Can not create a new Mem link to {}: listener closed", name);
+            log::warn!("{}", e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+        let config = MemConfig { mtu, ..config };
+        let link = LinkUnicastMem::new(local, remote, config, tx_local, rx_local);
+        Ok(LinkUnicast(Arc::new(link)))
+    }
+
+    async fn new_listener(&self, endpoint: EndPoint) -> ZResult<Locator> {
+        let name = get_mem_name(&endpoint.locator)?;
+        let config = MemConfig::from_endpoint(&endpoint)?;
+
+        let (sender, receiver) = bounded(*MEM_ACCEPT_QUEUE_SIZE);
+        {
+            let mut guard = zlock!(MEM_LISTENERS);
+            if guard.contains_key(&name) {
+                let e = format!(
+                    "Can not create a new Mem listener on {}: name already in use",
+                    name
+                );
+                log::warn!("{}", e);
+                return zerror!(ZErrorKind::InvalidLink { descr: e });
+            }
+            let rendezvous = MemRendezvous {
+                sender,
+                mtu: config.mtu,
+            };
+            guard.insert(name.clone(), rendezvous);
+        }
+
+        // Spawn the accept loop for the listener
+        let active = Arc::new(AtomicBool::new(true));
+        let signal = Signal::new();
+
+        let c_active = active.clone();
+        let c_signal = signal.clone();
+        let c_manager = self.manager.clone();
+        let c_listeners = self.listeners.clone();
+        let c_name = name.clone();
+        let handle = task::spawn(async move {
+            // Wait for the accept loop to terminate
+            let res = accept_task(receiver, config, c_active, c_signal, c_manager).await;
+            zwrite!(c_listeners).remove(&c_name);
+            zlock!(MEM_LISTENERS).remove(&c_name);
+            res
+        });
+
+        let locator = endpoint.locator.clone();
+        let listener = ListenerMem::new(endpoint, active, signal, handle);
+        zwrite!(self.listeners).insert(name, listener);
+
+        Ok(locator)
+    }
+
+    async fn del_listener(&self, endpoint: &EndPoint) -> ZResult<()> {
+        let name = get_mem_name(&endpoint.locator)?;
+
+        // Stop the listener
+        let listener = zwrite!(self.listeners).remove(&name).ok_or_else(|| {
+            let e = format!(
+                "Can not delete the Mem listener because it has not been found: {}",
+                name
+            );
+            log::trace!("{}", e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+        // Send the stop signal
+        listener.active.store(false, Ordering::Release);
+        listener.signal.trigger();
+        listener.handle.await
+    }
+
+    fn get_listeners(&self) -> Vec<EndPoint> {
+        zread!(self.listeners)
+            .values()
+            .map(|x| x.endpoint.clone())
+            .collect()
+    }
+
+    fn get_locators(&self) -> Vec<Locator> {
+        zread!(self.listeners)
+            .values()
+            .map(|x| x.endpoint.locator.clone())
+            .collect()
+    }
+}
+
+async fn accept_task(
+    receiver: Receiver<MemConnection>,
+    config: MemConfig,
+    active: Arc<AtomicBool>,
+    signal: Signal,
+    manager: TransportManager,
+) -> ZResult<()> {
+    enum Action {
+        Accept(MemConnection),
+        Stop,
+    }
+
+    async fn accept(receiver: &Receiver<MemConnection>) -> ZResult<Action> {
+        let connection = receiver.recv().await.map_err(|e| {
+            zerror2!(ZErrorKind::IoError {
+                descr: e.to_string()
+            })
+        })?;
+        Ok(Action::Accept(connection))
+    }
+
+    async fn stop(signal: Signal) -> ZResult<Action> {
+        signal.wait().await;
+        Ok(Action::Stop)
+    }
+
+    while active.load(Ordering::Acquire) {
+        // Wait for incoming links
+        let connection = match accept(&receiver).race(stop(signal.clone())).await? {
+            Action::Accept(connection) => connection,
+            Action::Stop => break,
+        };
+
+        // The batches sent by the listener end follow the listener configuration
+        let config = MemConfig {
+            mtu: connection.mtu,
+            ..config.clone()
+        };
+        let link = LinkUnicastMem::new(
+            connection.src,
+            connection.dst,
+            config,
+            connection.tx,
+            connection.rx,
+        );
+
+        log::debug!("Accepted Mem link: {}", link);
+        // Communicate the new link to the initial transport manager
+        manager
+            .handle_new_link_unicast(LinkUnicast(Arc::new(link)))
+            .await;
+    }
+
+    Ok(())
+}
@@ -13,30 +13,56 @@
 //
 mod endpoint;
 mod manager;
+#[cfg(feature = "transport_mem")]
+pub mod mem;
 #[cfg(feature = "transport_quic")]
 pub mod quic;
+#[cfg(all(feature = "transport_serial", target_family = "unix"))]
+pub mod serial;
 #[cfg(feature = "transport_tcp")]
 pub mod tcp;
+#[cfg(any(feature = "transport_tcp", feature = "transport_tls"))]
+pub mod tcp_socket;
 #[cfg(feature = "transport_tls")]
 pub mod tls;
 #[cfg(feature = "transport_udp")]
 pub mod udp;
+#[cfg(all(
+    any(
+        feature = "transport_unixsock-dgram",
+        feature = "transport_unixsock-seqpacket"
+    ),
+    target_os = "linux"
+))]
+pub mod unixsock_packet;
 #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
 pub mod unixsock_stream;
+#[cfg(feature = "transport_ws")]
+pub mod ws;
 
-use crate::net::protocol::io::{WBuf, ZBuf};
+use crate::net::protocol::core::Channel;
+use crate::net::protocol::io::{WBuf, ZBuf, ZSlice};
 use crate::net::protocol::proto::TransportMessage;
+use crate::net::transport::TransportManager;
 use async_std::sync::Arc;
 use async_trait::async_trait;
 pub use endpoint::*;
-pub(crate) use manager::*;
+pub use manager::*;
 use std::cmp::PartialEq;
 use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::io::IoSlice;
 use std::ops::Deref;
 use janu_util::core::{ZError, ZErrorKind, ZResult};
 
 const WBUF_SIZE: usize = 64;
+// The maximum number of buffers passed to a single vectored write
+#[cfg(any(
+    feature = "transport_tcp",
+    feature = "transport_tls",
+    all(feature = "transport_unixsock-stream", target_family = "unix")
+))]
+const MAX_IOSLICES: usize = 1_024;
 
 /*************************************/
 /*            GENERAL                */
@@ -95,15 +121,70 @@ impl From<LinkMulticast> for Link {
     }
 }
 
+// Copy the buffers into a single contiguous buffer, for links that can not
+// write multiple buffers at once while preserving the batch boundaries
+fn coalesce(buffers: &[IoSlice<'_>]) -> Vec<u8> {
+    let len = buffers.iter().map(|b| b.len()).sum();
+    let mut buffer = Vec::with_capacity(len);
+    for b in buffers.iter() {
+        buffer.extend_from_slice(b);
+    }
+    buffer
+}
+
+// Write all the buffers on a byte stream, gathering them in as few writes as possible
+#[cfg(any(
+    feature = "transport_tcp",
+    feature = "transport_tls",
+    all(feature = "transport_unixsock-stream", target_family = "unix")
+))]
+pub(crate) async fn write_all_vectored<W>(
+    writer: &mut W,
+    buffers: &[IoSlice<'_>],
+) -> std::io::Result<()>
+where
+    W: async_std::io::Write + Unpin + ?Sized,
+{
+    use async_std::io::prelude::WriteExt;
+
+    // The index of the first buffer not entirely written and the bytes written out of it
+    let (mut idx, mut pos) = (0, 0);
+    while idx < buffers.len() {
+        let ioslices: Vec<IoSlice> = std::iter::once(IoSlice::new(&buffers[idx][pos..]))
+            .chain(buffers[idx + 1..].iter().map(|b| IoSlice::new(b)))
+            .take(MAX_IOSLICES)
+            .collect();
+        let mut n = writer.write_vectored(&ioslices).await?;
+        if n == 0 && ioslices.iter().any(|b| !b.is_empty()) {
+            return Err(std::io::ErrorKind::WriteZero.into());
+        }
+        // Skip the buffers that have been entirely written
+        while idx < buffers.len() && n >= buffers[idx].len() - pos {
+            n -= buffers[idx].len() - pos;
+            idx += 1;
+            pos = 0;
+        }
+        pos += n;
+    }
+    Ok(())
+}
+
 /*************************************/
 /*            UNICAST                */
 /*************************************/
 
+/// A unicast link, i.e. a point-to-point connection with another node.
 #[derive(Clone)]
-pub(crate) struct LinkUnicast(Arc<dyn LinkUnicastTrait>);
-
+pub struct LinkUnicast(Arc<dyn LinkUnicastTrait>);
+
+/// The interface of a unicast link of a link protocol.
+///
+/// Streamed links (e.g. TCP) transmit a byte stream: each batch is prefixed by
+/// its length on 16 bits, or on 32 bits when a batch size larger than 65_535 bytes
+/// has been agreed with the peer. Non-streamed links (e.g. UDP) preserve the boundaries
+/// of each batch written on them and must return a whole batch on each read.
 #[async_trait]
-pub(crate) trait LinkUnicastTrait: Send + Sync {
+pub trait LinkUnicastTrait: Send + Sync {
     fn get_mtu(&self) -> u16;
     fn get_src(&self) -> Locator;
     fn get_dst(&self) -> Locator;
@@ -111,6 +192,19 @@ pub(crate) trait LinkUnicastTrait: Send + Sync {
     fn is_streamed(&self) -> bool;
     async fn write(&self, buffer: &[u8]) -> ZResult<usize>;
     async fn write_all(&self, buffer: &[u8]) -> ZResult<()>;
+    /// Writes all the buffers as a whole. Links able to gather multiple buffers in
+    /// a single write (e.g. TCP) may override it to avoid copying the buffers.
+    async fn write_vectored(&self, buffers: &[IoSlice<'_>]) -> ZResult<()> {
+        match buffers {
+            [buffer] => self.write_all(buffer).await,
+            _ => self.write_all(&coalesce(buffers)).await,
+        }
+    }
+    /// Writes a whole batch of the given channel. Links able to transmit the
+    /// channels independently of each other (e.g. QUIC) may override it.
+    async fn write_batch(&self, buffers: &[IoSlice<'_>], _channel: Channel) -> ZResult<()> {
+        self.write_vectored(buffers).await
+    }
     async fn read(&self, buffer: &mut [u8]) -> ZResult<usize>;
     async fn read_exact(&self, buffer: &mut [u8]) -> ZResult<()>;
     async fn close(&self) -> ZResult<()>;
@@ -135,35 +229,39 @@ impl LinkUnicast {
             let bits = wbuf.get_first_slice_mut(..2);
             bits.copy_from_slice(&length.to_le_bytes());
         }
-        let mut buffer = vec![0u8; wbuf.len()];
-        wbuf.copy_into_slice(&mut buffer[..]);
 
         // Send the message on the link
-        let _ = self.0.write_all(&buffer).await?;
+        self.0.write_vectored(&wbuf.as_ioslices()).await?;
 
-        Ok(buffer.len())
+        Ok(wbuf.len())
     }
 
-    pub(crate) async fn read_transport_message(&self) -> ZResult<Vec<TransportMessage>> {
+    pub(crate) async fn read_transport_message(
+        &self,
+        manager: &TransportManager,
+    ) -> ZResult<Vec<TransportMessage>> {
+        // Retrieve one buffer from the reception pools
+        let mtu = self.get_mtu() as usize;
+        let mut buffer = manager.rx_pools.take(mtu);
         // Read from the link
-        let buffer = if self.is_streamed() {
+        let n = if self.is_streamed() {
             // Read and decode the message length
             let mut length_bytes = [0u8; 2];
             let _ = self.read_exact(&mut length_bytes).await?;
             let to_read = u16::from_le_bytes(length_bytes) as usize;
+            if to_read > buffer.len() {
+                buffer = vec![0u8; to_read].into_boxed_slice().into();
+            }
             // Read the message
-            let mut buffer = vec![0u8; to_read];
-            let _ = self.read_exact(&mut buffer).await?;
-            buffer
+            let _ = self.read_exact(&mut buffer[0..to_read]).await?;
+            to_read
         } else {
             // Read the message
-            let mut buffer = vec![0u8; self.get_mtu() as usize];
-            let n = self.read(&mut buffer).await?;
-            buffer.truncate(n);
-            buffer
+            self.read(&mut buffer).await?
         };
 
-        let mut zbuf = ZBuf::from(buffer);
+        let mut zbuf = ZBuf::new();
+        zbuf.add_zslice(ZSlice::new(buffer.into(), 0, n));
         let mut messages: Vec<TransportMessage> = Vec::with_capacity(1);
         while zbuf.can_read() {
             match zbuf.read_transport_message() {
@@ -241,6 +339,13 @@ pub(crate) trait LinkMulticastTrait: Send + Sync {
     fn is_reliable(&self) -> bool;
     async fn write(&self, buffer: &[u8]) -> ZResult<usize>;
     async fn write_all(&self, buffer: &[u8]) -> ZResult<()>;
+    // Write the buffers as a single datagram
+    async fn write_vectored(&self, buffers: &[IoSlice<'_>]) -> ZResult<()> {
+        match buffers {
+            [buffer] => self.write_all(buffer).await,
+            _ => self.write_all(&coalesce(buffers)).await,
+        }
+    }
     async fn read(&self, buffer: &mut [u8]) -> ZResult<(usize, Locator)>;
     async fn close(&self) -> ZResult<()>;
 }
@@ -253,13 +358,11 @@ impl LinkMulticast {
         // Create the buffer for serializing the message
         let mut wbuf = WBuf::new(WBUF_SIZE, false);
         wbuf.write_transport_message(msg);
-        let mut buffer = vec![0u8; wbuf.len()];
-        wbuf.copy_into_slice(&mut buffer[..]);
 
         // Send the message on the link
-        let _ = self.0.write_all(&buffer).await;
+        let _ = self.0.write_vectored(&wbuf.as_ioslices()).await;
 
-        Ok(buffer.len())
+        Ok(wbuf.len())
     }
 
     //     pub(crate) async fn read_transport_message(&self) -> ZResult<(Vec<TransportMessage>, Locator)> {
@@ -40,6 +40,9 @@ zconfigurable! {
     // Amount of time in microseconds to throttle the accept loop upon an error.
     // Default set to 100 ms.
     static ref QUIC_ACCEPT_THROTTLE_TIME: u64 = 100_000;
+    // Maximum number of batches of a given priority received on the streams and
+    // as datagrams of a QUIC connection that are waiting to be read.
+    static ref QUIC_RX_QUEUE_SIZE: usize = 16;
 }
 
 pub mod config {
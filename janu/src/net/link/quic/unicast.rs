@@ -14,6 +14,8 @@
 use super::config::*;
 use super::EndPoint as ZEndPoint;
 use super::*;
+use crate::net::protocol::core::{Channel, Priority, Reliability};
+use crate::net::protocol::proto::defaults::BATCH_SIZE_MAX;
 use crate::net::transport::TransportManager;
 use async_std::fs;
 use async_std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
@@ -26,6 +28,9 @@ use quinn::Endpoint as QuicEndPoint;
 use quinn::*;
 use std::collections::HashMap;
 use std::fmt;
+use std::io::{Cursor, IoSlice};
+#[cfg(test)]
+use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
@@ -33,11 +38,81 @@ use janu_util::core::{ZError, ZErrorKind, ZResult};
 use janu_util::sync::Signal;
 use janu_util::{zasynclock, zerror2, zread, zwrite};
 
+#[cfg(test)]
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_DATAGRAM: AtomicUsize = AtomicUsize::new(0);
+// The number of batches sent as datagrams on the QUIC links, indexed by priority
+#[cfg(test)]
+static DATAGRAMS_SENT: [AtomicUsize; Priority::NUM] = [NO_DATAGRAM; Priority::NUM];
+
 pub struct LinkUnicastQuic {
-    connection: NewConnection,
+    connection: Connection,
     src_addr: SocketAddr,
-    send: AsyncMutex<SendStream>,
-    recv: AsyncMutex<RecvStream>,
+    // The send streams indexed by priority: the bidirectional stream opened at
+    // establishment for the control priority and the unidirectional streams
+    // lazily opened for the other priorities
+    send: Box<[AsyncMutex<Option<SendStream>>]>,
+    // The batches received on any stream or as datagrams
+    recv: AsyncMutex<RecvBatches>,
+}
+
+// The batches received on a QUIC connection, read as a single byte stream.
+// Each priority has its own queue so that a full queue never blocks the
+// batches of the other priorities, and a batch is read out of the highest
+// priority queue holding one.
+struct RecvBatches {
+    // The queues indexed by priority
+    batches: Box<[flume::Receiver<Vec<u8>>]>,
+    // One token per batch pushed in any of the queues
+    ready: flume::Receiver<()>,
+    batch: Vec<u8>,
+    read: usize,
+}
+
+impl RecvBatches {
+    async fn next(&mut self) -> Option<Vec<u8>> {
+        self.ready.recv_async().await.ok()?;
+        // A batch is pushed before its token, hence there is at least one
+        self.batches.iter().find_map(|queue| queue.try_recv().ok())
+    }
+}
+
+// The senders to the per-priority queues of a QUIC connection
+#[derive(Clone)]
+struct RecvQueues {
+    batches: Arc<[flume::Sender<Vec<u8>>]>,
+    ready: flume::Sender<()>,
+}
+
+impl RecvQueues {
+    fn sender(&self, priority: u8) -> Option<RecvQueue> {
+        self.batches
+            .get(priority as usize)
+            .map(|batches| RecvQueue {
+                batches: batches.clone(),
+                ready: self.ready.clone(),
+            })
+    }
+}
+
+// The sender to the queue of a single priority
+struct RecvQueue {
+    batches: flume::Sender<Vec<u8>>,
+    ready: flume::Sender<()>,
+}
+
+impl RecvQueue {
+    async fn send(&self, batch: Vec<u8>) -> bool {
+        self.batches.send_async(batch).await.is_ok() && self.ready.send(()).is_ok()
+    }
+
+    fn try_send(&self, batch: Vec<u8>) -> bool {
+        match self.batches.try_send(batch) {
+            Ok(()) => self.ready.send(()).is_ok(),
+            Err(flume::TrySendError::Full(_)) => true,
+            Err(flume::TrySendError::Disconnected(_)) => false,
+        }
+    }
 }
 
 impl LinkUnicastQuic {
@@ -47,75 +122,159 @@ impl LinkUnicastQuic {
         send: SendStream,
         recv: RecvStream,
     ) -> LinkUnicastQuic {
+        let NewConnection {
+            connection,
+            uni_streams,
+            datagrams,
+            ..
+        } = connection;
+
+        // Spawn the tasks receiving the batches
+        let (senders, batches): (Vec<_>, Vec<_>) = (0..Priority::NUM)
+            .map(|_| flume::bounded(*QUIC_RX_QUEUE_SIZE))
+            .unzip();
+        let (ready_tx, ready) = flume::unbounded();
+        let queues = RecvQueues {
+            batches: senders.into(),
+            ready: ready_tx,
+        };
+        // The bidirectional stream carries the control priority
+        // It is safe to unwrap since the control priority always exists
+        let control = queues.sender(Priority::Control as u8).unwrap();
+        task::spawn(recv_stream_task(recv, control));
+        task::spawn(accept_uni_streams_task(uni_streams, queues.clone()));
+        task::spawn(recv_datagrams_task(datagrams, queues));
+
+        let mut streams = Vec::with_capacity(Priority::NUM);
+        streams.push(AsyncMutex::new(Some(send)));
+        streams.resize_with(Priority::NUM, || AsyncMutex::new(None));
+
         // Build the Quic object
         LinkUnicastQuic {
             connection,
             src_addr,
-            send: AsyncMutex::new(send),
-            recv: AsyncMutex::new(recv),
+            send: streams.into_boxed_slice(),
+            recv: AsyncMutex::new(RecvBatches {
+                batches: batches.into_boxed_slice(),
+                ready,
+                batch: vec![],
+                read: 0,
+            }),
         }
     }
+
+    async fn write_all_on(&self, buffers: &[IoSlice<'_>], priority: Priority) -> ZResult<()> {
+        let mut guard = zasynclock!(self.send[priority as usize]);
+        if guard.is_none() {
+            let mut stream = self.connection.open_uni().await.map_err(|e| {
+                let e = format!("Can not open a QUIC stream on link {}: {}", self, e);
+                log::trace!("{}", e);
+                zerror2!(ZErrorKind::IoError { descr: e })
+            })?;
+            // Higher priority values are transmitted first
+            let _ = stream.set_priority(-(priority as i32));
+            // The first byte of a unidirectional stream is its priority
+            stream.write_all(&[priority as u8]).await.map_err(|e| {
+                log::trace!("Write error on QUIC link {}: {}", self, e);
+                let e = e.to_string();
+                zerror2!(ZErrorKind::IoError { descr: e })
+            })?;
+            *guard = Some(stream);
+        }
+        // It is safe to unwrap since the stream has been opened above
+        let stream = guard.as_mut().unwrap();
+        // Each write is prefixed by its length on 32 bits, whatever the framing of the
+        // batches negotiated by the transport. Hold the stream while writing all the
+        // buffers to keep the write contiguous.
+        let len: usize = buffers.iter().map(|b| b.len()).sum();
+        let len = (len as u32).to_le_bytes();
+        for buffer in std::iter::once(&len[..]).chain(buffers.iter().map(|b| &b[..])) {
+            stream.write_all(buffer).await.map_err(|e| {
+                log::trace!("Write error on QUIC link {}: {}", self, e);
+                let e = e.to_string();
+                zerror2!(ZErrorKind::IoError { descr: e })
+            })?;
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl LinkUnicastTrait for LinkUnicastQuic {
     async fn close(&self) -> ZResult<()> {
         log::trace!("Closing QUIC link: {}", self);
-        // Flush the QUIC stream
-        let mut guard = zasynclock!(self.send);
-        if let Err(e) = guard.finish().await {
-            log::trace!("Error closing QUIC stream {}: {}", self, e);
+        // Flush the QUIC streams
+        for stream in self.send.iter() {
+            if let Some(stream) = zasynclock!(stream).as_mut() {
+                if let Err(e) = stream.finish().await {
+                    log::trace!("Error closing QUIC stream {}: {}", self, e);
+                }
+            }
         }
-        self.connection.connection.close(VarInt::from_u32(0), &[0]);
+        self.connection.close(VarInt::from_u32(0), &[0]);
         Ok(())
     }
 
     async fn write(&self, buffer: &[u8]) -> ZResult<usize> {
-        let mut guard = zasynclock!(self.send);
-        guard.write(buffer).await.map_err(|e| {
-            log::trace!("Write error on QUIC link {}: {}", self, e);
-            let e = e.to_string();
-            zerror2!(ZErrorKind::IoError { descr: e })
-        })
+        // The writes are framed as a whole
+        self.write_all(buffer).await?;
+        Ok(buffer.len())
     }
 
     async fn write_all(&self, buffer: &[u8]) -> ZResult<()> {
-        let mut guard = zasynclock!(self.send);
-        guard.write_all(buffer).await.map_err(|e| {
-            log::trace!("Write error on QUIC link {}: {}", self, e);
-            let e = e.to_string();
-            zerror2!(ZErrorKind::IoError { descr: e })
-        })
+        self.write_all_on(&[IoSlice::new(buffer)], Priority::Control)
+            .await
+    }
+
+    async fn write_batch(&self, buffers: &[IoSlice<'_>], channel: Channel) -> ZResult<()> {
+        let len: usize = buffers.iter().map(|b| b.len()).sum();
+        // Send the best effort batches as datagrams prefixed by their priority,
+        // unless they don't fit in one. Datagrams preserve the boundaries of the
+        // batches, hence they are not prefixed by their length.
+        if channel.reliability == Reliability::BestEffort
+            && self
+                .connection
+                .max_datagram_size()
+                .map_or(false, |max| len < max)
+        {
+            let mut datagram = Vec::with_capacity(1 + len);
+            datagram.push(channel.priority as u8);
+            datagram.extend_from_slice(&coalesce(buffers));
+            match self.connection.send_datagram(datagram.into()) {
+                Ok(()) => {
+                    #[cfg(test)]
+                    DATAGRAMS_SENT[channel.priority as usize].fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+                Err(e) => log::trace!("Can not send a QUIC datagram on link {}: {}", self, e),
+            }
+        }
+        self.write_all_on(buffers, channel.priority).await
     }
 
     async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
         let mut guard = zasynclock!(self.recv);
-        guard
-            .read(buffer)
-            .await
-            .map_err(|e| {
-                let e = format!("Read error on QUIC link {}: {}", self, e);
-                log::trace!("{}", e);
-                zerror2!(ZErrorKind::IoError { descr: e })
-            })?
-            .ok_or_else(|| {
-                let e = format!(
-                    "Read error on QUIC link {}: stream {} has been closed",
-                    self,
-                    guard.id()
-                );
+        if guard.read == guard.batch.len() {
+            guard.batch = guard.next().await.ok_or_else(|| {
+                let e = format!("Read error on QUIC link {}: connection closed", self);
                 log::trace!("{}", e);
                 zerror2!(ZErrorKind::IoError { descr: e })
-            })
+            })?;
+            guard.read = 0;
+        }
+        let start = guard.read;
+        let n = buffer.len().min(guard.batch.len() - start);
+        buffer[..n].copy_from_slice(&guard.batch[start..start + n]);
+        guard.read += n;
+        Ok(n)
     }
 
     async fn read_exact(&self, buffer: &mut [u8]) -> ZResult<()> {
-        let mut guard = zasynclock!(self.recv);
-        guard.read_exact(buffer).await.map_err(|e| {
-            let e = format!("Read error on QUIC link {}: {}", self, e);
-            log::trace!("{}", e);
-            zerror2!(ZErrorKind::IoError { descr: e })
-        })
+        let mut read = 0;
+        while read < buffer.len() {
+            read += self.read(&mut buffer[read..]).await?;
+        }
+        Ok(())
     }
 
     #[inline(always)]
@@ -130,7 +289,7 @@ impl LinkUnicastTrait for LinkUnicastQuic {
     fn get_dst(&self) -> Locator {
         Locator {
             address: LocatorAddress::Quic(LocatorQuic::SocketAddr(
-                self.connection.connection.remote_address(),
+                self.connection.remote_address(),
             )),
             metadata: None,
         }
@@ -154,7 +313,7 @@ impl LinkUnicastTrait for LinkUnicastQuic {
 
 impl Drop for LinkUnicastQuic {
     fn drop(&mut self) {
-        self.connection.connection.close(VarInt::from_u32(0), &[0]);
+        self.connection.close(VarInt::from_u32(0), &[0]);
     }
 }
 
@@ -164,7 +323,7 @@ impl fmt::Display for LinkUnicastQuic {
             f,
             "{} => {}",
             self.src_addr,
-            self.connection.connection.remote_address()
+            self.connection.remote_address()
         )?;
         Ok(())
     }
@@ -174,11 +333,84 @@ impl fmt::Debug for LinkUnicastQuic {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Quic")
             .field("src", &self.src_addr)
-            .field("dst", &self.connection.connection.remote_address())
+            .field("dst", &self.connection.remote_address())
             .finish()
     }
 }
 
+/*************************************/
+/*            RECEIVING              */
+/*************************************/
+// Read the writes of a stream, prefixed by their length on 32 bits. A write carries
+// a batch with its own framing, hence it is at most 4 bytes longer than a batch.
+async fn recv_stream_task(mut stream: RecvStream, queue: RecvQueue) {
+    loop {
+        let mut length = [0u8; 4];
+        if let Err(e) = stream.read_exact(&mut length).await {
+            log::trace!("QUIC stream {} closed: {}", stream.id(), e);
+            break;
+        }
+        let n = u32::from_le_bytes(length) as usize;
+        if n > BATCH_SIZE_MAX as usize + length.len() {
+            log::trace!(
+                "Invalid QUIC write of {} bytes on stream {}",
+                n,
+                stream.id()
+            );
+            break;
+        }
+        let mut batch = vec![0u8; n];
+        if let Err(e) = stream.read_exact(&mut batch).await {
+            log::trace!("QUIC stream {} closed: {}", stream.id(), e);
+            break;
+        }
+        if !queue.send(batch).await {
+            break;
+        }
+    }
+}
+
+// Unidirectional streams start with their priority
+async fn accept_uni_streams_task(mut streams: IncomingUniStreams, queues: RecvQueues) {
+    while let Some(Ok(mut stream)) = streams.next().await {
+        let mut priority = [0u8];
+        if let Err(e) = stream.read_exact(&mut priority).await {
+            log::trace!("QUIC stream {} closed: {}", stream.id(), e);
+            continue;
+        }
+        match queues.sender(priority[0]) {
+            Some(queue) => {
+                task::spawn(recv_stream_task(stream, queue));
+            }
+            None => log::trace!(
+                "Invalid QUIC stream {} priority: {}",
+                stream.id(),
+                priority[0]
+            ),
+        }
+    }
+}
+
+// Datagrams carry their priority and exactly one batch. Being best effort, they
+// are dropped when the queue of their priority is full.
+async fn recv_datagrams_task(mut datagrams: Datagrams, queues: RecvQueues) {
+    while let Some(Ok(datagram)) = datagrams.next().await {
+        let queue = datagram
+            .first()
+            .and_then(|priority| queues.sender(*priority));
+        let queue = match queue {
+            Some(queue) if datagram.len() > 1 => queue,
+            _ => {
+                log::trace!("Invalid QUIC datagram of {} bytes dropped", datagram.len());
+                continue;
+            }
+        };
+        if !queue.try_send(datagram[1..].to_vec()) {
+            break;
+        }
+    }
+}
+
 /*************************************/
 /*          LISTENER                 */
 /*************************************/
@@ -243,46 +475,56 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastQuic {
             None => vec![],
         };
 
-        let mut config = if bytes.is_empty() {
-            ClientConfigBuilder::default()
+        let mut root_cert_store = rustls::RootCertStore::empty();
+        if bytes.is_empty() {
+            match rustls_native_certs::load_native_certs() {
+                Ok(certs) => {
+                    for cert in certs {
+                        let _ = root_cert_store.add(&rustls::Certificate(cert.0));
+                    }
+                }
+                Err(e) => log::warn!("Can not load the native QUIC CA certificates: {}", e),
+            }
         } else {
-            let ca = Certificate::from_pem(&bytes).map_err(|e| {
-                let e = format!("Invalid QUIC CA certificate file: {}", e);
-                zerror2!(ZErrorKind::IoError { descr: e })
-            })?;
-
-            let mut cc = ClientConfigBuilder::default();
-            cc.protocols(ALPN_QUIC_HTTP);
-            cc.add_certificate_authority(ca).map_err(|e| {
+            let certs = rustls_pemfile::certs(&mut Cursor::new(&bytes)).map_err(|e| {
                 let e = format!("Invalid QUIC CA certificate file: {}", e);
                 zerror2!(ZErrorKind::IoError { descr: e })
             })?;
+            for cert in certs {
+                root_cert_store
+                    .add(&rustls::Certificate(cert))
+                    .map_err(|e| {
+                        let e = format!("Invalid QUIC CA certificate file: {}", e);
+                        zerror2!(ZErrorKind::IoError { descr: e })
+                    })?;
+            }
+        }
 
-            cc
-        };
-
-        config.protocols(ALPN_QUIC_HTTP);
-
-        let mut endpoint = Endpoint::builder();
-        endpoint.default_client_config(config.build());
+        let mut crypto = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(root_cert_store)
+            .with_no_client_auth();
+        crypto.alpn_protocols = ALPN_QUIC_HTTP.iter().map(|p| p.to_vec()).collect();
 
-        let (endpoint, _) = if addr.is_ipv4() {
-            endpoint.bind(&"0.0.0.0:0".parse().unwrap())
+        let mut endpoint = if addr.is_ipv4() {
+            Endpoint::client("0.0.0.0:0".parse().unwrap())
         } else {
-            endpoint.bind(&"[::]:0".parse().unwrap())
+            Endpoint::client("[::]:0".parse().unwrap())
         }
         .map_err(|e| {
             let e = format!("Can not create a new QUIC link bound to {}: {}", host, e);
             zerror2!(ZErrorKind::InvalidLink { descr: e })
         })?;
 
+        endpoint.set_default_client_config(ClientConfig::new(Arc::new(crypto)));
+
         let src_addr = endpoint.local_addr().map_err(|e| {
             let e = format!("Can not create a new QUIC link bound to {}: {}", host, e);
             zerror2!(ZErrorKind::InvalidLink { descr: e })
         })?;
 
         let quic_conn = endpoint
-            .connect(&addr, host)
+            .connect(addr, host)
             .map_err(|e| {
                 let e = format!("Can not create a new QUIC link bound to {}: {}", host, e);
                 zerror2!(ZErrorKind::InvalidLink { descr: e })
@@ -334,7 +576,22 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastQuic {
                 }
             },
         };
-        let keys = PrivateKey::from_pem(bytes.as_slice()).unwrap();
+        let mut keys = rustls_pemfile::pkcs8_private_keys(&mut Cursor::new(&bytes))
+            .and_then(|mut keys| {
+                if keys.is_empty() {
+                    keys = rustls_pemfile::rsa_private_keys(&mut Cursor::new(&bytes))?;
+                }
+                Ok(keys)
+            })
+            .map_err(|e| {
+                let e = format!("Invalid TLS private key file: {}", e);
+                zerror2!(ZErrorKind::IoError { descr: e })
+            })?;
+        if keys.is_empty() {
+            let e = "Invalid TLS private key file: no private key found".to_string();
+            return zerror!(ZErrorKind::IoError { descr: e });
+        }
+        let keys = rustls::PrivateKey(keys.remove(0));
 
         // Configure the server certificate
         let bytes = match config.get(TLS_SERVER_CERTIFICATE_RAW) {
@@ -355,35 +612,35 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastQuic {
                 }
             },
         };
-        let certs = CertificateChain::from_pem(bytes.as_slice()).map_err(|e| {
-            let e = format!("Invalid TLS server certificate file: {}", e);
-            zerror2!(ZErrorKind::IoError { descr: e })
-        })?;
+        let certs = rustls_pemfile::certs(&mut Cursor::new(&bytes))
+            .map_err(|e| {
+                let e = format!("Invalid TLS server certificate file: {}", e);
+                zerror2!(ZErrorKind::IoError { descr: e })
+            })?
+            .into_iter()
+            .map(rustls::Certificate)
+            .collect();
+
+        let mut crypto = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(certs, keys)
+            .map_err(|e| {
+                let e = format!("Invalid TLS server configuration: {}", e);
+                zerror2!(ZErrorKind::Other { descr: e })
+            })?;
+        crypto.alpn_protocols = ALPN_QUIC_HTTP.iter().map(|p| p.to_vec()).collect();
 
         let mut tc = TransportConfig::default();
-        // We do not accept unidireactional streams.
-        tc.max_concurrent_uni_streams(0).map_err(|e| {
-            let e = format!("Invalid QUIC server configuration: {}", e);
-            zerror2!(ZErrorKind::IoError { descr: e })
-        })?;
-        // For the time being we only allow one bidirectional stream
-        tc.max_concurrent_bidi_streams(1).map_err(|e| {
-            let e = format!("Invalid QUIC server configuration: {}", e);
-            zerror2!(ZErrorKind::IoError { descr: e })
-        })?;
-        let mut sc = ServerConfig::default();
+        // We accept one unidirectional stream per priority
+        tc.max_concurrent_uni_streams(VarInt::from_u32(Priority::NUM as u32));
+        // We only allow one bidirectional stream, used for the control priority
+        tc.max_concurrent_bidi_streams(VarInt::from_u32(1));
+        let mut sc = ServerConfig::with_crypto(Arc::new(crypto));
         sc.transport = Arc::new(tc);
-        let mut sc = ServerConfigBuilder::new(sc);
-        sc.protocols(ALPN_QUIC_HTTP);
-        sc.certificate(certs, keys).map_err(|e| {
-            let e = format!("Invalid TLS server configuration: {}", e);
-            zerror2!(ZErrorKind::Other { descr: e })
-        })?;
 
         // Initialize the Endpoint
-        let mut quic_endpoint = QuicEndPoint::builder();
-        quic_endpoint.listen(sc.build());
-        let (quic_endpoint, acceptor) = quic_endpoint.bind(&addr).map_err(|e| {
+        let (quic_endpoint, acceptor) = QuicEndPoint::server(sc, addr).map_err(|e| {
             let e = format!("Can not create a new QUIC listener on {}: {}", addr, e);
             zerror2!(ZErrorKind::InvalidLink { descr: e })
         })?;
@@ -556,7 +813,7 @@ async fn accept_task(
             }
         };
 
-        // Get the bideractional streams. Note that we don't allow unidirectional streams.
+        // Get the bidirectional stream. The unidirectional streams are accepted by the link.
         let (send, recv) = match quic_conn.bi_streams.next().await {
             Some(bs) => match bs {
                 Ok((send, recv)) => (send, recv),
@@ -582,3 +839,113 @@ async fn accept_task(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::protocol::core::{whatami, CongestionControl, PeerId, ResKey};
+    use crate::net::protocol::io::ZBuf;
+    use crate::net::protocol::proto::JanuMessage;
+    use crate::net::transport::{DummyTransportEventHandler, TransportManagerConfig};
+    use janu_util::properties::Properties;
+
+    const TIMEOUT: Duration = Duration::from_secs(60);
+    const SLEEP: Duration = Duration::from_secs(1);
+    const SLEEP_COUNT: Duration = Duration::from_millis(10);
+
+    const MSG_COUNT: usize = 100;
+
+    fn message(size: usize) -> JanuMessage {
+        JanuMessage::make_data(
+            ResKey::RName("/test".to_string()),
+            ZBuf::from(vec![0u8; size]),
+            Channel {
+                priority: Priority::Data,
+                reliability: Reliability::BestEffort,
+            },
+            CongestionControl::Block,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn quic_best_effort_datagrams() {
+        task::block_on(async {
+            // Generate a self-signed certificate for localhost, also used as root CA by the client
+            let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+            let mut endpoint: ZEndPoint = "quic/localhost:13462".parse().unwrap();
+            let mut config = Properties::default();
+            config.insert(
+                TLS_ROOT_CA_CERTIFICATE_RAW.to_string(),
+                cert.serialize_pem().unwrap(),
+            );
+            config.insert(
+                TLS_SERVER_PRIVATE_KEY_RAW.to_string(),
+                cert.serialize_private_key_pem(),
+            );
+            config.insert(
+                TLS_SERVER_CERTIFICATE_RAW.to_string(),
+                cert.serialize_pem().unwrap(),
+            );
+            endpoint.config = Some(Arc::new(config));
+
+            let config = TransportManagerConfig::builder()
+                .whatami(whatami::ROUTER)
+                .pid(PeerId::new(1, [1u8; PeerId::MAX_SIZE]))
+                .build(Arc::new(DummyTransportEventHandler));
+            let router_manager = TransportManager::new(config);
+            let config = TransportManagerConfig::builder()
+                .whatami(whatami::CLIENT)
+                .pid(PeerId::new(1, [0u8; PeerId::MAX_SIZE]))
+                .build(Arc::new(DummyTransportEventHandler));
+            let client_manager = TransportManager::new(config);
+
+            router_manager
+                .add_listener(endpoint.clone())
+                .timeout(TIMEOUT)
+                .await
+                .unwrap()
+                .unwrap();
+            let transport = client_manager
+                .open_transport(endpoint.clone())
+                .timeout(TIMEOUT)
+                .await
+                .unwrap()
+                .unwrap();
+
+            // Small best effort batches are sent as datagrams
+            let sent = || DATAGRAMS_SENT[Priority::Data as usize].load(Ordering::Relaxed);
+            let datagrams = sent();
+            for _ in 0..MSG_COUNT {
+                transport.schedule(message(8)).unwrap();
+                task::sleep(SLEEP_COUNT).await;
+            }
+            let check = async {
+                while sent() == datagrams {
+                    task::sleep(SLEEP_COUNT).await;
+                }
+            };
+            check.timeout(TIMEOUT).await.unwrap();
+
+            // Large best effort batches fall back to the stream of their priority
+            let datagrams = sent();
+            for _ in 0..MSG_COUNT {
+                transport.schedule(message(60_000)).unwrap();
+                task::sleep(SLEEP_COUNT).await;
+            }
+            task::sleep(SLEEP).await;
+            assert_eq!(sent(), datagrams);
+
+            transport.close().timeout(TIMEOUT).await.unwrap().unwrap();
+            router_manager
+                .del_listener(&endpoint)
+                .timeout(TIMEOUT)
+                .await
+                .unwrap()
+                .unwrap();
+        });
+    }
+}
@@ -0,0 +1,91 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::*;
+use std::fmt;
+use std::str::FromStr;
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::properties::config::ConfigProperties;
+use janu_util::properties::Properties;
+use janu_util::{zerror, zerror2};
+
+#[allow(unreachable_patterns)]
+pub(super) fn get_serial_path(locator: &Locator) -> ZResult<String> {
+    match &locator.address {
+        LocatorAddress::Serial(path) => Ok(path.path.clone()),
+        _ => {
+            let e = format!("Not a Serial locator: {:?}", locator);
+            log::debug!("{}", e);
+            zerror!(ZErrorKind::InvalidLocator { descr: e })
+        }
+    }
+}
+
+pub(super) fn get_serial_baudrate(endpoint: &EndPoint) -> ZResult<u32> {
+    match endpoint
+        .config
+        .as_ref()
+        .and_then(|c| c.get(config::SERIAL_BAUDRATE))
+    {
+        Some(baudrate) => baudrate.parse().map_err(|_| {
+            let e = format!("Invalid Serial baud rate: {}", baudrate);
+            log::debug!("{}", e);
+            zerror2!(ZErrorKind::InvalidLocator { descr: e })
+        }),
+        None => Ok(*SERIAL_DEFAULT_BAUDRATE),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LocatorSerial {
+    pub(super) path: String,
+}
+
+impl LocatorSerial {
+    pub fn is_multicast(&self) -> bool {
+        false
+    }
+}
+
+impl FromStr for LocatorSerial {
+    type Err = ZError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            let e = format!("Invalid Serial locator: {:?}", s);
+            return zerror!(ZErrorKind::InvalidLocator { descr: e });
+        }
+        Ok(LocatorSerial {
+            path: s.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for LocatorSerial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path)?;
+        Ok(())
+    }
+}
+
+/*************************************/
+/*          LOCATOR CONFIG           */
+/*************************************/
+#[derive(Clone)]
+pub struct LocatorConfigSerial;
+
+impl LocatorConfigSerial {
+    pub fn from_config(_config: &ConfigProperties) -> ZResult<Option<Properties>> {
+        Ok(None)
+    }
+}
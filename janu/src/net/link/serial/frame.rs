@@ -0,0 +1,153 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+// Each frame on the serial line carries one batch followed by its CRC32
+// (IEEE 802.3, little endian). The payload and the CRC are COBS-encoded
+// (Consistent Overhead Byte Stuffing) so that the frame does not contain
+// any 0x00 byte, and the frame is terminated by a 0x00 delimiter. This allows
+// the receiver to resynchronize on the next delimiter after a corrupted or
+// truncated frame.
+pub(super) const FRAME_DELIMITER: u8 = 0x00;
+
+const CRC_SIZE: usize = 4;
+const COBS_MAX_BLOCK: u8 = 0xff;
+
+// The maximum size of an encoded frame carrying a payload of the given size,
+// frame delimiter included.
+pub(super) fn max_frame_size(payload: usize) -> usize {
+    let len = payload + CRC_SIZE;
+    len + len / (COBS_MAX_BLOCK as usize - 1) + 2
+}
+
+pub(super) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for b in data.iter() {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+// Append the encoded frame carrying the payload to the output buffer
+pub(super) fn encode(payload: &[u8], out: &mut Vec<u8>) {
+    let crc = crc32(payload).to_le_bytes();
+
+    let mut code_index = out.len();
+    let mut code: u8 = 1;
+    out.push(code);
+    for b in payload.iter().chain(crc.iter()) {
+        if *b != 0 {
+            out.push(*b);
+            code += 1;
+        }
+        if *b == 0 || code == COBS_MAX_BLOCK {
+            out[code_index] = code;
+            code_index = out.len();
+            code = 1;
+            out.push(code);
+        }
+    }
+    out[code_index] = code;
+    out.push(FRAME_DELIMITER);
+}
+
+// Decode a frame (without its delimiter) in the output buffer. Return false if
+// the frame is malformed or if the CRC does not match, in which case the
+// content of the output buffer is undefined.
+pub(super) fn decode(frame: &[u8], out: &mut Vec<u8>) -> bool {
+    out.clear();
+
+    let mut i = 0;
+    while i < frame.len() {
+        let code = frame[i] as usize;
+        if code == 0 || i + code > frame.len() {
+            return false;
+        }
+        out.extend_from_slice(&frame[i + 1..i + code]);
+        i += code;
+        if code < COBS_MAX_BLOCK as usize && i < frame.len() {
+            out.push(0);
+        }
+    }
+
+    if out.len() < CRC_SIZE {
+        return false;
+    }
+    let len = out.len() - CRC_SIZE;
+    let mut crc = [0u8; CRC_SIZE];
+    crc.copy_from_slice(&out[len..]);
+    if crc32(&out[..len]) != u32::from_le_bytes(crc) {
+        return false;
+    }
+    out.truncate(len);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 7) as u8 * (i % 3) as u8).collect()
+    }
+
+    #[test]
+    fn frame_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn frame_roundtrip() {
+        let mut frame = vec![];
+        let mut decoded = vec![];
+        for len in [0, 1, 2, 253, 254, 255, 256, 508, 1_024].iter() {
+            let payload = payload(*len);
+            frame.clear();
+            encode(&payload, &mut frame);
+            assert!(frame.len() <= max_frame_size(*len));
+            assert_eq!(frame.last(), Some(&FRAME_DELIMITER));
+            assert_eq!(frame.iter().filter(|b| **b == FRAME_DELIMITER).count(), 1);
+            assert!(decode(&frame[..frame.len() - 1], &mut decoded));
+            assert_eq!(decoded, payload);
+
+            // Without zeros, COBS blocks are always full
+            let payload = vec![0xab; *len];
+            frame.clear();
+            encode(&payload, &mut frame);
+            assert!(frame.len() <= max_frame_size(*len));
+            assert!(decode(&frame[..frame.len() - 1], &mut decoded));
+            assert_eq!(decoded, payload);
+        }
+    }
+
+    #[test]
+    fn frame_corrupted() {
+        let payload = payload(300);
+        let mut frame = vec![];
+        encode(&payload, &mut frame);
+        let frame = &frame[..frame.len() - 1];
+
+        let mut decoded = vec![];
+        for i in 0..frame.len() {
+            let mut corrupted = frame.to_vec();
+            corrupted[i] ^= 0x10;
+            assert!(!decode(&corrupted, &mut decoded));
+        }
+        assert!(!decode(&frame[..frame.len() / 2], &mut decoded));
+        assert!(!decode(&[], &mut decoded));
+    }
+}
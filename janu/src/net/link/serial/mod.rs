@@ -0,0 +1,41 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+mod endpoint;
+mod frame;
+mod unicast;
+
+use super::*;
+pub use endpoint::*;
+pub use unicast::*;
+
+// Maximum MTU (Serial frame payload) in bytes.
+// NOTE: Serial lines are slow and subject to transmission errors. Keeping
+//       the frames small limits the amount of data that is lost when a
+//       frame is corrupted and dropped by the receiver.
+const SERIAL_MAX_MTU: u16 = 1_024;
+
+zconfigurable! {
+    // Default MTU (Serial frame payload) in bytes.
+    static ref SERIAL_DEFAULT_MTU: u16 = SERIAL_MAX_MTU;
+    // Default baud rate of the serial line.
+    static ref SERIAL_DEFAULT_BAUDRATE: u32 = 115_200;
+    // Amount of time in microseconds to throttle the re-opening of the
+    // serial device of a listener upon an error. Default set to 100 ms.
+    static ref SERIAL_OPEN_THROTTLE_TIME: u64 = 100_000;
+}
+
+pub mod config {
+    // The baud rate of the serial line, e.g. serial//dev/ttyUSB0#baudrate=115200
+    pub const SERIAL_BAUDRATE: &str = "baudrate";
+}
@@ -0,0 +1,502 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::frame::{self, FRAME_DELIMITER};
+use super::*;
+use crate::net::transport::TransportManager;
+use async_io::Async;
+use async_std::prelude::*;
+use async_std::sync::Mutex as AsyncMutex;
+use async_std::task;
+use async_std::task::JoinHandle;
+use async_trait::async_trait;
+use event_listener::Event;
+use nix::sys::termios::{self, BaudRate, ControlFlags, FlushArg, SetArg};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::sync::Signal;
+use janu_util::{zasynclock, zerror, zerror2, zread, zwrite};
+
+const SERIAL_READ_BUFFER_SIZE: usize = 256;
+
+fn get_baudrate(baudrate: u32) -> ZResult<BaudRate> {
+    let b = match baudrate {
+        1_200 => BaudRate::B1200,
+        2_400 => BaudRate::B2400,
+        4_800 => BaudRate::B4800,
+        9_600 => BaudRate::B9600,
+        19_200 => BaudRate::B19200,
+        38_400 => BaudRate::B38400,
+        57_600 => BaudRate::B57600,
+        115_200 => BaudRate::B115200,
+        230_400 => BaudRate::B230400,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        460_800 => BaudRate::B460800,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        921_600 => BaudRate::B921600,
+        _ => {
+            let e = format!("Unsupported Serial baud rate: {}", baudrate);
+            log::warn!("{}", e);
+            return zerror!(ZErrorKind::InvalidLocator { descr: e });
+        }
+    };
+    Ok(b)
+}
+
+// Like a Signal, but a wait after the trigger returns immediately
+#[derive(Clone)]
+struct SerialClosed {
+    flag: Arc<AtomicBool>,
+    event: Arc<Event>,
+}
+
+impl SerialClosed {
+    fn new() -> SerialClosed {
+        SerialClosed {
+            flag: Arc::new(AtomicBool::new(false)),
+            event: Arc::new(Event::new()),
+        }
+    }
+
+    fn trigger(&self) {
+        self.flag.store(true, Ordering::Release);
+        self.event.notify(usize::MAX);
+    }
+
+    async fn wait(&self) {
+        while !self.flag.load(Ordering::Acquire) {
+            let listener = self.event.listen();
+            if self.flag.load(Ordering::Acquire) {
+                break;
+            }
+            listener.await;
+        }
+    }
+}
+
+struct SerialRx {
+    // The bytes received and not yet consumed
+    pending: Vec<u8>,
+    // The last decoded frame
+    frame: Vec<u8>,
+}
+
+pub struct LinkUnicastSerial {
+    // The serial device in non-blocking mode
+    device: Async<File>,
+    // The path of the serial device
+    path: String,
+    // The maximum size of an encoded frame
+    max_frame_size: usize,
+    // Make sure there are no concurrent writes and reuse the frame buffer
+    tx: AsyncMutex<Vec<u8>>,
+    // Make sure there are no concurrent reads and keep the partial frames
+    rx: AsyncMutex<SerialRx>,
+    // Signal the closure of the link to interrupt any pending read
+    closed: SerialClosed,
+}
+
+impl LinkUnicastSerial {
+    fn open(path: &str, baudrate: u32) -> ZResult<LinkUnicastSerial> {
+        let speed = get_baudrate(baudrate)?;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(nix::libc::O_NOCTTY)
+            .open(path)
+            .map_err(|e| {
+                let e = format!("Can not open Serial device {}: {}", path, e);
+                log::warn!("{}", e);
+                zerror2!(ZErrorKind::InvalidLink { descr: e })
+            })?;
+
+        // Configure the serial line in raw mode with the requested baud rate
+        let fd = file.as_raw_fd();
+        let res = termios::tcgetattr(fd).and_then(|mut attrs| {
+            termios::cfmakeraw(&mut attrs);
+            termios::cfsetspeed(&mut attrs, speed)?;
+            attrs.control_flags |= ControlFlags::CLOCAL | ControlFlags::CREAD;
+            termios::tcsetattr(fd, SetArg::TCSANOW, &attrs)?;
+            // Discard any stale data
+            termios::tcflush(fd, FlushArg::TCIOFLUSH)
+        });
+        res.map_err(|e| {
+            let e = format!("Can not configure Serial device {}: {}", path, e);
+            log::warn!("{}", e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+        let device = Async::new(file).map_err(|e| {
+            let e = format!("Can not open Serial device {}: {}", path, e);
+            log::warn!("{}", e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+        let max_frame_size = frame::max_frame_size(*SERIAL_DEFAULT_MTU as usize);
+        Ok(LinkUnicastSerial {
+            device,
+            path: path.to_string(),
+            max_frame_size,
+            tx: AsyncMutex::new(Vec::with_capacity(max_frame_size)),
+            rx: AsyncMutex::new(SerialRx {
+                pending: Vec::with_capacity(max_frame_size),
+                frame: Vec::with_capacity(max_frame_size),
+            }),
+            closed: SerialClosed::new(),
+        })
+    }
+
+    // Read the next valid frame, dropping any corrupted frame on the way
+    async fn read_frame(&self, buffer: &mut [u8]) -> ZResult<usize> {
+        let mut guard = zasynclock!(self.rx);
+        let rx = &mut *guard;
+        let mut chunk = [0u8; SERIAL_READ_BUFFER_SIZE];
+        loop {
+            while let Some(idx) = rx.pending.iter().position(|b| *b == FRAME_DELIMITER) {
+                let is_valid = idx > 0 && frame::decode(&rx.pending[..idx], &mut rx.frame);
+                rx.pending.drain(..=idx);
+                if is_valid && rx.frame.len() <= buffer.len() {
+                    buffer[..rx.frame.len()].copy_from_slice(&rx.frame);
+                    return Ok(rx.frame.len());
+                }
+                if idx > 0 {
+                    log::debug!("Dropping invalid frame on Serial link {}", self);
+                }
+            }
+            // Discard the garbage that can not be part of a valid frame
+            if rx.pending.len() > self.max_frame_size {
+                log::debug!("Dropping oversized frame on Serial link {}", self);
+                rx.pending.clear();
+            }
+
+            let read = async {
+                (&self.device).read(&mut chunk).await.map_err(|e| {
+                    let e = format!("Read error on Serial link {}: {}", self, e);
+                    log::trace!("{}", e);
+                    zerror2!(ZErrorKind::IoError { descr: e })
+                })
+            };
+            let closed = async {
+                self.closed.wait().await;
+                let e = format!("Serial link has been closed: {}", self);
+                zerror!(ZErrorKind::IoError { descr: e })
+            };
+            let n = read.race(closed).await?;
+            if n == 0 {
+                let e = format!("Serial device has been hung up: {}", self);
+                log::trace!("{}", e);
+                return zerror!(ZErrorKind::IoError { descr: e });
+            }
+            rx.pending.extend_from_slice(&chunk[..n]);
+        }
+    }
+}
+
+#[async_trait]
+impl LinkUnicastTrait for LinkUnicastSerial {
+    async fn close(&self) -> ZResult<()> {
+        log::trace!("Closing Serial link: {}", self);
+        self.closed.trigger();
+        Ok(())
+    }
+
+    async fn write(&self, buffer: &[u8]) -> ZResult<usize> {
+        self.write_all(buffer).await?;
+        Ok(buffer.len())
+    }
+
+    async fn write_all(&self, buffer: &[u8]) -> ZResult<()> {
+        let mut frame = zasynclock!(self.tx);
+        frame.clear();
+        frame::encode(buffer, &mut frame);
+        (&self.device).write_all(&frame).await.map_err(|e| {
+            let e = format!("Write error on Serial link {}: {}", self, e);
+            log::trace!("{}", e);
+            zerror2!(ZErrorKind::IoError { descr: e })
+        })
+    }
+
+    async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
+        self.read_frame(buffer).await
+    }
+
+    async fn read_exact(&self, buffer: &mut [u8]) -> ZResult<()> {
+        let n = self.read_frame(buffer).await?;
+        if n != buffer.len() {
+            let e = format!(
+                "Read error on Serial link {}: expected {} bytes, received {} bytes",
+                self,
+                buffer.len(),
+                n
+            );
+            log::trace!("{}", e);
+            return zerror!(ZErrorKind::IoError { descr: e });
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn get_src(&self) -> Locator {
+        Locator {
+            address: LocatorAddress::Serial(LocatorSerial {
+                path: self.path.clone(),
+            }),
+            metadata: None,
+        }
+    }
+
+    #[inline(always)]
+    fn get_dst(&self) -> Locator {
+        Locator {
+            address: LocatorAddress::Serial(LocatorSerial {
+                path: self.path.clone(),
+            }),
+            metadata: None,
+        }
+    }
+
+    #[inline(always)]
+    fn get_mtu(&self) -> u16 {
+        *SERIAL_DEFAULT_MTU
+    }
+
+    #[inline(always)]
+    fn is_reliable(&self) -> bool {
+        false
+    }
+
+    #[inline(always)]
+    fn is_streamed(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for LinkUnicastSerial {
+    fn drop(&mut self) {
+        // Notify the listener, if any, that the device is available again
+        self.closed.trigger();
+    }
+}
+
+impl fmt::Display for LinkUnicastSerial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", STR_SERIAL, PROTO_SEPARATOR, self.path)?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for LinkUnicastSerial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Serial")
+            .field("path", &self.path)
+            .field("mtu", &self.get_mtu())
+            .finish()
+    }
+}
+
+/*************************************/
+/*          LISTENER                 */
+/*************************************/
+struct ListenerSerial {
+    endpoint: EndPoint,
+    active: Arc<AtomicBool>,
+    signal: Signal,
+    handle: JoinHandle<ZResult<()>>,
+}
+
+impl ListenerSerial {
+    fn new(
+        endpoint: EndPoint,
+        active: Arc<AtomicBool>,
+        signal: Signal,
+        handle: JoinHandle<ZResult<()>>,
+    ) -> ListenerSerial {
+        ListenerSerial {
+            endpoint,
+            active,
+            signal,
+            handle,
+        }
+    }
+}
+
+pub struct LinkManagerUnicastSerial {
+    manager: TransportManager,
+    listeners: Arc<RwLock<HashMap<String, ListenerSerial>>>,
+}
+
+impl LinkManagerUnicastSerial {
+    pub(crate) fn new(manager: TransportManager) -> Self {
+        Self {
+            manager,
+            listeners: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl LinkManagerUnicastTrait for LinkManagerUnicastSerial {
+    async fn new_link(&self, endpoint: EndPoint) -> ZResult<LinkUnicast> {
+        let path = get_serial_path(&endpoint.locator)?;
+        let baudrate = get_serial_baudrate(&endpoint)?;
+
+        let link = Arc::new(LinkUnicastSerial::open(&path, baudrate)?);
+        Ok(LinkUnicast(link))
+    }
+
+    async fn new_listener(&self, endpoint: EndPoint) -> ZResult<Locator> {
+        let path = get_serial_path(&endpoint.locator)?;
+        let baudrate = get_serial_baudrate(&endpoint)?;
+
+        if zread!(self.listeners).contains_key(&path) {
+            let e = format!(
+                "Can not create a new Serial listener on {}: device already in use",
+                path
+            );
+            log::warn!("{}", e);
+            return zerror!(ZErrorKind::InvalidLink { descr: e });
+        }
+
+        // Open the device straight away to report any error to the caller
+        let link = LinkUnicastSerial::open(&path, baudrate)?;
+
+        // Spawn the listen loop for the device
+        let active = Arc::new(AtomicBool::new(true));
+        let signal = Signal::new();
+
+        let c_active = active.clone();
+        let c_signal = signal.clone();
+        let c_manager = self.manager.clone();
+        let c_listeners = self.listeners.clone();
+        let c_path = path.clone();
+        let handle = task::spawn(async move {
+            // Wait for the listen loop to terminate
+            let res = listen_task(link, baudrate, c_active, c_signal, c_manager).await;
+            zwrite!(c_listeners).remove(&c_path);
+            res
+        });
+
+        let locator = endpoint.locator.clone();
+        let listener = ListenerSerial::new(endpoint, active, signal, handle);
+        zwrite!(self.listeners).insert(path, listener);
+
+        Ok(locator)
+    }
+
+    async fn del_listener(&self, endpoint: &EndPoint) -> ZResult<()> {
+        let path = get_serial_path(&endpoint.locator)?;
+
+        // Stop the listener
+        let listener = zwrite!(self.listeners).remove(&path).ok_or_else(|| {
+            let e = format!(
+                "Can not delete the Serial listener because it has not been found: {}",
+                path
+            );
+            log::trace!("{}", e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+        // Send the stop signal
+        listener.active.store(false, Ordering::Release);
+        listener.signal.trigger();
+        listener.handle.await
+    }
+
+    fn get_listeners(&self) -> Vec<EndPoint> {
+        zread!(self.listeners)
+            .values()
+            .map(|x| x.endpoint.clone())
+            .collect()
+    }
+
+    fn get_locators(&self) -> Vec<Locator> {
+        zread!(self.listeners)
+            .values()
+            .map(|x| x.endpoint.locator.clone())
+            .collect()
+    }
+}
+
+// A serial line is a point-to-point link: the listener hands over the device
+// to the transport manager and waits for the link to be closed before
+// re-opening the device and waiting for the next peer.
+async fn listen_task(
+    link: LinkUnicastSerial,
+    baudrate: u32,
+    active: Arc<AtomicBool>,
+    signal: Signal,
+    manager: TransportManager,
+) -> ZResult<()> {
+    enum Action {
+        Closed,
+        Stop,
+    }
+
+    async fn closed(closed: SerialClosed) -> Action {
+        closed.wait().await;
+        Action::Closed
+    }
+
+    async fn stop(signal: Signal) -> Action {
+        signal.wait().await;
+        Action::Stop
+    }
+
+    let path = link.path.clone();
+    let mut next = Some(link);
+
+    log::trace!("Ready to accept Serial links on: {}", path);
+    while active.load(Ordering::Acquire) {
+        let link = match next.take() {
+            Some(link) => link,
+            None => match LinkUnicastSerial::open(&path, baudrate) {
+                Ok(link) => link,
+                Err(e) => {
+                    log::warn!("{}", e);
+                    // Throttle the open loop upon an error
+                    let throttle = async {
+                        task::sleep(Duration::from_micros(*SERIAL_OPEN_THROTTLE_TIME)).await;
+                        Action::Closed
+                    };
+                    match throttle.race(stop(signal.clone())).await {
+                        Action::Closed => continue,
+                        Action::Stop => break,
+                    }
+                }
+            },
+        };
+
+        let c_closed = link.closed.clone();
+
+        // Communicate the new link to the initial transport manager
+        manager
+            .handle_new_link_unicast(LinkUnicast(Arc::new(link)))
+            .await;
+
+        // Wait for the link to be closed before re-opening the device
+        match closed(c_closed).race(stop(signal.clone())).await {
+            Action::Closed => log::debug!("Serial link closed, re-opening: {}", path),
+            Action::Stop => break,
+        }
+    }
+
+    Ok(())
+}
@@ -12,6 +12,8 @@
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
 use super::*;
+use crate::net::link::tcp_socket::TcpSocketConfig;
+use crate::net::link::write_all_vectored;
 use crate::net::transport::TransportManager;
 use async_std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream};
 use async_std::prelude::*;
@@ -21,6 +23,7 @@ use async_trait::async_trait;
 use std::collections::HashMap;
 use std::convert::TryInto;
 use std::fmt;
+use std::io::IoSlice;
 use std::net::Shutdown;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
@@ -102,6 +105,16 @@ impl LinkUnicastTrait for LinkUnicastTcp {
         })
     }
 
+    async fn write_vectored(&self, buffers: &[IoSlice<'_>]) -> ZResult<()> {
+        write_all_vectored(&mut &self.socket, buffers)
+            .await
+            .map_err(|e| {
+                let e = format!("Write error on TCP link {}: {}", self, e);
+                log::trace!("{}", e);
+                zerror2!(ZErrorKind::IoError { descr: e })
+            })
+    }
+
     async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
         (&self.socket).read(buffer).await.map_err(|e| {
             let e = format!("Read error on TCP link {}: {}", self, e);
@@ -217,8 +230,9 @@ impl LinkManagerUnicastTcp {
 impl LinkManagerUnicastTrait for LinkManagerUnicastTcp {
     async fn new_link(&self, endpoint: EndPoint) -> ZResult<LinkUnicast> {
         let dst_addr = get_tcp_addr(&endpoint.locator.address).await?;
+        let config = TcpSocketConfig::from_endpoint(&endpoint)?;
 
-        let stream = TcpStream::connect(dst_addr).await.map_err(|e| {
+        let stream = config.connect(dst_addr).await.map_err(|e| {
             let e = format!("Can not create a new TCP link bound to {}: {}", dst_addr, e);
             zerror2!(ZErrorKind::Other { descr: e })
         })?;
@@ -240,9 +254,10 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTcp {
 
     async fn new_listener(&self, mut endpoint: EndPoint) -> ZResult<Locator> {
         let addr = get_tcp_addr(&endpoint.locator.address).await?;
+        let config = TcpSocketConfig::from_endpoint(&endpoint)?;
 
         // Bind the TCP socket
-        let socket = TcpListener::bind(addr).await.map_err(|e| {
+        let socket = config.listen(addr).map_err(|e| {
             let e = format!("Can not create a new TCP listener on {}: {}", addr, e);
             zerror2!(ZErrorKind::InvalidLink { descr: e })
         })?;
@@ -266,7 +281,7 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTcp {
         let c_addr = local_addr;
         let handle = task::spawn(async move {
             // Wait for the accept loop to terminate
-            let res = accept_task(socket, c_active, c_signal, c_manager).await;
+            let res = accept_task(socket, config, c_active, c_signal, c_manager).await;
             zwrite!(c_listeners).remove(&c_addr);
             res
         });
@@ -356,6 +371,7 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTcp {
 
 async fn accept_task(
     socket: TcpListener,
+    config: TcpSocketConfig,
     active: Arc<AtomicBool>,
     signal: Signal,
     manager: TransportManager,
@@ -407,6 +423,14 @@ async fn accept_task(
         };
 
         log::debug!("Accepted TCP connection on {:?}: {:?}", src_addr, dst_addr);
+        if let Err(e) = config.set_stream_options(&stream) {
+            log::warn!(
+                "Unable to set the socket options on TCP link {} => {}: {}",
+                src_addr,
+                dst_addr,
+                e
+            );
+        }
         // Create the new link object
         let link = Arc::new(LinkUnicastTcp::new(stream, src_addr, dst_addr));
 
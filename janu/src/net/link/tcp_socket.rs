@@ -0,0 +1,239 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::EndPoint;
+use async_io::Async;
+use async_std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use socket2::{Domain, SockRef, Socket, TcpKeepalive, Type};
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::time::Duration;
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::properties::Properties;
+use janu_util::zerror2;
+
+// The options of the TCP sockets of tcp/ and tls/ endpoints, e.g.:
+//   tcp/192.168.1.1:7447#keepalive=30;sndbuf=4194304;dscp=46;bind=192.168.1.2
+pub mod config {
+    // Idle time in seconds before sending the TCP keepalive probes
+    pub const TCP_KEEPALIVE: &str = "keepalive";
+    // Interval in seconds between the TCP keepalive probes (Linux only)
+    pub const TCP_KEEPALIVE_INTERVAL: &str = "keepalive_interval";
+    // Number of unanswered TCP keepalive probes before dropping the connection (Linux only)
+    pub const TCP_KEEPALIVE_RETRIES: &str = "keepalive_retries";
+    // Size in bytes of the socket send buffer
+    pub const TCP_SEND_BUFFER_SIZE: &str = "sndbuf";
+    // Size in bytes of the socket receive buffer
+    pub const TCP_RECV_BUFFER_SIZE: &str = "rcvbuf";
+    // DSCP marking (0-63) of the outgoing packets
+    pub const TCP_DSCP: &str = "dscp";
+    // Full TOS byte (0-255) of the outgoing packets, alternative to the DSCP marking
+    pub const TCP_TOS: &str = "tos";
+    // Name of the network interface the socket is bound to (Linux only)
+    pub const TCP_BIND_DEVICE: &str = "iface";
+    // Source address, with an optional port, of the outgoing connections
+    pub const TCP_BIND_ADDRESS: &str = "bind";
+}
+
+use config::*;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TcpSocketConfig {
+    keepalive: Option<Duration>,
+    keepalive_interval: Option<Duration>,
+    keepalive_retries: Option<u32>,
+    send_buffer_size: Option<usize>,
+    recv_buffer_size: Option<usize>,
+    tos: Option<u8>,
+    bind_device: Option<String>,
+    bind_address: Option<SocketAddr>,
+}
+
+fn get_config<T>(config: &Properties, key: &str) -> ZResult<Option<T>>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    match config.get(key) {
+        Some(value) => value.parse().map(Some).map_err(|e| {
+            let e = format!("Invalid TCP socket option {}={}: {}", key, value, e);
+            log::warn!("{}", e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        }),
+        None => Ok(None),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unsupported(key: &str) -> ZError {
+    let e = format!(
+        "TCP socket option {} is not supported on this platform",
+        key
+    );
+    log::warn!("{}", e);
+    zerror2!(ZErrorKind::InvalidLink { descr: e })
+}
+
+impl TcpSocketConfig {
+    pub(crate) fn from_endpoint(endpoint: &EndPoint) -> ZResult<TcpSocketConfig> {
+        let config = match endpoint.config.as_ref() {
+            Some(config) => config,
+            None => return Ok(TcpSocketConfig::default()),
+        };
+
+        let mut res = TcpSocketConfig {
+            keepalive: get_config(config, TCP_KEEPALIVE)?.map(Duration::from_secs),
+            keepalive_interval: get_config(config, TCP_KEEPALIVE_INTERVAL)?
+                .map(Duration::from_secs),
+            keepalive_retries: get_config(config, TCP_KEEPALIVE_RETRIES)?,
+            send_buffer_size: get_config(config, TCP_SEND_BUFFER_SIZE)?,
+            recv_buffer_size: get_config(config, TCP_RECV_BUFFER_SIZE)?,
+            tos: get_config(config, TCP_TOS)?,
+            bind_device: config.get(TCP_BIND_DEVICE).cloned(),
+            bind_address: None,
+        };
+
+        if let Some(dscp) = get_config::<u8>(config, TCP_DSCP)? {
+            if dscp > 63 || res.tos.is_some() {
+                let e = format!(
+                    "Invalid TCP socket option {}={}: expected a value between 0 and 63 and no {} option",
+                    TCP_DSCP, dscp, TCP_TOS
+                );
+                log::warn!("{}", e);
+                return Err(zerror2!(ZErrorKind::InvalidLink { descr: e }));
+            }
+            // The DSCP is carried by the 6 most significant bits of the TOS byte
+            res.tos = Some(dscp << 2);
+        }
+
+        if let Some(addr) = config.get(TCP_BIND_ADDRESS) {
+            // The port is optional, any port is used by default
+            res.bind_address = match addr.parse::<SocketAddr>() {
+                Ok(addr) => Some(addr),
+                Err(_) => {
+                    get_config::<IpAddr>(config, TCP_BIND_ADDRESS)?.map(|ip| SocketAddr::new(ip, 0))
+                }
+            };
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            if res.keepalive_interval.is_some() {
+                return Err(unsupported(TCP_KEEPALIVE_INTERVAL));
+            }
+            if res.keepalive_retries.is_some() {
+                return Err(unsupported(TCP_KEEPALIVE_RETRIES));
+            }
+            if res.bind_device.is_some() {
+                return Err(unsupported(TCP_BIND_DEVICE));
+            }
+        }
+
+        Ok(res)
+    }
+
+    fn is_keepalive(&self) -> bool {
+        self.keepalive.is_some()
+            || self.keepalive_interval.is_some()
+            || self.keepalive_retries.is_some()
+    }
+
+    // Options to be set before connecting, they are inherited by the sockets
+    // accepted by a listener.
+    fn set_socket_options(&self, socket: SockRef) -> io::Result<()> {
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(device) = self.bind_device.as_ref() {
+            socket.bind_device(Some(device.as_bytes()))?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn connect(&self, dst_addr: SocketAddr) -> io::Result<TcpStream> {
+        let stream = if self.bind_address.is_none()
+            && self.bind_device.is_none()
+            && self.send_buffer_size.is_none()
+            && self.recv_buffer_size.is_none()
+        {
+            TcpStream::connect(dst_addr).await?
+        } else {
+            // The socket needs to be configured before connecting, the connection
+            // is then established without blocking the executor
+            let socket = Socket::new(Domain::for_address(dst_addr), Type::STREAM, None)?;
+            self.set_socket_options(SockRef::from(&socket))?;
+            if let Some(src_addr) = self.bind_address {
+                socket.bind(&src_addr.into())?;
+            }
+            socket.set_nonblocking(true)?;
+            match socket.connect(&dst_addr.into()) {
+                Ok(()) => {}
+                #[cfg(unix)]
+                Err(e) if e.raw_os_error() == Some(libc::EINPROGRESS) => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+            let stream = Async::new(std::net::TcpStream::from(socket))?;
+            // The socket becomes writable once the connection is established or has failed
+            stream.writable().await?;
+            if let Some(e) = stream.get_ref().take_error()? {
+                return Err(e);
+            }
+            TcpStream::from(stream.into_inner()?)
+        };
+        self.set_stream_options(&stream)?;
+        Ok(stream)
+    }
+
+    // The socket options are set before binding and listening, so that they
+    // apply to the listening socket from the start.
+    pub(crate) fn listen(&self, addr: SocketAddr) -> io::Result<TcpListener> {
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, None)?;
+        // As done by the standard library on Unix platforms
+        #[cfg(unix)]
+        socket.set_reuse_address(true)?;
+        self.set_socket_options(SockRef::from(&socket))?;
+        socket.bind(&addr.into())?;
+        socket.listen(128)?;
+        Ok(TcpListener::from(std::net::TcpListener::from(socket)))
+    }
+
+    pub(crate) fn set_stream_options(&self, stream: &TcpStream) -> io::Result<()> {
+        if self.is_keepalive() {
+            #[allow(unused_mut)]
+            let mut keepalive = TcpKeepalive::new();
+            if let Some(time) = self.keepalive {
+                keepalive = keepalive.with_time(time);
+            }
+            #[cfg(target_os = "linux")]
+            if let Some(interval) = self.keepalive_interval {
+                keepalive = keepalive.with_interval(interval);
+            }
+            #[cfg(target_os = "linux")]
+            if let Some(retries) = self.keepalive_retries {
+                keepalive = keepalive.with_retries(retries);
+            }
+            SockRef::from(stream).set_tcp_keepalive(&keepalive)?;
+        }
+        if let Some(tos) = self.tos {
+            janu_util::net::set_tos(stream, tos)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        }
+        Ok(())
+    }
+}
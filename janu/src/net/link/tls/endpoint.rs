@@ -13,8 +13,9 @@
 //
 use super::config::*;
 use super::*;
-pub use async_rustls::rustls::*;
-pub use async_rustls::webpki::*;
+pub use futures_rustls::rustls::*;
+pub use futures_rustls::webpki;
+use futures_rustls::webpki::{DnsName, DnsNameRef};
 use async_std::net::{SocketAddr, ToSocketAddrs};
 use std::fmt;
 use std::str::FromStr;
@@ -51,7 +52,7 @@ pub(super) async fn get_tls_addr(address: &LocatorAddress) -> ZResult<SocketAddr
 }
 
 #[allow(unreachable_patterns)]
-pub(super) async fn get_tls_dns(address: &LocatorAddress) -> ZResult<DNSName> {
+pub(super) async fn get_tls_dns(address: &LocatorAddress) -> ZResult<DnsName> {
     match &address {
         LocatorAddress::Tls(addr) => match addr {
             LocatorTls::SocketAddr(addr) => {
@@ -64,7 +65,7 @@ pub(super) async fn get_tls_dns(address: &LocatorAddress) -> ZResult<DNSName> {
                 let split: Vec<&str> = addr.split(':').collect();
                 match split.get(0) {
                     Some(dom) => {
-                        let domain = DNSNameRef::try_from_ascii_str(dom).map_err(|e| {
+                        let domain = DnsNameRef::try_from_ascii_str(dom).map_err(|e| {
                             let e = e.to_string();
                             zerror2!(ZErrorKind::InvalidLocator { descr: e })
                         })?;
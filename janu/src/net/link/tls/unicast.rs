@@ -13,11 +13,9 @@
 //
 use super::config::*;
 use super::*;
+use crate::net::link::tcp_socket::TcpSocketConfig;
+use crate::net::link::write_all_vectored;
 use crate::net::transport::TransportManager;
-use async_rustls::rustls::internal::pemfile;
-pub use async_rustls::rustls::*;
-pub use async_rustls::webpki::*;
-use async_rustls::{TlsAcceptor, TlsConnector, TlsStream};
 use async_std::fs;
 use async_std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream};
 use async_std::prelude::*;
@@ -25,11 +23,13 @@ use async_std::sync::Mutex as AsyncMutex;
 use async_std::task;
 use async_std::task::JoinHandle;
 use async_trait::async_trait;
+pub use futures_rustls::rustls::*;
+use futures_rustls::{TlsAcceptor, TlsConnector, TlsStream};
 use std::cell::UnsafeCell;
 use std::collections::HashMap;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::fmt;
-use std::io::Cursor;
+use std::io::{Cursor, IoSlice};
 use std::net::Shutdown;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
@@ -39,7 +39,7 @@ use janu_util::sync::Signal;
 use janu_util::{zerror2, zread, zwrite};
 
 pub struct LinkUnicastTls {
-    // The underlying socket as returned from the async-rustls library
+    // The underlying socket as returned from the futures-rustls library
     // NOTE: TlsStream requires &mut for read and write operations. This means
     //       that concurrent reads and writes are not possible. To achieve that,
     //       we use an UnsafeCell for interior mutability. Using an UnsafeCell
@@ -151,6 +151,18 @@ impl LinkUnicastTrait for LinkUnicastTls {
         })
     }
 
+    async fn write_vectored(&self, buffers: &[IoSlice<'_>]) -> ZResult<()> {
+        let _guard = zasynclock!(self.write_mtx);
+        write_all_vectored(self.get_sock_mut(), buffers)
+            .await
+            .map_err(|e| {
+                log::trace!("Write error on TLS link {}: {}", self, e);
+                zerror2!(ZErrorKind::IoError {
+                    descr: e.to_string()
+                })
+            })
+    }
+
     async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
         let _guard = zasynclock!(self.read_mtx);
         self.get_sock_mut().read(buffer).await.map_err(|e| {
@@ -273,9 +285,10 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTls {
         let domain = get_tls_dns(&endpoint.locator.address).await?;
         let addr = get_tls_addr(&endpoint.locator.address).await?;
         let host: &str = domain.as_ref().into();
+        let socket_config = TcpSocketConfig::from_endpoint(&endpoint)?;
 
         // Initialize the TcpStream
-        let tcp_stream = TcpStream::connect(addr).await.map_err(|e| {
+        let tcp_stream = socket_config.connect(addr).await.map_err(|e| {
             let e = format!("Can not create a new TLS link bound to {}: {}", host, e);
             zerror2!(ZErrorKind::Other { descr: e })
         })?;
@@ -308,24 +321,33 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTls {
             None => vec![],
         };
 
-        let config = if bytes.is_empty() {
-            Arc::new(ClientConfig::new())
-        } else {
-            let mut cc = ClientConfig::new();
-            let _ = cc
-                .root_store
-                .add_pem_file(&mut Cursor::new(&bytes))
-                .map_err(|_| {
-                    zerror2!(ZErrorKind::Other {
-                        descr: "Invalid TLS CA certificate file".to_string()
-                    })
-                })?;
-            Arc::new(cc)
-        };
+        let mut root_cert_store = RootCertStore::empty();
+        if !bytes.is_empty() {
+            let certs = rustls_pemfile::certs(&mut Cursor::new(&bytes)).map_err(|e| {
+                zerror2!(ZErrorKind::Other {
+                    descr: format!("Invalid TLS CA certificate file: {}", e)
+                })
+            })?;
+            let (valid, _) = root_cert_store.add_parsable_certificates(&certs);
+            if valid == 0 {
+                return zerror!(ZErrorKind::Other {
+                    descr: "Invalid TLS CA certificate file: no valid certificate found"
+                        .to_string()
+                });
+            }
+        }
+        let config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(root_cert_store)
+            .with_no_client_auth();
 
-        let connector = TlsConnector::from(config);
+        let server_name = ServerName::try_from(host).map_err(|e| {
+            let e = format!("Can not create a new TLS link bound to {}: {}", host, e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+        let connector = TlsConnector::from(Arc::new(config));
         let tls_stream = connector
-            .connect(domain.as_ref(), tcp_stream)
+            .connect(server_name, tcp_stream)
             .await
             .map_err(|e| {
                 let e = format!("Can not create a new TLS link bound to {}: {}", host, e);
@@ -340,6 +362,7 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTls {
 
     async fn new_listener(&self, mut endpoint: EndPoint) -> ZResult<Locator> {
         let addr = get_tls_addr(&endpoint.locator.address).await?;
+        let socket_config = TcpSocketConfig::from_endpoint(&endpoint)?;
 
         // Verify there is a valid ServerConfig
         let config = endpoint.config.as_ref().ok_or_else(|| {
@@ -369,7 +392,8 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTls {
                 }
             },
         };
-        let mut keys = pemfile::rsa_private_keys(&mut Cursor::new(bytes.as_slice())).unwrap();
+        let mut keys =
+            rustls_pemfile::rsa_private_keys(&mut Cursor::new(bytes.as_slice())).unwrap();
 
         // Configure the server certificate
         let bytes = match config.get(TLS_SERVER_CERTIFICATE_RAW) {
@@ -390,13 +414,20 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTls {
                 }
             },
         };
-        let certs = pemfile::certs(&mut Cursor::new(bytes.as_slice())).unwrap();
+        let certs = rustls_pemfile::certs(&mut Cursor::new(bytes.as_slice()))
+            .unwrap()
+            .into_iter()
+            .map(Certificate)
+            .collect();
 
-        let mut sc = ServerConfig::new(NoClientAuth::new());
-        sc.set_single_cert(certs, keys.remove(0)).unwrap();
+        let sc = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(certs, PrivateKey(keys.remove(0)))
+            .unwrap();
 
         // Initialize the TcpListener
-        let socket = TcpListener::bind(addr).await.map_err(|e| {
+        let socket = socket_config.listen(addr).map_err(|e| {
             let e = format!("Can not create a new TLS listener on {}: {}", addr, e);
             zerror2!(ZErrorKind::InvalidLink { descr: e })
         })?;
@@ -422,7 +453,15 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTls {
         let c_addr = local_addr;
         let handle = task::spawn(async move {
             // Wait for the accept loop to terminate
-            let res = accept_task(socket, acceptor, c_active, c_signal, c_manager).await;
+            let res = accept_task(
+                socket,
+                socket_config,
+                acceptor,
+                c_active,
+                c_signal,
+                c_manager,
+            )
+            .await;
             zwrite!(c_listeners).remove(&c_addr);
             res
         });
@@ -512,6 +551,7 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTls {
 
 async fn accept_task(
     socket: TcpListener,
+    config: TcpSocketConfig,
     acceptor: TlsAcceptor,
     active: Arc<AtomicBool>,
     signal: Signal,
@@ -562,6 +602,14 @@ async fn accept_task(
                 continue;
             }
         };
+        if let Err(e) = config.set_stream_options(&tcp_stream) {
+            log::warn!(
+                "Unable to set the socket options on TLS link {} => {}: {}",
+                src_addr,
+                dst_addr,
+                e
+            );
+        }
         // Accept the TLS connection
         let tls_stream = match acceptor.accept(tcp_stream).await {
             Ok(stream) => TlsStream::Server(stream),
@@ -54,6 +54,9 @@ zconfigurable! {
     // Amount of time in microseconds to throttle the accept loop upon an error.
     // Default set to 100 ms.
     static ref UDP_ACCEPT_THROTTLE_TIME: u64 = 100_000;
+    // Maximum number of datagrams the accept loop and the connected links receive at once.
+    // The datagrams are received in a single system call on Linux, and one at a time otherwise.
+    static ref UDP_RECV_BATCH_SIZE: usize = 16;
 }
 
 pub mod config {
@@ -19,7 +19,7 @@ use async_std::sync::Mutex as AsyncMutex;
 use async_std::task;
 use async_std::task::JoinHandle;
 use async_trait::async_trait;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, RwLock, Weak};
@@ -32,18 +32,55 @@ use janu_util::{zasynclock, zerror};
 type LinkHashMap = Arc<Mutex<HashMap<(SocketAddr, SocketAddr), Weak<LinkUnicastUdpUnconnected>>>>;
 type LinkInput = (RecyclingObject<Box<[u8]>>, usize);
 type LinkLeftOver = (RecyclingObject<Box<[u8]>>, usize, usize);
+type LinkBufferPool = RecyclingObjectPool<Box<[u8]>, fn() -> Box<[u8]>>;
 
 struct LinkUnicastUdpConnected {
     socket: Arc<UdpSocket>,
+    pool: LinkBufferPool,
+    received: AsyncMutex<VecDeque<LinkInput>>,
 }
 
 impl LinkUnicastUdpConnected {
+    fn new(socket: UdpSocket) -> LinkUnicastUdpConnected {
+        // Buffers for the datagrams received in a batch beyond the first one
+        let pool = RecyclingObjectPool::new(
+            UDP_RECV_BATCH_SIZE.saturating_sub(1),
+            (|| vec![0u8; UDP_MAX_MTU as usize].into_boxed_slice()) as fn() -> Box<[u8]>,
+        );
+        LinkUnicastUdpConnected {
+            socket: Arc::new(socket),
+            pool,
+            received: AsyncMutex::new(VecDeque::new()),
+        }
+    }
+
     async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
-        (&self.socket).recv(buffer).await.map_err(|e| {
+        let mut guard = zasynclock!(self.received);
+        // Return the datagrams received in a previous batch first
+        if let Some((slice, len)) = guard.pop_front() {
+            let len = len.min(buffer.len());
+            buffer[0..len].copy_from_slice(&slice[0..len]);
+            return Ok(len);
+        }
+
+        let n = (&self.socket).recv(buffer).await.map_err(|e| {
             zerror2!(ZErrorKind::IoError {
                 descr: e.to_string()
             })
-        })
+        })?;
+
+        // Receive at once the other datagrams already queued on the socket, if any
+        let mut buffs: Vec<RecyclingObject<Box<[u8]>>> =
+            std::iter::from_fn(|| self.pool.try_take()).collect();
+        if !buffs.is_empty() {
+            let mut slices: Vec<&mut [u8]> = buffs.iter_mut().map(|b| &mut b[..]).collect();
+            match janu_util::net::try_recv_batch_from(&self.socket, &mut slices) {
+                Ok(res) => guard.extend(buffs.drain(..).zip(res).map(|(b, (n, _))| (b, n))),
+                Err(e) => log::trace!("Can not receive UDP datagrams: {}", e),
+            }
+        }
+
+        Ok(n)
     }
 
     async fn write(&self, buffer: &[u8]) -> ZResult<usize> {
@@ -317,9 +354,7 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastUdp {
         let link = Arc::new(LinkUnicastUdp::new(
             src_addr,
             dst_addr,
-            LinkUnicastUdpVariant::Connected(LinkUnicastUdpConnected {
-                socket: Arc::new(socket),
-            }),
+            LinkUnicastUdpVariant::Connected(LinkUnicastUdpConnected::new(socket)),
         ));
 
         Ok(LinkUnicast(link))
@@ -497,7 +532,9 @@ async fn accept_read_task(
 
     log::trace!("Ready to accept UDP connections on: {:?}", src_addr);
     // Buffers for deserialization
-    let pool = RecyclingObjectPool::new(1, || vec![0u8; UDP_MAX_MTU as usize].into_boxed_slice());
+    let pool = RecyclingObjectPool::new(*UDP_RECV_BATCH_SIZE, || {
+        vec![0u8; UDP_MAX_MTU as usize].into_boxed_slice()
+    });
     while active.load(Ordering::Acquire) {
         let mut buff = pool.take().await;
         // Wait for incoming connections
@@ -522,38 +559,53 @@ async fn accept_read_task(
             }
         };
 
-        let link = loop {
-            let res = zgetlink!(src_addr, dst_addr);
-            match res {
-                Some(link) => break link.upgrade(),
-                None => {
-                    // A new peers has sent data to this socket
-                    log::debug!("Accepted UDP connection on {}: {}", src_addr, dst_addr);
-                    let unconnected = Arc::new(LinkUnicastUdpUnconnected {
-                        socket: Arc::downgrade(&socket),
-                        links: links.clone(),
-                        input: Mvar::new(),
-                        leftover: AsyncMutex::new(None),
-                    });
-                    zaddlink!(src_addr, dst_addr, Arc::downgrade(&unconnected));
-                    // Create the new link object
-                    let link = Arc::new(LinkUnicastUdp::new(
-                        src_addr,
-                        dst_addr,
-                        LinkUnicastUdpVariant::Unconnected(unconnected),
-                    ));
-                    // Add the new link to the set of connected peers
-                    manager.handle_new_link_unicast(LinkUnicast(link)).await;
-                }
-            }
-        };
+        let mut received = vec![(buff, n, dst_addr)];
 
-        match link {
-            Some(link) => {
-                link.received(buff, n).await;
+        // Receive at once the other datagrams already queued on the socket, if any
+        let mut buffs: Vec<RecyclingObject<Box<[u8]>>> =
+            std::iter::from_fn(|| pool.try_take()).collect();
+        if !buffs.is_empty() {
+            let mut slices: Vec<&mut [u8]> = buffs.iter_mut().map(|b| &mut b[..]).collect();
+            match janu_util::net::try_recv_batch_from(&socket, &mut slices) {
+                Ok(res) => received.extend(buffs.drain(..).zip(res).map(|(b, (n, a))| (b, n, a))),
+                Err(e) => log::trace!("Can not receive UDP datagrams on {}: {}", src_addr, e),
             }
-            None => {
-                zdellink!(src_addr, dst_addr);
+        }
+
+        for (buff, n, dst_addr) in received.drain(..) {
+            let link = loop {
+                let res = zgetlink!(src_addr, dst_addr);
+                match res {
+                    Some(link) => break link.upgrade(),
+                    None => {
+                        // A new peers has sent data to this socket
+                        log::debug!("Accepted UDP connection on {}: {}", src_addr, dst_addr);
+                        let unconnected = Arc::new(LinkUnicastUdpUnconnected {
+                            socket: Arc::downgrade(&socket),
+                            links: links.clone(),
+                            input: Mvar::new(),
+                            leftover: AsyncMutex::new(None),
+                        });
+                        zaddlink!(src_addr, dst_addr, Arc::downgrade(&unconnected));
+                        // Create the new link object
+                        let link = Arc::new(LinkUnicastUdp::new(
+                            src_addr,
+                            dst_addr,
+                            LinkUnicastUdpVariant::Unconnected(unconnected),
+                        ));
+                        // Add the new link to the set of connected peers
+                        manager.handle_new_link_unicast(LinkUnicast(link)).await;
+                    }
+                }
+            };
+
+            match link {
+                Some(link) => {
+                    link.received(buff, n).await;
+                }
+                None => {
+                    zdellink!(src_addr, dst_addr);
+                }
             }
         }
     }
@@ -0,0 +1,491 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::socket::{self, UnixSocket, UnixSocketFile};
+use super::*;
+use crate::net::transport::TransportManager;
+use async_io::Async;
+use async_std::prelude::*;
+use async_std::task;
+use async_std::task::JoinHandle;
+use async_trait::async_trait;
+use nix::sys::socket::{SockAddr, SockType, UnixAddr};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::Duration;
+use uuid::Uuid;
+use janu_util::collections::{RecyclingObject, RecyclingObjectPool};
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::sync::{Mvar, Signal};
+use janu_util::{zerror, zerror2, zlock, zread, zwrite};
+
+type LinkHashMap = Arc<Mutex<HashMap<String, Weak<LinkUnicastUnixSocketDgramUnconnected>>>>;
+type LinkInput = (RecyclingObject<Box<[u8]>>, usize);
+
+struct LinkUnicastUnixSocketDgramConnected {
+    socket: UnixSocket,
+    // The socket file bound by the link, removed once the link is dropped
+    _file: UnixSocketFile,
+}
+
+impl LinkUnicastUnixSocketDgramConnected {
+    async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
+        socket::recv(&self.socket, buffer).await.map_err(|e| {
+            zerror2!(ZErrorKind::IoError {
+                descr: e.to_string()
+            })
+        })
+    }
+
+    async fn write(&self, buffer: &[u8]) -> ZResult<usize> {
+        socket::send(&self.socket, buffer).await.map_err(|e| {
+            zerror2!(ZErrorKind::IoError {
+                descr: e.to_string()
+            })
+        })
+    }
+}
+
+struct LinkUnicastUnixSocketDgramUnconnected {
+    socket: Weak<UnixSocket>,
+    links: LinkHashMap,
+    input: Mvar<LinkInput>,
+    dst_addr: SockAddr,
+}
+
+impl LinkUnicastUnixSocketDgramUnconnected {
+    async fn received(&self, buffer: RecyclingObject<Box<[u8]>>, len: usize) {
+        self.input.put((buffer, len)).await;
+    }
+
+    async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
+        let (slice, len) = self.input.take().await;
+        if len > buffer.len() {
+            slice.recycle().await;
+            return zerror!(ZErrorKind::IoError {
+                descr: format!(
+                    "{} bytes do not fit in a buffer of {} bytes",
+                    len,
+                    buffer.len()
+                )
+            });
+        }
+        buffer[..len].copy_from_slice(&slice[..len]);
+        slice.recycle().await;
+        Ok(len)
+    }
+
+    async fn write(&self, buffer: &[u8]) -> ZResult<usize> {
+        match self.socket.upgrade() {
+            Some(socket) => socket::send_to(&socket, buffer, &self.dst_addr)
+                .await
+                .map_err(|e| {
+                    zerror2!(ZErrorKind::IoError {
+                        descr: e.to_string()
+                    })
+                }),
+            None => zerror!(ZErrorKind::IoError {
+                descr: "UnixSocketDgram listener has been dropped".to_string()
+            }),
+        }
+    }
+
+    fn close(&self, dst: &LocatorUnixSocketPacket) {
+        // Delete the link from the list of links
+        zlock!(self.links).remove(&dst.addr);
+    }
+}
+
+enum LinkUnicastUnixSocketDgramVariant {
+    Connected(LinkUnicastUnixSocketDgramConnected),
+    Unconnected(Arc<LinkUnicastUnixSocketDgramUnconnected>),
+}
+
+pub struct LinkUnicastUnixSocketDgram {
+    // The address of the local socket
+    src: LocatorUnixSocketPacket,
+    // The address of the remote socket
+    dst: LocatorUnixSocketPacket,
+    // The socket is connected to the peer (client side) or shared with the
+    // other links of a listener (listener side)
+    variant: LinkUnicastUnixSocketDgramVariant,
+}
+
+#[async_trait]
+impl LinkUnicastTrait for LinkUnicastUnixSocketDgram {
+    async fn close(&self) -> ZResult<()> {
+        log::trace!("Closing UnixSocketDgram link: {}", self);
+        if let LinkUnicastUnixSocketDgramVariant::Unconnected(link) = &self.variant {
+            link.close(&self.dst);
+        }
+        Ok(())
+    }
+
+    async fn write(&self, buffer: &[u8]) -> ZResult<usize> {
+        match &self.variant {
+            LinkUnicastUnixSocketDgramVariant::Connected(link) => link.write(buffer).await,
+            LinkUnicastUnixSocketDgramVariant::Unconnected(link) => link.write(buffer).await,
+        }
+    }
+
+    async fn write_all(&self, buffer: &[u8]) -> ZResult<()> {
+        // A datagram is either entirely sent or not sent at all
+        let n = self.write(buffer).await?;
+        if n != buffer.len() {
+            let e = format!(
+                "Write error on UnixSocketDgram link {}: {} bytes written out of {}",
+                self,
+                n,
+                buffer.len()
+            );
+            log::trace!("{}", e);
+            return zerror!(ZErrorKind::IoError { descr: e });
+        }
+        Ok(())
+    }
+
+    async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
+        match &self.variant {
+            LinkUnicastUnixSocketDgramVariant::Connected(link) => link.read(buffer).await,
+            LinkUnicastUnixSocketDgramVariant::Unconnected(link) => link.read(buffer).await,
+        }
+    }
+
+    async fn read_exact(&self, buffer: &mut [u8]) -> ZResult<()> {
+        let n = self.read(buffer).await?;
+        if n != buffer.len() {
+            let e = format!(
+                "Read error on UnixSocketDgram link {}: expected {} bytes, received {} bytes",
+                self,
+                buffer.len(),
+                n
+            );
+            log::trace!("{}", e);
+            return zerror!(ZErrorKind::IoError { descr: e });
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn get_src(&self) -> Locator {
+        Locator {
+            address: LocatorAddress::UnixSocketDgram(self.src.clone()),
+            metadata: None,
+        }
+    }
+
+    #[inline(always)]
+    fn get_dst(&self) -> Locator {
+        Locator {
+            address: LocatorAddress::UnixSocketDgram(self.dst.clone()),
+            metadata: None,
+        }
+    }
+
+    #[inline(always)]
+    fn get_mtu(&self) -> u16 {
+        *UNIXSOCKPACKET_DEFAULT_MTU
+    }
+
+    #[inline(always)]
+    fn is_reliable(&self) -> bool {
+        // Unix domain datagrams are neither lost nor reordered
+        true
+    }
+
+    #[inline(always)]
+    fn is_streamed(&self) -> bool {
+        false
+    }
+}
+
+impl fmt::Display for LinkUnicastUnixSocketDgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} => {}", self.src, self.dst)?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for LinkUnicastUnixSocketDgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnixSocketDgram")
+            .field("src", &self.src)
+            .field("dst", &self.dst)
+            .finish()
+    }
+}
+
+/*************************************/
+/*          LISTENER                 */
+/*************************************/
+struct ListenerUnixSocketDgram {
+    endpoint: EndPoint,
+    active: Arc<AtomicBool>,
+    signal: Signal,
+    handle: JoinHandle<ZResult<()>>,
+}
+
+impl ListenerUnixSocketDgram {
+    fn new(
+        endpoint: EndPoint,
+        active: Arc<AtomicBool>,
+        signal: Signal,
+        handle: JoinHandle<ZResult<()>>,
+    ) -> ListenerUnixSocketDgram {
+        ListenerUnixSocketDgram {
+            endpoint,
+            active,
+            signal,
+            handle,
+        }
+    }
+}
+
+pub struct LinkManagerUnicastUnixSocketDgram {
+    manager: TransportManager,
+    listeners: Arc<RwLock<HashMap<String, ListenerUnixSocketDgram>>>,
+}
+
+impl LinkManagerUnicastUnixSocketDgram {
+    pub(crate) fn new(manager: TransportManager) -> Self {
+        Self {
+            manager,
+            listeners: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl LinkManagerUnicastTrait for LinkManagerUnicastUnixSocketDgram {
+    async fn new_link(&self, endpoint: EndPoint) -> ZResult<LinkUnicast> {
+        let dst = get_unix_packet_addr(&endpoint.locator)?;
+        let dst_addr = socket::unix_addr(&dst)?;
+
+        macro_rules! zlinkerror {
+            ($err:expr) => {{
+                let e = format!(
+                    "Can not create a new UnixSocketDgram link bound to {}: {}",
+                    dst, $err
+                );
+                log::warn!("{}", e);
+                zerror2!(ZErrorKind::InvalidLink { descr: e })
+            }};
+        }
+
+        // The socket needs an address for the listener to reply: an abstract
+        // name assigned by the kernel, or a file next to the listener one.
+        let socket = socket::new_socket(SockType::Datagram).map_err(|e| zlinkerror!(e))?;
+        let (src_addr, file) = match dst.path() {
+            Some(path) => {
+                let path = format!("{}.{}", path, Uuid::new_v4());
+                let addr = UnixAddr::new(path.as_str()).map_err(|e| zlinkerror!(e))?;
+                (SockAddr::Unix(addr), UnixSocketFile::new(Some(path)))
+            }
+            None => {
+                let addr = UnixAddr::new("").map_err(|e| zlinkerror!(e))?;
+                (SockAddr::Unix(addr), UnixSocketFile::new(None))
+            }
+        };
+        socket::bind(&socket, &src_addr).map_err(|e| zlinkerror!(e))?;
+        socket::connect(&socket, &dst_addr).map_err(|e| zlinkerror!(e))?;
+
+        let src_addr = socket::local_addr(&socket).map_err(|e| zlinkerror!(e))?;
+        let src = socket::unix_locator(&src_addr)
+            .ok_or_else(|| zlinkerror!("the local socket is unnamed"))?;
+        let socket = Async::new(socket).map_err(|e| zlinkerror!(e))?;
+
+        let link = Arc::new(LinkUnicastUnixSocketDgram {
+            src,
+            dst,
+            variant: LinkUnicastUnixSocketDgramVariant::Connected(
+                LinkUnicastUnixSocketDgramConnected {
+                    socket,
+                    _file: file,
+                },
+            ),
+        });
+
+        Ok(LinkUnicast(link))
+    }
+
+    async fn new_listener(&self, endpoint: EndPoint) -> ZResult<Locator> {
+        let addr = get_unix_packet_addr(&endpoint.locator)?;
+
+        let socket = socket::new_socket(SockType::Datagram).map_err(|e| {
+            let e = format!(
+                "Can not create a new UnixSocketDgram listener on {}: {}",
+                addr, e
+            );
+            log::warn!("{}", e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+        let file = socket::bind_listener(&socket, &addr, endpoint.config.as_deref())?;
+        let socket = Async::new(socket).map_err(|e| {
+            let e = format!(
+                "Can not create a new UnixSocketDgram listener on {}: {}",
+                addr, e
+            );
+            log::warn!("{}", e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+        // Spawn the accept loop for the listener
+        let active = Arc::new(AtomicBool::new(true));
+        let signal = Signal::new();
+
+        let c_active = active.clone();
+        let c_signal = signal.clone();
+        let c_manager = self.manager.clone();
+        let c_listeners = self.listeners.clone();
+        let c_addr = addr.clone();
+        let handle = task::spawn(async move {
+            // Wait for the accept loop to terminate
+            let res = accept_read_task(socket, c_addr.clone(), c_active, c_signal, c_manager).await;
+            zwrite!(c_listeners).remove(&c_addr.addr);
+            // Remove the socket file once the socket is closed
+            drop(file);
+            res
+        });
+
+        let locator = endpoint.locator.clone();
+        let listener = ListenerUnixSocketDgram::new(endpoint, active, signal, handle);
+        zwrite!(self.listeners).insert(addr.addr, listener);
+
+        Ok(locator)
+    }
+
+    async fn del_listener(&self, endpoint: &EndPoint) -> ZResult<()> {
+        let addr = get_unix_packet_addr(&endpoint.locator)?;
+
+        // Stop the listener
+        let listener = zwrite!(self.listeners).remove(&addr.addr).ok_or_else(|| {
+            let e = format!(
+                "Can not delete the UnixSocketDgram listener because it has not been found: {}",
+                addr
+            );
+            log::trace!("{}", e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+        // Send the stop signal
+        listener.active.store(false, Ordering::Release);
+        listener.signal.trigger();
+        listener.handle.await
+    }
+
+    fn get_listeners(&self) -> Vec<EndPoint> {
+        zread!(self.listeners)
+            .values()
+            .map(|x| x.endpoint.clone())
+            .collect()
+    }
+
+    fn get_locators(&self) -> Vec<Locator> {
+        zread!(self.listeners)
+            .values()
+            .map(|x| x.endpoint.locator.clone())
+            .collect()
+    }
+}
+
+async fn accept_read_task(
+    socket: UnixSocket,
+    src: LocatorUnixSocketPacket,
+    active: Arc<AtomicBool>,
+    signal: Signal,
+    manager: TransportManager,
+) -> ZResult<()> {
+    let socket = Arc::new(socket);
+    let links: LinkHashMap = Arc::new(Mutex::new(HashMap::new()));
+
+    enum Action {
+        Receive((usize, Option<SockAddr>)),
+        Stop,
+    }
+
+    async fn receive(socket: Arc<UnixSocket>, buffer: &mut [u8]) -> ZResult<Action> {
+        let res = socket::recv_from(&socket, buffer).await.map_err(|e| {
+            zerror2!(ZErrorKind::IoError {
+                descr: e.to_string()
+            })
+        })?;
+        Ok(Action::Receive(res))
+    }
+
+    async fn stop(signal: Signal) -> ZResult<Action> {
+        signal.wait().await;
+        Ok(Action::Stop)
+    }
+
+    log::trace!("Ready to accept UnixSocketDgram connections on: {}", src);
+    // Buffers for deserialization
+    let pool = RecyclingObjectPool::new(1, || {
+        vec![0u8; UNIXSOCKPACKET_MAX_MTU as usize].into_boxed_slice()
+    });
+    while active.load(Ordering::Acquire) {
+        let mut buff = pool.take().await;
+        // Wait for incoming datagrams
+        let (n, dst_addr) = match receive(socket.clone(), &mut buff)
+            .race(stop(signal.clone()))
+            .await
+        {
+            Ok(Action::Receive(res)) => res,
+            Ok(Action::Stop) => break,
+            Err(e) => {
+                log::warn!("{}", e);
+                // Throttle the accept loop upon an error
+                task::sleep(Duration::from_micros(*UNIXSOCKPACKET_ACCEPT_THROTTLE_TIME)).await;
+                continue;
+            }
+        };
+
+        // Datagrams from unnamed sockets can not be replied to
+        let (dst, dst_addr) = match dst_addr.as_ref().and_then(socket::unix_locator) {
+            Some(dst) => (dst, dst_addr.unwrap()),
+            None => {
+                log::debug!("Dropping datagram from unnamed socket on: {}", src);
+                continue;
+            }
+        };
+
+        let link = zlock!(links).get(&dst.addr).and_then(|link| link.upgrade());
+        let link = match link {
+            Some(link) => link,
+            None => {
+                // A new peer has sent data to this socket
+                log::debug!("Accepted UnixSocketDgram connection on {}: {}", src, dst);
+                let unconnected = Arc::new(LinkUnicastUnixSocketDgramUnconnected {
+                    socket: Arc::downgrade(&socket),
+                    links: links.clone(),
+                    input: Mvar::new(),
+                    dst_addr,
+                });
+                zlock!(links).insert(dst.addr.clone(), Arc::downgrade(&unconnected));
+                // Create the new link object
+                let link = Arc::new(LinkUnicastUnixSocketDgram {
+                    src: src.clone(),
+                    dst,
+                    variant: LinkUnicastUnixSocketDgramVariant::Unconnected(unconnected.clone()),
+                });
+                // Add the new link to the set of connected peers
+                manager.handle_new_link_unicast(LinkUnicast(link)).await;
+                unconnected
+            }
+        };
+        link.received(buff, n).await;
+    }
+
+    Ok(())
+}
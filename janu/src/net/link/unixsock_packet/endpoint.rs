@@ -0,0 +1,98 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::*;
+use std::fmt;
+use std::str::FromStr;
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::properties::config::ConfigProperties;
+use janu_util::properties::Properties;
+use janu_util::zerror;
+
+// The prefix of the addresses in the abstract namespace
+pub(super) const ABSTRACT_PREFIX: char = '@';
+
+#[allow(unreachable_patterns)]
+pub(super) fn get_unix_packet_addr(locator: &Locator) -> ZResult<LocatorUnixSocketPacket> {
+    match &locator.address {
+        #[cfg(feature = "transport_unixsock-dgram")]
+        LocatorAddress::UnixSocketDgram(addr) => Ok(addr.clone()),
+        #[cfg(feature = "transport_unixsock-seqpacket")]
+        LocatorAddress::UnixSocketSeqpacket(addr) => Ok(addr.clone()),
+        _ => {
+            let e = format!(
+                "Not a UnixSocketDgram or UnixSocketSeqpacket locator: {:?}",
+                locator
+            );
+            log::debug!("{}", e);
+            zerror!(ZErrorKind::InvalidLocator { descr: e })
+        }
+    }
+}
+
+/// The address of a Unix domain socket preserving the message boundaries:
+/// either a filesystem path or, when prefixed by `@`, a name in the abstract namespace.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LocatorUnixSocketPacket {
+    pub(super) addr: String,
+}
+
+impl LocatorUnixSocketPacket {
+    pub fn is_multicast(&self) -> bool {
+        false
+    }
+
+    pub fn abstract_name(&self) -> Option<&str> {
+        self.addr.strip_prefix(ABSTRACT_PREFIX)
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        match self.abstract_name() {
+            Some(_) => None,
+            None => Some(&self.addr),
+        }
+    }
+}
+
+impl FromStr for LocatorUnixSocketPacket {
+    type Err = ZError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s.len() == ABSTRACT_PREFIX.len_utf8() && s.starts_with(ABSTRACT_PREFIX) {
+            let e = format!("Invalid Unix domain socket locator: {:?}", s);
+            return zerror!(ZErrorKind::InvalidLocator { descr: e });
+        }
+        Ok(LocatorUnixSocketPacket {
+            addr: s.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for LocatorUnixSocketPacket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.addr)?;
+        Ok(())
+    }
+}
+
+/*************************************/
+/*          LOCATOR CONFIG           */
+/*************************************/
+#[derive(Clone)]
+pub struct LocatorConfigUnixSocketPacket;
+
+impl LocatorConfigUnixSocketPacket {
+    pub fn from_config(_config: &ConfigProperties) -> ZResult<Option<Properties>> {
+        Ok(None)
+    }
+}
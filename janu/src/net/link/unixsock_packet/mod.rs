@@ -0,0 +1,52 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+#[cfg(feature = "transport_unixsock-dgram")]
+mod dgram;
+mod endpoint;
+#[cfg(feature = "transport_unixsock-seqpacket")]
+mod seqpacket;
+mod socket;
+
+use super::*;
+#[cfg(feature = "transport_unixsock-dgram")]
+pub use dgram::*;
+pub use endpoint::*;
+#[cfg(feature = "transport_unixsock-seqpacket")]
+pub use seqpacket::*;
+
+// Default MTU (UnixSocketDgram and UnixSocketSeqpacket PDU) in bytes.
+// NOTE: Since these sockets preserve the message boundaries, each batch is sent
+//       as a single message and the MTU is only limited by the socket buffer
+//       size. However, the usage of 16 bits in Janu to encode the batch size
+//       constrains the MTU to 2^16 - 1 bytes (i.e., 65535).
+const UNIXSOCKPACKET_MAX_MTU: u16 = u16::MAX;
+
+zconfigurable! {
+    // Default MTU (UnixSocketDgram and UnixSocketSeqpacket PDU) in bytes.
+    static ref UNIXSOCKPACKET_DEFAULT_MTU: u16 = UNIXSOCKPACKET_MAX_MTU;
+    // The maximum number of pending connections on a UnixSocketSeqpacket listener.
+    static ref UNIXSOCKPACKET_LISTEN_BACKLOG: usize = 128;
+    // Amount of time in microseconds to throttle the accept loop upon an error.
+    // Default set to 100 ms.
+    static ref UNIXSOCKPACKET_ACCEPT_THROTTLE_TIME: u64 = 100_000;
+}
+
+pub mod config {
+    // The permissions in octal of the socket file of a listener, e.g. #mode=660
+    pub const UNIXSOCK_MODE: &str = "mode";
+    // The owner (user name or uid) of the socket file of a listener
+    pub const UNIXSOCK_OWNER: &str = "owner";
+    // The group (group name or gid) of the socket file of a listener
+    pub const UNIXSOCK_GROUP: &str = "group";
+}
@@ -0,0 +1,377 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::socket::{self, UnixSocket};
+use super::*;
+use crate::net::transport::TransportManager;
+use async_io::Async;
+use async_std::prelude::*;
+use async_std::task;
+use async_std::task::JoinHandle;
+use async_trait::async_trait;
+use nix::sys::socket::SockType;
+use std::collections::HashMap;
+use std::fmt;
+use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use uuid::Uuid;
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::sync::Signal;
+use janu_util::{zerror, zerror2, zread, zwrite};
+
+pub struct LinkUnicastUnixSocketSeqpacket {
+    // The underlying socket in non-blocking mode
+    socket: UnixSocket,
+    // The address of the local socket
+    src: LocatorUnixSocketPacket,
+    // The address of the remote socket (random UUIDv4 if unnamed)
+    dst: LocatorUnixSocketPacket,
+}
+
+impl LinkUnicastUnixSocketSeqpacket {
+    fn new(
+        socket: UnixSocket,
+        src: LocatorUnixSocketPacket,
+        dst: LocatorUnixSocketPacket,
+    ) -> LinkUnicastUnixSocketSeqpacket {
+        LinkUnicastUnixSocketSeqpacket { socket, src, dst }
+    }
+}
+
+#[async_trait]
+impl LinkUnicastTrait for LinkUnicastUnixSocketSeqpacket {
+    async fn close(&self) -> ZResult<()> {
+        log::trace!("Closing UnixSocketSeqpacket link: {}", self);
+        // Close the underlying UnixSocketSeqpacket socket
+        let res = socket::shutdown(&self.socket);
+        log::trace!("UnixSocketSeqpacket link shutdown {}: {:?}", self, res);
+        res.map_err(|e| {
+            zerror2!(ZErrorKind::IoError {
+                descr: e.to_string()
+            })
+        })
+    }
+
+    async fn write(&self, buffer: &[u8]) -> ZResult<usize> {
+        socket::send(&self.socket, buffer).await.map_err(|e| {
+            let e = format!("Write error on UnixSocketSeqpacket link {}: {}", self, e);
+            log::trace!("{}", e);
+            zerror2!(ZErrorKind::IoError { descr: e })
+        })
+    }
+
+    async fn write_all(&self, buffer: &[u8]) -> ZResult<()> {
+        // A message is either entirely sent or not sent at all
+        let n = self.write(buffer).await?;
+        if n != buffer.len() {
+            let e = format!(
+                "Write error on UnixSocketSeqpacket link {}: {} bytes written out of {}",
+                self,
+                n,
+                buffer.len()
+            );
+            log::trace!("{}", e);
+            return zerror!(ZErrorKind::IoError { descr: e });
+        }
+        Ok(())
+    }
+
+    async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
+        socket::recv(&self.socket, buffer).await.map_err(|e| {
+            let e = format!("Read error on UnixSocketSeqpacket link {}: {}", self, e);
+            log::trace!("{}", e);
+            zerror2!(ZErrorKind::IoError { descr: e })
+        })
+    }
+
+    async fn read_exact(&self, buffer: &mut [u8]) -> ZResult<()> {
+        let n = self.read(buffer).await?;
+        if n != buffer.len() {
+            let e = format!(
+                "Read error on UnixSocketSeqpacket link {}: expected {} bytes, received {} bytes",
+                self,
+                buffer.len(),
+                n
+            );
+            log::trace!("{}", e);
+            return zerror!(ZErrorKind::IoError { descr: e });
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn get_src(&self) -> Locator {
+        Locator {
+            address: LocatorAddress::UnixSocketSeqpacket(self.src.clone()),
+            metadata: None,
+        }
+    }
+
+    #[inline(always)]
+    fn get_dst(&self) -> Locator {
+        Locator {
+            address: LocatorAddress::UnixSocketSeqpacket(self.dst.clone()),
+            metadata: None,
+        }
+    }
+
+    #[inline(always)]
+    fn get_mtu(&self) -> u16 {
+        *UNIXSOCKPACKET_DEFAULT_MTU
+    }
+
+    #[inline(always)]
+    fn is_reliable(&self) -> bool {
+        true
+    }
+
+    #[inline(always)]
+    fn is_streamed(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for LinkUnicastUnixSocketSeqpacket {
+    fn drop(&mut self) {
+        // Close the underlying UnixSocketSeqpacket socket
+        let _ = socket::shutdown(&self.socket);
+    }
+}
+
+impl fmt::Display for LinkUnicastUnixSocketSeqpacket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} => {}", self.src, self.dst)?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for LinkUnicastUnixSocketSeqpacket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnixSocketSeqpacket")
+            .field("src", &self.src)
+            .field("dst", &self.dst)
+            .finish()
+    }
+}
+
+/*************************************/
+/*          LISTENER                 */
+/*************************************/
+struct ListenerUnixSocketSeqpacket {
+    endpoint: EndPoint,
+    active: Arc<AtomicBool>,
+    signal: Signal,
+    handle: JoinHandle<ZResult<()>>,
+}
+
+impl ListenerUnixSocketSeqpacket {
+    fn new(
+        endpoint: EndPoint,
+        active: Arc<AtomicBool>,
+        signal: Signal,
+        handle: JoinHandle<ZResult<()>>,
+    ) -> ListenerUnixSocketSeqpacket {
+        ListenerUnixSocketSeqpacket {
+            endpoint,
+            active,
+            signal,
+            handle,
+        }
+    }
+}
+
+pub struct LinkManagerUnicastUnixSocketSeqpacket {
+    manager: TransportManager,
+    listeners: Arc<RwLock<HashMap<String, ListenerUnixSocketSeqpacket>>>,
+}
+
+impl LinkManagerUnicastUnixSocketSeqpacket {
+    pub(crate) fn new(manager: TransportManager) -> Self {
+        Self {
+            manager,
+            listeners: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl LinkManagerUnicastTrait for LinkManagerUnicastUnixSocketSeqpacket {
+    async fn new_link(&self, endpoint: EndPoint) -> ZResult<LinkUnicast> {
+        let dst = get_unix_packet_addr(&endpoint.locator)?;
+        let dst_addr = socket::unix_addr(&dst)?;
+
+        macro_rules! zlinkerror {
+            ($err:expr) => {{
+                let e = format!(
+                    "Can not create a new UnixSocketSeqpacket link bound to {}: {}",
+                    dst, $err
+                );
+                log::warn!("{}", e);
+                zerror2!(ZErrorKind::InvalidLink { descr: e })
+            }};
+        }
+
+        let socket = socket::new_socket(SockType::SeqPacket).map_err(|e| zlinkerror!(e))?;
+        socket::connect(&socket, &dst_addr).map_err(|e| zlinkerror!(e))?;
+        let socket = Async::new(socket).map_err(|e| zlinkerror!(e))?;
+
+        // The client socket is unnamed, identify it with a random UUIDv4
+        let src = LocatorUnixSocketPacket {
+            addr: format!("{}", Uuid::new_v4()),
+        };
+        let link = Arc::new(LinkUnicastUnixSocketSeqpacket::new(socket, src, dst));
+
+        Ok(LinkUnicast(link))
+    }
+
+    async fn new_listener(&self, endpoint: EndPoint) -> ZResult<Locator> {
+        let addr = get_unix_packet_addr(&endpoint.locator)?;
+
+        macro_rules! zlistenerror {
+            ($err:expr) => {{
+                let e = format!(
+                    "Can not create a new UnixSocketSeqpacket listener on {}: {}",
+                    addr, $err
+                );
+                log::warn!("{}", e);
+                zerror2!(ZErrorKind::InvalidLink { descr: e })
+            }};
+        }
+
+        let socket = socket::new_socket(SockType::SeqPacket).map_err(|e| zlistenerror!(e))?;
+        let file = socket::bind_listener(&socket, &addr, endpoint.config.as_deref())?;
+        nix::sys::socket::listen(socket.as_raw_fd(), *UNIXSOCKPACKET_LISTEN_BACKLOG)
+            .map_err(|e| zlistenerror!(e))?;
+        let socket = Async::new(socket).map_err(|e| zlistenerror!(e))?;
+
+        // Spawn the accept loop for the listener
+        let active = Arc::new(AtomicBool::new(true));
+        let signal = Signal::new();
+
+        let c_active = active.clone();
+        let c_signal = signal.clone();
+        let c_manager = self.manager.clone();
+        let c_listeners = self.listeners.clone();
+        let c_addr = addr.clone();
+        let handle = task::spawn(async move {
+            // Wait for the accept loop to terminate
+            let res = accept_task(socket, c_addr.clone(), c_active, c_signal, c_manager).await;
+            zwrite!(c_listeners).remove(&c_addr.addr);
+            // Remove the socket file once the socket is closed
+            drop(file);
+            res
+        });
+
+        let locator = endpoint.locator.clone();
+        let listener = ListenerUnixSocketSeqpacket::new(endpoint, active, signal, handle);
+        zwrite!(self.listeners).insert(addr.addr, listener);
+
+        Ok(locator)
+    }
+
+    async fn del_listener(&self, endpoint: &EndPoint) -> ZResult<()> {
+        let addr = get_unix_packet_addr(&endpoint.locator)?;
+
+        // Stop the listener
+        let listener = zwrite!(self.listeners).remove(&addr.addr).ok_or_else(|| {
+            let e = format!(
+                "Can not delete the UnixSocketSeqpacket listener because it has not been found: {}",
+                addr
+            );
+            log::trace!("{}", e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+        // Send the stop signal
+        listener.active.store(false, Ordering::Release);
+        listener.signal.trigger();
+        listener.handle.await
+    }
+
+    fn get_listeners(&self) -> Vec<EndPoint> {
+        zread!(self.listeners)
+            .values()
+            .map(|x| x.endpoint.clone())
+            .collect()
+    }
+
+    fn get_locators(&self) -> Vec<Locator> {
+        zread!(self.listeners)
+            .values()
+            .map(|x| x.endpoint.locator.clone())
+            .collect()
+    }
+}
+
+async fn accept_task(
+    socket: UnixSocket,
+    src: LocatorUnixSocketPacket,
+    active: Arc<AtomicBool>,
+    signal: Signal,
+    manager: TransportManager,
+) -> ZResult<()> {
+    enum Action {
+        Accept(UnixSocket),
+        Stop,
+    }
+
+    async fn accept(socket: &UnixSocket) -> ZResult<Action> {
+        let res = socket::accept(socket).await.map_err(|e| {
+            zerror2!(ZErrorKind::IoError {
+                descr: e.to_string()
+            })
+        })?;
+        Ok(Action::Accept(res))
+    }
+
+    async fn stop(signal: Signal) -> ZResult<Action> {
+        signal.wait().await;
+        Ok(Action::Stop)
+    }
+
+    log::trace!("Ready to accept UnixSocketSeqpacket connections on: {}", src);
+    while active.load(Ordering::Acquire) {
+        // Wait for incoming connections
+        let stream = match accept(&socket).race(stop(signal.clone())).await {
+            Ok(action) => match action {
+                Action::Accept(stream) => stream,
+                Action::Stop => break,
+            },
+            Err(e) => {
+                log::warn!("{}. Hint: increase the system open file limit.", e);
+                // Throttle the accept loop upon an error
+                // NOTE: This might be due to various factors. However, the most common case is that
+                //       the process has reached the maximum number of open files in the system. On
+                //       Linux systems this limit can be changed by using the "ulimit" command line
+                //       tool. In case of systemd-based systems, this can be changed by using the
+                //       "sysctl" command line tool.
+                task::sleep(Duration::from_micros(*UNIXSOCKPACKET_ACCEPT_THROTTLE_TIME)).await;
+                continue;
+            }
+        };
+
+        // The connecting socket is unnamed, identify it with a random UUIDv4
+        let dst = LocatorUnixSocketPacket {
+            addr: format!("{}", Uuid::new_v4()),
+        };
+        log::debug!("Accepted UnixSocketSeqpacket connection on {}: {}", src, dst);
+        let link = Arc::new(LinkUnicastUnixSocketSeqpacket::new(stream, src.clone(), dst));
+
+        // Communicate the new link to the initial transport manager
+        manager.handle_new_link_unicast(LinkUnicast(link)).await;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,321 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::config::*;
+use super::*;
+use async_io::Async;
+use nix::fcntl::{FlockArg, OFlag};
+use nix::sys::socket::{self, AddressFamily, MsgFlags, SockAddr, SockFlag, SockType, UnixAddr};
+use nix::sys::stat::{fchmod, Mode};
+use nix::unistd::{Gid, Group, Uid, User};
+use socket2::Socket;
+use std::fs::{remove_file, set_permissions, Permissions};
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::path::Path;
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::properties::Properties;
+use janu_util::zerror2;
+
+// A Unix domain socket in non-blocking mode
+pub(super) type UnixSocket = Async<Socket>;
+
+pub(super) fn unix_addr(addr: &LocatorUnixSocketPacket) -> ZResult<SockAddr> {
+    let res = match addr.abstract_name() {
+        Some(name) => UnixAddr::new_abstract(name.as_bytes()),
+        None => UnixAddr::new(addr.addr.as_str()),
+    };
+    res.map(SockAddr::Unix).map_err(|e| {
+        let e = format!("Invalid Unix domain socket address {}: {}", addr, e);
+        log::warn!("{}", e);
+        zerror2!(ZErrorKind::InvalidLocator { descr: e })
+    })
+}
+
+// The locator of a socket address, None if the socket is unnamed
+pub(super) fn unix_locator(addr: &SockAddr) -> Option<LocatorUnixSocketPacket> {
+    match addr {
+        SockAddr::Unix(addr) => match addr.as_abstract() {
+            Some(name) => Some(LocatorUnixSocketPacket {
+                addr: format!("{}{}", ABSTRACT_PREFIX, String::from_utf8_lossy(name)),
+            }),
+            None => addr
+                .path()
+                .and_then(|p| p.to_str())
+                .filter(|p| !p.is_empty())
+                .map(|p| LocatorUnixSocketPacket {
+                    addr: p.to_string(),
+                }),
+        },
+        _ => None,
+    }
+}
+
+pub(super) fn new_socket(ty: SockType) -> io::Result<Socket> {
+    let fd = socket::socket(AddressFamily::Unix, ty, SockFlag::SOCK_CLOEXEC, None)?;
+    // Safety: the socket takes the ownership of the newly created file descriptor
+    Ok(unsafe { Socket::from_raw_fd(fd) })
+}
+
+pub(super) fn bind(socket: &Socket, addr: &SockAddr) -> io::Result<()> {
+    socket::bind(socket.as_raw_fd(), addr)?;
+    Ok(())
+}
+
+pub(super) fn connect(socket: &Socket, addr: &SockAddr) -> io::Result<()> {
+    socket::connect(socket.as_raw_fd(), addr)?;
+    Ok(())
+}
+
+pub(super) fn local_addr(socket: &Socket) -> io::Result<SockAddr> {
+    Ok(socket::getsockname(socket.as_raw_fd())?)
+}
+
+pub(super) fn shutdown(socket: &UnixSocket) -> io::Result<()> {
+    socket::shutdown(socket.as_raw_fd(), socket::Shutdown::Both)?;
+    Ok(())
+}
+
+pub(super) async fn recv(socket: &UnixSocket, buffer: &mut [u8]) -> io::Result<usize> {
+    socket
+        .read_with(|s| Ok(socket::recv(s.as_raw_fd(), buffer, MsgFlags::empty())?))
+        .await
+}
+
+pub(super) async fn recv_from(
+    socket: &UnixSocket,
+    buffer: &mut [u8],
+) -> io::Result<(usize, Option<SockAddr>)> {
+    socket
+        .read_with(|s| Ok(socket::recvfrom(s.as_raw_fd(), buffer)?))
+        .await
+}
+
+pub(super) async fn send(socket: &UnixSocket, buffer: &[u8]) -> io::Result<usize> {
+    socket
+        .write_with(|s| Ok(socket::send(s.as_raw_fd(), buffer, MsgFlags::empty())?))
+        .await
+}
+
+pub(super) async fn send_to(
+    socket: &UnixSocket,
+    buffer: &[u8],
+    addr: &SockAddr,
+) -> io::Result<usize> {
+    socket
+        .write_with(|s| {
+            Ok(socket::sendto(
+                s.as_raw_fd(),
+                buffer,
+                addr,
+                MsgFlags::empty(),
+            )?)
+        })
+        .await
+}
+
+pub(super) async fn accept(socket: &UnixSocket) -> io::Result<UnixSocket> {
+    let fd = socket
+        .read_with(|s| Ok(socket::accept4(s.as_raw_fd(), SockFlag::SOCK_CLOEXEC)?))
+        .await?;
+    // Safety: the socket takes the ownership of the newly accepted file descriptor
+    Async::new(unsafe { Socket::from_raw_fd(fd) })
+}
+
+/*************************************/
+/*          SOCKET FILE              */
+/*************************************/
+// The socket file bound by a socket, if any. The socket file, and the lock
+// file of a listener, are removed when dropped.
+pub(super) struct UnixSocketFile {
+    path: Option<String>,
+    lock_fd: Option<RawFd>,
+}
+
+impl UnixSocketFile {
+    pub(super) fn new(path: Option<String>) -> UnixSocketFile {
+        UnixSocketFile {
+            path,
+            lock_fd: None,
+        }
+    }
+}
+
+impl Drop for UnixSocketFile {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.as_ref() {
+            let _ = remove_file(path);
+            if let Some(lock_fd) = self.lock_fd.take() {
+                // Release the lock
+                let _ = nix::fcntl::flock(lock_fd, FlockArg::UnlockNonblock);
+                let _ = nix::unistd::close(lock_fd);
+                let _ = remove_file(format!("{}.lock", path));
+            }
+        }
+    }
+}
+
+// Bind the socket of a listener and apply the permissions of the endpoint
+// configuration to its socket file, if any.
+pub(super) fn bind_listener(
+    socket: &Socket,
+    addr: &LocatorUnixSocketPacket,
+    config: Option<&Properties>,
+) -> ZResult<UnixSocketFile> {
+    let sockaddr = unix_addr(addr)?;
+    let path = match addr.path() {
+        Some(path) => path,
+        None => {
+            // Sockets in the abstract namespace have no file nor permissions
+            bind(socket, &sockaddr).map_err(|e| {
+                let e = format!("Can not create a new listener on {}: {}", addr, e);
+                log::warn!("{}", e);
+                zerror2!(ZErrorKind::InvalidLink { descr: e })
+            })?;
+            return Ok(UnixSocketFile::new(None));
+        }
+    };
+
+    // Check the permissions before creating any file
+    let permissions = match config {
+        Some(config) => FilePermissions::from_config(path, config)?,
+        None => FilePermissions::default(),
+    };
+
+    // As for the UnixSocketStream listeners, a lock file tells if the socket
+    // file is still in use by another process or if it can be replaced.
+    let lock_file_path = format!("{}.lock", path);
+    let lock_fd = nix::fcntl::open(
+        Path::new(&lock_file_path),
+        OFlag::O_CREAT | OFlag::O_RDONLY,
+        Mode::S_IRUSR | Mode::S_IWUSR,
+    )
+    .map_err(|e| {
+        let e = format!(
+            "Can not create a new listener on {} - Unable to open lock file: {}",
+            path, e
+        );
+        log::warn!("{}", e);
+        zerror2!(ZErrorKind::InvalidLink { descr: e })
+    })?;
+    nix::fcntl::flock(lock_fd, FlockArg::LockExclusiveNonblock).map_err(|e| {
+        let _ = nix::unistd::close(lock_fd);
+        let e = format!(
+            "Can not create a new listener on {} - Unable to acquire lock: {}",
+            path, e
+        );
+        log::warn!("{}", e);
+        zerror2!(ZErrorKind::InvalidLink { descr: e })
+    })?;
+    // The lock is acquired, the socket file can be removed if it exists
+    let _ = remove_file(path);
+
+    let file = UnixSocketFile {
+        path: Some(path.to_string()),
+        lock_fd: Some(lock_fd),
+    };
+    // On Linux, the socket file is created with the mode of the socket less the
+    // umask. Keep only the owner permissions until the ownership is set, so that
+    // the file is never more permissive than configured.
+    if let Some(mode) = permissions.mode {
+        fchmod(socket.as_raw_fd(), Mode::from_bits_truncate(mode & 0o700)).map_err(|e| {
+            let e = format!("Can not create a new listener on {}: {}", path, e);
+            log::warn!("{}", e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+    }
+    bind(socket, &sockaddr).map_err(|e| {
+        let e = format!("Can not create a new listener on {}: {}", path, e);
+        log::warn!("{}", e);
+        zerror2!(ZErrorKind::InvalidLink { descr: e })
+    })?;
+    permissions.apply(path)?;
+    Ok(file)
+}
+
+macro_rules! zconfigerror {
+    ($path:expr, $key:expr, $value:expr, $err:expr) => {{
+        let e = format!(
+            "Can not set the {} of {} to {}: {}",
+            $key, $path, $value, $err
+        );
+        log::warn!("{}", e);
+        zerror2!(ZErrorKind::InvalidLink { descr: e })
+    }};
+}
+
+// The permissions of a socket file in the endpoint configuration
+#[derive(Default)]
+struct FilePermissions {
+    mode: Option<u32>,
+    owner: Option<Uid>,
+    group: Option<Gid>,
+}
+
+impl FilePermissions {
+    fn from_config(path: &str, config: &Properties) -> ZResult<FilePermissions> {
+        let mode = match config.get(UNIXSOCK_MODE) {
+            Some(mode) => Some(
+                u32::from_str_radix(mode, 8)
+                    .map_err(|e| zconfigerror!(path, UNIXSOCK_MODE, mode, e))?,
+            ),
+            None => None,
+        };
+
+        let owner = match config.get(UNIXSOCK_OWNER) {
+            Some(owner) => match owner.parse::<u32>() {
+                Ok(uid) => Some(Uid::from_raw(uid)),
+                Err(_) => match User::from_name(owner) {
+                    Ok(Some(user)) => Some(user.uid),
+                    Ok(None) => {
+                        return Err(zconfigerror!(path, UNIXSOCK_OWNER, owner, "unknown user"))
+                    }
+                    Err(e) => return Err(zconfigerror!(path, UNIXSOCK_OWNER, owner, e)),
+                },
+            },
+            None => None,
+        };
+        let group = match config.get(UNIXSOCK_GROUP) {
+            Some(group) => match group.parse::<u32>() {
+                Ok(gid) => Some(Gid::from_raw(gid)),
+                Err(_) => match Group::from_name(group) {
+                    Ok(Some(group)) => Some(group.gid),
+                    Ok(None) => {
+                        return Err(zconfigerror!(path, UNIXSOCK_GROUP, group, "unknown group"))
+                    }
+                    Err(e) => return Err(zconfigerror!(path, UNIXSOCK_GROUP, group, e)),
+                },
+            },
+            None => None,
+        };
+
+        Ok(FilePermissions { mode, owner, group })
+    }
+
+    // The ownership is set before the mode, which may grant permissions to
+    // the new owner or group
+    fn apply(&self, path: &str) -> ZResult<()> {
+        if self.owner.is_some() || self.group.is_some() {
+            nix::unistd::chown(path, self.owner, self.group).map_err(|e| {
+                let value = format!("{:?}:{:?}", self.owner, self.group);
+                zconfigerror!(path, "ownership", value, e)
+            })?;
+        }
+        if let Some(mode) = self.mode {
+            set_permissions(path, Permissions::from_mode(mode))
+                .map_err(|e| zconfigerror!(path, UNIXSOCK_MODE, format!("{:o}", mode), e))?;
+        }
+        Ok(())
+    }
+}
@@ -12,6 +12,7 @@
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
 use super::*;
+use crate::net::link::write_all_vectored;
 use crate::net::transport::TransportManager;
 use async_std::os::unix::net::{UnixListener, UnixStream};
 use async_std::path::PathBuf;
@@ -22,6 +23,7 @@ use async_trait::async_trait;
 use std::collections::HashMap;
 use std::fmt;
 use std::fs::remove_file;
+use std::io::IoSlice;
 use std::net::Shutdown;
 use std::os::unix::io::RawFd;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -81,6 +83,16 @@ impl LinkUnicastTrait for LinkUnicastUnixSocketStream {
         })
     }
 
+    async fn write_vectored(&self, buffers: &[IoSlice<'_>]) -> ZResult<()> {
+        write_all_vectored(&mut &self.socket, buffers)
+            .await
+            .map_err(|e| {
+                let e = format!("Write error on UnixSocketStream link {}: {}", self, e);
+                log::trace!("{}", e);
+                zerror2!(ZErrorKind::IoError { descr: e })
+            })
+    }
+
     async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
         (&self.socket).read(buffer).await.map_err(|e| {
             let e = format!("Read error on UnixSocketStream link {}: {}", self, e);
@@ -0,0 +1,160 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::config::*;
+use super::*;
+use async_std::net::{SocketAddr, ToSocketAddrs};
+use futures_rustls::webpki::{DnsName, DnsNameRef};
+use std::fmt;
+use std::str::FromStr;
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::properties::config::*;
+use janu_util::properties::Properties;
+use janu_util::{zerror, zerror2};
+
+#[allow(unreachable_patterns)]
+pub(super) fn get_ws_locator(address: &LocatorAddress) -> ZResult<&LocatorWs> {
+    match address {
+        LocatorAddress::Ws(addr) | LocatorAddress::Wss(addr) => Ok(addr),
+        _ => {
+            let e = format!("Not a WebSocket locator address: {}", address);
+            zerror!(ZErrorKind::InvalidLocator { descr: e })
+        }
+    }
+}
+
+pub(super) async fn get_ws_addr(address: &LocatorAddress) -> ZResult<SocketAddr> {
+    match get_ws_locator(address)? {
+        LocatorWs::SocketAddr(addr) => Ok(*addr),
+        LocatorWs::DnsName(addr) => match addr.to_socket_addrs().await {
+            Ok(mut addr_iter) => {
+                if let Some(addr) = addr_iter.next() {
+                    Ok(addr)
+                } else {
+                    let e = format!("Couldn't resolve WebSocket locator address: {}", addr);
+                    zerror!(ZErrorKind::InvalidLocator { descr: e })
+                }
+            }
+            Err(e) => {
+                let e = format!("{}: {}", e, addr);
+                zerror!(ZErrorKind::InvalidLocator { descr: e })
+            }
+        },
+    }
+}
+
+pub(super) fn get_ws_dns(address: &LocatorAddress) -> ZResult<DnsName> {
+    match get_ws_locator(address)? {
+        LocatorWs::SocketAddr(addr) => {
+            let e = format!("Couldn't get domain from SocketAddr: {}", addr);
+            zerror!(ZErrorKind::InvalidLocator { descr: e })
+        }
+        LocatorWs::DnsName(addr) => {
+            // Separate the domain from the port.
+            // E.g. janu.io:7447 returns (janu.io, 7447).
+            let split: Vec<&str> = addr.split(':').collect();
+            match split.first() {
+                Some(dom) => {
+                    let domain = DnsNameRef::try_from_ascii_str(dom).map_err(|e| {
+                        let e = e.to_string();
+                        zerror2!(ZErrorKind::InvalidLocator { descr: e })
+                    })?;
+                    Ok(domain.to_owned())
+                }
+                None => {
+                    let e = format!("Couldn't get domain for: {}", addr);
+                    zerror!(ZErrorKind::InvalidLocator { descr: e })
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LocatorWs {
+    SocketAddr(SocketAddr),
+    DnsName(String),
+}
+
+impl LocatorWs {
+    pub fn is_multicast(&self) -> bool {
+        false
+    }
+}
+
+impl FromStr for LocatorWs {
+    type Err = ZError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse() {
+            Ok(addr) => Ok(LocatorWs::SocketAddr(addr)),
+            Err(_) => Ok(LocatorWs::DnsName(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for LocatorWs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LocatorWs::SocketAddr(addr) => write!(f, "{}", addr)?,
+            LocatorWs::DnsName(addr) => write!(f, "{}", addr)?,
+        }
+        Ok(())
+    }
+}
+
+/*************************************/
+/*          LOCATOR CONFIG           */
+/*************************************/
+#[derive(Clone)]
+pub struct LocatorConfigWs;
+
+impl LocatorConfigWs {
+    pub fn from_config(_config: &ConfigProperties) -> ZResult<Option<Properties>> {
+        Ok(None)
+    }
+}
+
+#[derive(Clone)]
+pub struct LocatorConfigWss;
+
+impl LocatorConfigWss {
+    pub fn from_config(config: &ConfigProperties) -> ZResult<Option<Properties>> {
+        let mut properties = Properties::default();
+
+        if let Some(tls_ca_certificate) = config.get(&ZN_TLS_ROOT_CA_CERTIFICATE_KEY) {
+            properties.insert(
+                WSS_ROOT_CA_CERTIFICATE_FILE.into(),
+                tls_ca_certificate.into(),
+            );
+        }
+        if let Some(tls_server_private_key) = config.get(&ZN_TLS_SERVER_PRIVATE_KEY_KEY) {
+            properties.insert(
+                WSS_SERVER_PRIVATE_KEY_FILE.into(),
+                tls_server_private_key.into(),
+            );
+        }
+        if let Some(tls_server_certificate) = config.get(&ZN_TLS_SERVER_CERTIFICATE_KEY) {
+            properties.insert(
+                WSS_SERVER_CERTIFICATE_FILE.into(),
+                tls_server_certificate.into(),
+            );
+        }
+
+        if properties.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(properties))
+        }
+    }
+}
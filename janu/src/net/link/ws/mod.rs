@@ -0,0 +1,52 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+mod endpoint;
+mod unicast;
+
+use super::*;
+pub use endpoint::*;
+pub use unicast::*;
+
+// Default MTU (WebSocket message) in bytes.
+// NOTE: Each batch is sent as a single WebSocket binary message. Although
+//       WebSocket messages are not limited to 16 bits, the size of the
+//       batches in Janu is constrained to 2^16 - 1 bytes (i.e., 65535).
+const WS_MAX_MTU: u16 = u16::MAX;
+
+zconfigurable! {
+    // Default MTU (WebSocket message) in bytes.
+    static ref WS_DEFAULT_MTU: u16 = WS_MAX_MTU;
+    // The LINGER option causes the shutdown() call to block until (1) all application data is delivered
+    // to the remote end or (2) a timeout expires. The timeout is expressed in seconds.
+    // More info on the LINGER option and its dynamics can be found at:
+    // https://blog.netherlabs.nl/articles/2009/01/18/the-ultimate-so_linger-page-or-why-is-my-tcp-not-reliable
+    static ref WS_LINGER_TIMEOUT: i32 = 10;
+    // Amount of time in microseconds to throttle the accept loop upon an error.
+    // Default set to 100 ms.
+    static ref WS_ACCEPT_THROTTLE_TIME: u64 = 100_000;
+}
+
+// The secure WebSocket links (wss) reuse the TLS configuration
+pub mod config {
+    use janu_util::properties::config::*;
+
+    pub const WSS_ROOT_CA_CERTIFICATE_FILE: &str = ZN_TLS_ROOT_CA_CERTIFICATE_STR;
+    pub const WSS_ROOT_CA_CERTIFICATE_RAW: &str = "tls_root_ca_certificate_raw";
+
+    pub const WSS_SERVER_PRIVATE_KEY_FILE: &str = ZN_TLS_SERVER_PRIVATE_KEY_STR;
+    pub const WSS_SERVER_PRIVATE_KEY_RAW: &str = "tls_server_private_key_raw";
+
+    pub const WSS_SERVER_CERTIFICATE_FILE: &str = ZN_TLS_SERVER_CERTIFICATE_STR;
+    pub const WSS_SERVER_CERTIFICATE_RAW: &str = "tls_server_certificate_raw";
+}
@@ -0,0 +1,710 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::config::*;
+use super::*;
+use crate::net::transport::TransportManager;
+use async_std::fs;
+use async_std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream};
+use async_std::prelude::*;
+use async_std::sync::Mutex as AsyncMutex;
+use async_std::task;
+use async_std::task::JoinHandle;
+use async_trait::async_trait;
+use async_tungstenite::tungstenite::{Error as WsError, Message};
+use async_tungstenite::WebSocketStream;
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::sink::{Sink, SinkExt};
+use futures_rustls::rustls::{
+    Certificate, ClientConfig, PrivateKey, RootCertStore, ServerConfig, ServerName,
+};
+use futures_rustls::{TlsAcceptor, TlsConnector, TlsStream};
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::io::Cursor;
+use std::net::Shutdown;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::sync::Signal;
+use janu_util::{zerror2, zread, zwrite};
+
+type WsSink = Box<dyn Sink<Message, Error = WsError> + Send + Unpin>;
+type WsStream = Box<dyn Stream<Item = Result<Message, WsError>> + Send + Unpin>;
+
+pub struct LinkUnicastWs {
+    // The underlying TCP stream, used to shut down the connection
+    socket: TcpStream,
+    // The sending and receiving halves of the WebSocket. Each transport batch
+    // is sent and received as a single WebSocket binary message.
+    sink: AsyncMutex<WsSink>,
+    stream: AsyncMutex<WsStream>,
+    // The source socket address of this link (address used on the local host)
+    src_addr: SocketAddr,
+    // The destination socket address of this link (address used on the local host)
+    dst_addr: SocketAddr,
+    // Whether the WebSocket runs on top of TLS (wss) or not (ws)
+    secure: bool,
+}
+
+impl LinkUnicastWs {
+    fn new<S>(
+        socket: TcpStream,
+        websocket: WebSocketStream<S>,
+        src_addr: SocketAddr,
+        dst_addr: SocketAddr,
+        secure: bool,
+    ) -> LinkUnicastWs
+    where
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        // Set the TCP nodelay option
+        if let Err(err) = socket.set_nodelay(true) {
+            log::warn!(
+                "Unable to set NODEALY option on WebSocket link {} => {} : {}",
+                src_addr,
+                dst_addr,
+                err
+            );
+        }
+
+        // Set the TCP linger option
+        if let Err(err) = janu_util::net::set_linger(
+            &socket,
+            Some(Duration::from_secs(
+                (*WS_LINGER_TIMEOUT).try_into().unwrap(),
+            )),
+        ) {
+            log::warn!(
+                "Unable to set LINGER option on WebSocket link {} => {} : {}",
+                src_addr,
+                dst_addr,
+                err
+            );
+        }
+
+        // Split the WebSocket to allow concurrent reads and writes
+        let (sink, stream) = futures::StreamExt::split(websocket);
+
+        // Build the WebSocket object
+        LinkUnicastWs {
+            socket,
+            sink: AsyncMutex::new(Box::new(sink)),
+            stream: AsyncMutex::new(Box::new(stream)),
+            src_addr,
+            dst_addr,
+            secure,
+        }
+    }
+
+    fn get_address(&self, addr: SocketAddr) -> LocatorAddress {
+        if self.secure {
+            LocatorAddress::Wss(LocatorWs::SocketAddr(addr))
+        } else {
+            LocatorAddress::Ws(LocatorWs::SocketAddr(addr))
+        }
+    }
+}
+
+#[async_trait]
+impl LinkUnicastTrait for LinkUnicastWs {
+    async fn close(&self) -> ZResult<()> {
+        log::trace!("Closing WebSocket link: {}", self);
+        // Send the WebSocket close frame
+        let res = zasynclock!(self.sink).close().await;
+        log::trace!("WebSocket link close {}: {:?}", self, res);
+        // Close the underlying TCP stream
+        let res = self.socket.shutdown(Shutdown::Both);
+        log::trace!("WebSocket link shutdown {}: {:?}", self, res);
+        res.map_err(|e| {
+            zerror2!(ZErrorKind::IoError {
+                descr: e.to_string(),
+            })
+        })
+    }
+
+    async fn write(&self, buffer: &[u8]) -> ZResult<usize> {
+        self.write_all(buffer).await?;
+        Ok(buffer.len())
+    }
+
+    async fn write_all(&self, buffer: &[u8]) -> ZResult<()> {
+        let mut sink = zasynclock!(self.sink);
+        sink.send(Message::Binary(buffer.to_vec()))
+            .await
+            .map_err(|e| {
+                log::trace!("Write error on WebSocket link {}: {}", self, e);
+                zerror2!(ZErrorKind::IoError {
+                    descr: e.to_string()
+                })
+            })
+    }
+
+    async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
+        let mut stream = zasynclock!(self.stream);
+        loop {
+            match stream.next().await {
+                Some(Ok(Message::Binary(data))) => {
+                    if data.len() > buffer.len() {
+                        let e = format!(
+                            "WebSocket message of {} bytes exceeds the buffer of {} bytes on link {}",
+                            data.len(),
+                            buffer.len(),
+                            self
+                        );
+                        log::trace!("{}", e);
+                        return zerror!(ZErrorKind::IoError { descr: e });
+                    }
+                    buffer[..data.len()].copy_from_slice(&data);
+                    return Ok(data.len());
+                }
+                Some(Ok(Message::Close(_))) | None => {
+                    let e = format!("WebSocket link closed by peer: {}", self);
+                    log::trace!("{}", e);
+                    return zerror!(ZErrorKind::IoError { descr: e });
+                }
+                // Ping and pong messages are handled by the WebSocket itself.
+                // Text messages are not part of the protocol and are discarded.
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => {
+                    log::trace!("Read error on WebSocket link {}: {}", self, e);
+                    return zerror!(ZErrorKind::IoError {
+                        descr: e.to_string()
+                    });
+                }
+            }
+        }
+    }
+
+    async fn read_exact(&self, buffer: &mut [u8]) -> ZResult<()> {
+        let mut read: usize = 0;
+        while read < buffer.len() {
+            let n = self.read(&mut buffer[read..]).await?;
+            read += n;
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn get_src(&self) -> Locator {
+        Locator {
+            address: self.get_address(self.src_addr),
+            metadata: None,
+        }
+    }
+
+    #[inline(always)]
+    fn get_dst(&self) -> Locator {
+        Locator {
+            address: self.get_address(self.dst_addr),
+            metadata: None,
+        }
+    }
+
+    #[inline(always)]
+    fn get_mtu(&self) -> u16 {
+        *WS_DEFAULT_MTU
+    }
+
+    #[inline(always)]
+    fn is_reliable(&self) -> bool {
+        true
+    }
+
+    #[inline(always)]
+    fn is_streamed(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for LinkUnicastWs {
+    fn drop(&mut self) {
+        // Close the underlying TCP stream
+        let _ = self.socket.shutdown(Shutdown::Both);
+    }
+}
+
+impl fmt::Display for LinkUnicastWs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} => {}", self.src_addr, self.dst_addr)?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for LinkUnicastWs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ws")
+            .field("src", &self.src_addr)
+            .field("dst", &self.dst_addr)
+            .field("secure", &self.secure)
+            .finish()
+    }
+}
+
+/*************************************/
+/*          LISTENER                 */
+/*************************************/
+struct ListenerUnicastWs {
+    endpoint: EndPoint,
+    active: Arc<AtomicBool>,
+    signal: Signal,
+    handle: JoinHandle<ZResult<()>>,
+}
+
+impl ListenerUnicastWs {
+    fn new(
+        endpoint: EndPoint,
+        active: Arc<AtomicBool>,
+        signal: Signal,
+        handle: JoinHandle<ZResult<()>>,
+    ) -> ListenerUnicastWs {
+        ListenerUnicastWs {
+            endpoint,
+            active,
+            signal,
+            handle,
+        }
+    }
+}
+
+pub struct LinkManagerUnicastWs {
+    manager: TransportManager,
+    listeners: Arc<RwLock<HashMap<SocketAddr, ListenerUnicastWs>>>,
+    // Whether the links are WebSockets on top of TLS (wss) or not (ws)
+    secure: bool,
+}
+
+impl LinkManagerUnicastWs {
+    pub(crate) fn new(manager: TransportManager, secure: bool) -> Self {
+        Self {
+            manager,
+            listeners: Arc::new(RwLock::new(HashMap::new())),
+            secure,
+        }
+    }
+
+    fn get_address(&self, addr: SocketAddr) -> LocatorAddress {
+        if self.secure {
+            LocatorAddress::Wss(LocatorWs::SocketAddr(addr))
+        } else {
+            LocatorAddress::Ws(LocatorWs::SocketAddr(addr))
+        }
+    }
+}
+
+async fn get_client_config(endpoint: &EndPoint) -> ZResult<Arc<ClientConfig>> {
+    let bytes = match endpoint.config.as_ref() {
+        Some(config) => match config.get(WSS_ROOT_CA_CERTIFICATE_RAW) {
+            Some(tls_ca_certificate) => tls_ca_certificate.as_bytes().to_vec(),
+            None => match config.get(WSS_ROOT_CA_CERTIFICATE_FILE) {
+                Some(tls_ca_certificate) => fs::read(tls_ca_certificate).await.map_err(|e| {
+                    zerror2!(ZErrorKind::Other {
+                        descr: format!("Invalid TLS CA certificate file: {}", e)
+                    })
+                })?,
+                None => vec![],
+            },
+        },
+        None => vec![],
+    };
+
+    let mut root_cert_store = RootCertStore::empty();
+    if !bytes.is_empty() {
+        let certs = rustls_pemfile::certs(&mut Cursor::new(&bytes)).map_err(|e| {
+            zerror2!(ZErrorKind::Other {
+                descr: format!("Invalid TLS CA certificate file: {}", e)
+            })
+        })?;
+        // A CA file without any usable certificate would fail every handshake
+        let (valid, _) = root_cert_store.add_parsable_certificates(&certs);
+        if valid == 0 {
+            return zerror!(ZErrorKind::Other {
+                descr: "Invalid TLS CA certificate file: no valid certificate found".to_string()
+            });
+        }
+    }
+    let cc = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_cert_store)
+        .with_no_client_auth();
+    Ok(Arc::new(cc))
+}
+
+async fn get_server_config(endpoint: &EndPoint, addr: &SocketAddr) -> ZResult<ServerConfig> {
+    // Verify there is a valid ServerConfig
+    let config = endpoint.config.as_ref().ok_or_else(|| {
+        let e = format!(
+            "Can not create a new WSS listener on {}: no ServerConfig provided",
+            addr
+        );
+        zerror2!(ZErrorKind::InvalidLink { descr: e })
+    })?;
+
+    // Configure the server private key
+    let bytes = match config.get(WSS_SERVER_PRIVATE_KEY_RAW) {
+        Some(tls_server_private_key) => tls_server_private_key.as_bytes().to_vec(),
+        None => match config.get(WSS_SERVER_PRIVATE_KEY_FILE) {
+            Some(tls_server_private_key) => {
+                fs::read(tls_server_private_key).await.map_err(|e| {
+                    let e = format!("Invalid TLS private key file: {}", e);
+                    zerror2!(ZErrorKind::IoError { descr: e })
+                })?
+            }
+            None => {
+                let e = format!(
+                    "Can not create a new WSS listener on {}. ServerConfig not provided: {}.",
+                    addr, WSS_SERVER_PRIVATE_KEY_FILE
+                );
+                return zerror!(ZErrorKind::InvalidLink { descr: e });
+            }
+        },
+    };
+    // Accept both PKCS8 and RSA private keys
+    let mut keys = rustls_pemfile::pkcs8_private_keys(&mut Cursor::new(bytes.as_slice()))
+        .ok()
+        .filter(|keys| !keys.is_empty())
+        .or_else(|| rustls_pemfile::rsa_private_keys(&mut Cursor::new(bytes.as_slice())).ok())
+        .filter(|keys| !keys.is_empty())
+        .ok_or_else(|| {
+            let e = format!("Invalid TLS private key for WSS listener on {}", addr);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+    // Configure the server certificate
+    let bytes = match config.get(WSS_SERVER_CERTIFICATE_RAW) {
+        Some(tls_server_certificate) => tls_server_certificate.as_bytes().to_vec(),
+        None => match config.get(WSS_SERVER_CERTIFICATE_FILE) {
+            Some(tls_server_certificate) => {
+                fs::read(tls_server_certificate).await.map_err(|e| {
+                    let e = format!("Invalid TLS server certificate file: {}", e);
+                    zerror2!(ZErrorKind::IoError { descr: e })
+                })?
+            }
+            None => {
+                let e = format!(
+                    "Can not create a new WSS listener on {}. ServerConfig not provided: {}.",
+                    addr, WSS_SERVER_CERTIFICATE_FILE
+                );
+                return zerror!(ZErrorKind::InvalidLink { descr: e });
+            }
+        },
+    };
+    let certs = rustls_pemfile::certs(&mut Cursor::new(bytes.as_slice()))
+        .map_err(|_| {
+            let e = format!(
+                "Invalid TLS server certificate for WSS listener on {}",
+                addr
+            );
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+
+    ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, PrivateKey(keys.remove(0)))
+        .map_err(|e| {
+            let e = format!(
+                "Invalid TLS configuration for WSS listener on {}: {}",
+                addr, e
+            );
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })
+}
+
+#[async_trait]
+impl LinkManagerUnicastTrait for LinkManagerUnicastWs {
+    async fn new_link(&self, endpoint: EndPoint) -> ZResult<LinkUnicast> {
+        let addr = get_ws_addr(&endpoint.locator.address).await?;
+        let locator = get_ws_locator(&endpoint.locator.address)?;
+        let url = format!("{}://{}/", endpoint.locator.address.get_proto(), locator);
+
+        // Initialize the TcpStream
+        let tcp_stream = TcpStream::connect(addr).await.map_err(|e| {
+            let e = format!(
+                "Can not create a new WebSocket link bound to {}: {}",
+                url, e
+            );
+            zerror2!(ZErrorKind::Other { descr: e })
+        })?;
+
+        let src_addr = tcp_stream.local_addr().map_err(|e| {
+            let e = format!(
+                "Can not create a new WebSocket link bound to {}: {}",
+                url, e
+            );
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+        let dst_addr = tcp_stream.peer_addr().map_err(|e| {
+            let e = format!(
+                "Can not create a new WebSocket link bound to {}: {}",
+                url, e
+            );
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+        let handshake_error = |e: WsError| {
+            let e = format!(
+                "Can not create a new WebSocket link bound to {}: {}",
+                url, e
+            );
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        };
+
+        let link = if self.secure {
+            // Initialize the TLS stream
+            let domain = get_ws_dns(&endpoint.locator.address)?;
+            let host: &str = domain.as_ref().into();
+            let server_name = ServerName::try_from(host).map_err(|e| {
+                let e = format!("Can not create a new WSS link bound to {}: {}", url, e);
+                zerror2!(ZErrorKind::InvalidLink { descr: e })
+            })?;
+            let connector = TlsConnector::from(get_client_config(&endpoint).await?);
+            let tls_stream = connector
+                .connect(server_name, tcp_stream.clone())
+                .await
+                .map_err(|e| {
+                    let e = format!("Can not create a new WSS link bound to {}: {}", url, e);
+                    zerror2!(ZErrorKind::InvalidLink { descr: e })
+                })?;
+            let tls_stream = TlsStream::Client(tls_stream);
+            let (websocket, _) = async_tungstenite::client_async(url.as_str(), tls_stream)
+                .await
+                .map_err(handshake_error)?;
+            LinkUnicastWs::new(tcp_stream, websocket, src_addr, dst_addr, true)
+        } else {
+            let (websocket, _) = async_tungstenite::client_async(url.as_str(), tcp_stream.clone())
+                .await
+                .map_err(handshake_error)?;
+            LinkUnicastWs::new(tcp_stream, websocket, src_addr, dst_addr, false)
+        };
+
+        Ok(LinkUnicast(Arc::new(link)))
+    }
+
+    async fn new_listener(&self, mut endpoint: EndPoint) -> ZResult<Locator> {
+        let addr = get_ws_addr(&endpoint.locator.address).await?;
+
+        // Initialize the TlsAcceptor for secure WebSockets
+        let acceptor = if self.secure {
+            let sc = get_server_config(&endpoint, &addr).await?;
+            Some(TlsAcceptor::from(Arc::new(sc)))
+        } else {
+            None
+        };
+
+        // Initialize the TcpListener
+        let socket = TcpListener::bind(addr).await.map_err(|e| {
+            let e = format!("Can not create a new WebSocket listener on {}: {}", addr, e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+        let local_addr = socket.local_addr().map_err(|e| {
+            let e = format!("Can not create a new WebSocket listener on {}: {}", addr, e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+        // Update the endpoint locator address
+        endpoint.locator.address = self.get_address(local_addr);
+
+        let active = Arc::new(AtomicBool::new(true));
+        let signal = Signal::new();
+
+        // Spawn the accept loop for the listener
+        let c_active = active.clone();
+        let c_signal = signal.clone();
+        let c_manager = self.manager.clone();
+        let c_listeners = self.listeners.clone();
+        let c_addr = local_addr;
+        let handle = task::spawn(async move {
+            // Wait for the accept loop to terminate
+            let res = accept_task(socket, acceptor, c_active, c_signal, c_manager).await;
+            zwrite!(c_listeners).remove(&c_addr);
+            res
+        });
+
+        let locator = endpoint.locator.clone();
+        let listener = ListenerUnicastWs::new(endpoint, active, signal, handle);
+        // Update the list of active listeners on the manager
+        zwrite!(self.listeners).insert(local_addr, listener);
+
+        Ok(locator)
+    }
+
+    async fn del_listener(&self, endpoint: &EndPoint) -> ZResult<()> {
+        let addr = get_ws_addr(&endpoint.locator.address).await?;
+
+        // Stop the listener
+        let listener = zwrite!(self.listeners).remove(&addr).ok_or_else(|| {
+            let e = format!(
+                "Can not delete the WebSocket listener because it has not been found: {}",
+                addr
+            );
+            log::trace!("{}", e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+        // Send the stop signal
+        listener.active.store(false, Ordering::Release);
+        listener.signal.trigger();
+        listener.handle.await
+    }
+
+    fn get_listeners(&self) -> Vec<EndPoint> {
+        zread!(self.listeners)
+            .values()
+            .map(|x| x.endpoint.clone())
+            .collect()
+    }
+
+    fn get_locators(&self) -> Vec<Locator> {
+        let mut locators = vec![];
+        let default_ipv4 = Ipv4Addr::new(0, 0, 0, 0);
+        let default_ipv6 = Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0);
+
+        for (key, value) in zread!(self.listeners).iter() {
+            if key.ip() == default_ipv4 {
+                match janu_util::net::get_local_addresses() {
+                    Ok(ipaddrs) => {
+                        for ipaddr in ipaddrs {
+                            if !ipaddr.is_loopback() && !ipaddr.is_multicast() && ipaddr.is_ipv4() {
+                                locators.push((
+                                    SocketAddr::new(ipaddr, key.port()),
+                                    value.endpoint.locator.metadata.clone(),
+                                ));
+                            }
+                        }
+                    }
+                    Err(err) => log::error!("Unable to get local addresses : {}", err),
+                }
+            } else if key.ip() == default_ipv6 {
+                match janu_util::net::get_local_addresses() {
+                    Ok(ipaddrs) => {
+                        for ipaddr in ipaddrs {
+                            if !ipaddr.is_loopback() && !ipaddr.is_multicast() && ipaddr.is_ipv6() {
+                                locators.push((
+                                    SocketAddr::new(ipaddr, key.port()),
+                                    value.endpoint.locator.metadata.clone(),
+                                ));
+                            }
+                        }
+                    }
+                    Err(err) => log::error!("Unable to get local addresses : {}", err),
+                }
+            } else {
+                locators.push((*key, value.endpoint.locator.metadata.clone()));
+            }
+        }
+
+        locators
+            .into_iter()
+            .map(|(addr, metadata)| Locator {
+                address: self.get_address(addr),
+                metadata,
+            })
+            .collect()
+    }
+}
+
+async fn accept_task(
+    socket: TcpListener,
+    acceptor: Option<TlsAcceptor>,
+    active: Arc<AtomicBool>,
+    signal: Signal,
+    manager: TransportManager,
+) -> ZResult<()> {
+    enum Action {
+        Accept((TcpStream, SocketAddr)),
+        Stop,
+    }
+
+    async fn accept(socket: &TcpListener) -> ZResult<Action> {
+        let res = socket.accept().await.map_err(|e| {
+            zerror2!(ZErrorKind::IoError {
+                descr: e.to_string()
+            })
+        })?;
+        Ok(Action::Accept(res))
+    }
+
+    async fn stop(signal: Signal) -> ZResult<Action> {
+        signal.wait().await;
+        Ok(Action::Stop)
+    }
+
+    let src_addr = socket.local_addr().map_err(|e| {
+        let e = format!("Can not accept WebSocket connections: {}", e);
+        log::warn!("{}", e);
+        zerror2!(ZErrorKind::IoError { descr: e })
+    })?;
+
+    log::trace!("Ready to accept WebSocket connections on: {:?}", src_addr);
+    while active.load(Ordering::Acquire) {
+        // Wait for incoming connections
+        let (tcp_stream, dst_addr) = match accept(&socket).race(stop(signal.clone())).await {
+            Ok(action) => match action {
+                Action::Accept((tcp_stream, dst_addr)) => (tcp_stream, dst_addr),
+                Action::Stop => break,
+            },
+            Err(e) => {
+                log::warn!("{}. Hint: increase the system open file limit.", e);
+                // Throttle the accept loop upon an error
+                // NOTE: This might be due to various factors. However, the most common case is that
+                //       the process has reached the maximum number of open files in the system. On
+                //       Linux systems this limit can be changed by using the "ulimit" command line
+                //       tool. In case of systemd-based systems, this can be changed by using the
+                //       "sysctl" command line tool.
+                task::sleep(Duration::from_micros(*WS_ACCEPT_THROTTLE_TIME)).await;
+                continue;
+            }
+        };
+        // Accept the WebSocket connection, on top of TLS if secure
+        let res = match acceptor.as_ref() {
+            Some(acceptor) => match acceptor.accept(tcp_stream.clone()).await {
+                Ok(stream) => async_tungstenite::accept_async(TlsStream::Server(stream))
+                    .await
+                    .map(|ws| LinkUnicastWs::new(tcp_stream, ws, src_addr, dst_addr, true))
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            },
+            None => async_tungstenite::accept_async(tcp_stream.clone())
+                .await
+                .map(|ws| LinkUnicastWs::new(tcp_stream, ws, src_addr, dst_addr, false))
+                .map_err(|e| e.to_string()),
+        };
+        let link = match res {
+            Ok(link) => link,
+            Err(e) => {
+                log::warn!("Can not accept WebSocket connection: {}", e);
+                continue;
+            }
+        };
+
+        log::debug!(
+            "Accepted WebSocket connection on {:?}: {:?}",
+            src_addr,
+            dst_addr
+        );
+        // Communicate the new link to the initial transport manager
+        manager
+            .handle_new_link_unicast(LinkUnicast(Arc::new(link)))
+            .await;
+    }
+
+    Ok(())
+}
@@ -88,7 +88,9 @@ use runtime::Runtime;
 use janu_util::properties::config::*;
 // Shared memory and zero-copy
 #[cfg(feature = "zero-copy")]
-pub use protocol::io::{SharedMemoryBuf, SharedMemoryBufInfo, SharedMemoryManager};
+pub use protocol::io::{
+    SharedMemoryBuf, SharedMemoryBufInfo, SharedMemoryManager, SharedMemorySegmentStats,
+};
 
 #[macro_use]
 mod types;
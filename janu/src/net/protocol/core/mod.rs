@@ -488,12 +488,35 @@ impl Default for QueryConsolidation {
     }
 }
 
+/// The strategy used to choose the queryables a [`Target::Balanced`] query is routed to.
+#[derive(Debug, Clone, PartialEq, Copy, Default)]
+#[repr(u8)]
+pub enum BalancingStrategy {
+    /// The matching queryables are chosen in turn.
+    #[default]
+    RoundRobin,
+    /// The matching queryables with the fewest pending queries are chosen.
+    LeastPending,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Target {
     BestMatching,
-    Complete { n: ZInt },
+    Complete {
+        n: ZInt,
+    },
     All,
     None,
+    /// The query is routed to `n` of the matching queryables (including the ones of the
+    /// querying session), chosen in turn for each key expression with the given strategy.
+    /// As with `Complete { n }`, `n` distinct queryables answer the query: a final reply
+    /// without data (e.g. from a storage holding no matching value) counts as an answer,
+    /// but if a chosen queryable closes or is undeclared before replying, the query is
+    /// routed to another one.
+    Balanced {
+        n: ZInt,
+        strategy: BalancingStrategy,
+    },
 }
 
 impl Default for Target {
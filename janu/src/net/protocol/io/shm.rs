@@ -19,17 +19,23 @@ use std::collections::HashMap;
 use std::fmt;
 use std::mem::align_of;
 use std::sync::atomic;
-use std::sync::atomic::{AtomicPtr, AtomicUsize};
+use std::sync::atomic::{AtomicPtr, AtomicU64};
+use std::time::{Duration, Instant};
 use janu_util::core::{ZError, ZErrorKind, ZResult};
 use janu_util::zerror;
 
 const MIN_FREE_CHUNK_SIZE: usize = 1_024;
 const ACCOUNTED_OVERHEAD: usize = 4_096;
 const JANU_SHM_PREFIX: &str = "janu_shm_pid";
+const ALLOC_WAIT_MIN_BACKOFF: Duration = Duration::from_micros(100);
+const ALLOC_WAIT_MAX_BACKOFF: Duration = Duration::from_millis(10);
 
-// Chunk header
-type ChunkHeaderType = AtomicUsize;
+// Chunk header: the generation of the chunk in the upper 32 bits and its reference count
+// in the lower 32 bits, so that both can be checked and updated at once.
+type ChunkHeaderType = AtomicU64;
 const CHUNK_HEADER_SIZE: usize = std::mem::size_of::<ChunkHeaderType>();
+const CHUNK_RC_MASK: u64 = 0xffff_ffff;
+const CHUNK_GENERATION_SHIFT: u32 = 32;
 
 fn align_addr_at(addr: usize, align: usize) -> usize {
     match addr % align {
@@ -72,6 +78,10 @@ pub struct SharedMemoryBufInfo {
     pub length: usize,
     pub shm_manager: String,
     pub kind: u8,
+    /// The generation of the chunk at the time the buffer was allocated. A chunk gets a
+    /// new generation every time it is allocated, so that a stale info is not mistaken
+    /// for the buffer reallocated at the same offset.
+    pub generation: u32,
 }
 
 impl SharedMemoryBufInfo {
@@ -81,6 +91,7 @@ impl SharedMemoryBufInfo {
             length,
             shm_manager: manager,
             kind,
+            generation: 0,
         }
     }
 }
@@ -92,6 +103,7 @@ impl Clone for SharedMemoryBufInfo {
             kind: self.kind,
             offset: self.offset,
             length: self.length,
+            generation: self.generation,
         }
     }
 }
@@ -108,10 +120,8 @@ pub struct SharedMemoryBuf {
 
 impl std::fmt::Debug for SharedMemoryBuf {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let ptr = self.rc_ptr.load(atomic::Ordering::SeqCst);
-        let rc = unsafe { (*ptr).load(atomic::Ordering::SeqCst) };
         f.debug_struct("SharedMemoryBuf")
-            .field("rc", &rc)
+            .field("rc", &self.ref_count())
             .field("buf", &self.buf)
             .field("len", &self.len)
             .field("info", &self.info)
@@ -142,7 +152,7 @@ impl SharedMemoryBuf {
 
     pub fn ref_count(&self) -> usize {
         let rc = self.rc_ptr.load(atomic::Ordering::SeqCst);
-        unsafe { (*rc).load(atomic::Ordering::SeqCst) }
+        unsafe { ((*rc).load(atomic::Ordering::SeqCst) & CHUNK_RC_MASK) as usize }
     }
 
     pub fn inc_ref_count(&self) {
@@ -150,6 +160,25 @@ impl SharedMemoryBuf {
         unsafe { (*rc).fetch_add(1, atomic::Ordering::SeqCst) };
     }
 
+    // Increments the reference count only if the buffer has not been released yet, i.e.
+    // if the sender is still holding a reference on it, and if the chunk has not been
+    // reallocated since the info of the buffer was issued.
+    pub(crate) fn try_inc_ref_count(&self) -> bool {
+        let rc = self.rc_ptr.load(atomic::Ordering::SeqCst);
+        let generation = u64::from(self.info.generation);
+        unsafe {
+            (*rc)
+                .fetch_update(atomic::Ordering::SeqCst, atomic::Ordering::SeqCst, |h| {
+                    if h >> CHUNK_GENERATION_SHIFT != generation || h & CHUNK_RC_MASK == 0 {
+                        None
+                    } else {
+                        Some(h + 1)
+                    }
+                })
+                .is_ok()
+        }
+    }
+
     pub fn dec_ref_count(&self) {
         let rc = self.rc_ptr.load(atomic::Ordering::SeqCst);
         unsafe { (*rc).fetch_sub(1, atomic::Ordering::SeqCst) };
@@ -199,6 +228,16 @@ impl Clone for SharedMemoryBuf {
     }
 }
 
+/// The owner of the reference taken on a [`SharedMemoryBuf`] when it is sent as a
+/// [`SharedMemoryBufInfo`].
+pub(crate) enum SharedMemoryRelease<'a> {
+    /// The single reader of the info owns the reference and releases it once done.
+    Reader,
+    /// The sender keeps the buffers alive by retaining them in the given list. Every
+    /// reader takes its own reference upon reception.
+    Sender(&'a mut Vec<SharedMemoryBuf>),
+}
+
 /*************************************/
 /*       SHARED MEMORY READER        */
 /*************************************/
@@ -282,9 +321,9 @@ impl fmt::Debug for SharedMemoryReader {
 }
 
 /*************************************/
-/*       SHARED MEMORY MANAGER       */
+/*       SHARED MEMORY SEGMENT       */
 /*************************************/
-pub struct SharedMemoryManager {
+struct SharedMemorySegment {
     segment_path: String,
     size: usize,
     available: usize,
@@ -292,18 +331,11 @@ pub struct SharedMemoryManager {
     free_list: BinaryHeap<Chunk>,
     busy_list: Vec<Chunk>,
     alignment: usize,
+    generation: u32,
 }
 
-unsafe impl Send for SharedMemoryManager {}
-
-impl SharedMemoryManager {
-    /// Creates a new SharedMemoryManager managing allocations of a region of the
-    /// given size.
-    pub fn new(id: String, size: usize) -> ZResult<SharedMemoryManager> {
-        let mut temp_dir = std::env::temp_dir();
-        let file_name: String = format!("{}_{}", JANU_SHM_PREFIX, id);
-        temp_dir.push(file_name);
-        let path: String = temp_dir.to_str().unwrap().to_string();
+impl SharedMemorySegment {
+    fn new(path: String, size: usize) -> ZResult<SharedMemorySegment> {
         log::trace!("Creating file at: {}", path);
         let real_size = size + ACCOUNTED_OVERHEAD;
         let shmem = match ShmemConf::new()
@@ -336,7 +368,7 @@ impl SharedMemoryManager {
         };
         free_list.push(chunk);
         let busy_list = vec![];
-        let shm = SharedMemoryManager {
+        let segment = SharedMemorySegment {
             segment_path: path,
             size,
             available: real_size,
@@ -344,23 +376,29 @@ impl SharedMemoryManager {
             free_list,
             busy_list,
             alignment: align_of::<ChunkHeaderType>(),
+            // Start from a random generation so that the infos issued by a previous
+            // instance of the segment are not mistaken for the new buffers
+            generation: rand::random::<u32>(),
         };
         log::trace!(
-            "Created SharedMemoryManager for {:?}",
-            shm.own_segment.as_ptr()
+            "Created SharedMemorySegment for {:?}",
+            segment.own_segment.as_ptr()
         );
-        Ok(shm)
+        Ok(segment)
     }
 
-    fn free_chunk_map_to_shmbuf(&self, chunk: &Chunk) -> SharedMemoryBuf {
+    fn free_chunk_map_to_shmbuf(&mut self, chunk: &Chunk) -> SharedMemoryBuf {
+        self.generation = self.generation.wrapping_add(1);
         let info = SharedMemoryBufInfo {
             offset: chunk.offset,
             length: chunk.size,
             shm_manager: self.segment_path.clone(),
             kind: 0,
+            generation: self.generation,
         };
         let rc = chunk.base_addr as *mut ChunkHeaderType;
-        unsafe { (*rc).store(1, atomic::Ordering::SeqCst) };
+        let header = (u64::from(self.generation) << CHUNK_GENERATION_SHIFT) | 1;
+        unsafe { (*rc).store(header, atomic::Ordering::SeqCst) };
         let rc_ptr = AtomicPtr::<ChunkHeaderType>::new(rc);
         SharedMemoryBuf {
             rc_ptr,
@@ -370,8 +408,8 @@ impl SharedMemoryManager {
         }
     }
 
-    pub fn alloc(&mut self, len: usize) -> Option<SharedMemoryBuf> {
-        log::trace!("SharedMemoryManager::alloc({})", len);
+    fn alloc(&mut self, len: usize) -> Option<SharedMemoryBuf> {
+        log::trace!("SharedMemorySegment::alloc({})", len);
         // Always allocate a size that will keep the proper alignment requirements
         let required_len = align_addr_at(len + CHUNK_HEADER_SIZE, self.alignment);
         if self.available < required_len {
@@ -404,24 +442,25 @@ impl SharedMemoryManager {
                 Some(c) => {
                     self.free_list.push(c);
                     log::trace!(
-                        "SharedMemoryManager::alloc({}) cannot find any available chunk of the appropriate size.",
+                        "SharedMemorySegment::alloc({}) cannot find any available chunk of the appropriate size.",
                         len
                     );
-                    log::trace!("SharedMemoryManager::free_list = {:?}", self.free_list);
+                    log::trace!("SharedMemorySegment::free_list = {:?}", self.free_list);
                     None
                 }
                 None => {
                     log::trace!(
-                        "SharedMemoryManager::alloc({}) cannot find any available chunk",
+                        "SharedMemorySegment::alloc({}) cannot find any available chunk",
                         len
                     );
-                    log::trace!("SharedMemoryManager::free_list = {:?}", self.free_list);
+                    log::trace!("SharedMemorySegment::free_list = {:?}", self.free_list);
                     None
                 }
             }
         } else {
-            log::warn!(
-                "SharedMemoryManager does not have sufficient free memory to allocate {} bytes, try de-fragmenting!",
+            log::trace!(
+                "SharedMemorySegment {} does not have sufficient free memory to allocate {} bytes",
+                self.segment_path,
                 len
             );
             None
@@ -430,8 +469,8 @@ impl SharedMemoryManager {
 
     fn is_free_chunk(chunk: &Chunk) -> bool {
         let rc_ptr = chunk.base_addr as *mut ChunkHeaderType;
-        let rc = unsafe { (*rc_ptr).load(atomic::Ordering::SeqCst) };
-        rc == 0
+        let header = unsafe { (*rc_ptr).load(atomic::Ordering::SeqCst) };
+        header & CHUNK_RC_MASK == 0
     }
 
     fn try_merge_adjacent_chunks(a: &Chunk, b: &Chunk) -> Option<Chunk> {
@@ -446,8 +485,9 @@ impl SharedMemoryManager {
             None
         }
     }
+
     // Returns the amount of memory that it was able to de-fragment
-    pub fn defragment(&mut self) -> usize {
+    fn defragment(&mut self) -> usize {
         if self.free_list.len() > 1 {
             let mut fbs: Vec<Chunk> = self.free_list.drain().collect();
             fbs.sort_by(|x, y| x.offset.partial_cmp(&y.offset).unwrap());
@@ -458,7 +498,7 @@ impl SharedMemoryManager {
             for chunk in fbs.iter() {
                 i += 1;
                 let next = *chunk;
-                match SharedMemoryManager::try_merge_adjacent_chunks(&current, &next) {
+                match SharedMemorySegment::try_merge_adjacent_chunks(&current, &next) {
                     Some(c) => {
                         current = c;
                         defrag_mem += current.size;
@@ -482,15 +522,15 @@ impl SharedMemoryManager {
         }
     }
 
-    /// Returns the amount of memory freed
-    pub fn garbage_collect(&mut self) -> usize {
-        log::trace!("Running Garbage Collector");
+    // Returns the amount of memory freed
+    fn garbage_collect(&mut self) -> usize {
+        log::trace!("Running Garbage Collector on {}", self.segment_path);
 
         let mut freed = 0;
         let (free, busy) = self
             .busy_list
             .iter()
-            .partition(|&c| SharedMemoryManager::is_free_chunk(c));
+            .partition(|&c| SharedMemorySegment::is_free_chunk(c));
         self.busy_list = busy;
 
         for f in free {
@@ -501,11 +541,21 @@ impl SharedMemoryManager {
         self.available += freed;
         freed
     }
+
+    fn stats(&self) -> SharedMemorySegmentStats {
+        SharedMemorySegmentStats {
+            path: self.segment_path.clone(),
+            size: self.size,
+            available: self.available,
+            free_chunks: self.free_list.len(),
+            busy_chunks: self.busy_list.len(),
+        }
+    }
 }
 
-impl fmt::Debug for SharedMemoryManager {
+impl fmt::Debug for SharedMemorySegment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("SharedMemoryManager")
+        f.debug_struct("SharedMemorySegment")
             .field("segment_path", &self.segment_path)
             .field("size", &self.size)
             .field("available", &self.available)
@@ -514,3 +564,250 @@ impl fmt::Debug for SharedMemoryManager {
             .finish()
     }
 }
+
+/// Allocation statistics of a single segment of a [`SharedMemoryManager`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SharedMemorySegmentStats {
+    pub path: String,
+    pub size: usize,
+    pub available: usize,
+    pub free_chunks: usize,
+    pub busy_chunks: usize,
+}
+
+/*************************************/
+/*       SHARED MEMORY MANAGER       */
+/*************************************/
+/// A manager of shared memory allocations spanning one or more segments.
+///
+/// The first segment is created together with the manager, additional segments are
+/// created on demand when an allocation cannot be satisfied by the existing ones, up
+/// to `max_segments`. Every segment is a distinct shared memory region identified by
+/// its own path, so the [`SharedMemoryReader`] on the receiving side maps additional
+/// segments transparently upon reception of a [`SharedMemoryBufInfo`] pointing to them.
+pub struct SharedMemoryManager {
+    id: String,
+    segment_size: usize,
+    max_segments: usize,
+    segments: Vec<SharedMemorySegment>,
+}
+
+unsafe impl Send for SharedMemoryManager {}
+
+impl SharedMemoryManager {
+    /// Creates a new SharedMemoryManager managing allocations of a region of the
+    /// given size.
+    pub fn new(id: String, size: usize) -> ZResult<SharedMemoryManager> {
+        SharedMemoryManager::with_segments(id, size, 1)
+    }
+
+    /// Creates a new SharedMemoryManager managing allocations of up to `max_segments`
+    /// regions of `segment_size` bytes each. Only the first region is created upfront.
+    pub fn with_segments(
+        id: String,
+        segment_size: usize,
+        max_segments: usize,
+    ) -> ZResult<SharedMemoryManager> {
+        if max_segments == 0 {
+            return zerror!(ZErrorKind::SharedMemoryError {
+                descr: "A SharedMemoryManager requires at least one segment".to_string()
+            });
+        }
+        let mut shm = SharedMemoryManager {
+            id,
+            segment_size,
+            max_segments,
+            segments: Vec::with_capacity(max_segments),
+        };
+        shm.add_segment(segment_size)?;
+        Ok(shm)
+    }
+
+    fn segment_path(&self, index: usize) -> String {
+        let mut temp_dir = std::env::temp_dir();
+        // The first segment keeps the historical name for backward compatibility.
+        // Additional segments carry a random suffix so that they cannot collide with
+        // the segments of another manager whose id happens to end with "_<n>".
+        let file_name: String = match index {
+            0 => format!("{}_{}", JANU_SHM_PREFIX, self.id),
+            n => format!(
+                "{}_{}_{}_{:08x}",
+                JANU_SHM_PREFIX,
+                self.id,
+                n,
+                rand::random::<u32>()
+            ),
+        };
+        temp_dir.push(file_name);
+        temp_dir.to_str().unwrap().to_string()
+    }
+
+    fn add_segment(&mut self, size: usize) -> ZResult<&mut SharedMemorySegment> {
+        let path = self.segment_path(self.segments.len());
+        let segment = SharedMemorySegment::new(path, size)?;
+        log::debug!(
+            "SharedMemoryManager {} added segment #{}: {:?}",
+            self.id,
+            self.segments.len(),
+            segment
+        );
+        self.segments.push(segment);
+        Ok(self.segments.last_mut().unwrap())
+    }
+
+    /// Allocates a buffer of the given length. The released buffers are automatically
+    /// garbage collected and the segments de-fragmented when needed. If no existing segment
+    /// can accommodate the allocation, a new segment is added as long as `max_segments`
+    /// has not been reached. Returns `None` if the allocation cannot be satisfied.
+    pub fn alloc(&mut self, len: usize) -> Option<SharedMemoryBuf> {
+        log::trace!("SharedMemoryManager::alloc({})", len);
+        for segment in self.segments.iter_mut() {
+            if let Some(buf) = segment.alloc(len) {
+                return Some(buf);
+            }
+        }
+        // Try harder by merging the free chunks
+        for segment in self.segments.iter_mut() {
+            if segment.defragment() > 0 {
+                if let Some(buf) = segment.alloc(len) {
+                    return Some(buf);
+                }
+            }
+        }
+        if self.segments.len() < self.max_segments {
+            // Make sure the new segment is big enough for the requested allocation
+            let size = self.segment_size.max(len + CHUNK_HEADER_SIZE);
+            match self.add_segment(size) {
+                Ok(segment) => return segment.alloc(len),
+                Err(e) => log::warn!("SharedMemoryManager unable to add a new segment: {}", e),
+            }
+        }
+        log::warn!(
+            "SharedMemoryManager does not have sufficient free memory to allocate {} bytes",
+            len
+        );
+        None
+    }
+
+    /// Allocates a buffer of the given length, waiting up to `timeout` for previously
+    /// allocated buffers to be released if no memory is currently available.
+    /// Returns `None` if the allocation could not be satisfied before the timeout.
+    pub async fn alloc_wait(&mut self, len: usize, timeout: Duration) -> Option<SharedMemoryBuf> {
+        let deadline = Instant::now() + timeout;
+        let mut backoff = ALLOC_WAIT_MIN_BACKOFF;
+        loop {
+            if let Some(buf) = self.alloc(len) {
+                return Some(buf);
+            }
+            // Buffers are released by decrementing their reference count, possibly
+            // in a different process: there is no notification, thus poll with backoff.
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            async_std::task::sleep(backoff.min(deadline - now)).await;
+            backoff = (backoff * 2).min(ALLOC_WAIT_MAX_BACKOFF);
+        }
+    }
+
+    /// Returns the amount of memory that it was able to de-fragment
+    pub fn defragment(&mut self) -> usize {
+        self.segments.iter_mut().map(|s| s.defragment()).sum()
+    }
+
+    /// Returns the amount of memory freed
+    pub fn garbage_collect(&mut self) -> usize {
+        self.segments.iter_mut().map(|s| s.garbage_collect()).sum()
+    }
+
+    /// Returns the number of segments currently allocated.
+    pub fn segments(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Returns the allocation statistics of each segment.
+    pub fn stats(&self) -> Vec<SharedMemorySegmentStats> {
+        self.segments.iter().map(|s| s.stats()).collect()
+    }
+}
+
+impl fmt::Debug for SharedMemoryManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedMemoryManager")
+            .field("id", &self.id)
+            .field("segment_size", &self.segment_size)
+            .field("max_segments", &self.max_segments)
+            .field("segments", &self.segments)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shm_manager_grow() {
+        const SIZE: usize = 4 * 1_024;
+
+        let mut shm =
+            SharedMemoryManager::with_segments("test_shm_manager_grow".to_string(), SIZE, 2)
+                .unwrap();
+        assert_eq!(shm.segments(), 1);
+
+        // Fill the first segment
+        let a = shm.alloc(SIZE).unwrap();
+        assert_eq!(shm.segments(), 1);
+
+        // A new segment is added on demand
+        let b = shm.alloc(SIZE).unwrap();
+        assert_eq!(shm.segments(), 2);
+        assert_ne!(a.owner(), b.owner());
+
+        // The cap is reached
+        assert!(shm.alloc(SIZE).is_none());
+        assert_eq!(shm.segments(), 2);
+
+        // Released buffers are automatically garbage collected
+        drop(a);
+        let c = shm.alloc(SIZE).unwrap();
+        assert_eq!(shm.segments(), 2);
+
+        // The receiver maps the additional segment transparently
+        let mut reader = SharedMemoryReader::new();
+        for buf in [&b, &c].iter() {
+            let r = reader.read_shmbuf(&buf.info).unwrap();
+            assert_eq!(r.len(), buf.len());
+            // Reading does not increment the reference count
+            std::mem::forget(r);
+        }
+
+        let stats = shm.stats();
+        assert_eq!(stats.len(), 2);
+        assert!(stats.iter().all(|s| s.busy_chunks == 1));
+    }
+
+    #[test]
+    fn shm_manager_alloc_wait() {
+        const SIZE: usize = 4 * 1_024;
+
+        async_std::task::block_on(async {
+            let mut shm =
+                SharedMemoryManager::new("test_shm_manager_alloc_wait".to_string(), SIZE).unwrap();
+            let a = shm.alloc(SIZE).unwrap();
+
+            // Nothing is released: the allocation times out
+            let res = shm.alloc_wait(SIZE, Duration::from_millis(50)).await;
+            assert!(res.is_none());
+
+            // The buffer is released while waiting
+            let release = async_std::task::spawn(async move {
+                async_std::task::sleep(Duration::from_millis(50)).await;
+                drop(a);
+            });
+            let res = shm.alloc_wait(SIZE, Duration::from_secs(5)).await;
+            assert!(res.is_some());
+            release.await;
+        });
+    }
+}
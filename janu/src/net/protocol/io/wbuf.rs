@@ -33,6 +33,12 @@ use std::ops::RangeBounds;
 //      - the internal Vec<u8> is initialized with the specified capacity but can be expended
 //      - user can get the WBuf content as a list of IoSlices
 //      - user can request the copy any sub-part of WBuf into a slice using copy_into_slice()
+//    - bounded (a non-contiguous flavor):
+//      - it is initialized with a fixed capacity and won't be extended
+//      - the capacity accounts for both the internal Vec<u8> and the ZSlices
+//      - if a write exceeds capacity, 'false' is returned
+//      - ZSlices at least as large as a given threshold are added to the slices list => 0-copy,
+//        smaller ZSlices are copied into the internal Vec<u8>
 
 #[derive(Clone)]
 enum Slice {
@@ -63,6 +69,7 @@ impl fmt::Debug for Slice {
 struct WBufMark {
     slices: Vec<Slice>,
     buf_idx: usize,
+    external_len: usize,
 }
 
 #[derive(Clone)]
@@ -73,6 +80,8 @@ pub struct WBuf {
     capacity: usize,
     copy_pos: (usize, usize), // (index in slices, index in the slice)
     mark: WBufMark,
+    zslice_threshold: Option<usize>, // Only for the bounded flavor
+    external_len: usize,             // The total length of the ZSlices in the slices list
 }
 
 impl WBuf {
@@ -83,33 +92,43 @@ impl WBuf {
             mark: WBufMark {
                 slices: slices.clone(),
                 buf_idx: 0,
+                external_len: 0,
             },
             slices,
             buf,
             contiguous,
             capacity,
             copy_pos: (0, 0),
+            zslice_threshold: None,
+            external_len: 0,
         }
     }
 
+    /// Create a bounded [`WBuf`][WBuf]: its total length never exceeds `capacity`, and the
+    /// ZSlices of at least `zslice_threshold` bytes are referenced instead of being copied.
+    pub fn new_bounded(capacity: usize, zslice_threshold: usize) -> WBuf {
+        let mut wbuf = WBuf::new(capacity, false);
+        wbuf.zslice_threshold = Some(zslice_threshold);
+        wbuf
+    }
+
     #[inline]
     pub fn capacity(&self) -> usize {
-        self.buf.capacity()
+        if self.is_bounded() {
+            self.capacity
+        } else {
+            self.buf.capacity()
+        }
     }
 
-    fn slice_len(&self, s: &Slice) -> usize {
-        match s {
-            Slice::External(s) => s.len(),
-            Slice::Internal(start, Some(end)) => end - start,
-            Slice::Internal(start, None) => self.buf.len() - start,
-        }
+    #[inline]
+    fn is_bounded(&self) -> bool {
+        self.zslice_threshold.is_some()
     }
 
     #[inline]
     pub fn len(&self) -> usize {
-        self.slices
-            .iter()
-            .fold(0, |acc, slice| acc + self.slice_len(slice))
+        self.buf.len() + self.external_len
     }
 
     #[inline]
@@ -122,8 +141,10 @@ impl WBuf {
         self.slices.clear();
         self.slices.push(Slice::Internal(0, None));
         self.copy_pos = (0, 0);
+        self.external_len = 0;
         self.mark.slices.clear();
         self.mark.buf_idx = 0;
+        self.mark.external_len = 0;
     }
 
     pub fn as_zslices(&self) -> Vec<ZSlice> {
@@ -260,7 +281,7 @@ impl WBuf {
         if src.len() - self.copy_pos.1 >= dest_len {
             // Copy a sub-part of src into dest
             let end_pos = self.copy_pos.1 + dest_len;
-            if !(dest.write_bytes(&src[self.copy_pos.1..end_pos])) {
+            if !self.write_sub_slice_into_wbuf(dest, end_pos) {
                 panic!("Failed to copy bytes into wbuf: destination is probably not big enough");
             };
             // Move copy_pos
@@ -272,7 +293,7 @@ impl WBuf {
         } else {
             // Copy the remaining of src into dest
             let copy_len = src.len() - self.copy_pos.1;
-            if !(dest.write_bytes(&src[self.copy_pos.1..])) {
+            if !self.write_sub_slice_into_wbuf(dest, src.len()) {
                 panic!("Failed to copy bytes into wbuf: destination is probably not big enough");
             };
             // Move copy_pos to next slice and recurse
@@ -281,11 +302,23 @@ impl WBuf {
         }
     }
 
+    // Write the current slice to copy up to end_pos into dest: the ZSlices are
+    // passed by reference so that dest copies them only if needed
+    fn write_sub_slice_into_wbuf(&self, dest: &mut WBuf, end_pos: usize) -> bool {
+        match self.slices.get(self.copy_pos.0) {
+            Some(Slice::External(s)) => {
+                dest.write_zslice(s.new_sub_slice(self.copy_pos.1, end_pos))
+            }
+            _ => dest.write_bytes(&self.get_zslice_to_copy()[self.copy_pos.1..end_pos]),
+        }
+    }
+
     #[inline]
     pub fn mark(&mut self) {
         self.mark.slices.clear();
         self.mark.slices.extend_from_slice(self.slices.as_slice());
         self.mark.buf_idx = self.buf.len();
+        self.mark.external_len = self.external_len;
     }
 
     #[inline]
@@ -294,14 +327,19 @@ impl WBuf {
         self.slices.clear();
         self.slices.extend_from_slice(self.mark.slices.as_slice());
         self.buf.truncate(self.mark.buf_idx);
+        self.external_len = self.mark.external_len;
     }
 
     #[inline]
     fn can_write_in_buf(&self, size: usize) -> bool {
         // We can write in buf if
-        //   - non contiguous
-        //   - OR: writing won't exceed buf capacity
-        !self.contiguous || self.buf.len() + size <= self.buf.capacity()
+        //   - non contiguous and not bounded
+        //   - OR: writing won't exceed the capacity
+        if self.is_bounded() {
+            self.len() + size <= self.capacity
+        } else {
+            !self.contiguous || self.buf.len() + size <= self.buf.capacity()
+        }
     }
 
     pub fn write(&mut self, b: u8) -> bool {
@@ -325,7 +363,16 @@ impl WBuf {
 
     // NOTE: if not-contiguous, this is 0-copy (the slice is just added to slices list)
     //       otherwise, it's a copy into buf, if doesn't exceed the capacity.
+    //       If bounded, this is 0-copy only for ZSlices larger than the threshold.
     pub fn write_zslice(&mut self, zslice: ZSlice) -> bool {
+        if let Some(threshold) = self.zslice_threshold {
+            if zslice.len() < threshold {
+                return self.write_bytes(zslice.as_slice());
+            }
+            if !self.can_write_in_buf(zslice.len()) {
+                return false;
+            }
+        }
         if !self.contiguous {
             // If last slice was an internal without end, set it
             if let Some(&mut Slice::Internal(start, None)) = self.slices.last_mut() {
@@ -334,6 +381,7 @@ impl WBuf {
                     .push(Slice::Internal(start, Some(self.buf.len())));
             }
             // Push the ZSlice in slices list
+            self.external_len += zslice.len();
             self.slices.push(Slice::External(zslice));
             // Push a new internal slice ready for future writes
             self.slices.push(Slice::Internal(self.buf.len(), None));
@@ -883,4 +931,63 @@ mod tests {
         buf.copy_into_wbuf(&mut copy, 4);
         assert_eq!(to_vec_vec!(copy), &[[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]]);
     }
+
+    #[test]
+    fn wbuf_bounded_write_zslice() {
+        let mut buf = WBuf::new_bounded(8, 3);
+        assert!(buf.write(0));
+        // Smaller than the threshold: copied
+        assert!(buf.write_zslice(ZSlice::from(&[1u8, 2] as &[u8])));
+        assert_eq!(to_vec_vec!(buf), [[0, 1, 2]]);
+        // As large as the threshold: referenced
+        assert!(buf.write_zslice(ZSlice::from(&[3u8, 4, 5] as &[u8])));
+        assert_eq!(buf.len(), 6);
+        assert_eq!(buf.capacity(), 8);
+        assert_eq!(to_vec_vec!(buf), [vec![0, 1, 2], vec![3, 4, 5]]);
+
+        // The capacity accounts for the referenced slices
+        assert!(!buf.write_zslice(ZSlice::from(&[6u8, 7, 8] as &[u8])));
+        assert!(!buf.write_bytes(&[6, 7, 8]));
+        assert!(buf.write_bytes(&[6, 7]));
+        assert_eq!(buf.len(), 8);
+        assert_eq!(to_vec_vec!(buf), [vec![0, 1, 2], vec![3, 4, 5], vec![6, 7]]);
+        assert!(!buf.write(8));
+
+        // also test clear()
+        buf.clear();
+        assert_eq!(buf.len(), 0);
+        assert!(buf.is_empty());
+        assert_eq!(buf.capacity(), 8);
+        assert!(vec_vec_is_empty!(buf));
+    }
+
+    #[test]
+    fn wbuf_bounded_mark_reset() {
+        let mut buf = WBuf::new_bounded(8, 2);
+        assert!(buf.write(0));
+        buf.mark();
+        assert!(buf.write_zslice(ZSlice::from(&[1u8, 2, 3] as &[u8])));
+        assert_eq!(buf.len(), 4);
+        buf.revert();
+        assert_eq!(buf.len(), 1);
+        assert_eq!(to_vec_vec!(buf), [[0]]);
+
+        assert!(buf.write_zslice(ZSlice::from(&[1u8, 2, 3, 4, 5, 6, 7] as &[u8])));
+        assert_eq!(buf.len(), 8);
+    }
+
+    #[test]
+    fn wbuf_bounded_copy_into_wbuf() {
+        let mut buf = WBuf::new(6, false);
+        assert!(buf.write(0));
+        assert!(buf.write_zslice(ZSlice::from(&[1u8, 2, 3, 4, 5] as &[u8])));
+
+        // The sub-slices of the referenced slices are referenced as well
+        let mut copy = WBuf::new_bounded(6, 2);
+        buf.copy_into_wbuf(&mut copy, 2);
+        assert_eq!(to_vec_vec!(copy), [[0, 1]]);
+        buf.copy_into_wbuf(&mut copy, 4);
+        assert_eq!(to_vec_vec!(copy), [vec![0, 1], vec![2, 3, 4, 5]]);
+        assert_eq!(copy.len(), 6);
+    }
 }
@@ -12,7 +12,7 @@
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
 #[cfg(feature = "zero-copy")]
-use super::shm::{SharedMemoryBuf, SharedMemoryReader};
+use super::shm::{SharedMemoryBuf, SharedMemoryReader, SharedMemoryRelease};
 use super::ZSlice;
 #[cfg(feature = "zero-copy")]
 use super::ZSliceBuffer;
@@ -455,7 +455,11 @@ impl ZBuf {
 
     #[cfg(feature = "zero-copy")]
     #[inline(never)]
-    pub(crate) fn map_to_shmbuf(&mut self, shmr: Arc<RwLock<SharedMemoryReader>>) -> ZResult<bool> {
+    pub(crate) fn map_to_shmbuf(
+        &mut self,
+        shmr: Arc<RwLock<SharedMemoryReader>>,
+        acquire: bool,
+    ) -> ZResult<bool> {
         if !self.has_shminfo() {
             return Ok(false);
         }
@@ -465,12 +469,12 @@ impl ZBuf {
         let mut res = false;
         match &mut self.slices {
             ZBufInner::Single(s) => {
-                res = s.map_to_shmbuf(shmr)?;
+                res = s.map_to_shmbuf(shmr, acquire)?;
                 self.pos.len += s.len();
             }
             ZBufInner::Multiple(m) => {
                 for s in m.iter_mut() {
-                    res = res || s.map_to_shmbuf(shmr.clone())?;
+                    res = s.map_to_shmbuf(shmr.clone(), acquire)? || res;
                     self.pos.len += s.len();
                 }
             }
@@ -484,7 +488,7 @@ impl ZBuf {
 
     #[cfg(feature = "zero-copy")]
     #[inline(never)]
-    pub(crate) fn map_to_shminfo(&mut self) -> ZResult<bool> {
+    pub(crate) fn map_to_shminfo(&mut self, release: &mut SharedMemoryRelease) -> ZResult<bool> {
         if !self.has_shmbuf() {
             return Ok(false);
         }
@@ -494,12 +498,12 @@ impl ZBuf {
         let mut res = false;
         match &mut self.slices {
             ZBufInner::Single(s) => {
-                res = s.map_to_shminfo()?;
+                res = s.map_to_shminfo(release)?;
                 self.pos.len = s.len();
             }
             ZBufInner::Multiple(m) => {
                 for s in m.iter_mut() {
-                    res = res || s.map_to_shminfo()?;
+                    res = s.map_to_shminfo(release)? || res;
                     self.pos.len += s.len();
                 }
             }
@@ -12,7 +12,7 @@
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
 #[cfg(feature = "zero-copy")]
-use super::{SharedMemoryBuf, SharedMemoryBufInfo, SharedMemoryReader};
+use super::{SharedMemoryBuf, SharedMemoryBufInfo, SharedMemoryReader, SharedMemoryRelease};
 use std::convert::AsRef;
 use std::fmt;
 use std::io::IoSlice;
@@ -24,7 +24,9 @@ use std::sync::Arc;
 use std::sync::RwLock;
 use janu_util::collections::RecyclingObject;
 #[cfg(feature = "zero-copy")]
-use janu_util::core::ZResult;
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+#[cfg(feature = "zero-copy")]
+use janu_util::zerror;
 
 /*************************************/
 /*           ZSLICE BUFFER           */
@@ -199,7 +201,7 @@ impl From<SharedMemoryBuf> for ZSliceBuffer {
 /*************************************/
 /*               ZSLICE              */
 /*************************************/
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ZSliceKind {
     Net,
     Shm,
@@ -279,7 +281,11 @@ impl ZSlice {
 
     #[cfg(feature = "zero-copy")]
     #[inline(never)]
-    pub(crate) fn map_to_shmbuf(&mut self, shmr: Arc<RwLock<SharedMemoryReader>>) -> ZResult<bool> {
+    pub(crate) fn map_to_shmbuf(
+        &mut self,
+        shmr: Arc<RwLock<SharedMemoryReader>>,
+        acquire: bool,
+    ) -> ZResult<bool> {
         match &self.buf {
             ZSliceBuffer::ShmInfo(info) => {
                 // Deserialize the shmb info into shm buff
@@ -293,6 +299,15 @@ impl ZSlice {
                     let mut w_guard = zwrite!(shmr);
                     w_guard.read_shmbuf(&shmbinfo)
                 })?;
+                // Take our own reference if the sender did not hand one over to us
+                if acquire && !smb.try_inc_ref_count() {
+                    // The buffer has already been released by the sender, or its chunk
+                    // reallocated: no reference has been taken, thus none must be released
+                    std::mem::forget(smb);
+                    return zerror!(ZErrorKind::SharedMemoryError {
+                        descr: format!("Shared memory buffer expired: {:?}", shmbinfo)
+                    });
+                }
 
                 // Replace the content of the slice
                 self.buf = ZSliceBuffer::ShmBuffer(smb.into());
@@ -307,13 +322,16 @@ impl ZSlice {
 
     #[cfg(feature = "zero-copy")]
     #[inline(never)]
-    pub(crate) fn map_to_shminfo(&mut self) -> ZResult<bool> {
+    pub(crate) fn map_to_shminfo(&mut self, release: &mut SharedMemoryRelease) -> ZResult<bool> {
         match &self.buf {
             ZSliceBuffer::ShmBuffer(shmb) => {
                 // Serialize the shmb info
                 let info = shmb.info.serialize()?;
-                // Increase the reference count so to keep the SharedMemoryBuf valid
-                shmb.inc_ref_count();
+                // Take a reference so to keep the SharedMemoryBuf valid until released
+                match release {
+                    SharedMemoryRelease::Reader => shmb.inc_ref_count(),
+                    SharedMemoryRelease::Sender(retained) => retained.push((**shmb).clone()),
+                }
                 // Replace the content of the slice
                 self.buf = ZSliceBuffer::ShmInfo(info.into());
                 // Update the indexes
@@ -34,6 +34,11 @@ pub mod defaults {
     ///       the boundary of the serialized messages. The length is encoded as little-endian.
     ///       In any case, the length of a message must not exceed 65_535 bytes.
     pub const BATCH_SIZE: u16 = u16::MAX;
+
+    /// NOTE: Stream-oriented transports may agree during the INIT handshake on a batch size larger than
+    ///       65_535 bytes. In that case, 32 bits (4 bytes) are prepended to the serialized batch instead
+    ///       of 16 bits. This is the maximum batch size that can be agreed: 16 MiB.
+    pub const BATCH_SIZE_MAX: u32 = 16 * 1_024 * 1_024;
 }
 
 pub mod data_kind {
@@ -63,7 +68,7 @@ pub mod encoding {
     use janu_util::zerror;
 
     lazy_static! {
-    static ref MIMES: [Mime; 20] = [
+    static ref MIMES: [Mime; 26] = [
         /*  0 */ Mime::from_str("application/octet-stream").unwrap(),
         /*  1 */ Mime::from_str("application/custom").unwrap(), // non iana standard
         /*  2 */ Mime::from_str("text/plain").unwrap(),
@@ -84,6 +89,12 @@ pub mod encoding {
         /* 17 */ Mime::from_str("image/jpeg").unwrap(),
         /* 18 */ Mime::from_str("image/png").unwrap(),
         /* 19 */ Mime::from_str("image/gif").unwrap(),
+        /* 20 */ Mime::from_str("application/cbor").unwrap(),
+        /* 21 */ Mime::from_str("application/msgpack").unwrap(), // non iana standard
+        /* 22 */ Mime::from_str("application/int64").unwrap(), // non iana standard - little-endian
+        /* 23 */ Mime::from_str("application/float64").unwrap(), // non iana standard - little-endian
+        /* 24 */ Mime::from_str("application/int64-array").unwrap(), // non iana standard - little-endian
+        /* 25 */ Mime::from_str("application/float64-array").unwrap(), // non iana standard - little-endian
     ];
     }
 
@@ -127,6 +138,12 @@ pub mod encoding {
             "image/jpeg" => Ok(IMG_JPG),
             "image/png" => Ok(IMG_PNG),
             "image/gif" => Ok(IMG_GIF),
+            "application/cbor" => Ok(APP_CBOR),
+            "application/msgpack" | "application/x-msgpack" => Ok(APP_MSGPACK),
+            "application/int64" => Ok(APP_INT64),
+            "application/float64" => Ok(APP_FLOAT64),
+            "application/int64-array" => Ok(APP_INT64_ARRAY),
+            "application/float64-array" => Ok(APP_FLOAT64_ARRAY),
             s => zerror!(ZErrorKind::Other {
                 descr: format!("Unknown encoding '{}'", s)
             }),
@@ -155,6 +172,12 @@ pub mod encoding {
     pub const IMG_JPG: ZInt = 17;
     pub const IMG_PNG: ZInt = 18;
     pub const IMG_GIF: ZInt = 19;
+    pub const APP_CBOR: ZInt = 20;
+    pub const APP_MSGPACK: ZInt = 21;
+    pub const APP_INT64: ZInt = 22;
+    pub const APP_FLOAT64: ZInt = 23;
+    pub const APP_INT64_ARRAY: ZInt = 24;
+    pub const APP_FLOAT64_ARRAY: ZInt = 25;
 
     pub const DEFAULT: ZInt = APP_OCTET_STREAM;
 }
@@ -126,6 +126,9 @@ pub mod tmsg {
         use super::ZInt;
 
         pub const QOS: ZInt = 1 << 0; // 0x01 QoS       if PRIORITY==1 then the transport supports QoS
+        pub const LARGE_BATCH: ZInt = 1 << 1; // 0x02 LargeBatch if LARGE_BATCH==1 then the batch size is encoded in the options
+
+        pub const BATCH_SIZE_SHIFT: u32 = 8; // The batch size is encoded in the options starting from this bit
     }
 
     pub mod join_options {
@@ -248,6 +251,8 @@ pub mod zmsg {
         pub const PID: ZInt = 1; // 0x01
         pub const WAI: ZInt = 1 << 1; // 0x02
         pub const LOC: ZInt = 1 << 2; // 0x04
+        pub const COS: ZInt = 1 << 3; // 0x08
+        pub const ACC: ZInt = 1 << 4; // 0x10
     }
 
     pub mod conduit {
@@ -1013,7 +1018,7 @@ impl Header for Query {
 
 //  7 6 5 4 3 2 1 0
 // +-+-+-+-+-+-+-+-+
-// ~X|X|X|X|X|L|W|P~
+// ~X|X|X|A|C|L|W|P~
 // +-+-+-+-+-+-+-+-+
 // ~     psid      ~
 // +---------------+
@@ -1027,6 +1032,12 @@ impl Header for Query {
 // +---------------+
 // ~    [links]    ~
 // +---------------+
+// ~    [costs]    ~ if C == 1
+// +---------------+
+//
+// - costs: if present, the cost of each link in [links], in the same order.
+// - A: the sender accepts link states with costs. Since the costs are not skipped by
+//      the decoders unaware of them, they are only sent to the nodes that set this flag.
 #[derive(Debug, Clone, PartialEq)]
 pub struct LinkState {
     pub psid: ZInt,
@@ -1035,6 +1046,8 @@ pub struct LinkState {
     pub whatami: Option<WhatAmI>,
     pub locators: Option<Vec<Locator>>,
     pub links: Vec<ZInt>,
+    pub costs: Option<Vec<ZInt>>,
+    pub accept_costs: bool,
 }
 
 impl Options for LinkState {
@@ -1049,6 +1062,12 @@ impl Options for LinkState {
         if self.locators.is_some() {
             opts |= zmsg::link_state::LOC;
         }
+        if self.costs.is_some() {
+            opts |= zmsg::link_state::COS;
+        }
+        if self.accept_costs {
+            opts |= zmsg::link_state::ACC;
+        }
         opts
     }
 
@@ -1418,7 +1437,7 @@ impl fmt::Display for Hello {
 /// +-+-+-+-+-+-+-+-+
 /// |O|S|A|   INIT  |
 /// +-+-+-+-+-------+
-/// ~             |Q~ if O==1
+/// ~ batch_size|L|Q~ if O==1
 /// +---------------+
 /// | v_maj | v_min | if A==0 -- Protocol Version VMaj.VMin
 /// +-------+-------+
@@ -1435,6 +1454,12 @@ impl fmt::Display for Hello {
 ///     if A==1 and S==0 then the agreed resolution is the one communicated by the initiator.
 ///
 /// - if Q==1 then the initiator/responder support QoS.
+/// - if L==1 then the initiator/responder support streamed batches larger than 65_535 bytes.
+///   The batch size is encoded in the options starting from bit 8. If A==0 it is the maximum
+///   batch size supported by the initiator, if A==1 it is the batch size agreed by the responder.
+///   Once the transport is opened, batches on stream-oriented links are prepended by 32 bits
+///   (4 bytes) instead of 16 bits indicating their length. Peers that do not support large batches
+///   ignore the L option and keep using the 16-bit length.
 /// ```
 #[derive(Debug, Clone, PartialEq)]
 pub struct InitSyn {
@@ -1443,6 +1468,7 @@ pub struct InitSyn {
     pub pid: PeerId,
     pub sn_resolution: ZInt,
     pub is_qos: bool,
+    pub batch_size: Option<u32>,
 }
 
 impl Header for InitSyn {
@@ -1465,11 +1491,15 @@ impl Options for InitSyn {
         if self.is_qos {
             options |= tmsg::init_options::QOS;
         }
+        if let Some(batch_size) = self.batch_size {
+            options |= tmsg::init_options::LARGE_BATCH;
+            options |= (batch_size as ZInt) << tmsg::init_options::BATCH_SIZE_SHIFT;
+        }
         options
     }
 
     fn has_options(&self) -> bool {
-        self.is_qos
+        self.is_qos || self.batch_size.is_some()
     }
 }
 
@@ -1479,6 +1509,7 @@ pub struct InitAck {
     pub pid: PeerId,
     pub sn_resolution: Option<ZInt>,
     pub is_qos: bool,
+    pub batch_size: Option<u32>,
     pub cookie: ZSlice,
 }
 
@@ -1503,11 +1534,15 @@ impl Options for InitAck {
         if self.is_qos {
             options |= tmsg::init_options::QOS;
         }
+        if let Some(batch_size) = self.batch_size {
+            options |= tmsg::init_options::LARGE_BATCH;
+            options |= (batch_size as ZInt) << tmsg::init_options::BATCH_SIZE_SHIFT;
+        }
         options
     }
 
     fn has_options(&self) -> bool {
-        self.is_qos
+        self.is_qos || self.batch_size.is_some()
     }
 }
 
@@ -2039,6 +2074,7 @@ impl TransportMessage {
         pid: PeerId,
         sn_resolution: ZInt,
         is_qos: bool,
+        batch_size: Option<u32>,
         attachment: Option<Attachment>,
     ) -> TransportMessage {
         TransportMessage {
@@ -2048,6 +2084,7 @@ impl TransportMessage {
                 pid,
                 sn_resolution,
                 is_qos,
+                batch_size,
             }),
             attachment,
             #[cfg(feature = "stats")]
@@ -2060,6 +2097,7 @@ impl TransportMessage {
         pid: PeerId,
         sn_resolution: Option<ZInt>,
         is_qos: bool,
+        batch_size: Option<u32>,
         cookie: ZSlice,
         attachment: Option<Attachment>,
     ) -> TransportMessage {
@@ -2069,6 +2107,7 @@ impl TransportMessage {
                 pid,
                 sn_resolution,
                 is_qos,
+                batch_size,
                 cookie,
             }),
             attachment,
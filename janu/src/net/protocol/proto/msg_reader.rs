@@ -15,7 +15,7 @@ use super::core::*;
 use super::defaults::SEQ_NUM_RES;
 use super::io::ZBuf;
 use super::msg::*;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::time::Duration;
 
 impl ZBuf {
@@ -206,6 +206,11 @@ impl ZBuf {
             SEQ_NUM_RES
         };
         let is_qos = imsg::has_option(options, tmsg::init_options::QOS);
+        let batch_size = if imsg::has_option(options, tmsg::init_options::LARGE_BATCH) {
+            Some(u32::try_from(options >> tmsg::init_options::BATCH_SIZE_SHIFT).ok()?)
+        } else {
+            None
+        };
 
         Some(TransportBody::InitSyn(InitSyn {
             version,
@@ -213,6 +218,7 @@ impl ZBuf {
             pid,
             sn_resolution,
             is_qos,
+            batch_size,
         }))
     }
 
@@ -230,6 +236,11 @@ impl ZBuf {
             None
         };
         let is_qos = imsg::has_option(options, tmsg::init_options::QOS);
+        let batch_size = if imsg::has_option(options, tmsg::init_options::LARGE_BATCH) {
+            Some(u32::try_from(options >> tmsg::init_options::BATCH_SIZE_SHIFT).ok()?)
+        } else {
+            None
+        };
         let cookie = self.read_zslice_array()?;
 
         Some(TransportBody::InitAck(InitAck {
@@ -237,6 +248,7 @@ impl ZBuf {
             pid,
             sn_resolution,
             is_qos,
+            batch_size,
             cookie,
         }))
     }
@@ -722,6 +734,16 @@ impl ZBuf {
         for _ in 0..len {
             links.push(self.read_zint()?);
         }
+        let costs = if imsg::has_option(options, zmsg::link_state::COS) {
+            let mut costs: Vec<ZInt> = Vec::with_capacity(links.len());
+            for _ in 0..links.len() {
+                costs.push(self.read_zint()?);
+            }
+            Some(costs)
+        } else {
+            None
+        };
+        let accept_costs = imsg::has_option(options, zmsg::link_state::ACC);
 
         Some(LinkState {
             psid,
@@ -730,6 +752,8 @@ impl ZBuf {
             whatami,
             locators,
             links,
+            costs,
+            accept_costs,
         })
     }
 
@@ -774,6 +798,18 @@ impl ZBuf {
             }
             2 => Some(Target::All),
             3 => Some(Target::None),
+            4 => {
+                let n = self.read_zint()?;
+                let strategy = match self.read_zint()? {
+                    0 => BalancingStrategy::RoundRobin,
+                    1 => BalancingStrategy::LeastPending,
+                    unknown => {
+                        log::trace!("Invalid balancing strategy: {}", unknown);
+                        return None;
+                    }
+                };
+                Some(Target::Balanced { n, strategy })
+            }
             id => {
                 log::trace!("UNEXPECTED ID FOR Target: {}", id);
                 None
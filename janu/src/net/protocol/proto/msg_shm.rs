@@ -12,7 +12,7 @@
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
 #[cfg(feature = "zero-copy")]
-use super::io::SharedMemoryReader;
+use super::io::{SharedMemoryReader, SharedMemoryRelease};
 #[cfg(feature = "zero-copy")]
 use super::msg::*;
 #[cfg(feature = "zero-copy")]
@@ -55,11 +55,15 @@ macro_rules! set_sliced {
 
 #[cfg(feature = "zero-copy")]
 impl JanuMessage {
-    pub(crate) fn map_to_shmbuf(&mut self, shmr: Arc<RwLock<SharedMemoryReader>>) -> ZResult<bool> {
+    pub(crate) fn map_to_shmbuf(
+        &mut self,
+        shmr: Arc<RwLock<SharedMemoryReader>>,
+        acquire: bool,
+    ) -> ZResult<bool> {
         let mut res = false;
 
         if let Some(attachment) = self.attachment.as_mut() {
-            res = attachment.buffer.map_to_shmbuf(shmr.clone())?;
+            res = attachment.buffer.map_to_shmbuf(shmr.clone(), acquire)?;
         }
 
         if let JanuBody::Data(Data {
@@ -67,7 +71,7 @@ impl JanuMessage {
         }) = &mut self.body
         {
             if payload.has_shminfo() {
-                res = res || payload.map_to_shmbuf(shmr)?;
+                res = payload.map_to_shmbuf(shmr, acquire)? || res;
                 unset_sliced!(self, data_info);
             }
         }
@@ -75,11 +79,11 @@ impl JanuMessage {
         Ok(res)
     }
 
-    pub(crate) fn map_to_shminfo(&mut self) -> ZResult<bool> {
+    pub(crate) fn map_to_shminfo(&mut self, release: &mut SharedMemoryRelease) -> ZResult<bool> {
         let mut res = false;
 
         if let Some(attachment) = self.attachment.as_mut() {
-            res = attachment.buffer.map_to_shminfo()?;
+            res = attachment.buffer.map_to_shminfo(release)?;
         }
 
         if let JanuBody::Data(Data {
@@ -87,7 +91,7 @@ impl JanuMessage {
         }) = &mut self.body
         {
             if payload.has_shmbuf() {
-                res = res || payload.map_to_shminfo()?;
+                res = payload.map_to_shminfo(release)? || res;
                 set_sliced!(self, data_info);
             }
         }
@@ -99,21 +103,25 @@ impl JanuMessage {
 #[allow(dead_code)]
 #[cfg(feature = "zero-copy")]
 impl TransportMessage {
-    pub(crate) fn map_to_shmbuf(&mut self, shmr: Arc<RwLock<SharedMemoryReader>>) -> ZResult<bool> {
+    pub(crate) fn map_to_shmbuf(
+        &mut self,
+        shmr: Arc<RwLock<SharedMemoryReader>>,
+        acquire: bool,
+    ) -> ZResult<bool> {
         let mut res = false;
 
         if let Some(attachment) = self.attachment.as_mut() {
-            res = attachment.buffer.map_to_shmbuf(shmr)?;
+            res = attachment.buffer.map_to_shmbuf(shmr, acquire)?;
         }
 
         Ok(res)
     }
 
-    pub(crate) fn map_to_shminfo(&mut self) -> ZResult<bool> {
+    pub(crate) fn map_to_shminfo(&mut self, release: &mut SharedMemoryRelease) -> ZResult<bool> {
         let mut res = false;
 
         if let Some(attachment) = self.attachment.as_mut() {
-            res = attachment.buffer.map_to_shminfo()?;
+            res = attachment.buffer.map_to_shminfo(release)?;
         }
 
         Ok(res)
@@ -506,6 +506,11 @@ impl WBuf {
         for link in &link_state.links {
             zcheck!(self.write_zint(*link));
         }
+        if let Some(costs) = link_state.costs.as_ref() {
+            for cost in costs {
+                zcheck!(self.write_zint(*cost));
+            }
+        }
 
         true
     }
@@ -522,6 +527,14 @@ impl WBuf {
             Target::Complete { n } => self.write_zint(1 as ZInt) && self.write_zint(*n),
             Target::All => self.write_zint(2 as ZInt),
             Target::None => self.write_zint(3 as ZInt),
+            Target::Balanced { n, strategy } => {
+                self.write_zint(4 as ZInt)
+                    && self.write_zint(*n)
+                    && self.write_zint(match strategy {
+                        BalancingStrategy::RoundRobin => 0,
+                        BalancingStrategy::LeastPending => 1,
+                    })
+            }
         }
     }
 
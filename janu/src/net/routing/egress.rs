@@ -0,0 +1,470 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::face::FaceState;
+use super::protocol::core::{rname, whatami, Channel, CongestionControl, ResKey};
+use super::protocol::io::ZBuf;
+use super::protocol::proto::{DataInfo, RoutingContext};
+use crate::net::link::Locator;
+use async_std::sync::{Arc, Weak};
+use janu_util::zlock;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+const RATE_SUFFIX: &str = "msg/s";
+const BANDWIDTH_SUFFIX: &str = "B/s";
+
+/// The limit enforced by an [`EgressPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EgressLimit {
+    /// At most one message per key every `1/rate` second. Messages exceeding the rate
+    /// are dropped, except the latest one which is sent as soon as the rate allows it.
+    Rate(f64),
+    /// At most the given number of payload bytes per second (with bursts of up to
+    /// one second worth of bytes). Messages exceeding the bandwidth are dropped.
+    Bandwidth(u64),
+}
+
+impl fmt::Display for EgressLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EgressLimit::Rate(rate) => write!(f, "{}{}", rate, RATE_SUFFIX),
+            EgressLimit::Bandwidth(bw) => write!(f, "{}{}", bw, BANDWIDTH_SUFFIX),
+        }
+    }
+}
+
+struct Pending {
+    reskey: ResKey,
+    payload: ZBuf,
+    channel: Channel,
+    congestion_control: CongestionControl,
+    info: Option<DataInfo>,
+    routing_context: Option<RoutingContext>,
+}
+
+struct RateState {
+    last: Option<Instant>,
+    pending: Option<Pending>,
+}
+
+struct BandwidthState {
+    tokens: f64,
+    last: Instant,
+}
+
+// The number of rate states above which the idle ones are evicted.
+const RATE_STATES_SWEEP_LEN: usize = 64;
+
+struct EgressStates {
+    rates: HashMap<(usize, String), RateState>,
+    rates_sweep_len: usize,
+    bandwidths: HashMap<usize, BandwidthState>,
+}
+
+impl EgressStates {
+    // Evicts the rate states without pending message whose interval elapsed,
+    // which behave as missing ones.
+    fn sweep_rates(&mut self, interval: Duration, now: Instant) {
+        if self.rates.len() > self.rates_sweep_len {
+            self.rates.retain(|_, state| {
+                state.pending.is_some() || state.last.is_some_and(|last| now < last + interval)
+            });
+            self.rates_sweep_len = RATE_STATES_SWEEP_LEN.max(2 * self.rates.len());
+        }
+    }
+}
+
+enum Verdict {
+    Admit,
+    // The message must wait for the deadline (or replace the pending one).
+    Defer { deadline: Instant, pending: bool },
+    Drop,
+}
+
+/// An egress policy limiting the data routed to some faces on some key expression.
+pub struct EgressPolicy {
+    pub keyexpr: String,
+    pub face: Option<String>,
+    pub limit: EgressLimit,
+    forwarded: AtomicU64,
+    dropped: AtomicU64,
+    states: Mutex<EgressStates>,
+}
+
+impl EgressPolicy {
+    pub fn new(keyexpr: String, face: Option<String>, limit: EgressLimit) -> EgressPolicy {
+        EgressPolicy {
+            keyexpr,
+            face,
+            limit,
+            forwarded: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            states: Mutex::new(EgressStates {
+                rates: HashMap::new(),
+                rates_sweep_len: RATE_STATES_SWEEP_LEN,
+                bandwidths: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Parses a list of egress policies with the format
+    /// `<keyexpr>=<limit>[@<face>][,<keyexpr>=<limit>[@<face>]]*`
+    /// where `<limit>` is either `<number>msg/s` or `<number>B/s`.
+    /// Invalid policies are logged and ignored.
+    pub fn parse_list(s: &str) -> Vec<Arc<EgressPolicy>> {
+        s.split(',')
+            .filter(|s| !s.trim().is_empty())
+            .filter_map(|s| {
+                let res = EgressPolicy::parse(s);
+                if res.is_none() {
+                    log::error!("Invalid egress policy configuration: {}", s);
+                }
+                res.map(Arc::new)
+            })
+            .collect()
+    }
+
+    fn parse(s: &str) -> Option<EgressPolicy> {
+        let (keyexpr, limit) = s.split_once('=')?;
+        let keyexpr = keyexpr.trim();
+        if !keyexpr.starts_with('/') {
+            return None;
+        }
+        let (limit, face) = match limit.split_once('@') {
+            Some((limit, face)) => (limit.trim(), Some(face.trim().to_string())),
+            None => (limit.trim(), None),
+        };
+        let limit = if let Some(rate) = limit.strip_suffix(RATE_SUFFIX) {
+            let rate = rate.trim().parse::<f64>().ok()?;
+            if !rate.is_finite() || rate <= 0.0 {
+                return None;
+            }
+            EgressLimit::Rate(rate)
+        } else if let Some(bw) = limit.strip_suffix(BANDWIDTH_SUFFIX) {
+            EgressLimit::Bandwidth(bw.trim().parse::<u64>().ok()?)
+        } else {
+            return None;
+        };
+        Some(EgressPolicy::new(keyexpr.to_string(), face, limit))
+    }
+
+    /// Returns `true` if this policy applies to the face with the given peer,
+    /// kind and link locators. A face selector matches a face if it is equal to
+    /// its peer id, its kind (`router`, `peer` or `client`) or is a prefix of one
+    /// of its source or destination locators (e.g. `udp/192.168.1.`).
+    pub(crate) fn applies_to(&self, face: &FaceState, locators: &[Locator]) -> bool {
+        match &self.face {
+            None => true,
+            Some(selector) => {
+                *selector == face.pid.to_string()
+                    || whatami::parse(&selector.to_lowercase()).ok() == Some(face.whatami)
+                    || locators
+                        .iter()
+                        .any(|locator| locator.to_string().starts_with(selector.as_str()))
+            }
+        }
+    }
+
+    /// Releases the states kept for the given face.
+    pub(crate) fn forget_face(&self, face_id: usize) {
+        let mut states = zlock!(self.states);
+        states.rates.retain(|(id, _), _| *id != face_id);
+        states.bandwidths.remove(&face_id);
+    }
+
+    /// The number of messages admitted by this policy.
+    #[inline]
+    pub fn forwarded(&self) -> u64 {
+        self.forwarded.load(Ordering::Relaxed)
+    }
+
+    /// The number of messages dropped by this policy.
+    #[inline]
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn check(
+        &self,
+        states: &EgressStates,
+        face_id: usize,
+        name: &str,
+        len: usize,
+        now: Instant,
+    ) -> Verdict {
+        match self.limit {
+            EgressLimit::Bandwidth(bytes_per_sec) => {
+                let capacity = bytes_per_sec as f64;
+                let tokens = states.bandwidths.get(&face_id).map_or(capacity, |state| {
+                    (state.tokens + now.duration_since(state.last).as_secs_f64() * capacity)
+                        .min(capacity)
+                });
+                if tokens >= len as f64 {
+                    Verdict::Admit
+                } else {
+                    Verdict::Drop
+                }
+            }
+            EgressLimit::Rate(rate) => match states.rates.get(&(face_id, name.to_string())) {
+                Some(state) => {
+                    let deadline = state
+                        .last
+                        .map_or(now, |last| last + Duration::from_secs_f64(1.0 / rate));
+                    if state.pending.is_none() && now >= deadline {
+                        Verdict::Admit
+                    } else {
+                        Verdict::Defer {
+                            deadline,
+                            pending: state.pending.is_some(),
+                        }
+                    }
+                }
+                None => Verdict::Admit,
+            },
+        }
+    }
+
+    fn commit(
+        &self,
+        states: &mut EgressStates,
+        face_id: usize,
+        name: &str,
+        len: usize,
+        now: Instant,
+    ) {
+        match self.limit {
+            EgressLimit::Bandwidth(bytes_per_sec) => {
+                let capacity = bytes_per_sec as f64;
+                let state = states.bandwidths.entry(face_id).or_insert(BandwidthState {
+                    tokens: capacity,
+                    last: now,
+                });
+                state.tokens = (state.tokens
+                    + now.duration_since(state.last).as_secs_f64() * capacity)
+                    .min(capacity)
+                    - len as f64;
+                state.last = now;
+            }
+            EgressLimit::Rate(rate) => {
+                states.sweep_rates(Duration::from_secs_f64(1.0 / rate), now);
+                states
+                    .rates
+                    .entry((face_id, name.to_string()))
+                    .or_insert(RateState {
+                        last: None,
+                        pending: None,
+                    })
+                    .last = Some(now);
+            }
+        }
+        self.forwarded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Keeps the latest message and sends it once the deadline is reached.
+    fn defer(
+        self: &Arc<Self>,
+        states: &mut EgressStates,
+        face: &Arc<FaceState>,
+        name: &str,
+        deadline: Instant,
+        pending: Pending,
+    ) {
+        let key = (face.id, name.to_string());
+        let state = states.rates.entry(key.clone()).or_insert(RateState {
+            last: None,
+            pending: None,
+        });
+        if state.pending.replace(pending).is_some() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        } else {
+            let policy = Arc::downgrade(self);
+            let face = Arc::downgrade(face);
+            async_std::task::spawn(async move {
+                async_std::task::sleep(deadline.saturating_duration_since(Instant::now())).await;
+                flush_pending(policy, face, key);
+            });
+        }
+    }
+}
+
+// Sends the pending message once the policy that deferred it allows it, if the
+// other policies of the face still admit it.
+fn flush_pending(policy: Weak<EgressPolicy>, face: Weak<FaceState>, key: (usize, String)) {
+    if let Some(policy) = policy.upgrade() {
+        let pending = {
+            let mut states = zlock!(policy.states);
+            match face.upgrade() {
+                Some(_) => states
+                    .rates
+                    .get_mut(&key)
+                    .and_then(|state| state.pending.take()),
+                None => {
+                    states.rates.remove(&key);
+                    None
+                }
+            }
+        };
+        if let (Some(face), Some(pending)) = (face.upgrade(), pending) {
+            if admit(
+                &face,
+                &key.1,
+                &pending.reskey,
+                &pending.payload,
+                pending.channel,
+                pending.congestion_control,
+                &pending.info,
+                pending.routing_context,
+            ) {
+                face.primitives.send_data(
+                    &pending.reskey,
+                    pending.payload,
+                    pending.channel,
+                    pending.congestion_control,
+                    pending.info,
+                    pending.routing_context,
+                );
+            }
+        }
+    }
+}
+
+/// Applies the egress policies of `face` to a data with key `name`.
+/// Returns `true` if the data can be sent right away on `face`.
+/// The data is only accounted for by the policies if all of them admit it.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn admit(
+    face: &Arc<FaceState>,
+    name: &str,
+    reskey: &ResKey,
+    payload: &ZBuf,
+    channel: Channel,
+    congestion_control: CongestionControl,
+    info: &Option<DataInfo>,
+    routing_context: Option<RoutingContext>,
+) -> bool {
+    let policies: Vec<&Arc<EgressPolicy>> = face
+        .egress
+        .iter()
+        .filter(|policy| rname::intersect(&policy.keyexpr, name))
+        .collect();
+    if policies.is_empty() {
+        return true;
+    }
+    // The states of all the policies are locked (always in the same order) so that
+    // they are checked and updated atomically
+    let mut states: Vec<MutexGuard<'_, EgressStates>> = policies
+        .iter()
+        .map(|policy| zlock!(policy.states))
+        .collect();
+    let now = Instant::now();
+    let len = payload.len();
+    let verdicts: Vec<Verdict> = policies
+        .iter()
+        .zip(states.iter())
+        .map(|(policy, states)| policy.check(states, face.id, name, len, now))
+        .collect();
+
+    let mut dropped = false;
+    for (policy, verdict) in policies.iter().zip(verdicts.iter()) {
+        if let Verdict::Drop = verdict {
+            policy.dropped.fetch_add(1, Ordering::Relaxed);
+            dropped = true;
+        }
+    }
+    if dropped {
+        return false;
+    }
+
+    // A deferred message replaces the pending one if any, or waits for the
+    // latest deadline
+    let deferred = verdicts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, verdict)| match verdict {
+            Verdict::Defer { deadline, pending } => Some((i, *pending, *deadline)),
+            _ => None,
+        })
+        .max_by_key(|(_, pending, deadline)| (*pending, *deadline));
+    if let Some((i, _, deadline)) = deferred {
+        let pending = Pending {
+            reskey: reskey.clone(),
+            payload: payload.clone(),
+            channel,
+            congestion_control,
+            info: info.clone(),
+            routing_context,
+        };
+        policies[i].defer(&mut states[i], face, name, deadline, pending);
+        return false;
+    }
+
+    for (policy, states) in policies.iter().zip(states.iter_mut()) {
+        policy.commit(states, face.id, name, len, now);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn egress_rate_states_sweep() {
+        let interval = Duration::from_millis(100);
+        let now = Instant::now();
+        let mut states = EgressStates {
+            rates: HashMap::new(),
+            rates_sweep_len: RATE_STATES_SWEEP_LEN,
+            bandwidths: HashMap::new(),
+        };
+        let state = |last: Instant, pending: bool| RateState {
+            last: Some(last),
+            pending: if pending {
+                Some(Pending {
+                    reskey: ResKey::RName("/test".to_string()),
+                    payload: ZBuf::new(),
+                    channel: Channel::default(),
+                    congestion_control: CongestionControl::default(),
+                    info: None,
+                    routing_context: None,
+                })
+            } else {
+                None
+            },
+        };
+        for i in 0..RATE_STATES_SWEEP_LEN {
+            states
+                .rates
+                .insert((0, i.to_string()), state(now - interval, false));
+        }
+        states.sweep_rates(interval, now);
+        assert_eq!(states.rates.len(), RATE_STATES_SWEEP_LEN);
+
+        // Only the idle states are evicted
+        states
+            .rates
+            .insert((1, "busy".to_string()), state(now, false));
+        states
+            .rates
+            .insert((1, "pending".to_string()), state(now - interval, true));
+        states.sweep_rates(interval, now);
+        assert_eq!(states.rates.len(), 2);
+        assert!(states.rates.contains_key(&(1, "busy".to_string())));
+        assert!(states.rates.contains_key(&(1, "pending".to_string())));
+        assert_eq!(states.rates_sweep_len, RATE_STATES_SWEEP_LEN);
+    }
+}
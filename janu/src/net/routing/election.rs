@@ -0,0 +1,233 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::protocol::core::{rname, whatami, PeerId, ZInt};
+use super::router::Tables;
+use petgraph::graph::NodeIndex;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::zerror;
+
+/// A router that can be elected to route some data or query between
+/// the router mesh and the peer mesh.
+#[derive(Clone, Copy)]
+pub struct Candidate<'a> {
+    pub pid: &'a PeerId,
+    /// The sum of the link costs of the cheapest path from the source of the
+    /// data or query to this router, or `None` if it is unknown.
+    pub cost: Option<ZInt>,
+}
+
+/// A strategy electing, among the routers that are part of both the router mesh
+/// and the peer mesh, the one in charge of routing some data or query between both meshes.
+///
+/// All the routers must elect the same router for the same resource name and
+/// candidates, so a strategy must be deterministic and must not depend on
+/// the local router.
+pub trait RouterElection: Send + Sync {
+    /// The name of this strategy.
+    fn name(&self) -> &str;
+
+    /// Elects a router among the given non empty list of candidates.
+    fn elect<'a>(&self, res_name: &str, candidates: &[Candidate<'a>]) -> &'a PeerId;
+}
+
+/// Elects the router with the highest hash of the resource name and its id.
+///
+/// When a router joins or leaves, only the resources for which it is
+/// or becomes the elected router are reassigned.
+#[derive(Default)]
+pub struct HashElection;
+
+impl RouterElection for HashElection {
+    fn name(&self) -> &str {
+        "hash"
+    }
+
+    fn elect<'a>(&self, res_name: &str, candidates: &[Candidate<'a>]) -> &'a PeerId {
+        hash_elect(res_name, candidates.iter().map(|c| c.pid))
+    }
+}
+
+/// Elects the router closest (by link cost) to the source of the data or query.
+/// Among the routers at the same cost, the one with the lowest id is elected.
+/// If no cost is known, the router is elected as with [`HashElection`].
+#[derive(Default)]
+pub struct ClosestElection;
+
+impl RouterElection for ClosestElection {
+    fn name(&self) -> &str {
+        "closest"
+    }
+
+    fn elect<'a>(&self, res_name: &str, candidates: &[Candidate<'a>]) -> &'a PeerId {
+        candidates
+            .iter()
+            .filter_map(|c| Some((c.cost?, c.pid)))
+            .min_by(|(c1, pid1), (c2, pid2)| {
+                c1.cmp(c2)
+                    .then_with(|| pid1.as_slice().cmp(pid2.as_slice()))
+            })
+            .map(|(_, pid)| pid)
+            .unwrap_or_else(|| hash_elect(res_name, candidates.iter().map(|c| c.pid)))
+    }
+}
+
+/// Elects the home router configured for the resource name.
+/// The first home whose key expression intersects the resource name is used.
+/// If no home matches or if the home router is not a candidate, the router is
+/// elected as with [`HashElection`].
+#[derive(Default)]
+pub struct HomeElection {
+    homes: Vec<(String, PeerId)>,
+}
+
+impl HomeElection {
+    pub fn new(homes: Vec<(String, PeerId)>) -> HomeElection {
+        HomeElection { homes }
+    }
+
+    /// Parses a list of homes with the format
+    /// `<keyexpr>=<router id>[,<keyexpr>=<router id>]*`.
+    /// Invalid homes are logged and ignored.
+    pub fn parse_list(s: &str) -> HomeElection {
+        HomeElection::new(
+            s.split(',')
+                .filter(|s| !s.trim().is_empty())
+                .filter_map(|s| {
+                    let res = s.split_once('=').and_then(|(keyexpr, pid)| {
+                        let keyexpr = keyexpr.trim();
+                        if !keyexpr.starts_with('/') {
+                            return None;
+                        }
+                        Some((keyexpr.to_string(), parse_pid(pid.trim())?))
+                    });
+                    if res.is_none() {
+                        log::error!("Invalid router home configuration: {}", s);
+                    }
+                    res
+                })
+                .collect(),
+        )
+    }
+
+    /// The configured homes.
+    pub fn homes(&self) -> &[(String, PeerId)] {
+        &self.homes
+    }
+}
+
+impl RouterElection for HomeElection {
+    fn name(&self) -> &str {
+        "home"
+    }
+
+    fn elect<'a>(&self, res_name: &str, candidates: &[Candidate<'a>]) -> &'a PeerId {
+        self.homes
+            .iter()
+            .find(|(keyexpr, _)| rname::intersect(keyexpr, res_name))
+            .and_then(|(_, home)| candidates.iter().find(|c| c.pid == home))
+            .map(|c| c.pid)
+            .unwrap_or_else(|| hash_elect(res_name, candidates.iter().map(|c| c.pid)))
+    }
+}
+
+/// Creates the router election strategy with the given name
+/// (`hash`, `closest` or `home`) and the given homes.
+pub fn from_config(strategy: &str, homes: Option<&str>) -> ZResult<Box<dyn RouterElection>> {
+    match strategy.trim() {
+        "hash" => Ok(Box::new(HashElection)),
+        "closest" => Ok(Box::new(ClosestElection)),
+        "home" => Ok(Box::new(HomeElection::parse_list(homes.unwrap_or("")))),
+        s => zerror!(ZErrorKind::Other {
+            descr: format!("Unknown router election strategy: {}", s)
+        }),
+    }
+}
+
+fn parse_pid(s: &str) -> Option<PeerId> {
+    let bytes = hex::decode(s.replace('-', "")).ok()?;
+    if bytes.is_empty() || bytes.len() > PeerId::MAX_SIZE {
+        return None;
+    }
+    let mut id = [0u8; PeerId::MAX_SIZE];
+    id[..bytes.len()].copy_from_slice(&bytes);
+    Some(PeerId::new(bytes.len(), id))
+}
+
+fn hash_elect<'a, I>(res_name: &str, routers: I) -> &'a PeerId
+where
+    I: Iterator<Item = &'a PeerId>,
+{
+    routers
+        .map(|router| {
+            let mut hasher = DefaultHasher::new();
+            for b in res_name.as_bytes() {
+                hasher.write_u8(*b);
+            }
+            for b in router.as_slice() {
+                hasher.write_u8(*b);
+            }
+            (router, hasher.finish())
+        })
+        .max_by(|(_, s1), (_, s2)| s1.cmp(s2))
+        .unwrap()
+        .0
+}
+
+/// Elects the router in charge of routing the data or query with the given
+/// resource name and source between the router mesh and the peer mesh.
+pub(super) fn elect_router<'a>(
+    tables: &'a Tables,
+    res_name: &str,
+    source: Option<usize>,
+    source_type: whatami::Type,
+) -> &'a PeerId {
+    let routers = &tables.shared_nodes;
+    if routers.len() == 1 {
+        return &routers[0];
+    }
+    // The costs are computed from the same graph by all the routers evaluating
+    // the same data or query, whatever the mesh it was received from: the router
+    // graph if the source is a router and the peer graph otherwise.
+    let source = match source_type {
+        whatami::ROUTER => tables
+            .routers_net
+            .as_ref()
+            .zip(source)
+            .and_then(|(net, source)| net.graph.node_weight(NodeIndex::new(source)))
+            .map(|node| node.pid),
+        whatami::PEER => tables
+            .peers_net
+            .as_ref()
+            .zip(source)
+            .and_then(|(net, source)| net.graph.node_weight(NodeIndex::new(source)))
+            .map(|node| node.pid),
+        _ => Some(tables.pid),
+    };
+    let net = match (&tables.routers_net, &tables.peers_net) {
+        (Some(net), _) if source.is_some_and(|pid| net.get_idx(&pid).is_some()) => Some(net),
+        (_, net) => net.as_ref(),
+    };
+    let candidates: Vec<Candidate> = routers
+        .iter()
+        .map(|pid| Candidate {
+            pid,
+            cost: net
+                .zip(source.as_ref())
+                .and_then(|(net, source)| net.cost(source, pid)),
+        })
+        .collect();
+    tables.router_election.elect(res_name, &candidates)
+}
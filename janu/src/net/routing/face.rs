@@ -11,6 +11,7 @@
 // Contributors:
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
+use super::egress::EgressPolicy;
 use super::protocol::core::{
     whatami, Channel, CongestionControl, PeerId, QueryConsolidation, QueryTarget, ResKey, SubInfo,
     WhatAmI, ZInt,
@@ -20,6 +21,7 @@ use super::protocol::proto::{DataInfo, RoutingContext};
 use super::router::*;
 use super::transport::Primitives;
 use async_std::sync::Arc;
+use serde_json::json;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::sync::RwLock;
@@ -38,6 +40,7 @@ pub struct FaceState {
     pub(super) remote_qabls: HashSet<Arc<Resource>>,
     pub(super) next_qid: ZInt,
     pub(super) pending_queries: HashMap<ZInt, Arc<Query>>,
+    pub(super) egress: Vec<Arc<EgressPolicy>>,
 }
 
 impl FaceState {
@@ -62,6 +65,7 @@ impl FaceState {
             remote_qabls: HashSet::new(),
             next_qid: 0,
             pending_queries: HashMap::new(),
+            egress: Vec::new(),
         })
     }
 
@@ -83,6 +87,56 @@ impl FaceState {
     }
 }
 
+impl FaceState {
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        let names = |set: &mut dyn Iterator<Item = &Arc<Resource>>| {
+            let mut names: Vec<String> = set.map(|res| res.name()).collect();
+            names.sort();
+            names
+        };
+        let subscribers: Vec<serde_json::Value> = self
+            .remote_subs
+            .iter()
+            .map(|res| {
+                let sub_info = res
+                    .session_ctxs
+                    .get(&self.id)
+                    .and_then(|ctx| ctx.subs.as_ref());
+                json!({
+                    "key": res.name(),
+                    "mode": sub_info.map(|info| format!("{:?}", info.mode)),
+                    "reliability": sub_info.map(|info| format!("{:?}", info.reliability)),
+                    "period": sub_info.and_then(|info| info.period).map(|period| json!({
+                        "origin": period.origin,
+                        "period": period.period,
+                        "duration": period.duration,
+                    })),
+                })
+            })
+            .collect();
+        let queryables: Vec<serde_json::Value> = self
+            .remote_qabls
+            .iter()
+            .map(|res| {
+                json!({
+                    "key": res.name(),
+                    "kind": res.session_ctxs.get(&self.id).and_then(|ctx| ctx.qabl),
+                })
+            })
+            .collect();
+        json!({
+            "id": self.id,
+            "pid": self.pid.to_string(),
+            "whatami": whatami::to_string(self.whatami),
+            "link_id": self.link_id,
+            "subscribers": subscribers,
+            "queryables": queryables,
+            "local_subscribers": names(&mut self.local_subs.iter()),
+            "local_queryables": names(&mut self.local_qabls.keys()),
+        })
+    }
+}
+
 impl fmt::Display for FaceState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Face{{{}, {}}}", self.id, self.pid)
@@ -142,7 +196,8 @@ impl Primitives for Face {
                         suffix,
                         sub_info,
                         router,
-                    )
+                    );
+                    schedule_net_samplers(&self.tables, &tables);
                 }
 
                 None => {
@@ -177,20 +232,30 @@ impl Primitives for Face {
                         suffix,
                         sub_info,
                         peer,
-                    )
+                    );
+                    schedule_net_samplers(&self.tables, &tables);
                 }
 
                 None => {
                     log::error!("Received peer subscription with no routing context");
                 }
             },
-            _ => declare_client_subscription(
-                &mut tables,
-                &mut self.state.clone(),
-                prefixid,
-                suffix,
-                sub_info,
-            ),
+            _ => {
+                declare_client_subscription(
+                    &mut tables,
+                    &mut self.state.clone(),
+                    prefixid,
+                    suffix,
+                    sub_info,
+                );
+                schedule_periodic_subscription(
+                    &self.tables,
+                    &tables,
+                    &self.state,
+                    prefixid,
+                    suffix,
+                );
+            }
         }
     }
 
@@ -11,6 +11,8 @@
 // Contributors:
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
+pub mod egress;
+pub mod election;
 pub mod face;
 pub mod network;
 pub mod pubsub;
@@ -11,15 +11,79 @@
 // Contributors:
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
-use super::link::Locator;
-use super::protocol::core::{whatami, PeerId, ZInt};
+use super::link::{EndPoint, Locator, LocatorAddress};
+use super::protocol::core::{whatami, PeerId, WhatAmI, ZInt};
 use super::protocol::proto::{LinkState, JanuMessage};
+use super::runtime::advertisement::LocatorsAdvertisement;
+use super::runtime::orchestrator::Autoconnect;
 use super::runtime::Runtime;
 use super::transport::TransportUnicast;
 use petgraph::graph::NodeIndex;
-use petgraph::visit::{IntoNodeReferences, VisitMap, Visitable};
+use petgraph::visit::{EdgeRef, IntoNodeReferences, VisitMap, Visitable};
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use vec_map::VecMap;
+use janu_util::properties::config::ZN_LINK_COSTS_KEY;
+use janu_util::{zconfigurable, zlock};
+
+zconfigurable! {
+    // The cost of a link whose round-trip time has not been measured yet
+    static ref LINK_COST_DEFAULT: ZInt = 100;
+    // The minimal relative change (in percent) of the measured cost of a link before
+    // the new cost is advertised, to avoid route flapping
+    static ref LINK_COST_HYSTERESIS: ZInt = 25;
+    // The initial and maximal delays in milliseconds before connecting again to a locator
+    // of a node learned through the link states, the delay doubling after each failure
+    static ref GOSSIP_CONNECT_RETRY_INITIAL: u64 = 2_000;
+    static ref GOSSIP_CONNECT_RETRY_MAX: u64 = 60_000;
+}
+
+// Link costs are expressed in units of 100 microseconds of round-trip time
+const LINK_COST_UNIT_US: u128 = 100;
+
+#[inline]
+fn rtt_to_cost(rtt: Duration) -> ZInt {
+    ((rtt.as_micros() / LINK_COST_UNIT_US) as ZInt).max(1)
+}
+
+fn parse_link_costs(s: &str) -> Vec<(LocatorAddress, ZInt)> {
+    s.split(',')
+        .filter(|s| !s.trim().is_empty())
+        .filter_map(|s| {
+            let res = s.rsplit_once('=').and_then(|(locator, cost)| {
+                let locator = locator.trim().parse::<Locator>().ok()?;
+                let cost = cost.trim().parse::<ZInt>().ok()?;
+                Some((locator.address, cost.max(1)))
+            });
+            if res.is_none() {
+                log::error!("Invalid link cost configuration: {}", s);
+            }
+            res
+        })
+        .collect()
+}
+
+// Returns the static cost configured for a link established over the given locators
+fn static_link_cost(
+    link_costs: &[(LocatorAddress, ZInt)],
+    links: &[super::link::Link],
+) -> Option<ZInt> {
+    link_costs.iter().find_map(|(address, cost)| {
+        links
+            .iter()
+            .any(|l| l.src.address == *address || l.dst.address == *address)
+            .then_some(*cost)
+    })
+}
+
+// Returns true if the measured cost of a link differs enough from the advertised one
+#[inline]
+fn link_cost_changed(advertised: ZInt, measured: ZInt) -> bool {
+    let delta = advertised.abs_diff(measured);
+    delta > 1 && delta * 100 > advertised * *LINK_COST_HYSTERESIS
+}
 
 pub(crate) struct Node {
     pub(crate) pid: PeerId,
@@ -27,6 +91,7 @@ pub(crate) struct Node {
     pub(crate) locators: Option<Vec<Locator>>,
     pub(crate) sn: ZInt,
     pub(crate) links: Vec<PeerId>,
+    pub(crate) costs: Option<HashMap<PeerId, ZInt>>,
 }
 
 impl std::fmt::Debug for Node {
@@ -38,18 +103,29 @@ impl std::fmt::Debug for Node {
 pub(crate) struct Link {
     pub(crate) transport: TransportUnicast,
     pid: PeerId,
+    whatami: WhatAmI,
     mappings: VecMap<PeerId>,
     local_mappings: VecMap<ZInt>,
+    cost: ZInt,
+    static_cost: bool,
+    // Whether the link states sent on this link may carry costs
+    accept_costs: bool,
 }
 
 impl Link {
-    fn new(transport: TransportUnicast) -> Self {
+    fn new(transport: TransportUnicast, link_costs: &[(LocatorAddress, ZInt)]) -> Self {
         let pid = transport.get_pid().unwrap();
+        let whatami = transport.get_whatami().unwrap();
+        let static_cost = static_link_cost(link_costs, &transport.get_links().unwrap_or_default());
         Link {
             transport,
             pid,
+            whatami,
             mappings: VecMap::new(),
             local_mappings: VecMap::new(),
+            cost: static_cost.unwrap_or(*LINK_COST_DEFAULT),
+            static_cost: static_cost.is_some(),
+            accept_costs: false,
         }
     }
 
@@ -80,6 +156,44 @@ pub(crate) struct Tree {
     pub(crate) parent: Option<NodeIndex>,
     pub(crate) childs: Vec<NodeIndex>,
     pub(crate) directions: Vec<Option<NodeIndex>>,
+    pub(crate) costs: Vec<Option<ZInt>>,
+}
+
+// The time before connecting again to a locator after failing to connect to it
+struct ConnectBackoff {
+    delay: u64,
+    next: Instant,
+}
+
+// The nodes this node is connecting to and the backoffs of the locators it failed to
+// connect to
+#[derive(Default)]
+struct Connector {
+    connecting: HashSet<PeerId>,
+    backoffs: HashMap<LocatorAddress, ConnectBackoff>,
+}
+
+impl Connector {
+    fn is_ready(&self, address: &LocatorAddress, now: Instant) -> bool {
+        match self.backoffs.get(address) {
+            Some(backoff) => backoff.next <= now,
+            None => true,
+        }
+    }
+
+    fn failed(&mut self, address: LocatorAddress, now: Instant) {
+        let backoff = self
+            .backoffs
+            .entry(address)
+            .and_modify(|backoff| {
+                backoff.delay = (backoff.delay * 2).min(*GOSSIP_CONNECT_RETRY_MAX)
+            })
+            .or_insert(ConnectBackoff {
+                delay: *GOSSIP_CONNECT_RETRY_INITIAL,
+                next: now,
+            });
+        backoff.next = now + Duration::from_millis(backoff.delay);
+    }
 }
 
 pub(crate) struct Network {
@@ -91,6 +205,10 @@ pub(crate) struct Network {
     pub(crate) trees: Vec<Tree>,
     pub(crate) graph: petgraph::stable_graph::StableUnGraph<Node, f64>,
     pub(crate) runtime: Runtime,
+    link_costs: Vec<(LocatorAddress, ZInt)>,
+    autoconnect: Autoconnect,
+    connector: Arc<Mutex<Connector>>,
+    advertisement: LocatorsAdvertisement,
 }
 
 impl Network {
@@ -109,7 +227,15 @@ impl Network {
             locators: None,
             sn: 1,
             links: vec![],
+            costs: Some(HashMap::new()),
         });
+        let link_costs = runtime
+            .config
+            .get(&ZN_LINK_COSTS_KEY)
+            .map(|s| parse_link_costs(s))
+            .unwrap_or_default();
+        let autoconnect = Autoconnect::from_config(&runtime.config, runtime.whatami);
+        let advertisement = LocatorsAdvertisement::from_config(&runtime.config);
         Network {
             name,
             peers_autoconnect,
@@ -120,9 +246,14 @@ impl Network {
                 parent: None,
                 childs: vec![],
                 directions: vec![None],
+                costs: vec![Some(0)],
             }],
             graph,
             runtime,
+            link_costs,
+            autoconnect,
+            connector: Arc::new(Mutex::new(Connector::default())),
+            advertisement,
         }
     }
 
@@ -158,9 +289,11 @@ impl Network {
             .unwrap()
     }
 
+    // Returns the locators of the local node advertised to a node of the given kind
     #[inline]
-    fn get_locators(&self) -> Vec<Locator> {
-        self.runtime.manager().get_locators()
+    fn get_locators(&self, whatami: WhatAmI) -> Vec<Locator> {
+        self.advertisement
+            .locators(&self.runtime.manager().get_locators(), whatami)
     }
 
     fn add_node(&mut self, node: Node) -> NodeIndex {
@@ -174,13 +307,123 @@ impl Network {
         idx
     }
 
-    fn make_link_state(&self, idx: NodeIndex, details: bool) -> LinkState {
-        let links = self.graph[idx]
+    #[inline]
+    fn autoconnect_discovered(&self) -> bool {
+        (self.peers_autoconnect && self.runtime.whatami == whatami::PEER)
+            || (self.routers_autoconnect_gossip && self.runtime.whatami == whatami::ROUTER)
+    }
+
+    // Connects to the given discovered nodes that are selected by the autoconnect
+    // configuration and that this node is neither connected nor connecting to,
+    // through their locators that are not backing off from a failed connection.
+    fn connect_discovered<I: IntoIterator<Item = NodeIndex>>(&self, idxs: I) {
+        if !self.autoconnect_discovered() {
+            return;
+        }
+        for idx in idxs {
+            let node = match self.graph.node_weight(idx) {
+                Some(node) => node,
+                None => continue,
+            };
+            if idx == self.idx
+                || !self.autoconnect.matches(node.whatami)
+                || self.links.values().any(|link| link.pid == node.pid)
+            {
+                continue;
+            }
+            if let Some(locators) = &node.locators {
+                let locators = self.autoconnect.locators(locators);
+                if locators.is_empty() {
+                    log::trace!(
+                        "{} No allowed locator to connect to {}",
+                        self.name,
+                        node.pid
+                    );
+                    continue;
+                }
+                let pid = node.pid;
+                let locators = {
+                    let mut connector = zlock!(self.connector);
+                    let now = Instant::now();
+                    let locators: Vec<Locator> = locators
+                        .into_iter()
+                        .filter(|locator| connector.is_ready(&locator.address, now))
+                        .collect();
+                    if locators.is_empty() || !connector.connecting.insert(pid) {
+                        continue;
+                    }
+                    locators
+                };
+                let runtime = self.runtime.clone();
+                let connector = self.connector.clone();
+                async_std::task::spawn(async move {
+                    // random backoff
+                    async_std::task::sleep(std::time::Duration::from_millis(
+                        rand::random::<u64>() % 100,
+                    ))
+                    .await;
+                    for locator in locators {
+                        if runtime.manager().get_transport(&pid).is_some() {
+                            break;
+                        }
+                        let endpoint = EndPoint {
+                            locator: locator.clone(),
+                            config: None,
+                        };
+                        match runtime.manager().open_transport(endpoint).await {
+                            Ok(_) => {
+                                log::debug!("Successfully connected to {} on {}", pid, locator);
+                                zlock!(connector).backoffs.remove(&locator.address);
+                                break;
+                            }
+                            Err(e) => {
+                                log::debug!("Unable to connect to {} on {}: {}", pid, locator, e);
+                                zlock!(connector).failed(locator.address, Instant::now());
+                            }
+                        }
+                    }
+                    zlock!(connector).connecting.remove(&pid);
+                });
+            }
+        }
+    }
+
+    /// Connects to all the known nodes selected by the autoconnect configuration that this
+    /// node is not connected to, so that the nodes that could not be connected when they
+    /// were discovered, or that got disconnected since, are connected again.
+    pub(crate) fn connect_known(&self) {
+        // Forget the backoffs of the locators of the nodes no longer in the graph
+        let known: HashSet<&LocatorAddress> = self
+            .graph
+            .node_indices()
+            .filter_map(|idx| self.graph[idx].locators.as_ref())
+            .flatten()
+            .map(|locator| &locator.address)
+            .collect();
+        zlock!(self.connector)
+            .backoffs
+            .retain(|address, _| known.contains(address));
+        self.connect_discovered(self.graph.node_indices().collect::<Vec<NodeIndex>>());
+    }
+
+    fn make_link_state(
+        &self,
+        idx: NodeIndex,
+        details: bool,
+        whatami: WhatAmI,
+        accept_costs: bool,
+    ) -> LinkState {
+        let node_costs = self.graph[idx].costs.as_ref();
+        let (links, costs): (Vec<ZInt>, Vec<ZInt>) = self.graph[idx]
             .links
             .iter()
             .filter_map(|pid| {
                 if let Some(idx2) = self.get_idx(pid) {
-                    Some(idx2.index().try_into().unwrap())
+                    let cost = node_costs
+                        .and_then(|costs| costs.get(pid).copied())
+                        .unwrap_or(*LINK_COST_DEFAULT);
+                    let psid: ZInt = idx2.index().try_into().unwrap();
+                    Some((psid, cost))
                 } else {
                     log::error!(
                         "{} Internal error building link state: cannot get index of {}",
@@ -190,7 +433,7 @@ impl Network {
                     None
                 }
             })
-            .collect();
+            .unzip();
         LinkState {
             psid: idx.index().try_into().unwrap(),
             sn: self.graph[idx].sn,
@@ -200,27 +443,34 @@ impl Network {
                 None
             },
             whatami: Some(self.graph[idx].whatami),
+            // The locators of the other nodes are stored as received and filtered
+            // for the kind of the node they are sent to, like the local ones
             locators: if idx == self.idx {
-                Some(self.get_locators())
+                Some(self.get_locators(whatami))
             } else {
-                self.graph[idx].locators.clone()
+                self.graph[idx]
+                    .locators
+                    .as_ref()
+                    .map(|locators| self.advertisement.filter(locators, whatami))
             },
             links,
+            costs: node_costs.filter(|_| accept_costs).map(|_| costs),
+            accept_costs: true,
         }
     }
 
-    fn make_msg(&self, idxs: Vec<(NodeIndex, bool)>) -> JanuMessage {
+    fn make_msg(&self, idxs: &[(NodeIndex, bool)], link: &Link) -> JanuMessage {
         let mut list = vec![];
         for (idx, details) in idxs {
-            list.push(self.make_link_state(idx, details));
+            list.push(self.make_link_state(*idx, *details, link.whatami, link.accept_costs));
         }
         JanuMessage::make_link_state_list(list, None)
     }
 
-    fn send_on_link(&self, idxs: Vec<(NodeIndex, bool)>, transport: &TransportUnicast) {
-        let msg = self.make_msg(idxs);
-        log::trace!("{} Send to {:?} {:?}", self.name, transport.get_pid(), msg);
-        if let Err(e) = transport.handle_message(msg) {
+    fn send_on_link(&self, idxs: Vec<(NodeIndex, bool)>, link: &Link) {
+        let msg = self.make_msg(&idxs, link);
+        log::trace!("{} Send to {} {:?}", self.name, link.pid, msg);
+        if let Err(e) = link.transport.handle_message(msg) {
             log::debug!("{} Error sending LinkStateList: {}", self.name, e);
         }
     }
@@ -229,19 +479,35 @@ impl Network {
     where
         P: FnMut(&Link) -> bool,
     {
-        let msg = self.make_msg(idxs);
         for link in self.links.values() {
             if predicate(link) {
-                log::trace!("{} Send to {} {:?}", self.name, link.pid, msg);
-                if let Err(e) = link.transport.handle_message(msg.clone()) {
-                    log::debug!("{} Error sending LinkStateList: {}", self.name, e);
-                }
+                self.send_on_link(idxs.clone(), link);
             }
         }
     }
 
+    #[inline]
+    fn get_cost(&self, idx1: NodeIndex, idx2: NodeIndex) -> Option<ZInt> {
+        self.graph[idx1]
+            .costs
+            .as_ref()
+            .and_then(|costs| costs.get(&self.graph[idx2].pid).copied())
+    }
+
+    fn edge_cost(&self, idx1: NodeIndex, idx2: NodeIndex) -> ZInt {
+        // Both ends of a link advertise its cost: the highest one is retained so that
+        // all the nodes compute the same weight regardless of the order of the states.
+        match (self.get_cost(idx1, idx2), self.get_cost(idx2, idx1)) {
+            (Some(c1), Some(c2)) => c1.max(c2),
+            (Some(c), None) | (None, Some(c)) => c,
+            (None, None) => *LINK_COST_DEFAULT,
+        }
+    }
+
     fn update_edge(&mut self, idx1: NodeIndex, idx2: NodeIndex) {
         use std::hash::Hasher;
+        let cost = self.edge_cost(idx1, idx2);
+        // The hash of the pids of both ends is used as a deterministic tie-breaker
         let mut hasher = std::collections::hash_map::DefaultHasher::default();
         if self.graph[idx1].pid.as_slice() > self.graph[idx2].pid.as_slice() {
             hasher.write(self.graph[idx2].pid.as_slice());
@@ -250,7 +516,7 @@ impl Network {
             hasher.write(self.graph[idx1].pid.as_slice());
             hasher.write(self.graph[idx2].pid.as_slice());
         }
-        let weight = 100.0 + ((hasher.finish() as u32) as f64) / std::u32::MAX as f64;
+        let weight = cost as f64 + ((hasher.finish() as u32) as f64) / u32::MAX as f64;
         self.graph.update_edge(idx1, idx2, weight);
     }
 
@@ -276,6 +542,11 @@ impl Network {
             }
         };
 
+        // The costs are only sent to the nodes able to decode them: send them the
+        // whole graph again with its costs once they are known to accept them
+        let resend = !src_link.accept_costs && link_states.iter().any(|ls| ls.accept_costs);
+        src_link.accept_costs |= resend;
+
         // register psid<->pid mappings & apply mapping to nodes
         #[allow(clippy::needless_collect)] // need to release borrow on self
         let link_states = link_states
@@ -292,6 +563,7 @@ impl Network {
                         link_state.locators,
                         link_state.sn,
                         link_state.links,
+                        link_state.costs,
                     ))
                 } else {
                     match src_link.get_pid(&link_state.psid) {
@@ -301,6 +573,7 @@ impl Network {
                             link_state.locators,
                             link_state.sn,
                             link_state.links,
+                            link_state.costs,
                         )),
                         None => {
                             log::error!(
@@ -313,17 +586,31 @@ impl Network {
                     }
                 }
             })
-            .collect::<Vec<(PeerId, whatami::Type, Option<Vec<Locator>>, ZInt, Vec<ZInt>)>>();
+            .collect::<Vec<(
+                PeerId,
+                whatami::Type,
+                Option<Vec<Locator>>,
+                ZInt,
+                Vec<ZInt>,
+                Option<Vec<ZInt>>,
+            )>>();
 
         // apply psid<->pid mapping to links
         let src_link = self.get_link_from_pid(&src).unwrap();
         let link_states = link_states
             .into_iter()
-            .map(|(pid, wai, locs, sn, links)| {
+            .map(|(pid, wai, locs, sn, links, costs)| {
+                let mut mapped_costs = costs.as_ref().map(|_| HashMap::new());
                 let links: Vec<PeerId> = links
                     .iter()
-                    .filter_map(|l| {
+                    .enumerate()
+                    .filter_map(|(i, l)| {
                         if let Some(pid) = src_link.get_pid(l) {
+                            if let Some(mapped_costs) = mapped_costs.as_mut() {
+                                if let Some(cost) = costs.as_ref().and_then(|c| c.get(i)) {
+                                    mapped_costs.insert(*pid, *cost);
+                                }
+                            }
                             Some(*pid)
                         } else {
                             log::error!(
@@ -336,7 +623,7 @@ impl Network {
                         }
                     })
                     .collect();
-                (pid, wai, locs, sn, links)
+                (pid, wai, locs, sn, links, mapped_costs)
             })
             .collect::<Vec<(
                 PeerId,
@@ -344,6 +631,7 @@ impl Network {
                 Option<Vec<Locator>>,
                 ZInt,
                 Vec<PeerId>,
+                Option<HashMap<PeerId, ZInt>>,
             )>>();
 
         // log::trace!(
@@ -365,13 +653,14 @@ impl Network {
         let mut link_states = link_states
             .into_iter()
             .filter_map(
-                |(pid, whatami, locators, sn, links)| match self.get_idx(&pid) {
+                |(pid, whatami, locators, sn, links, costs)| match self.get_idx(&pid) {
                     Some(idx) => {
                         let node = &mut self.graph[idx];
                         let oldsn = node.sn;
                         if oldsn < sn {
                             node.sn = sn;
                             node.links = links.clone();
+                            node.costs = costs;
                             if locators.is_some() {
                                 node.locators = locators;
                             }
@@ -391,6 +680,7 @@ impl Network {
                             locators,
                             sn,
                             links: links.clone(),
+                            costs,
                         };
                         log::debug!("{} Add node (state) {}", self.name, pid);
                         let idx = self.add_node(node);
@@ -421,6 +711,7 @@ impl Network {
                         locators: None,
                         sn: 0,
                         links: vec![],
+                        costs: None,
                     };
                     log::debug!("{} Add node (reintroduced) {}", self.name, link.clone());
                     let idx = self.add_node(node);
@@ -448,32 +739,8 @@ impl Network {
             .filter(|ls| !removed.iter().any(|(idx, _)| idx == &ls.1))
             .collect::<Vec<(Vec<PeerId>, NodeIndex, bool)>>();
 
-        if (self.peers_autoconnect && self.runtime.whatami == whatami::PEER)
-            || (self.routers_autoconnect_gossip && self.runtime.whatami == whatami::ROUTER)
-        {
-            // Connect discovered peers
-            for (_, idx, _) in &link_states {
-                let node = &self.graph[*idx];
-                if (self.runtime.whatami == whatami::PEER
-                    && (node.whatami == whatami::PEER || node.whatami == whatami::ROUTER))
-                    || (self.runtime.whatami == whatami::ROUTER && node.whatami == whatami::ROUTER)
-                {
-                    if let Some(locators) = &node.locators {
-                        let runtime = self.runtime.clone();
-                        let pid = node.pid;
-                        let locators = locators.clone();
-                        async_std::task::spawn(async move {
-                            // random backoff
-                            async_std::task::sleep(std::time::Duration::from_millis(
-                                rand::random::<u64>() % 100,
-                            ))
-                            .await;
-                            runtime.connect_peer(&pid, &locators).await;
-                        });
-                    }
-                }
-            }
-        }
+        // Connect discovered nodes
+        self.connect_discovered(link_states.iter().map(|(_, idx, _)| *idx));
 
         // Propagate link states
         // Note: we need to send all states at once for each face
@@ -502,16 +769,19 @@ impl Network {
                         })
                         .collect();
                     if !new_idxs.is_empty() || !updated_idxs.is_empty() {
-                        self.send_on_link(
-                            [&new_idxs[..], &updated_idxs[..]].concat(),
-                            &link.transport,
-                        );
+                        self.send_on_link([&new_idxs[..], &updated_idxs[..]].concat(), link);
                     }
                 } else if !new_idxs.is_empty() {
-                    self.send_on_link(new_idxs.clone(), &link.transport);
+                    self.send_on_link(new_idxs.clone(), link);
                 }
             }
         }
+        if resend {
+            if let Some(link) = self.get_link_from_pid(&src) {
+                let idxs = self.graph.node_indices().map(|i| (i, true)).collect();
+                self.send_on_link(idxs, link);
+            }
+        }
         removed
     }
 
@@ -523,7 +793,13 @@ impl Network {
             }
             i
         };
-        self.links.insert(free_index, Link::new(transport.clone()));
+        let link = Link::new(transport.clone(), &self.link_costs);
+        let cost = link.cost;
+        // The links without a static cost are assigned a cost derived from their round-trip time
+        if !link.static_cost {
+            let _ = transport.enable_rtt();
+        }
+        self.links.insert(free_index, link);
 
         let pid = transport.get_pid().unwrap();
         let whatami = transport.get_whatami().unwrap();
@@ -538,11 +814,16 @@ impl Network {
                         locators: None,
                         sn: 0,
                         links: vec![],
+                        costs: None,
                     }),
                     true,
                 )
             }
         };
+        self.graph[self.idx]
+            .costs
+            .get_or_insert_with(HashMap::new)
+            .insert(pid, cost);
         if self.graph[idx].links.contains(&self.graph[self.idx].pid) {
             log::trace!("Update edge (link) {} {}", self.graph[self.idx].pid, pid);
             self.update_edge(self.idx, idx);
@@ -557,7 +838,7 @@ impl Network {
         }
 
         let idxs = self.graph.node_indices().map(|i| (i, true)).collect();
-        self.send_on_link(idxs, &transport);
+        self.send_on_link(idxs, &self.links[free_index]);
         free_index
     }
 
@@ -565,6 +846,9 @@ impl Network {
         log::trace!("{} remove_link {}", self.name, pid);
         self.links.retain(|_, link| link.pid != *pid);
         self.graph[self.idx].links.retain(|link| *link != *pid);
+        if let Some(costs) = self.graph[self.idx].costs.as_mut() {
+            costs.remove(pid);
+        }
 
         if let Some((edge, _)) = self
             .get_idx(pid)
@@ -577,26 +861,30 @@ impl Network {
 
         self.graph[self.idx].sn += 1;
 
-        let links = self
+        let (links, costs): (Vec<ZInt>, Vec<ZInt>) = self
             .links
             .values()
-            .map(|link| self.get_idx(&link.pid).unwrap().index().try_into().unwrap())
-            .collect::<Vec<ZInt>>();
-
-        let msg = JanuMessage::make_link_state_list(
-            vec![LinkState {
-                psid: self.idx.index().try_into().unwrap(),
-                sn: self.graph[self.idx].sn,
-                pid: None,
-                whatami: Some(self.graph[self.idx].whatami),
-                locators: Some(self.get_locators()),
-                links,
-            }],
-            None,
-        );
+            .map(|link| {
+                let psid: ZInt = self.get_idx(&link.pid).unwrap().index().try_into().unwrap();
+                (psid, link.cost)
+            })
+            .unzip();
 
         for link in self.links.values() {
-            if let Err(e) = link.transport.handle_message(msg.clone()) {
+            let msg = JanuMessage::make_link_state_list(
+                vec![LinkState {
+                    psid: self.idx.index().try_into().unwrap(),
+                    sn: self.graph[self.idx].sn,
+                    pid: None,
+                    whatami: Some(self.graph[self.idx].whatami),
+                    locators: Some(self.get_locators(link.whatami)),
+                    links: links.clone(),
+                    costs: link.accept_costs.then(|| costs.clone()),
+                    accept_costs: true,
+                }],
+                None,
+            );
+            if let Err(e) = link.transport.handle_message(msg) {
                 log::debug!("{} Error sending LinkStateList: {}", self.name, e);
             }
         }
@@ -604,6 +892,62 @@ impl Network {
         removed
     }
 
+    /// Updates the costs of the links from their measured round-trip time and advertises
+    /// them if they changed significantly. Returns `true` if the trees need to be recomputed.
+    pub(crate) fn update_link_costs(&mut self) -> bool {
+        let measured = self
+            .links
+            .values()
+            .filter_map(|link| match link.transport.get_rtt() {
+                Ok(Some(rtt)) => Some((link.pid, rtt_to_cost(rtt))),
+                _ => None,
+            })
+            .collect::<Vec<(PeerId, ZInt)>>();
+        self.update_measured_link_costs(measured)
+    }
+
+    // Applies the measured costs to the links without a static cost, provided that
+    // they differ enough from the advertised ones, and advertises the new costs
+    fn update_measured_link_costs(&mut self, measured: Vec<(PeerId, ZInt)>) -> bool {
+        let mut changed = vec![];
+        for (pid, cost) in measured {
+            let link = match self.links.values_mut().find(|link| link.pid == pid) {
+                Some(link) => link,
+                None => continue,
+            };
+            if !link.static_cost && link_cost_changed(link.cost, cost) {
+                log::debug!(
+                    "{} Update cost of link {}: {} -> {}",
+                    self.name,
+                    link.pid,
+                    link.cost,
+                    cost
+                );
+                link.cost = cost;
+                changed.push((link.pid, cost));
+            }
+        }
+        if changed.is_empty() {
+            return false;
+        }
+
+        let local_pid = self.graph[self.idx].pid;
+        for (pid, cost) in changed {
+            self.graph[self.idx]
+                .costs
+                .get_or_insert_with(HashMap::new)
+                .insert(pid, cost);
+            if let Some(idx) = self.get_idx(&pid) {
+                if self.graph[idx].links.contains(&local_pid) {
+                    self.update_edge(self.idx, idx);
+                }
+            }
+        }
+        self.graph[self.idx].sn += 1;
+        self.send_on_links(vec![(self.idx, false)], |_| true);
+        true
+    }
+
     fn remove_detached_nodes(&mut self) -> Vec<(NodeIndex, Node)> {
         let mut dfs_stack = vec![self.idx];
         let mut visit_map = self.graph.visit_map();
@@ -640,6 +984,7 @@ impl Network {
             parent: None,
             childs: vec![],
             directions: vec![],
+            costs: vec![],
         });
 
         for tree_root_idx in &indexes {
@@ -705,6 +1050,15 @@ impl Network {
                         };
                 }
             }
+
+            // The integer costs are computed apart from the weights of the graph
+            // so that they are not altered by the tie-breakers of the edges
+            let costs = petgraph::algo::dijkstra(&self.graph, *tree_root_idx, None, |edge| {
+                self.edge_cost(edge.source(), edge.target())
+            });
+            self.trees[tree_root_idx.index()].costs = (0..=max_idx.index())
+                .map(|idx| costs.get(&NodeIndex::new(idx)).copied())
+                .collect();
         }
 
         let mut new_childs = Vec::with_capacity(self.trees.len());
@@ -725,6 +1079,19 @@ impl Network {
 
         new_childs
     }
+
+    /// Returns the sum of the link costs of the cheapest path from the node with
+    /// id `source` to the node with id `pid`, or `None` if it is unknown or unreachable.
+    pub(crate) fn cost(&self, source: &PeerId, pid: &PeerId) -> Option<ZInt> {
+        let source = self.get_idx(source)?;
+        let idx = self.get_idx(pid)?;
+        self.trees
+            .get(source.index())?
+            .costs
+            .get(idx.index())
+            .copied()
+            .flatten()
+    }
 }
 
 #[inline]
@@ -739,3 +1106,202 @@ pub(super) fn shared_nodes(net1: &Network, net2: &Network) -> Vec<PeerId> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::link::Link as TransportLink;
+
+    fn transport_link(src: &str, dst: &str) -> TransportLink {
+        TransportLink {
+            src: src.parse().unwrap(),
+            dst: dst.parse().unwrap(),
+            group: None,
+            mtu: u16::MAX,
+            is_reliable: true,
+            is_streamed: true,
+        }
+    }
+
+    #[test]
+    fn link_cost_from_rtt() {
+        assert_eq!(rtt_to_cost(Duration::from_micros(0)), 1);
+        assert_eq!(rtt_to_cost(Duration::from_micros(150)), 1);
+        assert_eq!(rtt_to_cost(Duration::from_millis(10)), 100);
+    }
+
+    #[test]
+    fn link_cost_hysteresis() {
+        // Changes below the hysteresis are not advertised
+        assert!(!link_cost_changed(100, 100));
+        assert!(!link_cost_changed(100, 125));
+        assert!(!link_cost_changed(100, 75));
+        assert!(link_cost_changed(100, 126));
+        assert!(link_cost_changed(100, 74));
+        // Neither are changes of a single unit on low costs
+        assert!(!link_cost_changed(1, 2));
+        assert!(link_cost_changed(1, 3));
+    }
+
+    #[cfg(all(feature = "transport_tcp", feature = "transport_udp"))]
+    #[test]
+    fn link_cost_static() {
+        let link_costs = parse_link_costs(
+            "tcp/10.0.0.1:7447=10, invalid, udp/10.0.0.2:7447=0,tcp/10.0.0.3:7447=x",
+        );
+        assert_eq!(link_costs.len(), 2);
+        // A null cost is raised to the minimal one
+        assert_eq!(link_costs[1].1, 1);
+
+        // Static costs match both the source and the destination of a link
+        let links = [transport_link("tcp/10.0.0.1:7447", "tcp/10.0.0.9:7447")];
+        assert_eq!(static_link_cost(&link_costs, &links), Some(10));
+        let links = [transport_link("udp/10.0.0.9:7447", "udp/10.0.0.2:7447")];
+        assert_eq!(static_link_cost(&link_costs, &links), Some(1));
+        // The protocol is part of the match
+        let links = [transport_link("udp/10.0.0.1:7447", "udp/10.0.0.9:7447")];
+        assert_eq!(static_link_cost(&link_costs, &links), None);
+        assert_eq!(static_link_cost(&[], &links), None);
+    }
+
+    #[cfg(feature = "transport_tcp")]
+    #[test]
+    fn connect_backoff() {
+        let address: LocatorAddress = "tcp/127.0.0.1:7447".parse().unwrap();
+        let mut connector = Connector::default();
+        let now = Instant::now();
+        assert!(connector.is_ready(&address, now));
+
+        // The delay before connecting again doubles after each failure, up to the maximal one
+        let mut delays = vec![];
+        for _ in 0..8 {
+            connector.failed(address.clone(), now);
+            delays.push(connector.backoffs[&address].delay);
+        }
+        assert_eq!(delays[0], *GOSSIP_CONNECT_RETRY_INITIAL);
+        assert_eq!(delays[1], 2 * *GOSSIP_CONNECT_RETRY_INITIAL);
+        assert_eq!(*delays.last().unwrap(), *GOSSIP_CONNECT_RETRY_MAX);
+        assert!(delays.windows(2).all(|d| d[0] <= d[1]));
+        assert!(!connector.is_ready(&address, now));
+        let later = now + Duration::from_millis(*GOSSIP_CONNECT_RETRY_MAX);
+        assert!(connector.is_ready(&address, later));
+    }
+
+    fn node(pid: PeerId, links: &[PeerId], costs: &[(PeerId, ZInt)]) -> Node {
+        Node {
+            pid,
+            whatami: whatami::ROUTER,
+            locators: None,
+            sn: 1,
+            links: links.to_vec(),
+            costs: Some(costs.iter().copied().collect()),
+        }
+    }
+
+    fn link(pid: PeerId, cost: ZInt, static_cost: bool) -> Link {
+        Link {
+            transport: TransportUnicast::closed(),
+            pid,
+            whatami: whatami::ROUTER,
+            mappings: VecMap::new(),
+            local_mappings: VecMap::new(),
+            cost,
+            static_cost,
+            accept_costs: true,
+        }
+    }
+
+    #[test]
+    fn link_cost_routes() {
+        use janu_util::properties::config::*;
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, "".to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let runtime = async_std::task::block_on(Runtime::new(0, config, None)).unwrap();
+
+        // Two paths from a to d: a-b-d and a-c-d, the link a-c having a static cost
+        let (a, b, c, d) = (
+            PeerId::new(1, [1; 16]),
+            PeerId::new(1, [2; 16]),
+            PeerId::new(1, [3; 16]),
+            PeerId::new(1, [4; 16]),
+        );
+        let mut net = Network::new("[Routers network]".to_string(), a, runtime, false, false);
+        net.graph[net.idx].links = vec![b, c];
+        net.graph[net.idx].costs = Some([(b, 10), (c, 30)].iter().copied().collect());
+        net.links.insert(0, link(b, 10, false));
+        net.links.insert(1, link(c, 30, true));
+        let ia = net.idx;
+        let ib = net.add_node(node(b, &[a, d], &[(d, 10)]));
+        let ic = net.add_node(node(c, &[a, d], &[(d, 10)]));
+        let id = net.add_node(node(d, &[b, c], &[(b, 10), (c, 10)]));
+        for (i1, i2) in [(ia, ib), (ia, ic), (ib, id), (ic, id)].iter() {
+            net.update_edge(*i1, *i2);
+        }
+
+        let direction = |net: &Network| net.trees[net.idx.index()].directions[id.index()];
+        net.compute_trees();
+        assert_eq!(direction(&net), Some(ib));
+        assert_eq!(net.cost(&a, &d), Some(20));
+
+        // A cost change within the hysteresis is neither applied nor advertised
+        assert!(!net.update_measured_link_costs(vec![(b, 12)]));
+        assert_eq!(net.graph[net.idx].sn, 1);
+        net.compute_trees();
+        assert_eq!(direction(&net), Some(ib));
+        assert_eq!(net.cost(&a, &d), Some(20));
+
+        // A cost change beyond the hysteresis reroutes through the cheapest path
+        assert!(net.update_measured_link_costs(vec![(b, 50)]));
+        assert_eq!(net.graph[net.idx].sn, 2);
+        net.compute_trees();
+        assert_eq!(direction(&net), Some(ic));
+        assert_eq!(net.cost(&a, &d), Some(40));
+        assert_eq!(net.cost(&d, &a), Some(40));
+
+        // The measured cost of a link with a static cost is ignored
+        assert!(!net.update_measured_link_costs(vec![(c, 1)]));
+        assert_eq!(net.links[1].cost, 30);
+        net.compute_trees();
+        assert_eq!(direction(&net), Some(ic));
+        assert_eq!(net.cost(&a, &d), Some(40));
+
+        // The cheapest path is restored once its cost decreased enough
+        assert!(net.update_measured_link_costs(vec![(b, 10), (c, 1)]));
+        net.compute_trees();
+        assert_eq!(direction(&net), Some(ib));
+        assert_eq!(net.cost(&a, &d), Some(20));
+    }
+
+    #[cfg(feature = "transport_tcp")]
+    #[test]
+    fn connect_backoff_forget_nodes() {
+        use janu_util::properties::config::*;
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, "".to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let runtime = async_std::task::block_on(Runtime::new(0, config, None)).unwrap();
+        let (a, b) = (PeerId::new(1, [1; 16]), PeerId::new(1, [2; 16]));
+        let mut net = Network::new("[Routers network]".to_string(), a, runtime, false, false);
+        let known: Locator = "tcp/127.0.0.1:7447".parse().unwrap();
+        let forgotten: LocatorAddress = "tcp/127.0.0.1:7448".parse().unwrap();
+        let mut node = node(b, &[a], &[]);
+        node.locators = Some(vec![known.clone()]);
+        net.add_node(node);
+        {
+            let mut connector = zlock!(net.connector);
+            connector.failed(known.address.clone(), Instant::now());
+            connector.failed(forgotten.clone(), Instant::now());
+        }
+
+        // Only the backoffs of the locators of the nodes in the graph are kept
+        net.connect_known();
+        let connector = zlock!(net.connector);
+        assert!(connector.backoffs.contains_key(&known.address));
+        assert!(!connector.backoffs.contains_key(&forgotten));
+    }
+}
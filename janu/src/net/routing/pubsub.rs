@@ -11,23 +11,28 @@
 // Contributors:
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
-use async_std::sync::Arc;
+use async_std::sync::{Arc, Weak};
 use petgraph::graph::NodeIndex;
+use serde_json::json;
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::sync::RwLock;
+use std::time::Duration;
 use janu_util::sync::get_mut_unchecked;
-use janu_util::zread;
+use janu_util::{zread, zwrite};
 
 use super::protocol::core::{
-    whatami, Channel, CongestionControl, PeerId, Priority, Reliability, SubInfo, SubMode, ZInt,
+    rname, whatami, Channel, CongestionControl, PeerId, Period, Priority, Reliability, SubInfo,
+    SubMode, ZInt,
 };
 use super::protocol::io::ZBuf;
 use super::protocol::proto::{DataInfo, RoutingContext};
 
+use super::egress;
+use super::election::elect_router;
 use super::face::FaceState;
 use super::network::Network;
-use super::resource::{elect_router, PullCaches, Resource, Route, SessionContext};
+use super::resource::{route_json, PullCaches, Resource, Route, SessionContext};
 use super::router::Tables;
 
 #[inline]
@@ -127,27 +132,36 @@ fn register_router_subscription(
     sub_info: &SubInfo,
     router: PeerId,
 ) {
-    if !res.context().router_subs.contains(&router) {
-        // Register router subscription
+    let period = sampled_period(sub_info);
+    let registered = res.context().router_subs.contains(&router);
+    if !registered || res.context().router_subs_periods.get(&router) != period.as_ref() {
+        // Register router subscription (or its new period)
         {
             log::debug!(
                 "Register router subscription {} (router: {})",
                 res.name(),
                 router
             );
-            get_mut_unchecked(res)
-                .context_mut()
-                .router_subs
-                .insert(router);
+            let context = get_mut_unchecked(res).context_mut();
+            context.router_subs.insert(router);
+            match period {
+                Some(period) => context.router_subs_periods.insert(router, period),
+                None => context.router_subs_periods.remove(&router),
+            };
             tables.router_subs.insert(res.clone());
         }
+        update_net_samplers(tables, res);
 
         // Propagate subscription to routers
         propagate_sourced_subscription(tables, res, sub_info, Some(face), &router, whatami::ROUTER);
 
-        // Propagate subscription to peers
-        if face.whatami != whatami::PEER {
-            register_peer_subscription(tables, face, res, sub_info, tables.pid)
+        // Propagate subscription to peers (the data is sampled when entering the routers network)
+        if !registered && face.whatami != whatami::PEER {
+            let sub_info = SubInfo {
+                period: None,
+                ..sub_info.clone()
+            };
+            register_peer_subscription(tables, face, res, &sub_info, tables.pid)
         }
     }
 
@@ -182,13 +196,21 @@ fn register_peer_subscription(
     sub_info: &SubInfo,
     peer: PeerId,
 ) {
-    if !res.context().peer_subs.contains(&peer) {
-        // Register peer subscription
+    let period = sampled_period(sub_info);
+    let registered = res.context().peer_subs.contains(&peer);
+    if !registered || res.context().peer_subs_periods.get(&peer) != period.as_ref() {
+        // Register peer subscription (or its new period)
         {
             log::debug!("Register peer subscription {} (peer: {})", res.name(), peer);
-            get_mut_unchecked(res).context_mut().peer_subs.insert(peer);
+            let context = get_mut_unchecked(res).context_mut();
+            context.peer_subs.insert(peer);
+            match period {
+                Some(period) => context.peer_subs_periods.insert(peer, period),
+                None => context.peer_subs_periods.remove(&peer),
+            };
             tables.peer_subs.insert(res.clone());
         }
+        update_net_samplers(tables, res);
 
         // Propagate subscription to peers
         propagate_sourced_subscription(tables, res, sub_info, Some(face), &peer, whatami::PEER);
@@ -210,9 +232,19 @@ pub fn declare_peer_subscription(
             register_peer_subscription(tables, face, &mut res, sub_info, peer);
 
             if tables.whatami == whatami::ROUTER {
-                let mut propa_sub_info = sub_info.clone();
-                propa_sub_info.mode = SubMode::Push;
-                register_router_subscription(tables, face, &mut res, &propa_sub_info, tables.pid);
+                let propa_sub_info = advertised_sub_info(tables, &res, sub_info);
+                let mut propa_res = advertised_subscription(tables, &res);
+                let registered = propa_res.context().router_subs.contains(&tables.pid);
+                register_router_subscription(
+                    tables,
+                    face,
+                    &mut propa_res,
+                    &propa_sub_info,
+                    tables.pid,
+                );
+                if !registered && !Arc::ptr_eq(&propa_res, &res) {
+                    compute_matches_data_routes(tables, &mut propa_res);
+                }
             }
 
             compute_matches_data_routes(tables, &mut res);
@@ -221,6 +253,146 @@ pub fn declare_peer_subscription(
     }
 }
 
+// A push subscription with a period gets sampled: the latest value of each matching key is
+// cached like for pull subscriptions and delivered once per period by a dedicated task.
+#[inline]
+fn is_periodic(sub_info: &SubInfo) -> bool {
+    sub_info.mode == SubMode::Push && matches!(sub_info.period, Some(period) if period.period > 0)
+}
+
+// Returns the period with which the nodes a subscription is advertised to may sample it.
+// The delivery windows of the subscriptions with a duration are not aligned from one node
+// to another, hence those subscriptions are only sampled by the node closest to them.
+#[inline]
+fn sampled_period(sub_info: &SubInfo) -> Option<Period> {
+    sub_info
+        .period
+        .filter(|period| is_periodic(sub_info) && period.duration == 0)
+}
+
+// Returns the subscription info with which the local subscriptions on `res` are propagated
+// to the other routers (or peers). It carries the smallest period of the client and peer
+// subscriptions reached through this node if all of them can be sampled, so that the
+// upstream nodes can sample them too.
+fn local_sub_info(tables: &Tables, res: &Arc<Resource>, sub_info: &SubInfo) -> SubInfo {
+    let mut periods = res
+        .session_ctxs
+        .values()
+        .filter_map(|ctx| ctx.subs.as_ref().map(sampled_period))
+        .collect::<Vec<Option<Period>>>();
+    if tables.whatami == whatami::ROUTER && res.context.is_some() {
+        let context = res.context();
+        periods.extend(
+            context
+                .peer_subs
+                .iter()
+                .filter(|peer| **peer != tables.pid)
+                .map(|peer| context.peer_subs_periods.get(peer).copied()),
+        );
+    }
+    let period = periods
+        .into_iter()
+        .try_fold(None, |min: Option<Period>, period| {
+            period.map(|p| match min {
+                Some(min) if min.period <= p.period => Some(min),
+                _ => Some(p),
+            })
+        })
+        .flatten();
+    SubInfo {
+        reliability: sub_info.reliability,
+        mode: SubMode::Push,
+        period,
+    }
+}
+
+// Re-advertises the local subscriptions on `res` if their period changed.
+fn update_local_subscription(tables: &mut Tables, face: &mut Arc<FaceState>, res: &Arc<Resource>) {
+    let mut res = res.clone();
+    let sub_info = SubInfo {
+        reliability: Reliability::Reliable, // @TODO
+        mode: SubMode::Push,
+        period: None,
+    };
+    let sub_info = advertised_sub_info(tables, &res, &sub_info);
+    match tables.whatami {
+        whatami::ROUTER if res.context().router_subs.contains(&tables.pid) => {
+            register_router_subscription(tables, face, &mut res, &sub_info, tables.pid)
+        }
+        whatami::PEER if res.context().peer_subs.contains(&tables.pid) => {
+            register_peer_subscription(tables, face, &mut res, &sub_info, tables.pid)
+        }
+        _ => (),
+    }
+}
+
+// Updates the samplers of the periodic router and peer subscriptions on `res`. The data
+// entering the routers (or peers) network through this node is sampled per outgoing face
+// when all the router (or peer) subscriptions reached through that face are periodic, at
+// the smallest of their periods.
+fn update_net_samplers(tables: &Tables, res: &mut Arc<Resource>) {
+    let mut periods: HashMap<usize, (Arc<FaceState>, Period)> = HashMap::new();
+    let context = res.context();
+    for (net, subs_periods) in [
+        (tables.routers_net.as_ref(), &context.router_subs_periods),
+        (tables.peers_net.as_ref(), &context.peer_subs_periods),
+    ] {
+        let net = match net {
+            Some(net) => net,
+            None => continue,
+        };
+        if let Some(tree) = net.trees.get(net.idx.index()) {
+            for (sub, period) in subs_periods {
+                let face = net
+                    .get_idx(sub)
+                    .and_then(|idx| tree.directions.get(idx.index()).copied().flatten())
+                    .filter(|direction| net.graph.contains_node(*direction))
+                    .and_then(|direction| tables.get_face(&net.graph[direction].pid));
+                if let Some(face) = face {
+                    let entry = periods
+                        .entry(face.id)
+                        .or_insert_with(|| (face.clone(), *period));
+                    if period.period < entry.1.period {
+                        entry.1 = *period;
+                    }
+                }
+            }
+        }
+    }
+
+    let samplers = &mut get_mut_unchecked(res).context_mut().net_samplers;
+    samplers.retain(|id, _| periods.contains_key(id));
+    for (id, (face, period)) in periods {
+        let sub_info = SubInfo {
+            reliability: Reliability::Reliable, // @TODO
+            mode: SubMode::Push,
+            period: Some(period),
+        };
+        match samplers.get_mut(&id) {
+            Some(ctx) => {
+                if ctx.subs.as_ref().and_then(|info| info.period) != Some(period) {
+                    get_mut_unchecked(ctx).subs = Some(sub_info);
+                }
+            }
+            None => {
+                samplers.insert(
+                    id,
+                    Arc::new(SessionContext {
+                        face,
+                        local_rid: None,
+                        remote_rid: None,
+                        subs: Some(sub_info),
+                        qabl: None,
+                        last_values: HashMap::new(),
+                        sampler: None,
+                        window: false,
+                    }),
+                );
+            }
+        }
+    }
+}
+
 fn register_client_subscription(
     _tables: &mut Tables,
     face: &mut Arc<FaceState>,
@@ -234,7 +406,7 @@ fn register_client_subscription(
         match res.session_ctxs.get_mut(&face.id) {
             Some(mut ctx) => match &ctx.subs {
                 Some(info) => {
-                    if SubMode::Pull == info.mode {
+                    if SubMode::Pull == info.mode || is_periodic(info) {
                         get_mut_unchecked(&mut ctx).subs = Some(sub_info.clone());
                     }
                 }
@@ -252,6 +424,8 @@ fn register_client_subscription(
                         subs: Some(sub_info.clone()),
                         qabl: None,
                         last_values: HashMap::new(),
+                        sampler: None,
+                        window: false,
                     }),
                 );
             }
@@ -275,20 +449,34 @@ pub fn declare_client_subscription(
             register_client_subscription(tables, face, &mut res, sub_info);
             match tables.whatami {
                 whatami::ROUTER => {
-                    let mut propa_sub_info = sub_info.clone();
-                    propa_sub_info.mode = SubMode::Push;
+                    let propa_sub_info = advertised_sub_info(tables, &res, sub_info);
+                    let mut propa_res = advertised_subscription(tables, &res);
+                    let registered = propa_res.context().router_subs.contains(&tables.pid);
                     register_router_subscription(
                         tables,
                         face,
-                        &mut res,
+                        &mut propa_res,
                         &propa_sub_info,
                         tables.pid,
                     );
+                    if !registered && !Arc::ptr_eq(&propa_res, &res) {
+                        compute_matches_data_routes(tables, &mut propa_res);
+                    }
                 }
                 whatami::PEER => {
-                    let mut propa_sub_info = sub_info.clone();
-                    propa_sub_info.mode = SubMode::Push;
-                    register_peer_subscription(tables, face, &mut res, &propa_sub_info, tables.pid);
+                    let propa_sub_info = advertised_sub_info(tables, &res, sub_info);
+                    let mut propa_res = advertised_subscription(tables, &res);
+                    let registered = propa_res.context().peer_subs.contains(&tables.pid);
+                    register_peer_subscription(
+                        tables,
+                        face,
+                        &mut propa_res,
+                        &propa_sub_info,
+                        tables.pid,
+                    );
+                    if !registered && !Arc::ptr_eq(&propa_res, &res) {
+                        compute_matches_data_routes(tables, &mut propa_res);
+                    }
                 }
                 _ => {
                     propagate_simple_subscription(tables, &res, sub_info, face);
@@ -301,6 +489,127 @@ pub fn declare_client_subscription(
     }
 }
 
+/// Parses a list of key expressions covering the subscriptions to aggregate with the format
+/// `<keyexpr>[,<keyexpr>]*`. Invalid key expressions are logged and ignored.
+pub fn parse_subscriptions_aggregation(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| {
+            if s.starts_with('/') {
+                Some(s.to_string())
+            } else {
+                log::error!("Invalid subscriptions aggregation configuration: {}", s);
+                None
+            }
+        })
+        .collect()
+}
+
+/// The local subscriptions covered by a configured aggregation key expression.
+pub(crate) struct SubsAggregation {
+    expr: String,
+    res: Option<Arc<Resource>>,
+    covered: HashSet<Arc<Resource>>,
+}
+
+impl SubsAggregation {
+    pub(crate) fn new(expr: String) -> SubsAggregation {
+        SubsAggregation {
+            expr,
+            res: None,
+            covered: HashSet::new(),
+        }
+    }
+}
+
+// Returns the index of the aggregation covering the subscriptions on `res`, if any.
+// The subscriptions on the aggregation key expression itself are covered too, so that
+// the advertisement is only withdrawn with the last of the subscriptions sharing it.
+fn aggregation_of(tables: &Tables, res: &Arc<Resource>) -> Option<usize> {
+    let name = res.name();
+    tables
+        .subs_aggregation
+        .iter()
+        .position(|aggr| rname::include(&aggr.expr, &name))
+}
+
+// Returns the resource on which the local subscriptions on `res` are advertised to
+// the other routers or peers: the resource of the covering key expression if the
+// subscriptions on `res` are aggregated, `res` otherwise.
+fn advertised_subscription(tables: &mut Tables, res: &Arc<Resource>) -> Arc<Resource> {
+    match aggregation_of(tables, res) {
+        Some(idx) => {
+            tables.subs_aggregation[idx].covered.insert(res.clone());
+            match &tables.subs_aggregation[idx].res {
+                Some(aggr) => aggr.clone(),
+                None => {
+                    let mut root = tables.root_res.clone();
+                    let expr = tables.subs_aggregation[idx].expr.clone();
+                    let mut aggr = Resource::make_resource(tables, &mut root, &expr);
+                    Resource::match_resource(tables, &mut aggr);
+                    tables.subs_aggregation[idx].res = Some(aggr.clone());
+                    aggr
+                }
+            }
+        }
+        None => res.clone(),
+    }
+}
+
+// Returns the subscription info with which the local subscriptions on `res` are advertised.
+// The aggregated subscriptions are advertised without period, as the covering key expression
+// may also cover subscriptions that are not periodic.
+fn advertised_sub_info(tables: &Tables, res: &Arc<Resource>, sub_info: &SubInfo) -> SubInfo {
+    match aggregation_of(tables, res) {
+        Some(_) => SubInfo {
+            reliability: sub_info.reliability,
+            mode: SubMode::Push,
+            period: None,
+        },
+        None => local_sub_info(tables, res, sub_info),
+    }
+}
+
+// Returns the resource whose advertisement is withdrawn once there are no more local
+// subscriptions on `res`: `res` itself if its subscriptions are not aggregated, the resource
+// of the covering key expression if they were the last subscriptions it covered, none otherwise.
+fn withdrawn_subscription(tables: &mut Tables, res: &Arc<Resource>) -> Option<Arc<Resource>> {
+    match aggregation_of(tables, res) {
+        Some(idx) => {
+            let aggr = &mut tables.subs_aggregation[idx];
+            aggr.covered.remove(res);
+            if !aggr.covered.is_empty() {
+                return None;
+            }
+            aggr.res.take()
+        }
+        None => Some(res.clone()),
+    }
+}
+
+// Withdraws the advertisement of the local subscriptions on `res` once there are no more
+// local subscriptions on `res` or, if they are aggregated, on the covering key expression.
+fn undeclare_local_subscription(tables: &mut Tables, res: &mut Arc<Resource>) {
+    let mut propa_res = match withdrawn_subscription(tables, res) {
+        Some(propa_res) => propa_res,
+        None => return,
+    };
+    match tables.whatami {
+        whatami::ROUTER => {
+            undeclare_router_subscription(tables, None, &mut propa_res, &tables.pid.clone())
+        }
+        whatami::PEER => {
+            undeclare_peer_subscription(tables, None, &mut propa_res, &tables.pid.clone())
+        }
+        _ => (),
+    }
+    if !Arc::ptr_eq(&propa_res, res) {
+        compute_matches_data_routes(tables, &mut propa_res);
+        Resource::clean(&mut propa_res);
+    }
+}
+
 #[inline]
 fn remote_router_subs(tables: &Tables, res: &Arc<Resource>) -> bool {
     res.context.is_some()
@@ -417,10 +726,10 @@ fn unregister_router_subscription(tables: &mut Tables, res: &mut Arc<Resource>,
         res.name(),
         router
     );
-    get_mut_unchecked(res)
-        .context_mut()
-        .router_subs
-        .retain(|sub| sub != router);
+    let context = get_mut_unchecked(res).context_mut();
+    context.router_subs.retain(|sub| sub != router);
+    context.router_subs_periods.remove(router);
+    update_net_samplers(tables, res);
 
     if res.context().router_subs.is_empty() {
         tables.router_subs.retain(|sub| !Arc::ptr_eq(sub, res));
@@ -469,10 +778,10 @@ fn unregister_peer_subscription(tables: &mut Tables, res: &mut Arc<Resource>, pe
         res.name(),
         peer
     );
-    get_mut_unchecked(res)
-        .context_mut()
-        .peer_subs
-        .retain(|sub| sub != peer);
+    let context = get_mut_unchecked(res).context_mut();
+    context.peer_subs.retain(|sub| sub != peer);
+    context.peer_subs_periods.remove(peer);
+    update_net_samplers(tables, res);
 
     if res.context().peer_subs.is_empty() {
         tables.peer_subs.retain(|sub| !Arc::ptr_eq(sub, res));
@@ -507,7 +816,9 @@ pub fn forget_peer_subscription(
                     let client_subs = res.session_ctxs.values().any(|ctx| ctx.subs.is_some());
                     let peer_subs = remote_peer_subs(tables, &res);
                     if !client_subs && !peer_subs {
-                        undeclare_router_subscription(tables, None, &mut res, &tables.pid.clone());
+                        undeclare_local_subscription(tables, &mut res);
+                    } else {
+                        update_local_subscription(tables, face, &res);
                     }
                 }
 
@@ -537,12 +848,16 @@ pub(crate) fn undeclare_client_subscription(
     match tables.whatami {
         whatami::ROUTER => {
             if client_subs.is_empty() && !peer_subs {
-                undeclare_router_subscription(tables, None, res, &tables.pid.clone());
+                undeclare_local_subscription(tables, res);
+            } else {
+                update_local_subscription(tables, face, res);
             }
         }
         whatami::PEER => {
             if client_subs.is_empty() {
-                undeclare_peer_subscription(tables, None, res, &tables.pid.clone());
+                undeclare_local_subscription(tables, res);
+            } else {
+                update_local_subscription(tables, face, res);
             }
         }
         _ => {
@@ -639,7 +954,7 @@ pub(crate) fn pubsub_remove_node(tables: &mut Tables, node: &PeerId, net_type: w
                     let client_subs = res.session_ctxs.values().any(|ctx| ctx.subs.is_some());
                     let peer_subs = remote_peer_subs(tables, &res);
                     if !client_subs && !peer_subs {
-                        undeclare_router_subscription(tables, None, &mut res, &tables.pid.clone());
+                        undeclare_local_subscription(tables, &mut res);
                     }
                 }
 
@@ -676,10 +991,16 @@ pub(crate) fn pubsub_tree_change(
                     };
                     for sub in subs {
                         if *sub == tree_id {
+                            let period = match net_type {
+                                whatami::ROUTER => {
+                                    res.context().router_subs_periods.get(sub).copied()
+                                }
+                                _ => res.context().peer_subs_periods.get(sub).copied(),
+                            };
                             let sub_info = SubInfo {
                                 reliability: Reliability::Reliable, // @TODO
                                 mode: SubMode::Push,
-                                period: None,
+                                period,
                             };
                             send_sourced_subscription_to_net_childs(
                                 tables,
@@ -697,10 +1018,76 @@ pub(crate) fn pubsub_tree_change(
         }
     }
 
+    let subs_res: Vec<Arc<Resource>> = match net_type {
+        whatami::ROUTER => tables.router_subs.iter().cloned().collect(),
+        _ => tables.peer_subs.iter().cloned().collect(),
+    };
+    for mut res in subs_res {
+        update_net_samplers(tables, &mut res);
+    }
+
     // recompute routes
     compute_data_routes_from(tables, &mut tables.root_res.clone());
 }
 
+pub(crate) fn subscribers_json(tables: &Tables) -> serde_json::Value {
+    let names = |set: &HashSet<Arc<Resource>>| {
+        let mut names: Vec<String> = set.iter().map(|res| res.name()).collect();
+        names.sort();
+        names
+    };
+    let pids = |set: &HashSet<PeerId>| set.iter().map(|pid| pid.to_string()).collect::<Vec<_>>();
+    let resources: Vec<serde_json::Value> = Resource::tree(&tables.root_res)
+        .iter()
+        .filter_map(|res| {
+            let client_subs: Vec<serde_json::Value> = res
+                .session_ctxs
+                .values()
+                .filter_map(|ctx| {
+                    ctx.subs.as_ref().map(|info| {
+                        json!({
+                            "face": ctx.face.id,
+                            "mode": format!("{:?}", info.mode),
+                            "reliability": format!("{:?}", info.reliability),
+                            "period": info.period.map(|period| period.period),
+                        })
+                    })
+                })
+                .collect();
+            let (router_subs, peer_subs) = res
+                .context
+                .as_ref()
+                .map(|ctx| (pids(&ctx.router_subs), pids(&ctx.peer_subs)))
+                .unwrap_or_default();
+            if client_subs.is_empty() && router_subs.is_empty() && peer_subs.is_empty() {
+                None
+            } else {
+                Some(json!({
+                    "key": res.name(),
+                    "router_subs": router_subs,
+                    "peer_subs": peer_subs,
+                    "client_subs": client_subs,
+                }))
+            }
+        })
+        .collect();
+    json!({
+        "router_subs": names(&tables.router_subs),
+        "peer_subs": names(&tables.peer_subs),
+        "resources": resources,
+    })
+}
+
+/// The route followed by the data published on `key` by a local client of this node.
+pub(crate) fn data_route_json(tables: &Tables, key: &str) -> serde_json::Value {
+    let route = compute_data_route(tables, &tables.root_res, key, None, whatami::CLIENT);
+    let pulls = compute_matching_pulls(tables, &tables.root_res, key);
+    json!({
+        "route": route_json(&route),
+        "pull_caches": pulls.iter().map(|ctx| ctx.face.id).collect::<Vec<usize>>(),
+    })
+}
+
 #[inline]
 fn insert_faces_for_subs(
     route: &mut Route,
@@ -741,6 +1128,27 @@ fn insert_faces_for_subs(
     }
 }
 
+// Returns the subscriptions of the nodes of a network the data is routed to. The periodic
+// ones are left out when the data enters the network through this node, as it samples the
+// data for them instead.
+#[inline]
+fn routed_net_subs<'a>(
+    subs: &'a HashSet<PeerId>,
+    periods: &HashMap<PeerId, Period>,
+    entering: bool,
+) -> Cow<'a, HashSet<PeerId>> {
+    if !entering || periods.is_empty() {
+        Cow::Borrowed(subs)
+    } else {
+        Cow::Owned(
+            subs.iter()
+                .filter(|sub| !periods.contains_key(sub))
+                .copied()
+                .collect(),
+        )
+    }
+}
+
 fn compute_data_route(
     tables: &Tables,
     prefix: &Arc<Resource>,
@@ -759,7 +1167,7 @@ fn compute_data_route(
         .unwrap_or_else(|| Cow::from(Resource::get_matches(tables, &res_name)));
 
     let master = tables.whatami != whatami::ROUTER
-        || *elect_router(&res_name, &tables.shared_nodes) == tables.pid;
+        || *elect_router(tables, &res_name, source, source_type) == tables.pid;
 
     for mres in matches.iter() {
         let mres = mres.upgrade().unwrap();
@@ -770,6 +1178,11 @@ fn compute_data_route(
                     whatami::ROUTER => source.unwrap(),
                     _ => net.idx.index(),
                 };
+                let router_subs = routed_net_subs(
+                    &mres.context().router_subs,
+                    &mres.context().router_subs_periods,
+                    source_type != whatami::ROUTER,
+                );
                 insert_faces_for_subs(
                     &mut route,
                     prefix,
@@ -777,7 +1190,7 @@ fn compute_data_route(
                     tables,
                     net,
                     router_source,
-                    &mres.context().router_subs,
+                    &router_subs,
                 );
             }
 
@@ -787,6 +1200,11 @@ fn compute_data_route(
                     whatami::PEER => source.unwrap(),
                     _ => net.idx.index(),
                 };
+                let peer_subs = routed_net_subs(
+                    &mres.context().peer_subs,
+                    &mres.context().peer_subs_periods,
+                    source_type != whatami::PEER,
+                );
                 insert_faces_for_subs(
                     &mut route,
                     prefix,
@@ -794,7 +1212,7 @@ fn compute_data_route(
                     tables,
                     net,
                     peer_source,
-                    &mres.context().peer_subs,
+                    &peer_subs,
                 );
             }
         }
@@ -805,6 +1223,11 @@ fn compute_data_route(
                 whatami::ROUTER | whatami::PEER => source.unwrap(),
                 _ => net.idx.index(),
             };
+            let peer_subs = routed_net_subs(
+                &mres.context().peer_subs,
+                &mres.context().peer_subs_periods,
+                source_type != whatami::ROUTER && source_type != whatami::PEER,
+            );
             insert_faces_for_subs(
                 &mut route,
                 prefix,
@@ -812,14 +1235,14 @@ fn compute_data_route(
                 tables,
                 net,
                 peer_source,
-                &mres.context().peer_subs,
+                &peer_subs,
             );
         }
 
         if tables.whatami != whatami::ROUTER || master || source_type == whatami::ROUTER {
             for (sid, context) in &mres.session_ctxs {
                 if let Some(subinfo) = &context.subs {
-                    if subinfo.mode == SubMode::Push {
+                    if subinfo.mode == SubMode::Push && !is_periodic(subinfo) {
                         route.entry(*sid).or_insert_with(|| {
                             let reskey = Resource::get_best_key(prefix, suffix, *sid);
                             (context.face.clone(), reskey, None)
@@ -851,16 +1274,34 @@ fn compute_matching_pulls(
             ))
         });
 
+    let mut samplers: HashMap<usize, Arc<SessionContext>> = HashMap::new();
     for mres in matches.iter() {
         let mres = mres.upgrade().unwrap();
         for context in mres.session_ctxs.values() {
             if let Some(subinfo) = &context.subs {
-                if subinfo.mode == SubMode::Pull {
+                if subinfo.mode == SubMode::Pull || is_periodic(subinfo) {
                     pull_caches.push(context.clone());
                 }
             }
         }
+        // A single sampler per face, with the smallest period
+        if let Some(mres_context) = mres.context.as_ref() {
+            for (id, sampler) in &mres_context.net_samplers {
+                let period = |ctx: &SessionContext| {
+                    ctx.subs
+                        .as_ref()
+                        .and_then(|info| info.period.map(|p| p.period))
+                };
+                match samplers.get(id) {
+                    Some(other) if period(other) <= period(sampler) => (),
+                    _ => {
+                        samplers.insert(*id, sampler.clone());
+                    }
+                }
+            }
+        }
     }
+    pull_caches.extend(samplers.into_values());
     Arc::new(pull_caches)
 }
 
@@ -1072,9 +1513,21 @@ fn get_matching_pulls(
 }
 
 macro_rules! send_to_first {
-    ($route:expr, $srcface:expr, $payload:expr, $channel:expr, $cong_ctrl:expr, $data_info:expr) => {
+    ($route:expr, $srcface:expr, $prefix:expr, $suffix:expr, $payload:expr, $channel:expr, $cong_ctrl:expr, $data_info:expr) => {
         let (outface, reskey, context) = $route.values().next().unwrap();
-        if $srcface.id != outface.id {
+        if $srcface.id != outface.id
+            && (outface.egress.is_empty()
+                || egress::admit(
+                    outface,
+                    &[&$prefix.name(), $suffix].concat(),
+                    reskey,
+                    &$payload,
+                    $channel,
+                    $cong_ctrl,
+                    &$data_info,
+                    *context,
+                ))
+        {
             outface
                 .primitives
                 .send_data(
@@ -1090,9 +1543,21 @@ macro_rules! send_to_first {
 }
 
 macro_rules! send_to_all {
-    ($route:expr, $srcface:expr, $payload:expr, $channel:expr, $cong_ctrl:expr, $data_info:expr) => {
+    ($route:expr, $srcface:expr, $prefix:expr, $suffix:expr, $payload:expr, $channel:expr, $cong_ctrl:expr, $data_info:expr) => {
         for (outface, reskey, context) in $route.values() {
-            if $srcface.id != outface.id {
+            if $srcface.id != outface.id
+                && (outface.egress.is_empty()
+                    || egress::admit(
+                        outface,
+                        &[&$prefix.name(), $suffix].concat(),
+                        reskey,
+                        &$payload,
+                        $channel,
+                        $cong_ctrl,
+                        &$data_info,
+                        *context,
+                    ))
+            {
                 outface
                     .primitives
                     .send_data(
@@ -1108,19 +1573,121 @@ macro_rules! send_to_all {
     }
 }
 
+// Returns true if this node routes the data received from `src_face` into the network of the
+// nodes of kind `net_type` (see `compute_data_route`), in which case it samples the data for
+// the periodic subscriptions of those nodes.
+fn routes_into_net(
+    tables: &Tables,
+    src_face: &FaceState,
+    res_name: &str,
+    routing_context: Option<RoutingContext>,
+    net_type: whatami::Type,
+) -> bool {
+    if tables.whatami != whatami::ROUTER {
+        return src_face.whatami != whatami::ROUTER && src_face.whatami != whatami::PEER;
+    }
+    if src_face.whatami == net_type {
+        return false;
+    }
+    if net_type == whatami::PEER && src_face.whatami != whatami::ROUTER {
+        return true;
+    }
+    let net = match src_face.whatami {
+        whatami::ROUTER => tables.routers_net.as_ref(),
+        whatami::PEER => tables.peers_net.as_ref(),
+        _ => None,
+    };
+    let source = net.map(|net| {
+        net.get_local_context(routing_context.map(|rc| rc.tree_id), src_face.link_id)
+    });
+    *elect_router(tables, res_name, source, src_face.whatami) == tables.pid
+}
+
+// Returns true if the data routed from `src_face` through `route` must be cached for `ctx`.
+// The faces that already get the data through the route are not sampled for their periodic
+// subscriptions, and the samplers of the router and peer subscriptions only get the data
+// this node routes into their network.
+#[inline]
+fn is_cached_for(
+    tables: &Tables,
+    src_face: &FaceState,
+    route: &Route,
+    ctx: &SessionContext,
+    res_name: &str,
+    routing_context: Option<RoutingContext>,
+) -> bool {
+    match &ctx.subs {
+        Some(info) if is_periodic(info) => {
+            !route.contains_key(&ctx.face.id)
+                && match (tables.whatami, ctx.face.whatami) {
+                    (whatami::ROUTER, net_type @ whatami::ROUTER)
+                    | (whatami::ROUTER, net_type @ whatami::PEER) => {
+                        routes_into_net(tables, src_face, res_name, routing_context, net_type)
+                    }
+                    (whatami::PEER, whatami::ROUTER) | (whatami::PEER, whatami::PEER) => {
+                        routes_into_net(tables, src_face, res_name, routing_context, whatami::PEER)
+                    }
+                    _ => true,
+                }
+        }
+        _ => true,
+    }
+}
+
 macro_rules! cache_data {
     (
+        $tables:expr,
+        $srcface:expr,
+        $route:expr,
         $matching_pulls:expr,
         $prefix:expr,
         $suffix:expr,
         $payload:expr,
-        $info:expr
+        $channel:expr,
+        $congestion_control:expr,
+        $info:expr,
+        $routing_context:expr
     ) => {
+        let res_name = [&$prefix.name(), $suffix].concat();
         for context in $matching_pulls.iter() {
-            get_mut_unchecked(&mut context.clone()).last_values.insert(
-                [&$prefix.name(), $suffix].concat(),
-                ($info.clone(), $payload.clone()),
-            );
+            if is_cached_for(
+                $tables,
+                $srcface,
+                &$route,
+                context,
+                &res_name,
+                $routing_context,
+            ) {
+                if context.window {
+                    // The data is delivered as it is received during the delivery window
+                    let reskey = Resource::get_best_key($prefix, $suffix, context.face.id);
+                    if context.face.egress.is_empty()
+                        || egress::admit(
+                            &context.face,
+                            &res_name,
+                            &reskey,
+                            &$payload,
+                            $channel,
+                            $congestion_control,
+                            &$info,
+                            None,
+                        )
+                    {
+                        context.face.primitives.send_data(
+                            &reskey,
+                            $payload.clone(),
+                            $channel,
+                            $congestion_control,
+                            $info.clone(),
+                            None,
+                        );
+                    }
+                } else {
+                    get_mut_unchecked(&mut context.clone())
+                        .last_values
+                        .insert(res_name.clone(), ($info.clone(), $payload.clone()));
+                }
+            }
         }
     };
 }
@@ -1150,14 +1717,44 @@ pub fn route_data(
                 let data_info = treat_timestamp!(&tables.hlc, info);
 
                 if route.len() == 1 && matching_pulls.len() == 0 {
-                    send_to_first!(route, face, payload, channel, congestion_control, data_info);
+                    send_to_first!(
+                        route,
+                        face,
+                        prefix,
+                        suffix,
+                        payload,
+                        channel,
+                        congestion_control,
+                        data_info
+                    );
                 } else {
                     if !matching_pulls.is_empty() {
                         let lock = zlock!(tables.pull_caches_lock);
-                        cache_data!(matching_pulls, prefix, suffix, payload, data_info);
+                        cache_data!(
+                            tables,
+                            face,
+                            route,
+                            matching_pulls,
+                            &prefix,
+                            suffix,
+                            payload,
+                            channel,
+                            congestion_control,
+                            data_info,
+                            routing_context
+                        );
                         drop(lock);
                     }
-                    send_to_all!(route, face, payload, channel, congestion_control, data_info);
+                    send_to_all!(
+                        route,
+                        face,
+                        prefix,
+                        suffix,
+                        payload,
+                        channel,
+                        congestion_control,
+                        data_info
+                    );
                 }
             }
         }
@@ -1194,15 +1791,45 @@ pub fn full_reentrant_route_data(
 
                 if route.len() == 1 && matching_pulls.len() == 0 {
                     drop(tables);
-                    send_to_first!(route, face, payload, channel, congestion_control, data_info);
+                    send_to_first!(
+                        route,
+                        face,
+                        prefix,
+                        suffix,
+                        payload,
+                        channel,
+                        congestion_control,
+                        data_info
+                    );
                 } else {
                     if !matching_pulls.is_empty() {
                         let lock = zlock!(tables.pull_caches_lock);
-                        cache_data!(matching_pulls, prefix, suffix, payload, data_info);
+                        cache_data!(
+                            &tables,
+                            face,
+                            route,
+                            matching_pulls,
+                            &prefix,
+                            suffix,
+                            payload,
+                            channel,
+                            congestion_control,
+                            data_info,
+                            routing_context
+                        );
                         drop(lock);
                     }
                     drop(tables);
-                    send_to_all!(route, face, payload, channel, congestion_control, data_info);
+                    send_to_all!(
+                        route,
+                        face,
+                        prefix,
+                        suffix,
+                        payload,
+                        channel,
+                        congestion_control,
+                        data_info
+                    );
                 }
             }
         }
@@ -1212,6 +1839,46 @@ pub fn full_reentrant_route_data(
     }
 }
 
+fn send_last_values(
+    tables: &Tables,
+    face: &Arc<FaceState>,
+    ctx: &mut Arc<SessionContext>,
+    reliability: Reliability,
+) {
+    let lock = zlock!(tables.pull_caches_lock);
+    let channel = Channel {
+        priority: Priority::default(), // @TODO: Default value for the time being
+        reliability,
+    };
+    let congestion_control = CongestionControl::default(); // @TODO: Default value for the time being
+    for (name, (info, data)) in &ctx.last_values {
+        let reskey = Resource::get_best_key(&tables.root_res, name, face.id);
+        if face.egress.is_empty()
+            || egress::admit(
+                face,
+                name,
+                &reskey,
+                data,
+                channel,
+                congestion_control,
+                info,
+                None,
+            )
+        {
+            face.primitives.send_data(
+                &reskey,
+                data.clone(),
+                channel,
+                congestion_control,
+                info.clone(),
+                None,
+            );
+        }
+    }
+    get_mut_unchecked(ctx).last_values.clear();
+    drop(lock);
+}
+
 pub fn pull_data(
     tables: &mut Tables,
     face: &Arc<FaceState>,
@@ -1226,26 +1893,10 @@ pub fn pull_data(
             Some(mut res) => {
                 let res = get_mut_unchecked(&mut res);
                 match res.session_ctxs.get_mut(&face.id) {
-                    Some(mut ctx) => match &ctx.subs {
+                    Some(ctx) => match &ctx.subs {
                         Some(subinfo) => {
-                            let lock = zlock!(tables.pull_caches_lock);
-                            for (name, (info, data)) in &ctx.last_values {
-                                let reskey =
-                                    Resource::get_best_key(&tables.root_res, name, face.id);
-                                face.primitives.send_data(
-                                    &reskey,
-                                    data.clone(),
-                                    Channel {
-                                        priority: Priority::default(), // @TODO: Default value for the time being
-                                        reliability: subinfo.reliability,
-                                    },
-                                    CongestionControl::default(), // @TODO: Default value for the time being
-                                    info.clone(),
-                                    None,
-                                );
-                            }
-                            get_mut_unchecked(&mut ctx).last_values.clear();
-                            drop(lock);
+                            let reliability = subinfo.reliability;
+                            send_last_values(tables, face, ctx, reliability);
                         }
                         None => {
                             log::error!(
@@ -1274,3 +1925,200 @@ pub fn pull_data(
         }
     };
 }
+
+/// Starts the sampling task of the periodic subscription declared by `face` on
+/// `prefixid`/`suffix` (if that subscription is periodic and not already sampled).
+///
+/// After waiting `origin` milliseconds, the task delivers the latest value received
+/// for each matching key every `period` milliseconds. If the period has a `duration`,
+/// a delivery window then opens for `duration` milliseconds during which the data is
+/// delivered as it is received. The task stops as soon as the subscription is
+/// undeclared or redeclared with another period.
+pub(crate) fn schedule_periodic_subscription(
+    tables_ref: &Arc<RwLock<Tables>>,
+    tables: &Tables,
+    face: &Arc<FaceState>,
+    prefixid: ZInt,
+    suffix: &str,
+) {
+    let res = match tables
+        .get_mapping(face, &prefixid)
+        .and_then(|prefix| Resource::get_resource(prefix, suffix))
+    {
+        Some(res) => res,
+        None => return,
+    };
+    if let Some(ctx) = get_mut_unchecked(&mut res.clone())
+        .session_ctxs
+        .get_mut(&face.id)
+    {
+        if ctx.subs.as_ref().map(is_periodic) == Some(true) {
+            schedule_sampler(tables_ref, &res, ctx, false);
+        }
+    }
+}
+
+/// Starts the sampling tasks of the periodic router and peer subscriptions that are not
+/// sampled yet (see [`schedule_periodic_subscription`]).
+pub(crate) fn schedule_net_samplers(tables_ref: &Arc<RwLock<Tables>>, tables: &Tables) {
+    for res in tables.router_subs.iter().chain(tables.peer_subs.iter()) {
+        if let Some(context) = get_mut_unchecked(&mut res.clone()).context.as_mut() {
+            for ctx in context.net_samplers.values_mut() {
+                schedule_sampler(tables_ref, res, ctx, true);
+            }
+        }
+    }
+}
+
+fn schedule_sampler(
+    tables_ref: &Arc<RwLock<Tables>>,
+    res: &Arc<Resource>,
+    ctx: &mut Arc<SessionContext>,
+    net: bool,
+) {
+    let (period, reliability) = match &ctx.subs {
+        Some(SubInfo {
+            period: Some(period),
+            reliability,
+            ..
+        }) => (*period, *reliability),
+        _ => return,
+    };
+    if ctx.sampler == Some(period) {
+        return;
+    }
+    get_mut_unchecked(ctx).sampler = Some(period);
+
+    log::debug!(
+        "Sample subscription {} for {} every {}ms",
+        res.name(),
+        ctx.face,
+        period.period
+    );
+    let tables_ref = Arc::downgrade(tables_ref);
+    let res = Arc::downgrade(res);
+    let face_id = ctx.face.id;
+    let window = Duration::from_millis(period.duration.min(period.period));
+    async_std::task::spawn(async move {
+        async_std::task::sleep(Duration::from_millis(period.origin)).await;
+        loop {
+            async_std::task::sleep(Duration::from_millis(period.period) - window).await;
+            let open = !window.is_zero();
+            let sampled = with_sampler(&tables_ref, &res, face_id, net, period, |tables, ctx| {
+                let face = ctx.face.clone();
+                send_last_values(tables, &face, ctx, reliability);
+                get_mut_unchecked(ctx).window = open;
+            });
+            if !sampled {
+                break;
+            }
+            if open {
+                async_std::task::sleep(window).await;
+                let sampled = with_sampler(&tables_ref, &res, face_id, net, period, |_, ctx| {
+                    get_mut_unchecked(ctx).window = false;
+                });
+                if !sampled {
+                    break;
+                }
+            }
+        }
+    });
+}
+
+// Applies `f` to the sampled context of `face_id` on `res` under the write lock of the tables.
+// Returns false if the subscription is gone or has been redeclared with another period.
+fn with_sampler<F>(
+    tables_ref: &Weak<RwLock<Tables>>,
+    res: &Weak<Resource>,
+    face_id: usize,
+    net: bool,
+    period: Period,
+    f: F,
+) -> bool
+where
+    F: FnOnce(&Tables, &mut Arc<SessionContext>),
+{
+    let tables_ref = match tables_ref.upgrade() {
+        Some(tables_ref) => tables_ref,
+        None => return false,
+    };
+    let tables = zwrite!(tables_ref);
+    let mut res = match res.upgrade() {
+        Some(res) => res,
+        None => return false,
+    };
+    let res = get_mut_unchecked(&mut res);
+    let ctx = if net {
+        res.context
+            .as_mut()
+            .and_then(|context| context.net_samplers.get_mut(&face_id))
+    } else {
+        res.session_ctxs.get_mut(&face_id)
+    };
+    match ctx {
+        Some(ctx) => {
+            if ctx.subs.as_ref().map(|sub_info| sub_info.period) != Some(Some(period)) {
+                if ctx.sampler == Some(period) {
+                    let ctx = get_mut_unchecked(ctx);
+                    ctx.sampler = None;
+                    ctx.window = false;
+                }
+                return false;
+            }
+            f(&tables, ctx);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subs_aggregation_advertisement() {
+        assert_eq!(
+            parse_subscriptions_aggregation(" /fleet/**, invalid,,/x/*"),
+            vec!["/fleet/**".to_string(), "/x/*".to_string()]
+        );
+
+        let mut tables = Tables::new(PeerId::new(0, [0; 16]), whatami::ROUTER, None);
+        tables.subs_aggregation = vec![SubsAggregation::new("/fleet/**".to_string())];
+        let resource = |tables: &mut Tables, name: &str| {
+            let mut root = tables.root_res.clone();
+            let mut res = Resource::make_resource(tables, &mut root, name);
+            Resource::match_resource(tables, &mut res);
+            res
+        };
+        let r1 = resource(&mut tables, "/fleet/r1/x");
+        let r2 = resource(&mut tables, "/fleet/r2/y");
+        let other = resource(&mut tables, "/other/z");
+
+        // The subscriptions covered by the aggregation are advertised on a single resource
+        let aggr = advertised_subscription(&mut tables, &r1);
+        assert_eq!(aggr.name(), "/fleet/**");
+        assert!(Arc::ptr_eq(&advertised_subscription(&mut tables, &r2), &aggr));
+        assert!(Arc::ptr_eq(&advertised_subscription(&mut tables, &other), &other));
+
+        // The aggregated subscription is withdrawn with the last covered subscription
+        assert!(withdrawn_subscription(&mut tables, &r1).is_none());
+        let withdrawn = withdrawn_subscription(&mut tables, &r2).unwrap();
+        assert!(Arc::ptr_eq(&withdrawn, &aggr));
+        let withdrawn = withdrawn_subscription(&mut tables, &other).unwrap();
+        assert!(Arc::ptr_eq(&withdrawn, &other));
+
+        // A subscription on the aggregation key expression shares the aggregated subscription:
+        // it is withdrawn with the last of them, whatever the order of the undeclarations
+        for order in [[0, 1], [1, 0]].iter() {
+            let aggr = resource(&mut tables, "/fleet/**");
+            let subs = [aggr.clone(), r1.clone()];
+            for res in subs.iter() {
+                assert!(Arc::ptr_eq(&advertised_subscription(&mut tables, res), &aggr));
+            }
+            assert!(withdrawn_subscription(&mut tables, &subs[order[0]]).is_none());
+            let withdrawn = withdrawn_subscription(&mut tables, &subs[order[1]]).unwrap();
+            assert!(Arc::ptr_eq(&withdrawn, &aggr));
+        }
+    }
+}
@@ -13,24 +13,81 @@
 //
 use async_std::sync::Arc;
 use petgraph::graph::NodeIndex;
+use serde_json::json;
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
 use janu_util::sync::get_mut_unchecked;
+use janu_util::{zconfigurable, zlock};
 
 use super::protocol::core::{
-    queryable, whatami, PeerId, QueryConsolidation, QueryTarget, ResKey, ZInt,
+    queryable, whatami, BalancingStrategy, PeerId, QueryConsolidation, QueryTarget, ResKey, Target,
+    ZInt,
 };
 use super::protocol::io::ZBuf;
 use super::protocol::proto::{DataInfo, RoutingContext};
 
+use super::election::elect_router;
 use super::face::FaceState;
 use super::network::Network;
-use super::resource::{elect_router, Resource, Route, SessionContext};
+use super::resource::{route_json, Resource, Route, SessionContext};
 use super::router::Tables;
 
+zconfigurable! {
+    // The maximum number of key expressions for which the turn of the next
+    // balanced query is kept
+    static ref BALANCED_QUERIES_EXPRS: usize = 4096;
+}
+
+type Direction = (Arc<FaceState>, ResKey, Option<RoutingContext>);
+
+// A queryable as seen from this node: all the queryables of a remote router or peer
+// are reached through the same node, the ones of a session through its face.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Qabl {
+    Node(PeerId),
+    Face(usize),
+}
+
+impl PartialOrd for Qabl {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Qabl {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Qabl::Node(pid1), Qabl::Node(pid2)) => pid1.as_slice().cmp(pid2.as_slice()),
+            (Qabl::Node(_), Qabl::Face(_)) => Ordering::Less,
+            (Qabl::Face(_), Qabl::Node(_)) => Ordering::Greater,
+            (Qabl::Face(id1), Qabl::Face(id2)) => id1.cmp(id2),
+        }
+    }
+}
+
+type QablDirection = (Qabl, Arc<FaceState>, Option<RoutingContext>);
+
 pub(crate) struct Query {
     src_face: Arc<FaceState>,
     src_qid: ZInt,
+    balancing: Option<Mutex<Balancing>>,
+}
+
+// The state of a balanced query, used to route it to another queryable
+// if a chosen one closes or is undeclared before replying.
+struct Balancing {
+    res_name: String,
+    kind: ZInt,
+    source: Option<usize>,
+    source_type: whatami::Type,
+    strategy: BalancingStrategy,
+    predicate: String,
+    consolidation: QueryConsolidation,
+    shares: HashMap<usize, ZInt>,
+    replied: HashSet<usize>,
+    remaining: Vec<Direction>,
 }
 
 fn local_router_qabl_kind(tables: &Tables, res: &Arc<Resource>) -> ZInt {
@@ -339,6 +396,8 @@ fn register_client_queryable(
                         subs: None,
                         qabl: Some(kind),
                         last_values: HashMap::new(),
+                        sampler: None,
+                        window: false,
                     }),
                 );
             }
@@ -533,6 +592,7 @@ pub fn forget_router_queryable(
         Some(prefix) => match Resource::get_resource(prefix, suffix) {
             Some(mut res) => {
                 undeclare_router_queryable(tables, Some(face), &mut res, router);
+                retry_balanced_queries_on_net_undeclare(tables);
 
                 compute_matches_query_routes(tables, &mut res);
                 Resource::clean(&mut res)
@@ -586,6 +646,7 @@ pub fn forget_peer_queryable(
                         register_router_queryable(tables, None, &mut res, local_kind, tables.pid);
                     }
                 }
+                retry_balanced_queries_on_net_undeclare(tables);
 
                 compute_matches_query_routes(tables, &mut res);
                 Resource::clean(&mut res)
@@ -606,6 +667,7 @@ pub(crate) fn undeclare_client_queryable(
         get_mut_unchecked(&mut ctx).qabl = None;
     }
     get_mut_unchecked(face).remote_qabls.remove(res);
+    retry_balanced_queries_on_undeclare(tables, face);
 
     let mut client_qabls = client_qabls(res);
     let router_qabls = remote_router_qabls(tables, res);
@@ -710,6 +772,7 @@ pub(crate) fn queries_remove_node(tables: &mut Tables, node: &PeerId, net_type:
                 compute_matches_query_routes(tables, &mut res);
                 Resource::clean(&mut res)
             }
+            retry_balanced_queries_on_net_undeclare(tables);
         }
         whatami::PEER => {
             for mut res in tables
@@ -735,6 +798,7 @@ pub(crate) fn queries_remove_node(tables: &mut Tables, node: &PeerId, net_type:
                 compute_matches_query_routes(tables, &mut res);
                 Resource::clean(&mut res)
             }
+            retry_balanced_queries_on_net_undeclare(tables);
         }
         _ => (),
     }
@@ -787,37 +851,31 @@ pub(crate) fn queries_tree_change(
 
 #[inline]
 #[allow(clippy::too_many_arguments)]
-fn insert_faces_for_qabls(
-    route: &mut Route,
-    prefix: &Arc<Resource>,
-    suffix: &str,
+fn insert_qabls(
+    qabls: &mut Vec<QablDirection>,
     kind: ZInt,
     tables: &Tables,
     net: &Network,
     source: usize,
-    qabls: &HashMap<PeerId, ZInt>,
+    net_qabls: &HashMap<PeerId, ZInt>,
 ) {
     if net.trees.len() > source {
-        for (qabl, qabl_kind) in qabls.iter() {
+        for (qabl, qabl_kind) in net_qabls.iter() {
             if kind == queryable::ALL_KINDS || (kind & qabl_kind != 0) {
                 if let Some(qabl_idx) = net.get_idx(qabl) {
                     if net.trees[source].directions.len() > qabl_idx.index() {
                         if let Some(direction) = net.trees[source].directions[qabl_idx.index()] {
                             if net.graph.contains_node(direction) {
                                 if let Some(face) = tables.get_face(&net.graph[direction].pid) {
-                                    route.entry(face.id).or_insert_with(|| {
-                                        let reskey =
-                                            Resource::get_best_key(prefix, suffix, face.id);
-                                        (
-                                            face.clone(),
-                                            reskey,
-                                            if source != 0 {
-                                                Some(RoutingContext::new(source as ZInt))
-                                            } else {
-                                                None
-                                            },
-                                        )
-                                    });
+                                    qabls.push((
+                                        Qabl::Node(*qabl),
+                                        face.clone(),
+                                        if source != 0 {
+                                            Some(RoutingContext::new(source as ZInt))
+                                        } else {
+                                            None
+                                        },
+                                    ));
                                 }
                             }
                         }
@@ -830,15 +888,17 @@ fn insert_faces_for_qabls(
     }
 }
 
-fn compute_query_route(
+// Returns the queryables a query with the given source is routed to,
+// with the direction of each of them.
+fn compute_query_qabls(
     tables: &Tables,
     prefix: &Arc<Resource>,
     suffix: &str,
     kind: ZInt,
     source: Option<usize>,
     source_type: whatami::Type,
-) -> Arc<Route> {
-    let mut route = HashMap::new();
+) -> Vec<QablDirection> {
+    let mut qabls = vec![];
     let res_name = [&prefix.name(), suffix].concat();
     let res = Resource::get_resource(prefix, suffix);
     let matches = res
@@ -849,7 +909,7 @@ fn compute_query_route(
         .unwrap_or_else(|| Cow::from(Resource::get_matches(tables, &res_name)));
 
     let master = tables.whatami != whatami::ROUTER
-        || *elect_router(&res_name, &tables.shared_nodes) == tables.pid;
+        || *elect_router(tables, &res_name, source, source_type) == tables.pid;
 
     for mres in matches.iter() {
         let mres = mres.upgrade().unwrap();
@@ -860,10 +920,8 @@ fn compute_query_route(
                     whatami::ROUTER => source.unwrap(),
                     _ => net.idx.index(),
                 };
-                insert_faces_for_qabls(
-                    &mut route,
-                    prefix,
-                    suffix,
+                insert_qabls(
+                    &mut qabls,
                     kind,
                     tables,
                     net,
@@ -878,10 +936,8 @@ fn compute_query_route(
                     whatami::PEER => source.unwrap(),
                     _ => net.idx.index(),
                 };
-                insert_faces_for_qabls(
-                    &mut route,
-                    prefix,
-                    suffix,
+                insert_qabls(
+                    &mut qabls,
                     kind,
                     tables,
                     net,
@@ -897,10 +953,8 @@ fn compute_query_route(
                 whatami::ROUTER | whatami::PEER => source.unwrap(),
                 _ => net.idx.index(),
             };
-            insert_faces_for_qabls(
-                &mut route,
-                prefix,
-                suffix,
+            insert_qabls(
+                &mut qabls,
                 kind,
                 tables,
                 net,
@@ -912,17 +966,93 @@ fn compute_query_route(
         if tables.whatami != whatami::ROUTER || master || source_type == whatami::ROUTER {
             for (sid, context) in &mres.session_ctxs {
                 if context.qabl.is_some() {
-                    route.entry(*sid).or_insert_with(|| {
-                        let reskey = Resource::get_best_key(prefix, suffix, *sid);
-                        (context.face.clone(), reskey, None)
-                    });
+                    qabls.push((Qabl::Face(*sid), context.face.clone(), None));
                 }
             }
         }
     }
+    qabls
+}
+
+fn compute_query_route(
+    tables: &Tables,
+    prefix: &Arc<Resource>,
+    suffix: &str,
+    kind: ZInt,
+    source: Option<usize>,
+    source_type: whatami::Type,
+) -> Arc<Route> {
+    let mut route = HashMap::new();
+    for (_, face, context) in compute_query_qabls(tables, prefix, suffix, kind, source, source_type)
+    {
+        route.entry(face.id).or_insert_with(|| {
+            let reskey = Resource::get_best_key(prefix, suffix, face.id);
+            (face, reskey, context)
+        });
+    }
     Arc::new(route)
 }
 
+pub(crate) fn queryables_json(tables: &Tables) -> serde_json::Value {
+    let names = |set: &HashSet<Arc<Resource>>| {
+        let mut names: Vec<String> = set.iter().map(|res| res.name()).collect();
+        names.sort();
+        names
+    };
+    let kinds = |map: &HashMap<PeerId, ZInt>| {
+        map.iter()
+            .map(|(pid, kind)| json!({"pid": pid.to_string(), "kind": kind}))
+            .collect::<Vec<_>>()
+    };
+    let resources: Vec<serde_json::Value> = Resource::tree(&tables.root_res)
+        .iter()
+        .filter_map(|res| {
+            let client_qabls: Vec<serde_json::Value> = res
+                .session_ctxs
+                .values()
+                .filter_map(|ctx| {
+                    ctx.qabl
+                        .map(|kind| json!({"face": ctx.face.id, "kind": kind}))
+                })
+                .collect();
+            let (router_qabls, peer_qabls) = res
+                .context
+                .as_ref()
+                .map(|ctx| (kinds(&ctx.router_qabls), kinds(&ctx.peer_qabls)))
+                .unwrap_or_default();
+            if client_qabls.is_empty() && router_qabls.is_empty() && peer_qabls.is_empty() {
+                None
+            } else {
+                Some(json!({
+                    "key": res.name(),
+                    "router_qabls": router_qabls,
+                    "peer_qabls": peer_qabls,
+                    "client_qabls": client_qabls,
+                }))
+            }
+        })
+        .collect();
+    json!({
+        "router_qabls": names(&tables.router_qabls),
+        "peer_qabls": names(&tables.peer_qabls),
+        "resources": resources,
+    })
+}
+
+/// The route followed by the queries on `key` (for all kinds of queryables)
+/// issued by a local client of this node.
+pub(crate) fn query_route_json(tables: &Tables, key: &str) -> serde_json::Value {
+    let route = compute_query_route(
+        tables,
+        &tables.root_res,
+        key,
+        queryable::ALL_KINDS,
+        None,
+        whatami::CLIENT,
+    );
+    route_json(&route)
+}
+
 pub(crate) fn compute_query_routes(tables: &mut Tables, res: &mut Arc<Resource>) {
     if res.context.is_some() {
         let mut res_mut = res.clone();
@@ -1009,6 +1139,268 @@ pub(crate) fn compute_matches_query_routes(tables: &mut Tables, res: &mut Arc<Re
     }
 }
 
+fn send_query(
+    query: &Arc<Query>,
+    outface: &Arc<FaceState>,
+    reskey: &ResKey,
+    predicate: &str,
+    target: QueryTarget,
+    consolidation: QueryConsolidation,
+    routing_context: Option<RoutingContext>,
+) {
+    let mut outface = outface.clone();
+    let outface_mut = get_mut_unchecked(&mut outface);
+    outface_mut.next_qid += 1;
+    let qid = outface_mut.next_qid;
+    outface_mut.pending_queries.insert(qid, query.clone());
+
+    log::trace!("Propagate query {}:{} to {}", query.src_face, qid, outface);
+
+    outface.primitives.send_query(
+        reskey,
+        predicate,
+        qid,
+        target,
+        consolidation,
+        routing_context,
+    )
+}
+
+// Orders the candidate queryables of a balanced query on `res_name` by preference.
+// The queryables are chosen in turn for each key expression.
+fn balance(
+    tables: &mut Tables,
+    res_name: &str,
+    qabls: &mut [QablDirection],
+    strategy: BalancingStrategy,
+) {
+    if !tables.balanced_queries.contains_key(res_name)
+        && tables.balanced_queries.len() >= *BALANCED_QUERIES_EXPRS
+    {
+        tables.balanced_queries.clear();
+    }
+    let turn = tables
+        .balanced_queries
+        .entry(res_name.to_string())
+        .or_insert(0);
+    qabls.sort_by(|(qabl1, _, _), (qabl2, _, _)| qabl1.cmp(qabl2));
+    if !qabls.is_empty() {
+        qabls.rotate_left(*turn % qabls.len());
+    }
+    *turn = turn.wrapping_add(1);
+    if strategy == BalancingStrategy::LeastPending {
+        qabls.sort_by_key(|(_, outface, _)| outface.pending_queries.len());
+    }
+}
+
+// Routes a balanced query pending on `face` to another queryable if `face`
+// did not reply to it.
+fn retry_balanced_query(tables: &Tables, face: &Arc<FaceState>, query: &Arc<Query>) {
+    if let Some(balancing) = &query.balancing {
+        let mut balancing = zlock!(balancing);
+        if balancing.replied.contains(&face.id) {
+            return;
+        }
+        let share = match balancing.shares.remove(&face.id) {
+            Some(share) => share,
+            None => return,
+        };
+        balancing
+            .remaining
+            .retain(|(outface, _, _)| outface.id != face.id);
+        while !balancing.remaining.is_empty() {
+            let (outface, reskey, context) = balancing.remaining.remove(0);
+            if tables.faces.contains_key(&outface.id) {
+                log::debug!(
+                    "Retry balanced query {}:{} on {}",
+                    query.src_face,
+                    query.src_qid,
+                    outface
+                );
+                *balancing.shares.entry(outface.id).or_insert(0) += share;
+                send_query(
+                    query,
+                    &outface,
+                    &reskey,
+                    &balancing.predicate,
+                    QueryTarget {
+                        kind: balancing.kind,
+                        target: Target::Balanced {
+                            n: share,
+                            strategy: balancing.strategy,
+                        },
+                    },
+                    balancing.consolidation.clone(),
+                    context,
+                );
+                return;
+            }
+        }
+    }
+}
+
+// Routes the balanced queries pending on `face` to other queryables if none
+// of the queryables matching them is reachable through `face` anymore.
+fn retry_balanced_queries_on_undeclare(tables: &Tables, face: &Arc<FaceState>) {
+    for query in face.pending_queries.values() {
+        if let Some(balancing) = &query.balancing {
+            let reachable = {
+                let balancing = zlock!(balancing);
+                compute_query_qabls(
+                    tables,
+                    &tables.root_res,
+                    &balancing.res_name,
+                    balancing.kind,
+                    balancing.source,
+                    balancing.source_type,
+                )
+                .iter()
+                .any(|(_, outface, _)| outface.id == face.id)
+            };
+            if !reachable {
+                retry_balanced_query(tables, face, query);
+            }
+        }
+    }
+}
+
+// Routes the balanced queries pending on any face to other queryables once a
+// router or peer queryable is undeclared, as it may be reached through any face.
+fn retry_balanced_queries_on_net_undeclare(tables: &Tables) {
+    for face in tables.faces.values() {
+        retry_balanced_queries_on_undeclare(tables, face);
+    }
+}
+
+// Returns the source of the queries received on `face` and its type.
+fn query_source(
+    tables: &Tables,
+    face: &Arc<FaceState>,
+    routing_context: Option<RoutingContext>,
+) -> (Option<usize>, whatami::Type) {
+    match (tables.whatami, face.whatami) {
+        (whatami::ROUTER, whatami::ROUTER) => (
+            Some(
+                tables
+                    .routers_net
+                    .as_ref()
+                    .unwrap()
+                    .get_local_context(routing_context.map(|rc| rc.tree_id), face.link_id),
+            ),
+            whatami::ROUTER,
+        ),
+        (whatami::ROUTER, whatami::PEER)
+        | (whatami::PEER, whatami::ROUTER)
+        | (whatami::PEER, whatami::PEER) => (
+            Some(
+                tables
+                    .peers_net
+                    .as_ref()
+                    .unwrap()
+                    .get_local_context(routing_context.map(|rc| rc.tree_id), face.link_id),
+            ),
+            whatami::PEER,
+        ),
+        _ => (None, whatami::CLIENT),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn route_balanced_query(
+    tables: &mut Tables,
+    face: &Arc<FaceState>,
+    prefix: &Arc<Resource>,
+    suffix: &str,
+    predicate: &str,
+    qid: ZInt,
+    kind: ZInt,
+    n: ZInt,
+    strategy: BalancingStrategy,
+    consolidation: QueryConsolidation,
+    routing_context: Option<RoutingContext>,
+) {
+    let (source, source_type) = query_source(tables, face, routing_context);
+    let res_name = [&prefix.name(), suffix].concat();
+    // The query is routed back to the local queryables of a querying session
+    // so that they are balanced with the others. A router or peer forwarding
+    // a balanced query already balanced it with its own queryables and only
+    // forwarded the share of the queryables it reaches through us, hence the
+    // query is never routed back to it.
+    let mut known = HashSet::new();
+    let mut qabls: Vec<QablDirection> =
+        compute_query_qabls(tables, prefix, suffix, kind, source, source_type)
+            .into_iter()
+            .filter(|(qabl, outface, _)| {
+                (outface.id != face.id || face.whatami == whatami::CLIENT)
+                    && known.insert(qabl.clone())
+            })
+            .collect();
+    if qabls.is_empty() {
+        log::debug!("Send final reply {}:{} (no matching queryables)", face, qid);
+        face.primitives.clone().send_reply_final(qid);
+        return;
+    }
+    balance(tables, &res_name, &mut qabls, strategy);
+
+    // The n queries are shared among the chosen queryables, and the
+    // shares of the queryables reached through the same face are summed
+    let n = n.max(1);
+    let remaining = qabls.split_off((n as usize).min(qabls.len()));
+    let len = qabls.len() as ZInt;
+    let mut chosen: Vec<(Direction, ZInt)> = vec![];
+    for (i, (_, outface, context)) in qabls.into_iter().enumerate() {
+        let share = n / len + ((i as ZInt) < n % len) as ZInt;
+        match chosen.iter_mut().find(|((f, _, _), _)| f.id == outface.id) {
+            Some((_, s)) => *s += share,
+            None => {
+                let reskey = Resource::get_best_key(prefix, suffix, outface.id);
+                chosen.push(((outface, reskey, context), share));
+            }
+        }
+    }
+    let remaining = remaining
+        .into_iter()
+        .map(|(_, outface, context)| {
+            let reskey = Resource::get_best_key(prefix, suffix, outface.id);
+            (outface, reskey, context)
+        })
+        .collect();
+    let query = Arc::new(Query {
+        src_face: face.clone(),
+        src_qid: qid,
+        balancing: Some(Mutex::new(Balancing {
+            res_name,
+            kind,
+            source,
+            source_type,
+            strategy,
+            predicate: predicate.to_string(),
+            consolidation: consolidation.clone(),
+            shares: chosen
+                .iter()
+                .map(|((outface, _, _), share)| (outface.id, *share))
+                .collect(),
+            replied: HashSet::new(),
+            remaining,
+        })),
+    });
+
+    for ((outface, reskey, context), share) in chosen {
+        send_query(
+            &query,
+            &outface,
+            &reskey,
+            predicate,
+            QueryTarget {
+                kind,
+                target: Target::Balanced { n: share, strategy },
+            },
+            consolidation.clone(),
+            context,
+        )
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn route_query(
     tables: &mut Tables,
@@ -1031,117 +1423,38 @@ pub fn route_query(
                 suffix,
             );
 
-            let route = match tables.whatami {
-                whatami::ROUTER => match face.whatami {
-                    whatami::ROUTER => {
-                        let routers_net = tables.routers_net.as_ref().unwrap();
-                        let local_context = routers_net
-                            .get_local_context(routing_context.map(|rc| rc.tree_id), face.link_id);
-                        (target.kind == queryable::ALL_KINDS)
-                            .then(|| Resource::get_resource(prefix, suffix))
-                            .flatten()
-                            .map(|res| res.routers_query_route(local_context))
-                            .flatten()
-                            .unwrap_or_else(|| {
-                                compute_query_route(
-                                    tables,
-                                    prefix,
-                                    suffix,
-                                    target.kind,
-                                    Some(local_context),
-                                    whatami::ROUTER,
-                                )
-                            })
-                    }
-                    whatami::PEER => {
-                        let peers_net = tables.peers_net.as_ref().unwrap();
-                        let local_context = peers_net
-                            .get_local_context(routing_context.map(|rc| rc.tree_id), face.link_id);
-                        (target.kind == queryable::ALL_KINDS)
-                            .then(|| Resource::get_resource(prefix, suffix))
-                            .flatten()
-                            .map(|res| res.peers_query_route(local_context))
-                            .flatten()
-                            .unwrap_or_else(|| {
-                                compute_query_route(
-                                    tables,
-                                    prefix,
-                                    suffix,
-                                    target.kind,
-                                    Some(local_context),
-                                    whatami::PEER,
-                                )
-                            })
-                    }
-                    _ => (target.kind == queryable::ALL_KINDS)
-                        .then(|| Resource::get_resource(prefix, suffix))
-                        .flatten()
-                        .map(|res| res.routers_query_route(0))
-                        .flatten()
-                        .unwrap_or_else(|| {
-                            compute_query_route(
-                                tables,
-                                prefix,
-                                suffix,
-                                target.kind,
-                                None,
-                                whatami::CLIENT,
-                            )
-                        }),
-                },
-                whatami::PEER => match face.whatami {
-                    whatami::ROUTER | whatami::PEER => {
-                        let peers_net = tables.peers_net.as_ref().unwrap();
-                        let local_context = peers_net
-                            .get_local_context(routing_context.map(|rc| rc.tree_id), face.link_id);
-                        (target.kind == queryable::ALL_KINDS)
-                            .then(|| Resource::get_resource(prefix, suffix))
-                            .flatten()
-                            .map(|res| res.peers_query_route(local_context))
-                            .flatten()
-                            .unwrap_or_else(|| {
-                                compute_query_route(
-                                    tables,
-                                    prefix,
-                                    suffix,
-                                    target.kind,
-                                    Some(local_context),
-                                    whatami::PEER,
-                                )
-                            })
+            if let Target::Balanced { n, strategy } = target.target {
+                let prefix = prefix.clone();
+                return route_balanced_query(
+                    tables,
+                    face,
+                    &prefix,
+                    suffix,
+                    predicate,
+                    qid,
+                    target.kind,
+                    n,
+                    strategy,
+                    consolidation,
+                    routing_context,
+                );
+            }
+
+            let (source, source_type) = query_source(tables, face, routing_context);
+            let route = (target.kind == queryable::ALL_KINDS)
+                .then_some(prefix)
+                .and_then(|prefix| Resource::get_resource(prefix, suffix))
+                .and_then(|res| match (tables.whatami, source_type) {
+                    (whatami::ROUTER, whatami::ROUTER) => res.routers_query_route(source.unwrap()),
+                    (whatami::ROUTER, whatami::PEER) | (whatami::PEER, _) => {
+                        res.peers_query_route(source.unwrap_or(0))
                     }
-                    _ => (target.kind == queryable::ALL_KINDS)
-                        .then(|| Resource::get_resource(prefix, suffix))
-                        .flatten()
-                        .map(|res| res.peers_query_route(0))
-                        .flatten()
-                        .unwrap_or_else(|| {
-                            compute_query_route(
-                                tables,
-                                prefix,
-                                suffix,
-                                target.kind,
-                                None,
-                                whatami::CLIENT,
-                            )
-                        }),
-                },
-                _ => (target.kind == queryable::ALL_KINDS)
-                    .then(|| Resource::get_resource(prefix, suffix))
-                    .flatten()
-                    .map(|res| res.client_query_route())
-                    .flatten()
-                    .unwrap_or_else(|| {
-                        compute_query_route(
-                            tables,
-                            prefix,
-                            suffix,
-                            target.kind,
-                            None,
-                            whatami::CLIENT,
-                        )
-                    }),
-            };
+                    (whatami::ROUTER, _) => res.routers_query_route(0),
+                    _ => res.client_query_route(),
+                })
+                .unwrap_or_else(|| {
+                    compute_query_route(tables, prefix, suffix, target.kind, source, source_type)
+                });
 
             if route.is_empty()
                 || (route.len() == 1 && route.iter().next().unwrap().1 .0.id == face.id)
@@ -1152,22 +1465,16 @@ pub fn route_query(
                 let query = Arc::new(Query {
                     src_face: face.clone(),
                     src_qid: qid,
+                    balancing: None,
                 });
 
                 for (outface, reskey, context) in route.values() {
                     if face.id != outface.id {
-                        let mut outface = outface.clone();
-                        let outface_mut = get_mut_unchecked(&mut outface);
-                        outface_mut.next_qid += 1;
-                        let qid = outface_mut.next_qid;
-                        outface_mut.pending_queries.insert(qid, query.clone());
-
-                        log::trace!("Propagate query {}:{} to {}", query.src_face, qid, outface);
-
-                        outface.primitives.send_query(
+                        send_query(
+                            &query,
+                            outface,
                             reskey,
                             predicate,
-                            qid,
                             target.clone(),
                             consolidation.clone(),
                             *context,
@@ -1196,6 +1503,9 @@ pub(crate) fn route_send_reply_data(
 ) {
     match face.pending_queries.get(&qid) {
         Some(query) => {
+            if let Some(balancing) = &query.balancing {
+                zlock!(balancing).replied.insert(face.id);
+            }
             query.src_face.primitives.clone().send_reply_data(
                 query.src_qid,
                 replier_kind,
@@ -1232,8 +1542,9 @@ pub(crate) fn route_send_reply_final(_tables: &mut Tables, face: &mut Arc<FaceSt
     }
 }
 
-pub(crate) fn finalize_pending_queries(_tables: &mut Tables, face: &mut Arc<FaceState>) {
+pub(crate) fn finalize_pending_queries(tables: &mut Tables, face: &mut Arc<FaceState>) {
     for query in face.pending_queries.values() {
+        retry_balanced_query(tables, face, query);
         log::debug!(
             "Finalize reply {}:{} for closing {}",
             query.src_face,
@@ -1251,3 +1562,88 @@ pub(crate) fn finalize_pending_queries(_tables: &mut Tables, face: &mut Arc<Face
     }
     get_mut_unchecked(face).pending_queries.clear();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::network::Node;
+    use super::super::runtime::Runtime;
+    use super::super::transport::DummyPrimitives;
+    use super::*;
+    use crate::net::protocol::core::queryable::EVAL;
+    use janu_util::properties::config::*;
+    use janu_util::zwrite;
+
+    #[test]
+    fn balanced_queries_retry_on_router_undeclare() {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, "".to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let runtime = async_std::task::block_on(Runtime::new(0, config, None)).unwrap();
+        let router = runtime.router.clone();
+        let mut tables = zwrite!(router.tables);
+
+        // Two routers b and c directly linked to the local one both declare a queryable
+        let (b, c) = (PeerId::new(1, [2; 16]), PeerId::new(1, [3; 16]));
+        let net = tables.routers_net.as_mut().unwrap();
+        for pid in [b, c].iter() {
+            let idx = net.graph.add_node(Node {
+                pid: *pid,
+                whatami: whatami::ROUTER,
+                locators: None,
+                sn: 1,
+                links: vec![],
+                costs: None,
+            });
+            net.graph.update_edge(net.idx, idx, 1.0);
+        }
+        net.compute_trees();
+        let mut routers = vec![];
+        for pid in [b, c].iter() {
+            let mut face = tables
+                .open_face(*pid, whatami::ROUTER, Arc::new(DummyPrimitives::new()))
+                .upgrade()
+                .unwrap();
+            declare_router_queryable(&mut tables, &mut face, 0, "/test/eval", EVAL, *pid);
+            routers.push(face);
+        }
+        let pid = tables.pid;
+        let client = tables
+            .open_face(pid, whatami::CLIENT, Arc::new(DummyPrimitives::new()))
+            .upgrade()
+            .unwrap();
+
+        route_query(
+            &mut tables,
+            &client,
+            0,
+            "/test/eval",
+            "",
+            0,
+            QueryTarget {
+                kind: queryable::ALL_KINDS,
+                target: Target::Balanced {
+                    n: 1,
+                    strategy: BalancingStrategy::RoundRobin,
+                },
+            },
+            QueryConsolidation::default(),
+            None,
+        );
+        let pending = |tables: &Tables| -> Vec<usize> {
+            routers
+                .iter()
+                .map(|face| tables.faces[&face.id].pending_queries.len())
+                .collect()
+        };
+        let chosen = pending(&tables).iter().position(|n| *n == 1).unwrap();
+        assert_eq!(pending(&tables)[1 - chosen], 0);
+
+        // The query pending on the chosen router is routed to the other one
+        // once the queryable of the chosen router is undeclared
+        let mut face = routers[chosen].clone();
+        let pid = face.pid;
+        forget_router_queryable(&mut tables, &mut face, 0, "/test/eval", &pid);
+        assert_eq!(pending(&tables)[1 - chosen], 1);
+    }
+}
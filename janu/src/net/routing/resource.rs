@@ -13,12 +13,12 @@
 //
 use super::face::FaceState;
 use super::protocol::core::rname;
-use super::protocol::core::{PeerId, ResKey, SubInfo, ZInt};
+use super::protocol::core::{whatami, PeerId, Period, ResKey, SubInfo, ZInt};
 use super::protocol::io::ZBuf;
 use super::protocol::proto::{DataInfo, RoutingContext};
 use super::router::Tables;
 use async_std::sync::{Arc, Weak};
-use std::collections::hash_map::DefaultHasher;
+use serde_json::json;
 use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 use janu_util::sync::get_mut_unchecked;
@@ -26,6 +26,25 @@ use janu_util::sync::get_mut_unchecked;
 pub(super) type Route = HashMap<usize, (Arc<FaceState>, ResKey, Option<RoutingContext>)>;
 pub(super) type PullCaches = Vec<Arc<SessionContext>>;
 
+pub(super) fn route_json(route: &Route) -> serde_json::Value {
+    let mut route: Vec<_> = route.values().collect();
+    route.sort_by_key(|(face, _, _)| face.id);
+    serde_json::Value::Array(
+        route
+            .iter()
+            .map(|(face, reskey, context)| {
+                json!({
+                    "face": face.id,
+                    "pid": face.pid.to_string(),
+                    "whatami": whatami::to_string(face.whatami),
+                    "reskey": reskey.to_string(),
+                    "tree_id": context.map(|context| context.tree_id),
+                })
+            })
+            .collect(),
+    )
+}
+
 pub(super) struct SessionContext {
     pub(super) face: Arc<FaceState>,
     pub(super) local_rid: Option<ZInt>,
@@ -33,11 +52,22 @@ pub(super) struct SessionContext {
     pub(super) subs: Option<SubInfo>,
     pub(super) qabl: Option<ZInt>,
     pub(super) last_values: HashMap<String, (Option<DataInfo>, ZBuf)>,
+    // The period of the running sampling task of a periodic subscription (if any).
+    pub(super) sampler: Option<Period>,
+    // Whether the delivery window of a periodic subscription with a duration is open.
+    pub(super) window: bool,
 }
 
 pub(super) struct ResourceContext {
     pub(super) router_subs: HashSet<PeerId>,
+    // The periods of the periodic router subscriptions.
+    pub(super) router_subs_periods: HashMap<PeerId, Period>,
+    // The samplers of the periodic router and peer subscriptions per outgoing face, fed
+    // with the data entering the routers or peers network through this node.
+    pub(super) net_samplers: HashMap<usize, Arc<SessionContext>>,
     pub(super) peer_subs: HashSet<PeerId>,
+    // The periods of the periodic peer subscriptions.
+    pub(super) peer_subs_periods: HashMap<PeerId, Period>,
     pub(super) router_qabls: HashMap<PeerId, ZInt>,
     pub(super) peer_qabls: HashMap<PeerId, ZInt>,
     pub(super) matches: Vec<Weak<Resource>>,
@@ -54,7 +84,10 @@ impl ResourceContext {
     fn new() -> ResourceContext {
         ResourceContext {
             router_subs: HashSet::new(),
+            router_subs_periods: HashMap::new(),
+            net_samplers: HashMap::new(),
             peer_subs: HashSet::new(),
+            peer_subs_periods: HashMap::new(),
             router_qabls: HashMap::new(),
             peer_qabls: HashMap::new(),
             matches: Vec::new(),
@@ -242,6 +275,36 @@ impl Resource {
         result
     }
 
+    /// Returns `from` and all its descendants.
+    pub(super) fn tree(from: &Arc<Resource>) -> Vec<Arc<Resource>> {
+        let mut result = vec![from.clone()];
+        for child in from.childs.values() {
+            result.extend(Resource::tree(child));
+        }
+        result
+    }
+
+    pub(crate) fn tree_json(from: &Arc<Resource>) -> serde_json::Value {
+        serde_json::Value::Array(
+            Resource::tree(from)
+                .iter()
+                .map(|res| {
+                    let mut faces: Vec<&usize> = res.session_ctxs.keys().collect();
+                    faces.sort();
+                    json!({
+                        "key": res.name(),
+                        "matches": res.context.as_ref().map(|ctx| ctx
+                            .matches
+                            .iter()
+                            .filter_map(|m| m.upgrade().map(|m| m.name()))
+                            .collect::<Vec<String>>()),
+                        "faces": faces,
+                    })
+                })
+                .collect(),
+        )
+    }
+
     pub fn make_resource(
         tables: &mut Tables,
         from: &mut Arc<Resource>,
@@ -362,6 +425,8 @@ impl Resource {
                             subs: None,
                             qabl: None,
                             last_values: HashMap::new(),
+                            sampler: None,
+                            window: false,
                         })
                     });
 
@@ -531,6 +596,8 @@ pub fn declare_resource(
                             subs: None,
                             qabl: None,
                             last_values: HashMap::new(),
+                            sampler: None,
+                            window: false,
                         })
                     })
                     .clone();
@@ -581,26 +648,3 @@ pub fn undeclare_resource(_tables: &mut Tables, face: &mut Arc<FaceState>, rid:
 //         self.res.hash(state)
 //     }
 // }
-
-#[inline]
-pub(super) fn elect_router<'a>(res_name: &str, routers: &'a [PeerId]) -> &'a PeerId {
-    if routers.len() == 1 {
-        &routers[0]
-    } else {
-        routers
-            .iter()
-            .map(|router| {
-                let mut hasher = DefaultHasher::new();
-                for b in res_name.as_bytes() {
-                    hasher.write_u8(*b);
-                }
-                for b in router.as_slice() {
-                    hasher.write_u8(*b);
-                }
-                (router, hasher.finish())
-            })
-            .max_by(|(_, s1), (_, s2)| s1.partial_cmp(s2).unwrap())
-            .unwrap()
-            .0
-    }
-}
@@ -11,6 +11,8 @@
 // Contributors:
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
+use super::egress::EgressPolicy;
+use super::election::{HashElection, RouterElection};
 use super::face::{Face, FaceState};
 use super::network::{shared_nodes, Network};
 use super::protocol::core::{whatami, PeerId, WhatAmI, ZInt};
@@ -20,7 +22,7 @@ pub use super::queries::*;
 pub use super::resource::*;
 use super::runtime::Runtime;
 use super::transport::{DeMux, Mux, Primitives, TransportPeerEventHandler, TransportUnicast};
-use crate::net::link::Link;
+use crate::net::link::{Link, Locator};
 use async_std::sync::{Arc, Weak};
 use async_std::task::JoinHandle;
 use std::any::Any;
@@ -34,6 +36,8 @@ use janu_util::zconfigurable;
 zconfigurable! {
     static ref LINK_CLOSURE_DELAY: u64 = 200;
     static ref TREES_COMPUTATION_DELAY: u64 = 100;
+    static ref LINK_COSTS_UPDATE_INTERVAL: u64 = 5000;
+    static ref GOSSIP_CONNECT_INTERVAL: u64 = 2000;
 }
 
 pub struct Tables {
@@ -54,6 +58,10 @@ pub struct Tables {
     pub(crate) shared_nodes: Vec<PeerId>,
     pub(crate) routers_trees_task: Option<JoinHandle<()>>,
     pub(crate) peers_trees_task: Option<JoinHandle<()>>,
+    pub(crate) egress_policies: Vec<Arc<EgressPolicy>>,
+    pub(crate) router_election: Box<dyn RouterElection>,
+    pub(crate) balanced_queries: HashMap<String, usize>,
+    pub(crate) subs_aggregation: Vec<SubsAggregation>,
 }
 
 impl Tables {
@@ -75,6 +83,10 @@ impl Tables {
             shared_nodes: vec![],
             routers_trees_task: None,
             peers_trees_task: None,
+            egress_policies: vec![],
+            router_election: Box::new(HashElection),
+            balanced_queries: HashMap::new(),
+            subs_aggregation: vec![],
         }
     }
 
@@ -87,6 +99,12 @@ impl Tables {
         Resource::print_tree(&self.root_res)
     }
 
+    pub(crate) fn faces_json(&self) -> serde_json::Value {
+        let mut faces: Vec<&Arc<FaceState>> = self.faces.values().collect();
+        faces.sort_by_key(|face| face.id);
+        serde_json::Value::Array(faces.iter().map(|face| face.to_json()).collect())
+    }
+
     #[inline]
     #[allow(clippy::trivially_copy_pass_by_ref)]
     pub(crate) fn get_mapping<'a>(
@@ -120,6 +138,7 @@ impl Tables {
         whatami: WhatAmI,
         primitives: Arc<dyn Primitives + Send + Sync>,
         link_id: usize,
+        locators: &[Locator],
     ) -> Weak<FaceState> {
         let fid = self.face_counter;
         self.face_counter += 1;
@@ -130,6 +149,14 @@ impl Tables {
             .clone();
         log::debug!("New {}", newface);
 
+        let egress = self
+            .egress_policies
+            .iter()
+            .filter(|policy| policy.applies_to(&newface, locators))
+            .cloned()
+            .collect();
+        get_mut_unchecked(&mut newface).egress = egress;
+
         pubsub_new_face(self, &mut newface);
         queries_new_face(self, &mut newface);
 
@@ -142,7 +169,7 @@ impl Tables {
         whatami: WhatAmI,
         primitives: Arc<dyn Primitives + Send + Sync>,
     ) -> Weak<FaceState> {
-        self.open_net_face(pid, whatami, primitives, 0)
+        self.open_net_face(pid, whatami, primitives, 0, &[])
     }
 
     pub fn close_face(&mut self, face: &Weak<FaceState>) {
@@ -173,6 +200,9 @@ impl Tables {
                     undeclare_client_queryable(self, &mut face_clone, &mut res);
                     Resource::clean(&mut res);
                 }
+                for policy in face.egress.drain(..) {
+                    policy.forget_face(face.id);
+                }
                 self.faces.remove(&face.id);
             }
             None => log::error!("Face already closed!"),
@@ -220,6 +250,7 @@ impl Tables {
 
                 log::trace!("Compute routes");
                 pubsub_tree_change(&mut tables, &new_childs, net_type);
+                schedule_net_samplers(&tables_ref, &tables);
                 queries_tree_change(&mut tables, &new_childs, net_type);
 
                 log::trace!("Computations completed");
@@ -276,6 +307,73 @@ impl Router {
                 tables.peers_net.as_ref().unwrap(),
             );
         }
+        drop(tables);
+
+        // Periodically update the link costs from the measured round-trip times
+        let tables_ref = Arc::downgrade(&self.tables);
+        async_std::task::spawn(async move {
+            loop {
+                async_std::task::sleep(std::time::Duration::from_millis(
+                    *LINK_COSTS_UPDATE_INTERVAL,
+                ))
+                .await;
+                let tables_ref = match tables_ref.upgrade() {
+                    Some(tables_ref) => tables_ref,
+                    None => break,
+                };
+                let mut tables = zwrite!(tables_ref);
+                if let Some(true) = tables.routers_net.as_mut().map(|n| n.update_link_costs()) {
+                    tables.schedule_compute_trees(tables_ref.clone(), whatami::ROUTER);
+                }
+                if let Some(true) = tables.peers_net.as_mut().map(|n| n.update_link_costs()) {
+                    tables.schedule_compute_trees(tables_ref.clone(), whatami::PEER);
+                }
+            }
+        });
+
+        // Periodically connect the nodes learned through the link states that are not
+        // connected, either because the connection failed or because it was closed.
+        // The locators that failed to connect are retried with an exponential backoff.
+        if peers_autoconnect || routers_autoconnect_gossip {
+            let tables_ref = Arc::downgrade(&self.tables);
+            async_std::task::spawn(async move {
+                loop {
+                    async_std::task::sleep(std::time::Duration::from_millis(
+                        *GOSSIP_CONNECT_INTERVAL,
+                    ))
+                    .await;
+                    let tables_ref = match tables_ref.upgrade() {
+                        Some(tables_ref) => tables_ref,
+                        None => break,
+                    };
+                    let tables = zread!(tables_ref);
+                    if let Some(net) = tables.routers_net.as_ref() {
+                        net.connect_known();
+                    }
+                    if let Some(net) = tables.peers_net.as_ref() {
+                        net.connect_known();
+                    }
+                }
+            });
+        }
+    }
+
+    /// Sets the egress policies applied to the data routed to the faces opened from now on.
+    pub fn init_egress_policies(&self, policies: Vec<Arc<EgressPolicy>>) {
+        zwrite!(self.tables).egress_policies = policies;
+    }
+
+    /// Sets the strategy used to elect the router in charge of routing some data
+    /// or query between the router mesh and the peer mesh.
+    pub fn init_router_election(&self, election: Box<dyn RouterElection>) {
+        zwrite!(self.tables).router_election = election;
+    }
+
+    /// Sets the key expressions covering the subscriptions that are advertised to the
+    /// other routers and peers as a single subscription on the key expression.
+    pub fn init_subscriptions_aggregation(&self, exprs: Vec<String>) {
+        zwrite!(self.tables).subs_aggregation =
+            exprs.into_iter().map(SubsAggregation::new).collect();
     }
 
     pub fn new_primitives(&self, primitives: Arc<dyn Primitives + Send + Sync>) -> Arc<Face> {
@@ -322,6 +420,12 @@ impl Router {
             );
         }
 
+        let locators: Vec<Locator> = transport
+            .get_links()
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|link| vec![link.src, link.dst])
+            .collect();
         let handler = Arc::new(LinkStateInterceptor::new(
             transport.clone(),
             self.tables.clone(),
@@ -333,6 +437,7 @@ impl Router {
                         whatami,
                         Arc::new(Mux::new(transport)),
                         link_id,
+                        &locators,
                     )
                     .upgrade()
                     .unwrap(),
@@ -19,8 +19,12 @@ use super::protocol::{
     proto::{encoding, DataInfo, RoutingContext},
 };
 use super::routing::face::Face;
+use super::routing::pubsub::{data_route_json, subscribers_json};
+use super::routing::queries::{query_route_json, queryables_json};
+use super::routing::resource::Resource;
 use super::transport::Primitives;
 use super::Runtime;
+use crate::Selector;
 use async_std::sync::Arc;
 use async_std::task;
 use futures::future;
@@ -41,7 +45,7 @@ pub struct AdminContext {
     version: String,
 }
 
-type Handler = Box<dyn Fn(&AdminContext) -> BoxFuture<'_, (ZBuf, ZInt)> + Send + Sync>;
+type Handler = Box<dyn Fn(&AdminContext, String) -> BoxFuture<'_, (ZBuf, ZInt)> + Send + Sync>;
 
 pub struct AdminSpace {
     pid: PeerId,
@@ -59,15 +63,49 @@ impl AdminSpace {
         let mut handlers: HashMap<String, Arc<Handler>> = HashMap::new();
         handlers.insert(
             root_path.clone(),
-            Arc::new(Box::new(|context| router_data(context).boxed())),
+            Arc::new(Box::new(|context, _| router_data(context).boxed())),
         );
         handlers.insert(
             [&root_path, "/linkstate/routers"].concat(),
-            Arc::new(Box::new(|context| linkstate_routers_data(context).boxed())),
+            Arc::new(Box::new(|context, _| {
+                linkstate_routers_data(context).boxed()
+            })),
         );
         handlers.insert(
             [&root_path, "/linkstate/peers"].concat(),
-            Arc::new(Box::new(|context| linkstate_peers_data(context).boxed())),
+            Arc::new(Box::new(|context, _| linkstate_peers_data(context).boxed())),
+        );
+        handlers.insert(
+            [&root_path, "/egress"].concat(),
+            Arc::new(Box::new(|context, _| egress_data(context).boxed())),
+        );
+        handlers.insert(
+            [&root_path, "/routing/resources"].concat(),
+            Arc::new(Box::new(|context, _| {
+                routing_resources_data(context).boxed()
+            })),
+        );
+        handlers.insert(
+            [&root_path, "/routing/faces"].concat(),
+            Arc::new(Box::new(|context, _| routing_faces_data(context).boxed())),
+        );
+        handlers.insert(
+            [&root_path, "/routing/subscribers"].concat(),
+            Arc::new(Box::new(|context, _| {
+                routing_subscribers_data(context).boxed()
+            })),
+        );
+        handlers.insert(
+            [&root_path, "/routing/queryables"].concat(),
+            Arc::new(Box::new(|context, _| {
+                routing_queryables_data(context).boxed()
+            })),
+        );
+        handlers.insert(
+            [&root_path, "/routing/route"].concat(),
+            Arc::new(Box::new(|context, predicate| {
+                routing_route_data(context, predicate).boxed()
+            })),
         );
         let context = Arc::new(AdminContext {
             runtime: runtime.clone(),
@@ -199,11 +237,12 @@ impl Primitives for AdminSpace {
             }
             None => error!("Unknown ResKey!!"),
         };
+        let predicate = predicate.to_string();
 
         // router is not re-entrant
         task::spawn(async move {
             for (path, handler) in matching_handlers {
-                let (payload, encoding) = handler(&context).await;
+                let (payload, encoding) = handler(&context, predicate.clone()).await;
                 let mut data_info = DataInfo::new();
                 data_info.encoding = Some(encoding);
 
@@ -340,3 +379,65 @@ pub async fn linkstate_peers_data(context: &AdminContext) -> (ZBuf, ZInt) {
         encoding::TEXT_PLAIN,
     )
 }
+
+pub async fn egress_data(context: &AdminContext) -> (ZBuf, ZInt) {
+    let policies: Vec<serde_json::Value> = zread!(context.runtime.router.tables)
+        .egress_policies
+        .iter()
+        .map(|policy| {
+            json!({
+                "key_expr": policy.keyexpr,
+                "face": policy.face,
+                "limit": policy.limit.to_string(),
+                "forwarded": policy.forwarded(),
+                "dropped": policy.dropped(),
+            })
+        })
+        .collect();
+    let json = serde_json::Value::Array(policies);
+    log::trace!("AdminSpace egress_data: {:?}", json);
+    (ZBuf::from(json.to_string().as_bytes()), encoding::APP_JSON)
+}
+
+pub async fn routing_resources_data(context: &AdminContext) -> (ZBuf, ZInt) {
+    let json = Resource::tree_json(&zread!(context.runtime.router.tables).root_res);
+    (ZBuf::from(json.to_string().as_bytes()), encoding::APP_JSON)
+}
+
+pub async fn routing_faces_data(context: &AdminContext) -> (ZBuf, ZInt) {
+    let json = zread!(context.runtime.router.tables).faces_json();
+    (ZBuf::from(json.to_string().as_bytes()), encoding::APP_JSON)
+}
+
+pub async fn routing_subscribers_data(context: &AdminContext) -> (ZBuf, ZInt) {
+    let tables = zread!(context.runtime.router.tables);
+    let json = subscribers_json(&tables);
+    (ZBuf::from(json.to_string().as_bytes()), encoding::APP_JSON)
+}
+
+pub async fn routing_queryables_data(context: &AdminContext) -> (ZBuf, ZInt) {
+    let tables = zread!(context.runtime.router.tables);
+    let json = queryables_json(&tables);
+    (ZBuf::from(json.to_string().as_bytes()), encoding::APP_JSON)
+}
+
+/// The routes computed for the key given as `key` property of the selector
+/// (e.g. `/@/router/<pid>/routing/route?(key=/demo/example/test)`).
+pub async fn routing_route_data(context: &AdminContext, predicate: String) -> (ZBuf, ZInt) {
+    let key = Selector::new("/", &predicate)
+        .ok()
+        .and_then(|selector| selector.properties.get("key").cloned());
+    let json = match key {
+        Some(key) => {
+            let tables = zread!(context.runtime.router.tables);
+            json!({
+                "key": key,
+                "data": data_route_json(&tables, &key),
+                "query": query_route_json(&tables, &key),
+            })
+        }
+        None => json!({ "error": "missing 'key' property in selector" }),
+    };
+    log::trace!("AdminSpace routing_route_data: {:?}", json);
+    (ZBuf::from(json.to_string().as_bytes()), encoding::APP_JSON)
+}
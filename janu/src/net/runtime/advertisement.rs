@@ -0,0 +1,247 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::link::Locator;
+use super::protocol::core::{whatami, WhatAmI};
+use std::net::{IpAddr, SocketAddr};
+use janu_util::properties::config::*;
+
+/// The family of the address of a locator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocatorFamily {
+    Ipv4,
+    Ipv6,
+    UnixSock,
+}
+
+impl LocatorFamily {
+    /// Returns the family of the given locator or `None` if it
+    /// is unknown (e.g. for a locator with a DNS name).
+    pub fn of(locator: &Locator) -> Option<LocatorFamily> {
+        let proto = locator.address.get_proto().to_string();
+        if proto.starts_with("unixsock") {
+            return Some(LocatorFamily::UnixSock);
+        }
+        match socket_addr(locator)? {
+            SocketAddr::V4(_) => Some(LocatorFamily::Ipv4),
+            SocketAddr::V6(_) => Some(LocatorFamily::Ipv6),
+        }
+    }
+
+    fn parse(s: &str) -> Option<LocatorFamily> {
+        match s {
+            "ipv4" => Some(LocatorFamily::Ipv4),
+            "ipv6" => Some(LocatorFamily::Ipv6),
+            "unixsock" => Some(LocatorFamily::UnixSock),
+            _ => None,
+        }
+    }
+}
+
+/// A range of IP addresses with the format `<address>/<prefix length>` (e.g. `10.0.0.0/8`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressRange {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl AddressRange {
+    /// Parses a range of IP addresses or a single IP address.
+    pub fn parse(s: &str) -> Option<AddressRange> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, Some(prefix.parse().ok()?)),
+            None => (s.parse::<IpAddr>().ok()?, None),
+        };
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        if prefix > max {
+            return None;
+        }
+        Some(AddressRange { addr, prefix })
+    }
+
+    /// Returns true if the given address is part of this range.
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        fn prefix_eq(a: &[u8], b: &[u8], prefix: usize) -> bool {
+            let (bytes, bits) = (prefix / 8, prefix % 8);
+            a[..bytes] == b[..bytes] && (bits == 0 || (a[bytes] ^ b[bytes]) >> (8 - bits) == 0)
+        }
+        match (&self.addr, addr) {
+            (IpAddr::V4(a), IpAddr::V4(b)) => {
+                prefix_eq(&a.octets(), &b.octets(), self.prefix.into())
+            }
+            (IpAddr::V6(a), IpAddr::V6(b)) => {
+                prefix_eq(&a.octets(), &b.octets(), self.prefix.into())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Selects the locators a node advertises to other nodes in scouting
+/// replies and link-state messages.
+#[derive(Default)]
+pub struct LocatorsAdvertisement {
+    external: Vec<(Locator, Locator)>,
+    excluded: Vec<AddressRange>,
+    families: Vec<(LocatorFamily, WhatAmI)>,
+}
+
+impl LocatorsAdvertisement {
+    /// Creates the locators advertisement from the `advertised_locators`,
+    /// `advertised_locators_exclude` and `advertised_locators_families` configuration.
+    /// Invalid values are logged and ignored.
+    pub fn from_config(config: &ConfigProperties) -> LocatorsAdvertisement {
+        let entries = |key| {
+            config
+                .get(key)
+                .map(|s| {
+                    s.split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(str::to_string)
+                        .collect::<Vec<String>>()
+                })
+                .unwrap_or_default()
+        };
+
+        let external = entries(&ZN_ADVERTISED_LOCATORS_KEY)
+            .into_iter()
+            .filter_map(|s| {
+                let res = s.split_once('=').and_then(|(listener, locator)| {
+                    Some((listener.trim().parse().ok()?, locator.trim().parse().ok()?))
+                });
+                if res.is_none() {
+                    log::error!("Invalid advertised locator configuration: {}", s);
+                }
+                res
+            })
+            .collect();
+
+        let mut excluded = vec![];
+        for s in entries(&ZN_ADVERTISED_LOCATORS_EXCLUDE_KEY) {
+            match AddressRange::parse(&s) {
+                Some(range) => excluded.push(range),
+                None => match janu_util::net::get_unicast_addresses_of_interface(&s) {
+                    Ok(addrs) if !addrs.is_empty() => {
+                        excluded.extend(
+                            addrs
+                                .iter()
+                                .filter_map(|addr| AddressRange::parse(&addr.to_string())),
+                        );
+                    }
+                    _ => log::error!("Invalid advertised locators exclusion: {}", s),
+                },
+            }
+        }
+
+        let families = entries(&ZN_ADVERTISED_LOCATORS_FAMILIES_KEY)
+            .into_iter()
+            .filter_map(|s| {
+                let res = s.split_once('=').and_then(|(family, whatamis)| {
+                    let family = LocatorFamily::parse(family.trim())?;
+                    let mut mask = 0;
+                    for w in whatamis.split('|').map(str::trim).filter(|w| !w.is_empty()) {
+                        mask |= whatami::parse(w).ok()?;
+                    }
+                    Some((family, mask))
+                });
+                if res.is_none() {
+                    log::error!("Invalid advertised locators family configuration: {}", s);
+                }
+                res
+            })
+            .collect();
+
+        LocatorsAdvertisement {
+            external,
+            excluded,
+            families,
+        }
+    }
+
+    /// Returns the locators to advertise to nodes of the given kinds (as a mask)
+    /// among the given locators of the listeners of the local node. When several
+    /// kinds are given, only the families advertised to all of them are kept.
+    pub fn locators(&self, locators: &[Locator], whatami: WhatAmI) -> Vec<Locator> {
+        let mut advertised: Vec<Locator> = vec![];
+        let mut push = |locator: &Locator| {
+            if !advertised.contains(locator) {
+                advertised.push(locator.clone());
+            }
+        };
+        for locator in locators {
+            let mut external = self
+                .external
+                .iter()
+                .filter(|(listener, _)| listener_matches(listener, locator))
+                .map(|(_, external)| external)
+                .peekable();
+            if external.peek().is_some() {
+                external.for_each(&mut push);
+            } else if !self.is_excluded(locator) {
+                push(locator);
+            }
+        }
+        advertised.retain(|locator| self.is_advertised_to(locator, whatami));
+        advertised
+    }
+
+    /// Returns the locators of another node that can be forwarded to nodes of the
+    /// given kinds (as a mask) among the given ones, according to their family.
+    pub fn filter(&self, locators: &[Locator], whatami: WhatAmI) -> Vec<Locator> {
+        locators
+            .iter()
+            .filter(|locator| self.is_advertised_to(locator, whatami))
+            .cloned()
+            .collect()
+    }
+
+    fn is_excluded(&self, locator: &Locator) -> bool {
+        match socket_addr(locator) {
+            Some(addr) => self.excluded.iter().any(|range| range.contains(&addr.ip())),
+            None => false,
+        }
+    }
+
+    fn is_advertised_to(&self, locator: &Locator, whatami: WhatAmI) -> bool {
+        match LocatorFamily::of(locator) {
+            Some(family) => !self
+                .families
+                .iter()
+                .any(|(f, mask)| *f == family && mask & whatami != whatami),
+            None => true,
+        }
+    }
+}
+
+fn socket_addr(locator: &Locator) -> Option<SocketAddr> {
+    let address = locator.address.to_string();
+    let (_, addr) = address.split_once('/')?;
+    addr.parse().ok()
+}
+
+// Returns true if the locator is one of the locators of the configured listener,
+// i.e. if they have the same address or if the listener listens on all the
+// interfaces and they have the same port.
+fn listener_matches(listener: &Locator, locator: &Locator) -> bool {
+    if listener.address.get_proto() != locator.address.get_proto() {
+        return false;
+    }
+    match (socket_addr(listener), socket_addr(locator)) {
+        (Some(l), Some(addr)) => {
+            l.port() == addr.port() && (l.ip().is_unspecified() || l.ip() == addr.ip())
+        }
+        _ => listener.address == locator.address,
+    }
+}
@@ -12,6 +12,7 @@
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
 mod adminspace;
+pub mod advertisement;
 pub mod orchestrator;
 
 use super::link;
@@ -21,7 +22,10 @@ use super::protocol;
 use super::protocol::core::{whatami, PeerId, WhatAmI};
 use super::protocol::proto::{JanuBody, JanuMessage};
 use super::routing;
+use super::routing::egress::EgressPolicy;
+use super::routing::election;
 use super::routing::pubsub::full_reentrant_route_data;
+use super::routing::pubsub::parse_subscriptions_aggregation;
 use super::routing::router::{LinkStateInterceptor, Router};
 use super::transport;
 use super::transport::{
@@ -101,6 +105,18 @@ impl Runtime {
         };
 
         let router = Arc::new(Router::new(pid, whatami, hlc.clone()));
+        if let Some(policies) = config.get(&ZN_EGRESS_POLICIES_KEY) {
+            router.init_egress_policies(EgressPolicy::parse_list(policies));
+        }
+        if let Some(exprs) = config.get(&ZN_SUBSCRIPTIONS_AGGREGATION_KEY) {
+            router.init_subscriptions_aggregation(parse_subscriptions_aggregation(exprs));
+        }
+        if let Some(strategy) = config.get(&ZN_ROUTER_ELECTION_KEY) {
+            router.init_router_election(election::from_config(
+                strategy,
+                config.get(&ZN_ROUTER_HOMES_KEY).map(String::as_str),
+            )?);
+        }
 
         let handler = Arc::new(RuntimeTransportEventHandler {
             runtime: std::sync::RwLock::new(None),
@@ -11,7 +11,8 @@
 // Contributors:
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
-use super::link::{EndPoint, Locator};
+use super::advertisement::LocatorsAdvertisement;
+use super::link::{EndPoint, Locator, LocatorAddress};
 use super::protocol::core::{whatami, PeerId, WhatAmI};
 use super::protocol::io::{WBuf, ZBuf};
 use super::protocol::proto::{Hello, Scout, TransportBody, TransportMessage};
@@ -42,6 +43,70 @@ pub enum Loop {
     Break,
 }
 
+/// Selects the nodes discovered through multicast scouting or gossip discovery
+/// that a node automatically connects to, and the locators it connects them with.
+#[derive(Clone)]
+pub struct Autoconnect {
+    whatami: WhatAmI,
+    protocols: Option<Vec<String>>,
+}
+
+impl Autoconnect {
+    /// Creates the autoconnect filter of a node of the given kind from the
+    /// `autoconnect_whatami` and `autoconnect_protocols` configuration.
+    /// Invalid values are logged and ignored.
+    pub fn from_config(config: &ConfigProperties, whatami: WhatAmI) -> Autoconnect {
+        let whatami = match config.get(&ZN_AUTOCONNECT_WHATAMI_KEY) {
+            Some(s) => s
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .fold(0, |mask, s| match whatami::parse(s) {
+                    Ok(w) if w != whatami::CLIENT => mask | w,
+                    _ => {
+                        log::error!("Invalid autoconnect whatami configuration: {}", s);
+                        mask
+                    }
+                }),
+            None => match whatami {
+                whatami::PEER => whatami::PEER | whatami::ROUTER,
+                _ => whatami::ROUTER,
+            },
+        };
+        let protocols = config.get(&ZN_AUTOCONNECT_PROTOCOLS_KEY).map(|s| {
+            s.split(',')
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect()
+        });
+        Autoconnect { whatami, protocols }
+    }
+
+    /// The kinds of nodes to connect to.
+    #[inline]
+    pub fn whatami(&self) -> WhatAmI {
+        self.whatami
+    }
+
+    /// Returns true if the nodes of the given kind must be connected to.
+    #[inline]
+    pub fn matches(&self, whatami: WhatAmI) -> bool {
+        self.whatami & whatami != 0
+    }
+
+    /// Returns the locators with an allowed protocol among the given ones.
+    pub fn locators(&self, locators: &[Locator]) -> Vec<Locator> {
+        match &self.protocols {
+            Some(protocols) => locators
+                .iter()
+                .filter(|locator| protocols.contains(&locator.address.get_proto().to_string()))
+                .cloned()
+                .collect(),
+            None => locators.to_vec(),
+        }
+    }
+}
+
 impl Runtime {
     pub async fn start(&mut self) -> ZResult<()> {
         match self.whatami {
@@ -57,16 +122,27 @@ impl Runtime {
         }
     }
 
+    // Parses the comma-separated locators of the given configuration entry. The locators
+    // of the link protocols that are neither built in nor registered are rejected.
+    fn config_locators(&self, key: u64, default: &str) -> ZResult<Vec<Locator>> {
+        let mut locators = vec![];
+        for s in self.config.get_or(&key, default).split(',') {
+            let locator: Locator = match s.trim() {
+                "" => continue,
+                s => s.parse()?,
+            };
+            if let LocatorAddress::Custom(custom) = &locator.address {
+                self.manager()
+                    .get_link_protocol_unicast(custom.protocol())?;
+            }
+            locators.push(locator);
+        }
+        Ok(locators)
+    }
+
     async fn start_client(&self) -> ZResult<()> {
         let config = &self.config;
-        let peers = config
-            .get_or(&ZN_PEER_KEY, "")
-            .split(',')
-            .filter_map(|s| match s.trim() {
-                "" => None,
-                s => Some(s.parse().unwrap()),
-            })
-            .collect::<Vec<Locator>>();
+        let peers = self.config_locators(ZN_PEER_KEY, "")?;
         let scouting = config
             .get_or(&ZN_MULTICAST_SCOUTING_KEY, ZN_MULTICAST_SCOUTING_DEFAULT)
             .to_lowercase()
@@ -136,22 +212,8 @@ impl Runtime {
 
     async fn start_peer(&self) -> ZResult<()> {
         let config = &self.config;
-        let listeners = config
-            .get_or(&ZN_LISTENER_KEY, PEER_DEFAULT_LISTENER)
-            .split(',')
-            .filter_map(|s| match s.trim() {
-                "" => None,
-                s => Some(s.parse().unwrap()),
-            })
-            .collect::<Vec<Locator>>();
-        let peers = config
-            .get_or(&ZN_PEER_KEY, "")
-            .split(',')
-            .filter_map(|s| match s.trim() {
-                "" => None,
-                s => Some(s.parse().unwrap()),
-            })
-            .collect::<Vec<Locator>>();
+        let listeners = self.config_locators(ZN_LISTENER_KEY, PEER_DEFAULT_LISTENER)?;
+        let peers = self.config_locators(ZN_PEER_KEY, "")?;
         let scouting = config
             .get_or(&ZN_MULTICAST_SCOUTING_KEY, ZN_MULTICAST_SCOUTING_DEFAULT)
             .to_lowercase()
@@ -160,6 +222,7 @@ impl Runtime {
             .get_or(&ZN_PEERS_AUTOCONNECT_KEY, ZN_PEERS_AUTOCONNECT_DEFAULT)
             .to_lowercase()
             == ZN_TRUE;
+        let autoconnect = Autoconnect::from_config(config, self.whatami);
         let addr = config
             .get_or(
                 &ZN_MULTICAST_IPV4_ADDRESS_KEY,
@@ -192,21 +255,26 @@ impl Runtime {
                     .collect();
                 if !sockets.is_empty() {
                     let this = self.clone();
-                    async_std::task::spawn(async move {
-                        async_std::prelude::FutureExt::race(
-                            this.responder(&mcast_socket, &sockets),
-                            this.connect_all(
-                                &sockets,
-                                if peers_autoconnect {
-                                    whatami::PEER | whatami::ROUTER
-                                } else {
-                                    whatami::ROUTER
-                                },
-                                &addr,
-                            ),
-                        )
-                        .await;
-                    });
+                    // Without peers autoconnection, only the routers are connected to,
+                    // if the autoconnect configuration selects them
+                    let what = if peers_autoconnect {
+                        autoconnect.whatami()
+                    } else {
+                        autoconnect.whatami() & whatami::ROUTER
+                    };
+                    if what != 0 {
+                        async_std::task::spawn(async move {
+                            async_std::prelude::FutureExt::race(
+                                this.responder(&mcast_socket, &sockets),
+                                this.connect_all(&sockets, what, &addr, &autoconnect),
+                            )
+                            .await;
+                        });
+                    } else {
+                        async_std::task::spawn(async move {
+                            this.responder(&mcast_socket, &sockets).await;
+                        });
+                    }
                 }
             }
         }
@@ -216,22 +284,8 @@ impl Runtime {
 
     async fn start_router(&self) -> ZResult<()> {
         let config = &self.config;
-        let listeners = config
-            .get_or(&ZN_LISTENER_KEY, ROUTER_DEFAULT_LISTENER)
-            .split(',')
-            .filter_map(|s| match s.trim() {
-                "" => None,
-                s => Some(s.parse().unwrap()),
-            })
-            .collect::<Vec<Locator>>();
-        let peers = config
-            .get_or(&ZN_PEER_KEY, "")
-            .split(',')
-            .filter_map(|s| match s.trim() {
-                "" => None,
-                s => Some(s.parse().unwrap()),
-            })
-            .collect::<Vec<Locator>>();
+        let listeners = self.config_locators(ZN_LISTENER_KEY, ROUTER_DEFAULT_LISTENER)?;
+        let peers = self.config_locators(ZN_PEER_KEY, "")?;
         let scouting = config
             .get_or(&ZN_MULTICAST_SCOUTING_KEY, ZN_MULTICAST_SCOUTING_DEFAULT)
             .to_lowercase()
@@ -243,6 +297,7 @@ impl Runtime {
             )
             .to_lowercase()
             == ZN_TRUE;
+        let autoconnect = Autoconnect::from_config(config, self.whatami);
         let addr = config
             .get_or(
                 &ZN_MULTICAST_IPV4_ADDRESS_KEY,
@@ -273,7 +328,12 @@ impl Runtime {
                         async_std::task::spawn(async move {
                             async_std::prelude::FutureExt::race(
                                 this.responder(&mcast_socket, &sockets),
-                                this.connect_all(&sockets, whatami::ROUTER, &addr),
+                                this.connect_all(
+                                    &sockets,
+                                    autoconnect.whatami(),
+                                    &addr,
+                                    &autoconnect,
+                                ),
                             )
                             .await;
                         });
@@ -651,12 +711,23 @@ impl Runtime {
         async_std::prelude::FutureExt::race(scout, timeout).await
     }
 
-    async fn connect_all(&self, ucast_sockets: &[UdpSocket], what: WhatAmI, addr: &SocketAddr) {
+    async fn connect_all(
+        &self,
+        ucast_sockets: &[UdpSocket],
+        what: WhatAmI,
+        addr: &SocketAddr,
+        autoconnect: &Autoconnect,
+    ) {
         Runtime::scout(ucast_sockets, what, addr, move |hello| async move {
             match &hello.pid {
                 Some(pid) => {
                     if let Some(locators) = &hello.locators {
-                        self.connect_peer(pid, locators).await
+                        let locators = autoconnect.locators(locators);
+                        if !locators.is_empty() {
+                            self.connect_peer(pid, &locators).await
+                        } else {
+                            log::trace!("No allowed locator to connect to {:?}", hello);
+                        }
                     } else {
                         log::warn!("Received Hello with no locators : {:?}", hello);
                     }
@@ -697,6 +768,11 @@ impl Runtime {
                 })
         }
 
+        // The kind of the scouting node is unknown, hence only the locators
+        // advertised to all kinds of nodes are sent
+        let advertisement = LocatorsAdvertisement::from_config(&self.config);
+        let advertised_to = whatami::ROUTER | whatami::PEER | whatami::CLIENT;
+
         let mut buf = vec![0; RCV_BUF_SIZE];
         let local_addrs: Vec<SocketAddr> = ucast_sockets
             .iter()
@@ -728,7 +804,10 @@ impl Runtime {
                         let mut hello = TransportMessage::make_hello(
                             pid,
                             Some(self.whatami),
-                            Some(self.manager().get_locators().clone()),
+                            Some(
+                                advertisement
+                                    .locators(&self.manager().get_locators(), advertised_to),
+                            ),
                             None,
                         );
                         let socket = get_best_match(&peer.ip(), ucast_sockets).unwrap();
@@ -844,6 +844,8 @@ impl Session {
     ///         res_name: "/resource/name".to_string(),
     ///         payload: "value".as_bytes().into(),
     ///         data_info: None,
+    ///         channel: Channel::default(),
+    ///         congestion_control: CongestionControl::default(),
     ///     }).await;
     /// }
     /// # })
@@ -946,18 +948,27 @@ impl Session {
             data_info
         });
 
+        let channel = Channel {
+            priority: Priority::default(),
+            reliability: Reliability::Reliable, // @TODO: need to check subscriptions to determine the right reliability value
+        };
+        let congestion_control = CongestionControl::default(); // @TODO: default for the time being
         primitives.send_data(
             resource,
             payload.clone(),
-            Channel {
-                priority: Priority::default(),
-                reliability: Reliability::Reliable, // @TODO: need to check subscriptions to determine the right reliability value
-            },
-            CongestionControl::default(), // @TODO: default for the time being
+            channel,
+            congestion_control,
             data_info.clone(),
             None,
         );
-        self.handle_data(true, resource, data_info, payload);
+        self.handle_data(
+            true,
+            resource,
+            data_info,
+            payload,
+            channel,
+            congestion_control,
+        );
         zready(Ok(()))
     }
 
@@ -999,18 +1010,72 @@ impl Session {
         info.timestamp = self.runtime.new_timestamp();
         let data_info = Some(info);
 
+        let channel = Channel {
+            priority: Priority::default(),
+            reliability: Reliability::Reliable, // @TODO: need to check subscriptions to determine the right reliability value
+        };
         primitives.send_data(
             resource,
             payload.clone(),
-            Channel {
-                priority: Priority::default(),
-                reliability: Reliability::Reliable, // @TODO: need to check subscriptions to determine the right reliability value
-            },
+            channel,
+            congestion_control,
+            data_info.clone(),
+            None,
+        );
+        self.handle_data(
+            true,
+            resource,
+            data_info,
+            payload,
+            channel,
+            congestion_control,
+        );
+        zready(Ok(()))
+    }
+
+    /// Write data with a given [`DataInfo`].
+    ///
+    /// Unlike [`write_ext`](Session::write_ext), the given `data_info` is sent as is.
+    /// This allows to forward a [`Sample`] with its original encoding, kind and timestamp,
+    /// possibly tagged with a `source_id`.
+    ///
+    /// # Arguments
+    ///
+    /// * `resource` - The resource key to write
+    /// * `payload` - The value to write
+    /// * `data_info` - The info associated to the value
+    /// * `channel` - The priority and reliability with which the value is sent
+    /// * `congestion_control` - The value for the congestion control
+    pub fn write_with_info(
+        &self,
+        resource: &ResKey,
+        payload: ZBuf,
+        data_info: DataInfo,
+        channel: Channel,
+        congestion_control: CongestionControl,
+    ) -> impl ZFuture<Output = ZResult<()>> {
+        trace!("write_with_info({:?}, [...])", resource);
+        let state = zread!(self.state);
+        let primitives = state.primitives.as_ref().unwrap().clone();
+        drop(state);
+
+        let data_info = Some(data_info);
+        primitives.send_data(
+            resource,
+            payload.clone(),
+            channel,
             congestion_control,
             data_info.clone(),
             None,
         );
-        self.handle_data(true, resource, data_info, payload);
+        self.handle_data(
+            true,
+            resource,
+            data_info,
+            payload,
+            channel,
+            congestion_control,
+        );
         zready(Ok(()))
     }
 
@@ -1020,6 +1085,8 @@ impl Session {
         res_name: String,
         payload: ZBuf,
         data_info: Option<DataInfo>,
+        channel: Channel,
+        congestion_control: CongestionControl,
     ) {
         match invoker {
             SubscriberInvoker::Handler(handler) => {
@@ -1028,6 +1095,8 @@ impl Session {
                     res_name,
                     payload,
                     data_info,
+                    channel,
+                    congestion_control,
                 });
             }
             SubscriberInvoker::Sender(sender) => {
@@ -1035,6 +1104,8 @@ impl Session {
                     res_name,
                     payload,
                     data_info,
+                    channel,
+                    congestion_control,
                 }) {
                     error!("SubscriberInvoker error: {}", e);
                 }
@@ -1042,14 +1113,29 @@ impl Session {
         }
     }
 
-    fn handle_data(&self, local: bool, reskey: &ResKey, info: Option<DataInfo>, payload: ZBuf) {
+    fn handle_data(
+        &self,
+        local: bool,
+        reskey: &ResKey,
+        info: Option<DataInfo>,
+        payload: ZBuf,
+        channel: Channel,
+        congestion_control: CongestionControl,
+    ) {
         let state = zread!(self.state);
         if let ResKey::RId(rid) = reskey {
             match state.get_res(rid, local) {
                 Some(res) => {
                     if !local && res.subscribers.len() == 1 {
                         let sub = res.subscribers.get(0).unwrap();
-                        Session::invoke_subscriber(&sub.invoker, res.name.clone(), payload, info);
+                        Session::invoke_subscriber(
+                            &sub.invoker,
+                            res.name.clone(),
+                            payload,
+                            info,
+                            channel,
+                            congestion_control,
+                        );
                     } else {
                         if !local || state.local_routing {
                             for sub in &res.subscribers {
@@ -1058,6 +1144,8 @@ impl Session {
                                     res.name.clone(),
                                     payload.clone(),
                                     info.clone(),
+                                    channel,
+                                    congestion_control,
                                 );
                             }
                         }
@@ -1068,6 +1156,8 @@ impl Session {
                                     res.name.clone(),
                                     payload.clone(),
                                     info.clone(),
+                                    channel,
+                                    congestion_control,
                                 );
                             }
                         }
@@ -1088,6 +1178,8 @@ impl Session {
                                     resname.clone(),
                                     payload.clone(),
                                     info.clone(),
+                                    channel,
+                                    congestion_control,
                                 );
                             }
                         }
@@ -1100,6 +1192,8 @@ impl Session {
                                     resname.clone(),
                                     payload.clone(),
                                     info.clone(),
+                                    channel,
+                                    congestion_control,
                                 );
                             }
                         }
@@ -1165,7 +1259,11 @@ impl Session {
         let mut state = zwrite!(self.state);
         let qid = state.qid_counter.fetch_add(1, Ordering::SeqCst);
         let (rep_sender, rep_receiver) = bounded(*API_REPLY_RECEPTION_CHANNEL_SIZE);
-        let nb_final = if state.local_routing { 2 } else { 1 };
+        // Balanced queries are routed back to the local queryables by the router,
+        // that balances them with the queryables of the other sessions
+        let local_routing =
+            state.local_routing && !matches!(target.target, Target::Balanced { .. });
+        let nb_final = if local_routing { 2 } else { 1 };
         trace!("Register query {} (nb_final = {})", qid, nb_final);
         state.queries.insert(
             qid,
@@ -1182,7 +1280,6 @@ impl Session {
         );
 
         let primitives = state.primitives.as_ref().unwrap().clone();
-        let local_routing = state.local_routing;
         drop(state);
         primitives.send_query(
             resource,
@@ -1234,6 +1331,16 @@ impl Session {
                         )
                         .map(|qable| (qable.kind, qable.sender.clone()))
                         .collect::<Vec<(ZInt, Sender<Query>)>>();
+                    let kinds_and_senders = match target.target {
+                        Target::Balanced { n, .. } if !kinds_and_senders.is_empty() => {
+                            let mut kinds_and_senders = kinds_and_senders;
+                            let len = kinds_and_senders.len();
+                            kinds_and_senders.rotate_left(qid as usize % len);
+                            kinds_and_senders.truncate(n.max(1) as usize);
+                            kinds_and_senders
+                        }
+                        _ => kinds_and_senders,
+                    };
                     (
                         state.primitives.as_ref().unwrap().clone(),
                         resname,
@@ -1378,7 +1485,7 @@ impl Primitives for Session {
             congestion_control,
             info,
         );
-        self.handle_data(false, reskey, info, payload)
+        self.handle_data(false, reskey, info, payload, channel, congestion_control)
     }
 
     fn send_query(
@@ -1433,6 +1540,8 @@ impl Primitives for Session {
                         res_name,
                         payload,
                         data_info,
+                        channel: Channel::default(),
+                        congestion_control: CongestionControl::default(),
                     },
                     replier_kind,
                     replier_id,
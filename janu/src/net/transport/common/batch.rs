@@ -17,9 +17,22 @@ use super::protocol::core::{Priority, Reliability};
 use super::protocol::io::WBuf;
 use super::protocol::proto::{TransportMessage, JanuMessage};
 use super::seq_num::SeqNumGenerator;
-
-type LengthType = u16;
-const LENGTH_BYTES: [u8; 2] = [0u8, 0u8];
+use super::super::defaults::ZN_ZERO_COPY_THRESHOLD;
+use std::io::IoSlice;
+
+const LENGTH_BYTES: [u8; 4] = [0u8, 0u8, 0u8, 0u8];
+
+/// Get the amount of bytes used to encode the length of a streamed batch of a given size.
+/// Batches up to 65_535 bytes use a 16-bits length while larger batches use a 32-bits length.
+/// The latter is only used when both peers have agreed on a batch size larger than 65_535 bytes.
+#[inline(always)]
+pub(crate) fn streamed_length_bytes(batch_size: u32) -> usize {
+    if batch_size > u16::MAX as u32 {
+        4
+    } else {
+        2
+    }
+}
 
 #[derive(Clone, Copy, Debug)]
 enum CurrentFrame {
@@ -69,8 +82,9 @@ impl Default for SerializationBatchStats {
 
 /// Serialization Batch
 ///
-/// A [`SerializationBatch`][SerializationBatch] is a non-expandable and contiguous region of memory
-/// that is used to serialize [`TransportMessage`][TransportMessage] and [`JanuMessage`][JanuMessage].
+/// A [`SerializationBatch`][SerializationBatch] is a non-expandable region of memory that is used to
+/// serialize [`TransportMessage`][TransportMessage] and [`JanuMessage`][JanuMessage]. Payload slices of
+/// at least `ZN_ZERO_COPY_THRESHOLD` bytes are referenced by the batch instead of being copied into it.
 ///
 /// [`TransportMessage`][TransportMessage] are always serialized on the batch as they are, while
 /// [`JanuMessage`][JanuMessage] are always serializaed on the batch as part of a [`TransportMessage`]
@@ -92,8 +106,12 @@ pub(crate) struct SerializationBatch {
     buffer: WBuf,
     // It is a streamed batch
     is_streamed: bool,
+    // The amount of bytes used to encode the length of a streamed batch
+    length_bytes: usize,
     // The current frame being serialized: BestEffort/Reliable
     current_frame: CurrentFrame,
+    // The batch contains only best effort frames
+    is_best_effort: bool,
     // The last SeqNum serialized on this batch
     pub(crate) sn: SerializationBatchSeqNum,
     // Statistics related to this batch
@@ -105,28 +123,34 @@ impl SerializationBatch {
     /// Create a new [`SerializationBatch`][SerializationBatch] with a given size in bytes.
     ///
     /// # Arguments
-    /// * `size` - The size in bytes of the contiguous memory buffer to allocate on.
+    /// * `size` - The maximum size in bytes of the serialized batch.
     ///
     /// * `is_streamed` - The serialization batch is meant to be used for a stream-based transport
-    ///                   protocol (e.g., TCP) in constrast to datagram-based transport protocol (e.g., UDP).
-    ///                   In case of `is_streamed` being true, the first 2 bytes of the serialization batch
-    ///                   are used to encode the total amount of serialized bytes as 16-bits little endian.
-    ///                   Writing these 2 bytes allows the receiver to detect the amount of bytes it is expected
-    ///                   to read when operating on non-boundary preserving transport protocols.
+    ///   protocol (e.g., TCP) in constrast to datagram-based transport protocol (e.g., UDP).
+    ///   In case of `is_streamed` being true, the first 2 bytes of the serialization batch
+    ///   are used to encode the total amount of serialized bytes as 16-bits little endian.
+    ///   If `size` is larger than 65_535 bytes, the first 4 bytes are used instead to encode
+    ///   the total amount of serialized bytes as 32-bits little endian.
+    ///   Writing these bytes allows the receiver to detect the amount of bytes it is expected
+    ///   to read when operating on non-boundary preserving transport protocols.
     ///
     /// * `sn_reliable` - The sequence number generator for the reliable channel.
     ///
     /// * `sn_best_effort` - The sequence number generator for the best effort channel.
     ///
-    pub(crate) fn new(size: u16, is_streamed: bool) -> SerializationBatch {
-        let mut size: usize = size as usize;
-        if is_streamed {
-            size += 2;
-        }
+    pub(crate) fn new(size: u32, is_streamed: bool) -> SerializationBatch {
+        let length_bytes = if is_streamed {
+            streamed_length_bytes(size)
+        } else {
+            0
+        };
+        let size = size as usize + length_bytes;
         let mut batch = SerializationBatch {
-            buffer: WBuf::new(size, true),
+            buffer: WBuf::new_bounded(size, *ZN_ZERO_COPY_THRESHOLD),
             is_streamed,
+            length_bytes,
             current_frame: CurrentFrame::None,
+            is_best_effort: true,
             sn: SerializationBatchSeqNum {
                 reliable: None,
                 best_effort: None,
@@ -150,28 +174,36 @@ impl SerializationBatch {
     /// Get the total number of bytes that have been serialized on the [`SerializationBatch`][SerializationBatch].
     #[inline(always)]
     pub(crate) fn len(&self) -> usize {
-        let len = self.buffer.len();
-        if self.is_streamed() {
-            len - LENGTH_BYTES.len()
-        } else {
-            len
-        }
+        self.buffer.len() - self.length_bytes
     }
 
     /// Verify that the [`SerializationBatch`][SerializationBatch] is for a stream-based protocol, i.e., the first
-    /// 2 bytes are reserved to encode the total amount of serialized bytes as 16-bits little endian.
+    /// 2 (or 4) bytes are reserved to encode the total amount of serialized bytes as 16-bits (or 32-bits) little endian.
     #[inline(always)]
     pub(crate) fn is_streamed(&self) -> bool {
         self.is_streamed
     }
 
+    /// Get the reliability of the [`SerializationBatch`][SerializationBatch], i.e., best effort if it
+    /// only contains best effort frames and reliable otherwise. A batch containing any
+    /// [`TransportMessage`][TransportMessage] other than a frame is always reliable.
+    #[inline(always)]
+    pub(crate) fn reliability(&self) -> Reliability {
+        if self.is_best_effort {
+            Reliability::BestEffort
+        } else {
+            Reliability::Reliable
+        }
+    }
+
     /// Clear the [`SerializationBatch`][SerializationBatch] memory buffer and related internal state.
     #[inline(always)]
     pub(crate) fn clear(&mut self) {
         self.current_frame = CurrentFrame::None;
+        self.is_best_effort = true;
         self.buffer.clear();
         if self.is_streamed() {
-            self.buffer.write_bytes(&LENGTH_BYTES);
+            self.buffer.write_bytes(&LENGTH_BYTES[..self.length_bytes]);
         }
         self.sn.clear();
         #[cfg(feature = "stats")]
@@ -179,20 +211,25 @@ impl SerializationBatch {
     }
 
     /// In case the [`SerializationBatch`][SerializationBatch] is for a stream-based protocol, use the first 2 bytes
-    /// to encode the total amount of serialized bytes as 16-bits little endian.
+    /// (or 4 bytes for batches larger than 65_535 bytes) to encode the total amount of serialized bytes as little endian.
     #[inline(always)]
     pub(crate) fn write_len(&mut self) {
         if self.is_streamed() {
-            let length = self.len() as LengthType;
-            let bits = self.buffer.get_first_slice_mut(..LENGTH_BYTES.len());
-            bits.copy_from_slice(&length.to_le_bytes());
+            let length = self.len();
+            let bits = self.buffer.get_first_slice_mut(..self.length_bytes);
+            if self.length_bytes == 2 {
+                bits.copy_from_slice(&(length as u16).to_le_bytes());
+            } else {
+                bits.copy_from_slice(&(length as u32).to_le_bytes());
+            }
         }
     }
 
-    /// Get a `&[u8]` to access the internal memory buffer, usually for transmitting it on the network.
+    /// Get the `IoSlice`s referencing the serialized bytes, usually for transmitting them on the network
+    /// with a vectored write.
     #[inline(always)]
-    pub(crate) fn as_bytes(&self) -> &[u8] {
-        self.buffer.get_first_slice(..)
+    pub(crate) fn as_ioslices(&self) -> Vec<IoSlice<'_>> {
+        self.buffer.as_ioslices()
     }
 
     /// Try to serialize a [`TransportMessage`][TransportMessage] on the [`SerializationBatch`][SerializationBatch].
@@ -207,6 +244,7 @@ impl SerializationBatch {
         if res {
             // Reset the current frame value
             self.current_frame = CurrentFrame::None;
+            self.is_best_effort = false;
         } else {
             // Revert the write operation
             self.buffer.revert();
@@ -279,6 +317,9 @@ impl SerializationBatch {
 
             if res {
                 self.current_frame = frame;
+                if message.is_reliable() {
+                    self.is_best_effort = false;
+                }
                 let sn_state = SerializationBatchSeqNumState {
                     last: sn,
                     next: sn_gen.now(),
@@ -363,7 +404,10 @@ impl SerializationBatch {
                     next: sn_gen.now(),
                 };
                 match reliability {
-                    Reliability::Reliable => self.sn.reliable = Some(sn_state),
+                    Reliability::Reliable => {
+                        self.sn.reliable = Some(sn_state);
+                        self.is_best_effort = false;
+                    }
                     Reliability::BestEffort => self.sn.best_effort = Some(sn_state),
                 }
 
@@ -383,12 +427,12 @@ impl SerializationBatch {
     }
 
     #[cfg(test)]
-    pub(crate) fn get_serialized_messages(&self) -> &[u8] {
-        if self.is_streamed() {
-            self.buffer.get_first_slice(LENGTH_BYTES.len()..)
-        } else {
-            self.buffer.get_first_slice(..)
+    pub(crate) fn get_serialized_messages(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = vec![];
+        for slice in self.as_ioslices().iter() {
+            bytes.extend_from_slice(slice);
         }
+        bytes.split_off(self.length_bytes)
     }
 }
 
@@ -405,7 +449,7 @@ mod tests {
     };
     use std::convert::TryFrom;
 
-    fn serialize_no_fragmentation(batch_size: u16, payload_size: usize) {
+    fn serialize_no_fragmentation(batch_size: u32, payload_size: usize) {
         for is_streamed in [false, true].iter() {
             print!(
                 "Streamed: {}\t\tBatch: {}\t\tPload: {}",
@@ -513,7 +557,7 @@ mod tests {
         }
     }
 
-    fn serialize_fragmentation(batch_size: u16, payload_size: usize) {
+    fn serialize_fragmentation(batch_size: u32, payload_size: usize) {
         for is_streamed in [false, true].iter() {
             // Create the sequence number generators
             let mut sn_gen = SeqNumGenerator::new(0, SEQ_NUM_RES);
@@ -610,7 +654,9 @@ mod tests {
 
     #[test]
     fn serialization_batch() {
-        let batch_size: Vec<u16> = vec![128, 512, 1_024, 4_096, 8_192, 16_384, 32_768, 65_535];
+        let batch_size: Vec<u32> = vec![
+            128, 512, 1_024, 4_096, 8_192, 16_384, 32_768, 65_535, 131_072,
+        ];
         let mut payload_size: Vec<usize> = vec![];
         let mut size: usize = 8;
         for _ in 0..16 {
@@ -631,4 +677,116 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn serialization_batch_reliability() {
+        let make_data = |reliability| {
+            let channel = Channel {
+                priority: Priority::default(),
+                reliability,
+            };
+            JanuMessage::make_data(
+                ResKey::RName("test".to_string()),
+                ZBuf::from(vec![0u8; 8]),
+                channel,
+                CongestionControl::default(),
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+
+        let mut sn_gen = SeqNumGenerator::new(0, SEQ_NUM_RES);
+        let mut batch = SerializationBatch::new(1_024, true);
+
+        // A batch with only best effort frames is best effort
+        let mut msg = make_data(Reliability::BestEffort);
+        assert!(batch.serialize_janu_message(&mut msg, Priority::default(), &mut sn_gen));
+        assert_eq!(batch.reliability(), Reliability::BestEffort);
+
+        // A reliable frame makes the batch reliable
+        let mut msg = make_data(Reliability::Reliable);
+        assert!(batch.serialize_janu_message(&mut msg, Priority::default(), &mut sn_gen));
+        assert_eq!(batch.reliability(), Reliability::Reliable);
+
+        // Clearing the batch resets its reliability
+        batch.clear();
+        assert_eq!(batch.reliability(), Reliability::BestEffort);
+
+        // A transport message makes the batch reliable
+        let mut msg = TransportMessage::make_keep_alive(None, None);
+        assert!(batch.serialize_transport_message(&mut msg));
+        assert_eq!(batch.reliability(), Reliability::Reliable);
+    }
+
+    #[test]
+    fn serialization_batch_length() {
+        for (batch_size, length_bytes) in [(1_024, 2), (65_535, 2), (1_048_576, 4)].iter() {
+            let mut batch = SerializationBatch::new(*batch_size, true);
+            let mut msg = TransportMessage::make_keep_alive(None, None);
+            assert!(batch.serialize_transport_message(&mut msg));
+            batch.write_len();
+
+            let bytes = batch.as_ioslices();
+            assert_eq!(
+                bytes.iter().map(|b| b.len()).sum::<usize>(),
+                length_bytes + batch.len()
+            );
+            let mut len = [0u8; 4];
+            len[..*length_bytes].copy_from_slice(&bytes[0][..*length_bytes]);
+            assert_eq!(u32::from_le_bytes(len) as usize, batch.len());
+        }
+    }
+
+    #[test]
+    fn serialization_batch_zero_copy() {
+        let make_data = |size| {
+            JanuMessage::make_data(
+                ResKey::RName("test".to_string()),
+                ZBuf::from(vec![1u8; size]),
+                Channel::default(),
+                CongestionControl::default(),
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+
+        let mut sn_gen = SeqNumGenerator::new(0, SEQ_NUM_RES);
+        let mut batch = SerializationBatch::new(65_535, true);
+
+        // A payload smaller than the threshold is copied into the batch
+        let mut msg = make_data(*ZN_ZERO_COPY_THRESHOLD - 1);
+        assert!(batch.serialize_janu_message(&mut msg, Priority::default(), &mut sn_gen));
+        assert_eq!(batch.as_ioslices().len(), 1);
+
+        // A payload as large as the threshold is referenced by the batch
+        let mut msg = make_data(*ZN_ZERO_COPY_THRESHOLD);
+        assert!(batch.serialize_janu_message(&mut msg, Priority::default(), &mut sn_gen));
+        let slices = batch.as_ioslices();
+        assert_eq!(slices.len(), 2);
+        assert_eq!(slices[1].len(), *ZN_ZERO_COPY_THRESHOLD);
+        drop(slices);
+
+        // The referenced payload accounts for the batch size
+        let mut msg = make_data(65_535 - batch.len());
+        assert!(!batch.serialize_janu_message(&mut msg, Priority::default(), &mut sn_gen));
+
+        // The serialized messages are the same as if they were copied
+        batch.write_len();
+        let mut zbuf: ZBuf = batch.get_serialized_messages().into();
+        let msg = zbuf.read_transport_message().unwrap();
+        match msg.body {
+            TransportBody::Frame(Frame {
+                payload: FramePayload::Messages { messages },
+                ..
+            }) => {
+                assert_eq!(messages.len(), 2);
+                assert_eq!(messages[1], make_data(*ZN_ZERO_COPY_THRESHOLD));
+            }
+            _ => panic!(),
+        }
+    }
 }
@@ -15,6 +15,7 @@ pub(crate) mod batch;
 pub(crate) mod conduit;
 pub(crate) mod defragmentation;
 pub(crate) mod pipeline;
+pub(crate) mod rx_pool;
 pub(crate) mod seq_num;
 
 use super::protocol;
@@ -97,7 +97,7 @@ impl StageIn {
     fn new(
         priority: usize,
         capacity: usize,
-        batch_size: u16,
+        batch_size: u32,
         is_streamed: bool,
         bytes_topull: Arc<[AtomicUsize]>,
     ) -> StageIn {
@@ -215,7 +215,7 @@ pub(crate) struct TransmissionPipeline {
 impl TransmissionPipeline {
     /// Create a new link queue.
     pub(crate) fn new(
-        batch_size: u16,
+        batch_size: u32,
         is_streamed: bool,
         conduit: Arc<[TransportConduitTx]>,
     ) -> TransmissionPipeline {
@@ -725,7 +725,7 @@ mod tests {
         }
 
         // Pipeline
-        let batch_size = BATCH_SIZE;
+        let batch_size = BATCH_SIZE as u32;
         let is_streamed = true;
         let conduit = vec![TransportConduitTx::new(
             Priority::Control,
@@ -823,7 +823,7 @@ mod tests {
         }
 
         // Pipeline
-        let batch_size = BATCH_SIZE;
+        let batch_size = BATCH_SIZE as u32;
         let is_streamed = true;
         let conduit = vec![TransportConduitTx::new(
             Priority::Control,
@@ -933,7 +933,7 @@ mod tests {
         }
 
         // Queue
-        let batch_size = BATCH_SIZE;
+        let batch_size = BATCH_SIZE as u32;
         let is_streamed = true;
         let conduit = vec![TransportConduitTx::new(
             Priority::Control,
@@ -997,7 +997,7 @@ mod tests {
     #[ignore]
     fn tx_pipeline_thr() {
         // Queue
-        let batch_size = BATCH_SIZE;
+        let batch_size = BATCH_SIZE as u32;
         let is_streamed = true;
         let conduit = vec![TransportConduitTx::new(
             Priority::Control,
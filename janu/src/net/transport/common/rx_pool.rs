@@ -0,0 +1,113 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+use janu_util::collections::{RecyclingObject, RecyclingObjectPool};
+use janu_util::zlock;
+
+type RxBuffer = Box<[u8]>;
+type RxBufferAlloc = Box<dyn Fn() -> RxBuffer + Send + Sync>;
+type RxBufferPool = RecyclingObjectPool<RxBuffer, RxBufferAlloc>;
+
+/// Reception Buffer Pools
+///
+/// The [`RxBufferPools`][RxBufferPools] keep track of the pools of pre-allocated buffers the
+/// links read their batches onto. Each link reserves its own pool of `link_rx_buff_size` bytes,
+/// hence the memory allocated for the reception grows with the number of links as it would
+/// without sharing. The pools are indexed by the size of their buffers: a link whose pool is
+/// exhausted borrows the idle buffers of the other links reading batches of the same size
+/// before allocating a new one.
+///
+/// A buffer taken from a pool is automatically reinserted into it once all the messages
+/// referencing it have been dropped.
+///
+pub(crate) struct RxBufferPools {
+    // The amount of bytes pre-allocated for each link
+    capacity: usize,
+    pools: Mutex<HashMap<usize, Vec<Weak<RxBufferPool>>>>,
+}
+
+impl RxBufferPools {
+    pub(crate) fn new(capacity: usize) -> RxBufferPools {
+        RxBufferPools {
+            capacity,
+            pools: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reserve the pool of buffers of `size` bytes of a link. The pool is released when the
+    /// returned [`RxBuffers`][RxBuffers] is dropped.
+    pub(crate) fn reserve(self: &Arc<Self>, size: usize) -> RxBuffers {
+        let num = 1 + (self.capacity / size);
+        let alloc: RxBufferAlloc = Box::new(move || vec![0u8; size].into_boxed_slice());
+        let pool = Arc::new(RecyclingObjectPool::new(num, alloc));
+
+        let mut guard = zlock!(self.pools);
+        let pools = guard.entry(size).or_insert_with(Vec::new);
+        pools.retain(|p| p.strong_count() > 0);
+        pools.push(Arc::downgrade(&pool));
+        drop(guard);
+
+        RxBuffers {
+            pools: self.clone(),
+            size,
+            pool,
+        }
+    }
+
+    /// Take an idle buffer of `size` bytes from any of the reserved pools, or allocate a new
+    /// one if none is available.
+    pub(crate) fn take(&self, size: usize) -> RecyclingObject<RxBuffer> {
+        let guard = zlock!(self.pools);
+        if let Some(pools) = guard.get(&size) {
+            for pool in pools.iter().filter_map(|p| p.upgrade()) {
+                if let Some(buffer) = pool.try_take() {
+                    return buffer;
+                }
+            }
+        }
+        drop(guard);
+        vec![0u8; size].into_boxed_slice().into()
+    }
+}
+
+/// The pool of reception buffers reserved by a link.
+pub(crate) struct RxBuffers {
+    pools: Arc<RxBufferPools>,
+    size: usize,
+    pool: Arc<RxBufferPool>,
+}
+
+impl RxBuffers {
+    /// Take a buffer from the pool of the link, falling back on the pools of the other links.
+    pub(crate) fn take(&self) -> RecyclingObject<RxBuffer> {
+        match self.pool.try_take() {
+            Some(buffer) => buffer,
+            None => self.pools.take(self.size),
+        }
+    }
+}
+
+impl Drop for RxBuffers {
+    fn drop(&mut self) {
+        let mut guard = zlock!(self.pools.pools);
+        if let Some(pools) = guard.get_mut(&self.size) {
+            let pool = Arc::downgrade(&self.pool);
+            pools.retain(|p| !p.ptr_eq(&pool));
+            if pools.is_empty() {
+                guard.remove(&self.size);
+            }
+        }
+    }
+}
@@ -16,7 +16,8 @@ zconfigurable! {
     // - The size of each queue relates to the number of batches a given queue can contain.
     // - The amount of memory being allocated for each queue is then QUEUE_SIZE_XXX * ZN_BATCH_SIZE.
     //   In case the transport link MTU is smaller than the ZN_BATCH_SIZE, then amount of memory being
-    //   allocated for each queue is QUEUE_SIZE_XXX * LINK_MTU.
+    //   allocated for each queue is QUEUE_SIZE_XXX * LINK_MTU. On streamed links where a batch size
+    //   larger than 65_535 bytes has been agreed with the peer, the agreed batch size is used instead.
     pub static ref ZN_QUEUE_SIZE_CONTROL: usize = 1;
     pub static ref ZN_QUEUE_SIZE_REAL_TIME: usize = 4;
     pub static ref ZN_QUEUE_SIZE_INTERACTIVE_HIGH: usize = 4;
@@ -28,4 +29,21 @@ zconfigurable! {
 
     // The default backoff time in nanoseconds to allow the batching to potentially progress
     pub static ref ZN_QUEUE_PULL_BACKOFF: u64 = 100;
+
+    // The minimum size in bytes of a payload slice for it to be referenced by a batch
+    // instead of being copied into it. Referenced slices are written on the links
+    // supporting vectored writes (e.g. TCP) without any intermediate copy.
+    pub static ref ZN_ZERO_COPY_THRESHOLD: usize = 4_096;
+
+    // The interval in milliseconds between two Ping messages sent on a unicast link to
+    // measure its round-trip time. Pings are sent regardless of the traffic on the link,
+    // whereas KeepAlive messages are only sent when the link has been idle.
+    pub static ref ZN_LINK_PING_INTERVAL: u64 = 10_000;
+
+    // The time in milliseconds a shared memory buffer sent on a multicast transport is kept
+    // alive by the sender. Every receiver takes its own reference upon reception, hence the
+    // retention only needs to cover the time for the message to be delivered. A receiver
+    // attaching later discards the message, the chunk generation preventing it from reading
+    // a reallocated buffer.
+    pub static ref ZN_SHM_MULTICAST_RETENTION: u64 = 1_000;
 }
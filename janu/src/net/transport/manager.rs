@@ -11,11 +11,12 @@
 // Contributors:
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
+use super::common::rx_pool::RxBufferPools;
 use super::multicast::manager::{TransportManagerConfigMulticast, TransportManagerStateMulticast};
 use super::protocol::core::{whatami, PeerId, WhatAmI, ZInt};
 #[cfg(feature = "zero-copy")]
 use super::protocol::io::SharedMemoryReader;
-use super::protocol::proto::defaults::{BATCH_SIZE, SEQ_NUM_RES, VERSION};
+use super::protocol::proto::defaults::{BATCH_SIZE, BATCH_SIZE_MAX, SEQ_NUM_RES, VERSION};
 use super::unicast::manager::{TransportManagerConfigUnicast, TransportManagerStateUnicast};
 use super::unicast::TransportUnicast;
 use super::TransportEventHandler;
@@ -29,7 +30,7 @@ use std::sync::RwLock;
 use janu_util::core::{ZError, ZErrorKind, ZResult};
 use janu_util::crypto::{BlockCipher, PseudoRng};
 use janu_util::properties::{config::*, Properties};
-use janu_util::zparse;
+use janu_util::{zerror, zparse};
 
 /// # Examples
 /// ```
@@ -88,7 +89,7 @@ pub struct TransportManagerConfig {
     pub pid: PeerId,
     pub whatami: WhatAmI,
     pub sn_resolution: ZInt,
-    pub batch_size: u16,
+    pub batch_size: u32,
     pub defrag_buff_size: usize,
     pub link_rx_buff_size: usize,
     pub unicast: TransportManagerConfigUnicast,
@@ -108,7 +109,7 @@ pub struct TransportManagerConfigBuilder {
     pid: PeerId,
     whatami: WhatAmI,
     sn_resolution: ZInt,
-    batch_size: u16,
+    batch_size: u32,
     defrag_buff_size: usize,
     link_rx_buff_size: usize,
     unicast: TransportManagerConfigUnicast,
@@ -137,7 +138,7 @@ impl TransportManagerConfigBuilder {
         self
     }
 
-    pub fn batch_size(mut self, batch_size: u16) -> Self {
+    pub fn batch_size(mut self, batch_size: u32) -> Self {
         self.batch_size = batch_size;
         self
     }
@@ -200,7 +201,16 @@ impl TransportManagerConfigBuilder {
             self = self.sn_resolution(zparse!(v)?);
         }
         if let Some(v) = properties.get(&ZN_BATCH_SIZE_KEY) {
-            self = self.batch_size(zparse!(v)?);
+            let batch_size: u32 = zparse!(v)?;
+            if batch_size == 0 || batch_size > BATCH_SIZE_MAX {
+                return zerror!(ZErrorKind::ValueDecodingFailed {
+                    descr: format!(
+                        "Failed to read configuration: batch size {} is not between 1 and {} bytes",
+                        batch_size, BATCH_SIZE_MAX
+                    )
+                });
+            }
+            self = self.batch_size(batch_size);
         }
         if let Some(v) = properties.get(&ZN_DEFRAG_BUFF_SIZE_KEY) {
             self = self.defrag_buff_size(zparse!(v)?);
@@ -228,7 +238,7 @@ impl Default for TransportManagerConfigBuilder {
             pid: PeerId::rand(),
             whatami: whatami::parse(ZN_MODE_DEFAULT).unwrap(),
             sn_resolution: SEQ_NUM_RES,
-            batch_size: BATCH_SIZE,
+            batch_size: BATCH_SIZE as u32,
             defrag_buff_size: zparse!(ZN_DEFRAG_BUFF_SIZE_DEFAULT).unwrap(),
             link_rx_buff_size: zparse!(ZN_LINK_RX_BUFF_SIZE_DEFAULT).unwrap(),
             endpoint: HashMap::new(),
@@ -258,6 +268,7 @@ pub struct TransportManager {
     pub(crate) state: Arc<TransportManagerState>,
     pub(crate) prng: AsyncArc<AsyncMutex<PseudoRng>>,
     pub(crate) cipher: Arc<BlockCipher>,
+    pub(crate) rx_pools: Arc<RxBufferPools>,
     #[cfg(feature = "zero-copy")]
     pub(crate) shmr: Arc<RwLock<SharedMemoryReader>>,
 }
@@ -269,12 +280,15 @@ impl TransportManager {
         let mut key = [0u8; BlockCipher::BLOCK_SIZE];
         prng.fill_bytes(&mut key);
         let cipher = BlockCipher::new(key);
+        // The reception buffers are reserved by each link and lent among links
+        let rx_pools = RxBufferPools::new(config.link_rx_buff_size);
 
         TransportManager {
             config: Arc::new(config),
             state: Arc::new(TransportManagerState::default()),
             prng: AsyncArc::new(AsyncMutex::new(prng)),
             cipher: Arc::new(cipher),
+            rx_pools: Arc::new(rx_pools),
             #[cfg(feature = "zero-copy")]
             shmr: Arc::new(RwLock::new(SharedMemoryReader::new())),
         }
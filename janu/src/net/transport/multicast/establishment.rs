@@ -16,6 +16,7 @@ use super::protocol::core::{ConduitSn, ConduitSnList, Priority};
 use super::{TransportMulticast, TransportMulticastConfig, TransportMulticastInner};
 use crate::net::link::LinkMulticast;
 use rand::Rng;
+use std::convert::TryFrom;
 use std::sync::Arc;
 use janu_util::core::ZResult;
 use janu_util::zasynclock;
@@ -58,7 +59,9 @@ pub(crate) async fn open_link(
     zlock!(manager.state.multicast.transports).insert(locator.clone(), ti.clone());
 
     // Notify the transport event handler
-    let batch_size = manager.config.batch_size.min(link.get_mtu());
+    // The batches of a multicast link can not be larger than 65_535 bytes
+    let batch_size = manager.config.batch_size.min(link.get_mtu() as u32);
+    let batch_size = u16::try_from(batch_size).unwrap_or(u16::MAX);
     ti.start_tx(batch_size).map_err(|e| {
         zlock!(manager.state.multicast.transports).remove(&locator);
         let _ = ti.stop_tx();
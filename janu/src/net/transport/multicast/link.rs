@@ -13,13 +13,14 @@
 //
 use super::common::{conduit::TransportConduitTx, pipeline::TransmissionPipeline};
 use super::protocol::io::{ZBuf, ZSlice};
-use super::protocol::proto::TransportMessage;
+use super::protocol::proto::{Attachment, TransportMessage};
 use super::transport::TransportMulticastInner;
 #[cfg(feature = "stats")]
 use super::transport::TransportMulticastStatsInner;
 use crate::net::link::{LinkMulticast, Locator};
 use crate::net::protocol::core::{ConduitSn, ConduitSnList, PeerId, Priority, WhatAmI, ZInt};
 use crate::net::transport::common::batch::SerializationBatch;
+use crate::net::transport::common::rx_pool::RxBufferPools;
 use async_std::prelude::*;
 use async_std::task;
 use async_std::task::JoinHandle;
@@ -27,7 +28,6 @@ use std::convert::TryInto;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use janu_util::collections::RecyclingObjectPool;
 use janu_util::core::{ZError, ZErrorKind, ZResult};
 use janu_util::sync::Signal;
 use janu_util::zerror;
@@ -41,6 +41,7 @@ pub(super) struct TransportLinkMulticastConfig {
     pub(super) join_interval: Duration,
     pub(super) sn_resolution: ZInt,
     pub(super) batch_size: u16,
+    pub(super) attachment: Option<Attachment>,
 }
 
 #[derive(Clone)]
@@ -102,7 +103,7 @@ impl TransportLinkMulticast {
         if self.handle_tx.is_none() {
             // The pipeline
             let pipeline = Arc::new(TransmissionPipeline::new(
-                config.batch_size.min(self.inner.get_mtu()),
+                config.batch_size.min(self.inner.get_mtu()) as u32,
                 false,
                 conduit_tx,
             ));
@@ -146,7 +147,7 @@ impl TransportLinkMulticast {
             let c_transport = self.transport.clone();
             let c_signal = self.signal_rx.clone();
             let c_active = self.active_rx.clone();
-            let c_rx_buff_size = self.transport.manager.config.link_rx_buff_size;
+            let c_rx_pools = self.transport.manager.rx_pools.clone();
 
             let handle = task::spawn(async move {
                 // Start the consume task
@@ -155,7 +156,7 @@ impl TransportLinkMulticast {
                     c_transport.clone(),
                     c_signal.clone(),
                     c_active.clone(),
-                    c_rx_buff_size,
+                    c_rx_pools,
                 )
                 .await;
                 c_active.store(false, Ordering::Release);
@@ -206,7 +207,7 @@ async fn tx_task(
     #[cfg(feature = "stats")] stats: TransportMulticastStatsInner,
 ) -> ZResult<()> {
     enum Action {
-        Pull((SerializationBatch, usize)),
+        Pull(Box<SerializationBatch>, usize),
         Join,
         KeepAlive,
         Stop,
@@ -215,7 +216,7 @@ async fn tx_task(
     async fn pull(pipeline: &TransmissionPipeline, keep_alive: Duration) -> Action {
         match pipeline.pull().timeout(keep_alive).await {
             Ok(res) => match res {
-                Some(sb) => Action::Pull(sb),
+                Some((batch, priority)) => Action::Pull(Box::new(batch), priority),
                 None => Action::Stop,
             },
             Err(_) => Action::KeepAlive,
@@ -238,10 +239,10 @@ async fn tx_task(
             .race(join(last_join, config.join_interval))
             .await
         {
-            Action::Pull((batch, priority)) => {
+            Action::Pull(batch, priority) => {
                 // Send the buffer on the link
-                let bytes = batch.as_bytes();
-                let _ = link.write_all(bytes).await?;
+                let bytes = batch.as_ioslices();
+                link.write_vectored(&bytes).await?;
                 // Keep track of next SNs
                 if let Some(sn) = batch.sn.reliable {
                     next_sns[priority].reliable = sn.next;
@@ -252,13 +253,13 @@ async fn tx_task(
                 #[cfg(feature = "stats")]
                 {
                     stats.inc_tx_msgs(batch.stats.t_msgs);
-                    stats.inc_tx_bytes(bytes.len());
+                    stats.inc_tx_bytes(bytes.iter().map(|b| b.len()).sum());
                 }
                 // Reinsert the batch into the queue
-                pipeline.refill(batch, priority);
+                pipeline.refill(*batch, priority);
             }
             Action::Join => {
-                let attachment = None;
+                let attachment = config.attachment.clone();
                 let initial_sns = if next_sns.len() == Priority::NUM {
                     let tmp: [ConduitSn; Priority::NUM] = next_sns.clone().try_into().unwrap();
                     ConduitSnList::QoS(tmp.into())
@@ -296,8 +297,7 @@ async fn tx_task(
                 // Drain the transmission pipeline and write remaining bytes on the wire
                 let mut batches = pipeline.drain();
                 for (b, _) in batches.drain(..) {
-                    let _ = link
-                        .write_all(b.as_bytes())
+                    link.write_vectored(&b.as_ioslices())
                         .timeout(config.join_interval)
                         .await
                         .map_err(|_| {
@@ -328,7 +328,7 @@ async fn rx_task(
     transport: TransportMulticastInner,
     signal: Signal,
     active: Arc<AtomicBool>,
-    rx_buff_size: usize,
+    rx_pools: Arc<RxBufferPools>,
 ) -> ZResult<()> {
     enum Action {
         Read((usize, Locator)),
@@ -347,15 +347,13 @@ async fn rx_task(
 
     // The ZBuf to read a message batch onto
     let mut zbuf = ZBuf::new();
-    // The pool of buffers
-    let mtu = link.get_mtu() as usize;
-    let n = 1 + (rx_buff_size / mtu);
-    let pool = RecyclingObjectPool::new(n, || vec![0u8; mtu].into_boxed_slice());
+    // The pool of buffers reserved for this link
+    let pool = rx_pools.reserve(link.get_mtu() as usize);
     while active.load(Ordering::Acquire) {
         // Clear the zbuf
         zbuf.clear();
         // Retrieve one buffer
-        let mut buffer = pool.try_take().unwrap_or_else(|| pool.alloc());
+        let mut buffer = pool.take();
 
         // Async read from the underlying link
         let action = read(&link, &mut buffer).race(stop(signal.clone())).await?;
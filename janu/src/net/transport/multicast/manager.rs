@@ -29,6 +29,7 @@ pub struct TransportManagerConfigMulticast {
     pub join_interval: Duration,
     pub max_sessions: usize,
     pub is_qos: bool,
+    pub is_shm: bool,
 }
 
 impl Default for TransportManagerConfigMulticast {
@@ -49,6 +50,7 @@ pub struct TransportManagerConfigBuilderMulticast {
     join_interval: Duration,
     max_sessions: usize,
     is_qos: bool,
+    is_shm: bool,
 }
 
 impl Default for TransportManagerConfigBuilderMulticast {
@@ -59,6 +61,7 @@ impl Default for TransportManagerConfigBuilderMulticast {
             join_interval: Duration::from_millis(zparse!(ZN_JOIN_INTERVAL_DEFAULT).unwrap()),
             max_sessions: zparse!(ZN_MAX_SESSIONS_DEFAULT).unwrap(),
             is_qos: zparse!(ZN_QOS_DEFAULT).unwrap(),
+            is_shm: zparse!(ZN_SHM_MULTICAST_DEFAULT).unwrap(),
         }
     }
 }
@@ -89,6 +92,11 @@ impl TransportManagerConfigBuilderMulticast {
         self
     }
 
+    pub fn shm(mut self, is_shm: bool) -> Self {
+        self.is_shm = is_shm;
+        self
+    }
+
     pub async fn from_config(
         mut self,
         properties: &ConfigProperties,
@@ -108,6 +116,14 @@ impl TransportManagerConfigBuilderMulticast {
         if let Some(v) = properties.get(&ZN_QOS_KEY) {
             self = self.qos(zparse!(v)?);
         }
+        // Shared memory is used over multicast only if explicitly enabled
+        if let Some(v) = properties.get(&ZN_SHM_MULTICAST_KEY) {
+            self = self.shm(zparse!(v)?);
+        }
+        if let Some(v) = properties.get(&ZN_SHM_KEY) {
+            let is_shm = self.is_shm && zparse!(v)?;
+            self = self.shm(is_shm);
+        }
 
         Ok(self)
     }
@@ -119,6 +135,7 @@ impl TransportManagerConfigBuilderMulticast {
             join_interval: self.join_interval,
             max_sessions: self.max_sessions,
             is_qos: self.is_qos,
+            is_shm: self.is_shm,
         }
     }
 }
@@ -15,6 +15,8 @@ pub(crate) mod establishment;
 pub(crate) mod link;
 pub(crate) mod manager;
 pub(crate) mod rx;
+#[cfg(feature = "zero-copy")]
+pub(crate) mod shm;
 pub(crate) mod transport;
 pub(crate) mod tx;
 
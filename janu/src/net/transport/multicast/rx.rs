@@ -14,7 +14,7 @@
 use super::common::conduit::TransportChannelRx;
 use super::protocol::core::{Priority, Reliability, ZInt};
 use super::protocol::proto::{
-    Close, Frame, FramePayload, Join, TransportBody, TransportMessage, JanuMessage,
+    Attachment, Close, Frame, FramePayload, Join, TransportBody, TransportMessage, JanuMessage,
 };
 use super::transport::{TransportMulticastInner, TransportMulticastPeer};
 use crate::net::link::Locator;
@@ -32,8 +32,10 @@ impl TransportMulticastInner {
         mut msg: JanuMessage,
         peer: &TransportMulticastPeer,
     ) -> ZResult<()> {
+        // The sender retains the shared memory buffers sent over multicast:
+        // take our own reference on them
         #[cfg(feature = "zero-copy")]
-        let _ = msg.map_to_shmbuf(self.manager.shmr.clone())?;
+        let _ = msg.map_to_shmbuf(self.manager.shmr.clone(), true)?;
         peer.handler.handle_message(msg)
     }
 
@@ -92,7 +94,12 @@ impl TransportMulticastInner {
         }
     }
 
-    pub(super) fn handle_join(&self, join: Join, locator: &Locator) -> ZResult<()> {
+    pub(super) fn handle_join(
+        &self,
+        join: Join,
+        attachment: Option<Attachment>,
+        locator: &Locator,
+    ) -> ZResult<()> {
         if zread!(self.peers).len() >= self.manager.config.multicast.max_sessions {
             log::debug!(
                 "Ingoring Join on {} from peer: {}. Max sessions reached: {}.",
@@ -132,7 +139,7 @@ impl TransportMulticastInner {
             return Ok(());
         }
 
-        self.new_peer(locator, join)
+        self.new_peer(locator, join, attachment)
     }
 
     pub(super) fn handle_close(&self, close: Close, locator: &Locator) -> ZResult<()> {
@@ -180,7 +187,7 @@ impl TransportMulticastInner {
             None => {
                 drop(guard);
                 match msg.body {
-                    TransportBody::Join(join) => self.handle_join(join, locator),
+                    TransportBody::Join(join) => self.handle_join(join, msg.attachment, locator),
                     _ => Ok(()),
                 }
             }
@@ -0,0 +1,281 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use super::protocol::core::ZInt;
+use super::protocol::io::{
+    SharedMemoryBuf, SharedMemoryBufInfo, SharedMemoryManager, SharedMemoryReader, WBuf, ZBuf,
+};
+use super::protocol::proto::Attachment;
+use crate::net::transport::defaults::ZN_SHM_MULTICAST_RETENTION;
+use async_trait::async_trait;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+use std::convert::TryInto;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use janu_util::collections::Timed;
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::crypto::PseudoRng;
+use janu_util::{zcheck, zlock, zwrite};
+
+const WBUF_SIZE: usize = 64;
+// Version 1: the chunk header and the SharedMemoryBufInfo carry the generation of the chunk
+const SHM_VERSION: ZInt = 1;
+const SHM_NAME: &str = "shmmcast";
+// Let's use a ZInt as a challenge
+const SHM_SIZE: usize = std::mem::size_of::<ZInt>();
+
+/*************************************/
+/*          Join attachment          */
+/*************************************/
+///  7 6 5 4 3 2 1 0
+/// +-+-+-+-+-+-+-+-+
+/// ~    version    ~
+/// +---------------+
+/// ~   challenge   ~
+/// +---------------+
+/// ~   ShmMemBuf   ~
+/// +---------------+
+struct JoinShmAttachment {
+    version: ZInt,
+    challenge: ZInt,
+    shm: Vec<u8>,
+}
+
+impl WBuf {
+    fn write_join_shm_attachment(&mut self, attachment: &JoinShmAttachment) -> bool {
+        zcheck!(self.write_zint(attachment.version));
+        zcheck!(self.write_zint(attachment.challenge));
+        self.write_bytes_array(&attachment.shm)
+    }
+}
+
+impl ZBuf {
+    fn read_join_shm_attachment(&mut self) -> Option<JoinShmAttachment> {
+        let version = self.read_zint()?;
+        let challenge = self.read_zint()?;
+        let shm = self.read_bytes_array()?;
+        Some(JoinShmAttachment {
+            version,
+            challenge,
+            shm,
+        })
+    }
+}
+
+/*************************************/
+/*          Shared Memory            */
+/*************************************/
+/// Advertises the shared memory capabilities of a multicast transport in its Join
+/// messages. A remote peer is considered to be capable of shared memory if it is able
+/// to read the challenge stored in the advertised shared memory buffer, i.e. if it runs
+/// on the same host. Since any host may join the group, shared memory is only used over
+/// multicast if explicitly enabled by the configuration.
+///
+/// The set of peers receiving a multicast message is not known to the sender: a datagram
+/// may be lost or received by a peer that has not joined yet. Hence the buffers sent over
+/// shared memory are retained by the sender for a period of time while every receiver
+/// takes its own reference upon reception. The retention only bounds the time a receiver
+/// has to attach to a buffer: a receiver attaching once the buffer has been released, even
+/// if its chunk has been reallocated in the meantime, is detected by the generation of the
+/// chunk and discards the message as if the datagram had been lost. As for any other
+/// [`SharedMemoryBuf`], the manager the retained buffers were allocated from must outlive them.
+pub(super) struct SharedMemoryMulticast {
+    challenge: ZInt,
+    retention: Duration,
+    retained: Mutex<VecDeque<(Instant, Vec<SharedMemoryBuf>)>>,
+    // Rust guarantees that fields are dropped in the order of declaration.
+    // Buffer needs to be dropped before the manager.
+    buffer: SharedMemoryBuf,
+    _manager: SharedMemoryManager,
+}
+
+unsafe impl Send for SharedMemoryMulticast {}
+unsafe impl Sync for SharedMemoryMulticast {}
+
+impl SharedMemoryMulticast {
+    pub(super) fn new() -> ZResult<SharedMemoryMulticast> {
+        let mut prng = PseudoRng::from_entropy();
+        let challenge = prng.gen::<ZInt>();
+
+        let mut _manager =
+            SharedMemoryManager::new(format!("{}.{}", SHM_NAME, challenge), SHM_SIZE)?;
+        let mut buffer = match _manager.alloc(SHM_SIZE) {
+            Some(buffer) => buffer,
+            None => {
+                return zerror!(ZErrorKind::SharedMemoryError {
+                    descr: "Unable to allocate the multicast shared memory challenge".to_string()
+                })
+            }
+        };
+        let slice = unsafe { buffer.as_mut_slice() };
+        slice[0..SHM_SIZE].copy_from_slice(&challenge.to_le_bytes());
+
+        Ok(SharedMemoryMulticast {
+            challenge,
+            retention: Duration::from_millis(*ZN_SHM_MULTICAST_RETENTION),
+            retained: Mutex::new(VecDeque::new()),
+            buffer,
+            _manager,
+        })
+    }
+
+    pub(super) fn retention(&self) -> Duration {
+        self.retention
+    }
+
+    /// Keeps the buffers of a scheduled message alive for the retention period.
+    pub(super) fn retain(&self, buffers: Vec<SharedMemoryBuf>) {
+        if !buffers.is_empty() {
+            let expiry = Instant::now() + self.retention;
+            zlock!(self.retained).push_back((expiry, buffers));
+        }
+    }
+
+    /// Releases the buffers whose retention period has expired.
+    pub(super) fn release_expired(&self) {
+        let now = Instant::now();
+        let mut guard = zlock!(self.retained);
+        while matches!(guard.front(), Some((expiry, _)) if *expiry <= now) {
+            guard.pop_front();
+        }
+    }
+
+    pub(super) fn attachment(&self) -> ZResult<Attachment> {
+        let attachment = JoinShmAttachment {
+            version: SHM_VERSION,
+            challenge: self.challenge,
+            shm: self.buffer.info.serialize()?,
+        };
+        let mut wbuf = WBuf::new(WBUF_SIZE, false);
+        wbuf.write_join_shm_attachment(&attachment);
+        Ok(Attachment::new(wbuf.into()))
+    }
+
+    pub(super) fn is_shm(
+        attachment: Option<&Attachment>,
+        shmr: &Arc<RwLock<SharedMemoryReader>>,
+    ) -> bool {
+        let mut zbuf = match attachment {
+            Some(att) => att.buffer.clone(),
+            None => return false,
+        };
+        let att = match zbuf.read_join_shm_attachment() {
+            Some(att) => att,
+            None => return false,
+        };
+        if att.version != SHM_VERSION {
+            return false;
+        }
+        let info = match SharedMemoryBufInfo::deserialize(&att.shm) {
+            Ok(info) => info,
+            Err(_) => return false,
+        };
+        let buffer = match zwrite!(shmr).read_shmbuf(&info) {
+            Ok(buffer) => buffer,
+            Err(e) => {
+                log::debug!("Peer can not operate over shared memory: {}", e);
+                return false;
+            }
+        };
+        let res: Option<[u8; SHM_SIZE]> = buffer.as_slice().try_into().ok();
+        // Reading a shared memory buffer does not increment its reference count:
+        // the buffer must not be released by this side.
+        std::mem::forget(buffer);
+        res.map(ZInt::from_le_bytes) == Some(att.challenge)
+    }
+}
+
+/// Periodically releases the shared memory buffers retained by a multicast transport.
+pub(super) struct SharedMemoryMulticastRetentionTimer {
+    pub(super) shm: Arc<SharedMemoryMulticast>,
+}
+
+#[async_trait]
+impl Timed for SharedMemoryMulticastRetentionTimer {
+    async fn run(&mut self) {
+        self.shm.release_expired();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::protocol::io::SharedMemoryRelease;
+    use super::*;
+
+    #[test]
+    fn shm_multicast_retention() {
+        let mut manager =
+            SharedMemoryManager::new("shm_multicast_retention".to_string(), 1_024).unwrap();
+        let shmr = Arc::new(RwLock::new(SharedMemoryReader::new()));
+        let mut shm = SharedMemoryMulticast::new().unwrap();
+        shm.retention = Duration::from_secs(0);
+
+        // The sender retains the buffer once it has been mapped to its info
+        let mut sent: ZBuf = manager.alloc(64).unwrap().into();
+        let mut retained = vec![];
+        sent.map_to_shminfo(&mut SharedMemoryRelease::Sender(&mut retained))
+            .unwrap();
+        assert_eq!(retained.len(), 1);
+        let offset = retained[0].info.offset;
+        shm.retain(retained);
+        // Fill the rest of the segment so that the chunk of the buffer is reallocated
+        let available = manager.stats()[0].available - std::mem::size_of::<u64>();
+        let _filler = manager.alloc(available).unwrap();
+        assert_eq!(manager.garbage_collect(), 0);
+
+        // A receiver takes its own reference
+        let mut received = sent.clone();
+        assert!(received.map_to_shmbuf(shmr.clone(), true).unwrap());
+
+        // The buffer is kept alive by the receiver once the retention expired
+        shm.release_expired();
+        assert!(zlock!(shm.retained).is_empty());
+        assert_eq!(manager.garbage_collect(), 0);
+
+        // The buffer is released once the receiver is done with it
+        drop(received);
+        assert!(manager.garbage_collect() > 0);
+
+        // A late receiver can not access a released buffer
+        let mut late = sent.clone();
+        assert!(late.map_to_shmbuf(shmr.clone(), true).is_err());
+
+        // Nor the buffer reallocated in the same chunk
+        let realloc = manager.alloc(64).unwrap();
+        assert_eq!(realloc.info.offset, offset);
+        let mut late = sent.clone();
+        assert!(late.map_to_shmbuf(shmr, true).is_err());
+        assert_eq!(realloc.ref_count(), 1);
+    }
+
+    #[test]
+    fn shm_multicast_version() {
+        let shmr = Arc::new(RwLock::new(SharedMemoryReader::new()));
+        let shm = SharedMemoryMulticast::new().unwrap();
+
+        let attachment = shm.attachment().unwrap();
+        assert!(SharedMemoryMulticast::is_shm(Some(&attachment), &shmr));
+
+        // A peer running another version of the shared memory protocol falls back to copying
+        let attachment = JoinShmAttachment {
+            version: SHM_VERSION - 1,
+            challenge: shm.challenge,
+            shm: shm.buffer.info.serialize().unwrap(),
+        };
+        let mut wbuf = WBuf::new(WBUF_SIZE, false);
+        wbuf.write_join_shm_attachment(&attachment);
+        let attachment = Attachment::new(wbuf.into());
+        assert!(!SharedMemoryMulticast::is_shm(Some(&attachment), &shmr));
+    }
+}
@@ -14,7 +14,11 @@
 use super::common::conduit::{TransportConduitRx, TransportConduitTx};
 use super::link::{TransportLinkMulticast, TransportLinkMulticastConfig};
 use super::protocol::core::{ConduitSnList, PeerId, Priority, WhatAmI, ZInt};
-use super::protocol::proto::{tmsg, Join, TransportMessage, JanuMessage};
+use super::protocol::proto::{tmsg, Attachment, Join, TransportMessage, JanuMessage};
+#[cfg(feature = "zero-copy")]
+use super::protocol::io::SharedMemoryRelease;
+#[cfg(feature = "zero-copy")]
+use super::shm::{SharedMemoryMulticast, SharedMemoryMulticastRetentionTimer};
 use crate::net::link::{Link, LinkMulticast, Locator};
 use crate::net::transport::{
     TransportManager, TransportMulticastEventHandler, TransportPeer, TransportPeerEventHandler,
@@ -100,6 +104,7 @@ pub(super) struct TransportMulticastPeer {
     pub(super) whatami: WhatAmI,
     pub(super) sn_resolution: ZInt,
     pub(super) lease: Duration,
+    pub(super) is_shm: bool,
     pub(super) whatchdog: Arc<AtomicBool>,
     pub(super) handle: TimedHandle,
     pub(super) conduit_rx: Box<[TransportConduitRx]>,
@@ -151,6 +156,9 @@ pub(crate) struct TransportMulticastInner {
     pub(super) callback: Arc<RwLock<Option<Arc<dyn TransportMulticastEventHandler>>>>,
     // The timer for peer leases
     pub(super) timer: Arc<Timer>,
+    // The shared memory challenge advertised to the peers, if SHM is enabled
+    #[cfg(feature = "zero-copy")]
+    pub(super) shm: Option<Arc<SharedMemoryMulticast>>,
     // Transport statistics
     #[cfg(feature = "stats")]
     pub(super) stats: TransportMulticastStatsInner,
@@ -183,7 +191,8 @@ impl TransportMulticastInner {
             }
         }
 
-        let ti = TransportMulticastInner {
+        #[allow(unused_mut)]
+        let mut ti = TransportMulticastInner {
             manager: config.manager,
             locator: config.link.get_dst(),
             conduit_tx: conduit_tx.into_boxed_slice().into(),
@@ -191,9 +200,28 @@ impl TransportMulticastInner {
             link: Arc::new(RwLock::new(None)),
             callback: Arc::new(RwLock::new(None)),
             timer: Arc::new(Timer::new()),
+            #[cfg(feature = "zero-copy")]
+            shm: None,
             #[cfg(feature = "stats")]
             stats: TransportMulticastStatsInner::default(),
         };
+        #[cfg(feature = "zero-copy")]
+        if ti.manager.config.multicast.is_shm {
+            match SharedMemoryMulticast::new() {
+                Ok(shm) => {
+                    let shm = Arc::new(shm);
+                    let event = SharedMemoryMulticastRetentionTimer { shm: shm.clone() };
+                    let event = TimedEvent::periodic(shm.retention(), event);
+                    task::block_on(ti.timer.add(event));
+                    ti.shm = Some(shm);
+                }
+                Err(e) => log::warn!(
+                    "Unable to enable shared memory on multicast transport {}: {}",
+                    ti.locator,
+                    e
+                ),
+            }
+        }
 
         let mut w_guard = zwrite!(ti.link);
         *w_guard = Some(TransportLinkMulticast::new(ti.clone(), config.link));
@@ -218,8 +246,20 @@ impl TransportMulticastInner {
         self.conduit_tx.len() > 1
     }
 
+    /// A multicast transport operates over shared memory only if shared memory has been
+    /// explicitly enabled for multicast and all the known peers are able to access it.
+    /// The datagrams are also received by the hosts whose Join has not been received yet:
+    /// enabling it asserts that all the members of the group run on the same host.
     pub(crate) fn is_shm(&self) -> bool {
-        false
+        #[cfg(feature = "zero-copy")]
+        {
+            let guard = zread!(self.peers);
+            self.shm.is_some() && !guard.is_empty() && guard.values().all(|p| p.is_shm)
+        }
+        #[cfg(not(feature = "zero-copy"))]
+        {
+            false
+        }
     }
 
     pub(crate) fn get_callback(&self) -> Option<Arc<dyn TransportMulticastEventHandler>> {
@@ -290,8 +330,17 @@ impl TransportMulticastInner {
     /// Schedule a Janu message on the transmission queue    
     #[cfg(feature = "zero-copy")]
     pub(crate) fn schedule(&self, mut message: JanuMessage) {
-        // Multicast transports do not support SHM for the time being
-        let res = message.map_to_shmbuf(self.manager.shmr.clone());
+        // The shared memory buffers are retained by this transport until their retention
+        // expires, every peer receiving them takes its own reference.
+        let res = match self.shm.as_ref().filter(|_| self.is_shm()) {
+            Some(shm) => {
+                let mut retained = vec![];
+                let res = message.map_to_shminfo(&mut SharedMemoryRelease::Sender(&mut retained));
+                shm.retain(retained);
+                res
+            }
+            None => message.map_to_shmbuf(self.manager.shmr.clone(), false),
+        };
         if let Err(e) = res {
             log::trace!("Failed SHM conversion: {}", e);
             return;
@@ -321,6 +370,7 @@ impl TransportMulticastInner {
                     join_interval: self.manager.config.multicast.join_interval,
                     sn_resolution: self.manager.config.sn_resolution,
                     batch_size,
+                    attachment: self.join_attachment(),
                 };
                 l.start_tx(config, self.conduit_tx.clone());
                 Ok(())
@@ -393,15 +443,52 @@ impl TransportMulticastInner {
     /*************************************/
     /*               PEER                */
     /*************************************/
-    pub(super) fn new_peer(&self, locator: &Locator, join: Join) -> ZResult<()> {
+    #[cfg(feature = "zero-copy")]
+    fn join_attachment(&self) -> Option<Attachment> {
+        let shm = self.shm.as_ref()?;
+        match shm.attachment() {
+            Ok(attachment) => Some(attachment),
+            Err(e) => {
+                log::warn!(
+                    "Unable to advertise shared memory on multicast transport {}: {}",
+                    self.locator,
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "zero-copy"))]
+    fn join_attachment(&self) -> Option<Attachment> {
+        None
+    }
+
+    #[cfg(feature = "zero-copy")]
+    fn is_peer_shm(&self, attachment: Option<&Attachment>) -> bool {
+        self.shm.is_some() && SharedMemoryMulticast::is_shm(attachment, &self.manager.shmr)
+    }
+
+    #[cfg(not(feature = "zero-copy"))]
+    fn is_peer_shm(&self, _attachment: Option<&Attachment>) -> bool {
+        false
+    }
+
+    pub(super) fn new_peer(
+        &self,
+        locator: &Locator,
+        join: Join,
+        attachment: Option<Attachment>,
+    ) -> ZResult<()> {
         let mut link = Link::from(self.get_link());
         link.dst = locator.clone();
 
+        let is_shm = self.is_peer_shm(attachment.as_ref());
         let peer = TransportPeer {
             pid: join.pid,
             whatami: join.whatami,
             is_qos: join.is_qos(),
-            is_shm: self.is_shm(),
+            is_shm,
             links: vec![link],
         };
 
@@ -451,6 +538,7 @@ impl TransportMulticastInner {
             whatami: peer.whatami,
             sn_resolution: join.sn_resolution,
             lease: join.lease,
+            is_shm,
             whatchdog,
             handle,
             conduit_rx,
@@ -462,13 +550,14 @@ impl TransportMulticastInner {
         task::block_on(self.timer.add(event));
 
         log::debug!(
-                "New transport joined on {}: pid {}, whatami {}, sn resolution {}, locator {}, qos {}, initial sn: {}",
+                "New transport joined on {}: pid {}, whatami {}, sn resolution {}, locator {}, qos {}, shm {}, initial sn: {}",
                 self.locator,
                 join.pid,
                 join.whatami,
                 join.sn_resolution,
                 locator,
                 join.is_qos(),
+                is_shm,
                 join.next_sns,
             );
 
@@ -506,7 +595,7 @@ impl TransportMulticastInner {
                     pid: p.pid,
                     whatami: p.whatami,
                     is_qos: p.is_qos(),
-                    is_shm: self.is_shm(),
+                    is_shm: p.is_shm,
                     links: vec![link],
                 }
             })
@@ -29,7 +29,8 @@ use janu_util::properties::config::*;
 use janu_util::zcheck;
 
 const WBUF_SIZE: usize = 64;
-const SHM_VERSION: ZInt = 0;
+// Version 1: the chunk header and the SharedMemoryBufInfo carry the generation of the chunk
+const SHM_VERSION: ZInt = 1;
 const SHM_NAME: &str = "shmauth";
 // Let's use a ZInt as a challenge
 const SHM_SIZE: usize = std::mem::size_of::<ZInt>();
@@ -252,14 +253,17 @@ impl PeerAuthenticatorTrait for SharedMemoryAuthenticator {
             }
         };
 
-        if init_syn_property.version > SHM_VERSION {
-            return zerror!(ZErrorKind::InvalidMessage {
-                descr: format!("Rejected InitSyn with invalid attachment on link: {}", link),
-            });
+        if init_syn_property.version != SHM_VERSION {
+            log::debug!(
+                "Peer {} can not operate over shared memory: unsupported version {}",
+                peer_id,
+                init_syn_property.version
+            );
+            return Ok(PeerAuthenticatorOutput::default());
         }
 
         // Try to read from the shared memory
-        match init_syn_property.shm.map_to_shmbuf(self.reader.clone()) {
+        match init_syn_property.shm.map_to_shmbuf(self.reader.clone(), false) {
             Ok(res) => {
                 if !res {
                     log::debug!(
@@ -333,7 +337,7 @@ impl PeerAuthenticatorTrait for SharedMemoryAuthenticator {
         };
 
         // Try to read from the shared memory
-        match init_ack_property.shm.map_to_shmbuf(self.reader.clone()) {
+        match init_ack_property.shm.map_to_shmbuf(self.reader.clone(), false) {
             Ok(res) => {
                 if !res {
                     log::debug!(
@@ -18,12 +18,14 @@ use super::authenticator::{
 use super::manager::Opened;
 use super::protocol::core::{PeerId, Property, WhatAmI, ZInt};
 use super::protocol::io::{WBuf, ZBuf, ZSlice};
+use super::protocol::proto::defaults::BATCH_SIZE_MAX;
 use super::protocol::proto::{
     tmsg, Attachment, Close, OpenAck, OpenSyn, TransportBody, TransportMessage,
 };
 use super::{TransportConfigUnicast, TransportUnicast};
 use crate::net::link::{Link, LinkUnicast};
 use rand::Rng;
+use std::convert::TryFrom;
 use std::time::Duration;
 use janu_util::core::{ZError, ZErrorKind, ZResult};
 use janu_util::crypto::hmac;
@@ -50,6 +52,22 @@ fn attachment_from_config(ps: &[Property]) -> ZResult<Attachment> {
     }
 }
 
+// The batch size larger than 65_535 bytes to propose to the peer, if any. Large batches
+// are only supported on stream-oriented links, datagram links are bound to their MTU.
+fn large_batch_size(manager: &TransportManager, link: &LinkUnicast) -> Option<u32> {
+    let batch_size = manager.config.batch_size.min(BATCH_SIZE_MAX);
+    if link.is_streamed() && batch_size > u16::MAX as u32 {
+        Some(batch_size)
+    } else {
+        None
+    }
+}
+
+// The batch size to use on the link: the agreed one if any, at most 65_535 bytes otherwise
+fn link_batch_size(manager: &TransportManager, agreed: Option<u32>) -> u32 {
+    agreed.unwrap_or_else(|| manager.config.batch_size.min(u16::MAX as u32))
+}
+
 fn properties_from_attachment(mut att: Attachment) -> ZResult<Vec<Property>> {
     att.buffer.read_properties().ok_or_else(|| {
         let e = "Error while decoding attachment properties".to_string();
@@ -65,6 +83,7 @@ struct Cookie {
     pid: PeerId,
     sn_resolution: ZInt,
     is_qos: bool,
+    batch_size: u32,
     nonce: ZInt,
 }
 
@@ -74,6 +93,7 @@ impl WBuf {
         zcheck!(self.write_peerid(&cookie.pid));
         zcheck!(self.write_zint(cookie.sn_resolution));
         zcheck!(self.write(if cookie.is_qos { 1 } else { 0 }));
+        zcheck!(self.write_zint(cookie.batch_size as ZInt));
         zcheck!(self.write_zint(cookie.nonce));
         true
     }
@@ -85,6 +105,7 @@ impl ZBuf {
         let pid = self.read_peerid()?;
         let sn_resolution = self.read_zint()?;
         let is_qos = self.read()? == 1;
+        let batch_size = u32::try_from(self.read_zint()?).ok()?;
         let nonce = self.read_zint()?;
 
         Some(Cookie {
@@ -92,6 +113,7 @@ impl ZBuf {
             pid,
             sn_resolution,
             is_qos,
+            batch_size,
             nonce,
         })
     }
@@ -125,6 +147,7 @@ async fn close_link(
 /*************************************/
 struct OpenInitSynOutput {
     sn_resolution: ZInt,
+    batch_size: Option<u32>,
     auth_transport: AuthenticatedPeerTransport,
 }
 async fn open_send_init_syn(
@@ -142,12 +165,14 @@ async fn open_send_init_syn(
     }
 
     // Build and send the InitSyn message
+    let batch_size = large_batch_size(manager, link);
     let mut message = TransportMessage::make_init_syn(
         manager.config.version,
         manager.config.whatami,
         manager.config.pid,
         manager.config.sn_resolution,
         manager.config.unicast.is_qos,
+        batch_size,
         attachment_from_config(&auth.properties).ok(),
     );
     let _ = link
@@ -157,6 +182,7 @@ async fn open_send_init_syn(
 
     let output = OpenInitSynOutput {
         sn_resolution: manager.config.sn_resolution,
+        batch_size,
         auth_transport: auth.transport,
     };
     Ok(output)
@@ -167,6 +193,7 @@ struct OpenInitAckOutput {
     whatami: WhatAmI,
     sn_resolution: ZInt,
     is_qos: bool,
+    batch_size: u32,
     initial_sn_tx: ZInt,
     cookie: ZSlice,
     open_syn_attachment: Option<Attachment>,
@@ -179,7 +206,10 @@ async fn open_recv_init_ack(
     input: OpenInitSynOutput,
 ) -> IResult<OpenInitAckOutput> {
     // Wait to read an InitAck
-    let mut messages = link.read_transport_message().await.map_err(|e| (e, None))?;
+    let mut messages = link
+        .read_transport_message(manager)
+        .await
+        .map_err(|e| (e, None))?;
     if messages.len() != 1 {
         let e = format!(
             "Received multiple messages in response to an InitSyn on {}: {:?}",
@@ -250,6 +280,26 @@ async fn open_recv_init_ack(
         (sn_resolution, initial_sn_tx, false)
     };
 
+    // The responder can only agree on a large batch size if it has been proposed
+    let batch_size = match init_ack.batch_size {
+        Some(batch_size) => match input.batch_size {
+            Some(proposed) if batch_size > u16::MAX as u32 && batch_size <= proposed => {
+                Some(batch_size)
+            }
+            _ => {
+                let e = format!(
+                    "Rejecting InitAck on {}. Invalid batch size: {}",
+                    link, batch_size
+                );
+                return Err((
+                    zerror2!(ZErrorKind::InvalidMessage { descr: e }),
+                    Some(tmsg::close_reason::INVALID),
+                ));
+            }
+        },
+        None => None,
+    };
+
     let init_ack_properties: Vec<Property> = match msg.attachment.take() {
         Some(att) => {
             properties_from_attachment(att).map_err(|e| (e, Some(tmsg::close_reason::INVALID)))?
@@ -292,6 +342,7 @@ async fn open_recv_init_ack(
         whatami: init_ack.whatami,
         sn_resolution,
         is_qos: init_ack.is_qos,
+        batch_size: link_batch_size(manager, batch_size),
         initial_sn_tx,
         cookie: init_ack.cookie,
         open_syn_attachment: attachment_from_config(&auth.properties).ok(),
@@ -306,6 +357,7 @@ struct OpenOpenSynOutput {
     sn_resolution: ZInt,
     initial_sn_tx: ZInt,
     is_qos: bool,
+    batch_size: u32,
     auth_transport: AuthenticatedPeerTransport,
 }
 async fn open_send_open_syn(
@@ -333,6 +385,7 @@ async fn open_send_open_syn(
         sn_resolution: input.sn_resolution,
         initial_sn_tx: input.initial_sn_tx,
         is_qos: input.is_qos,
+        batch_size: input.batch_size,
         auth_transport: input.auth_transport,
     };
     Ok(output)
@@ -343,6 +396,7 @@ struct OpenAckOutput {
     whatami: WhatAmI,
     sn_resolution: ZInt,
     is_qos: bool,
+    batch_size: u32,
     initial_sn_tx: ZInt,
     initial_sn_rx: ZInt,
     lease: Duration,
@@ -355,7 +409,10 @@ async fn open_recv_open_ack(
     input: OpenOpenSynOutput,
 ) -> IResult<OpenAckOutput> {
     // Wait to read an OpenAck
-    let mut messages = link.read_transport_message().await.map_err(|e| (e, None))?;
+    let mut messages = link
+        .read_transport_message(manager)
+        .await
+        .map_err(|e| (e, None))?;
     if messages.len() != 1 {
         let e = format!(
             "Received multiple messages in response to an InitSyn on {}: {:?}",
@@ -407,6 +464,7 @@ async fn open_recv_open_ack(
         whatami: input.whatami,
         sn_resolution: input.sn_resolution,
         is_qos: input.is_qos,
+        batch_size: input.batch_size,
         initial_sn_tx: input.initial_sn_tx,
         initial_sn_rx,
         lease,
@@ -480,7 +538,7 @@ pub(crate) async fn open_link(
         let _ = t.add_link(link.clone())?;
 
         // Start the TX loop
-        let _ = t.start_tx(link, keep_alive, manager.config.batch_size)?;
+        let _ = t.start_tx(link, keep_alive, info.batch_size)?;
 
         // Assign a callback if the transport is new
         loop {
@@ -513,7 +571,7 @@ pub(crate) async fn open_link(
         }
 
         // Start the RX loop
-        let _ = t.start_rx(link, info.lease)?;
+        let _ = t.start_rx(link, info.lease, info.batch_size)?;
     }
     drop(a_guard);
 
@@ -532,6 +590,7 @@ struct AcceptInitSynOutput {
     pid: PeerId,
     sn_resolution: ZInt,
     is_qos: bool,
+    batch_size: Option<u32>,
     init_ack_attachment: Option<Attachment>,
     auth_transport: AuthenticatedPeerTransport,
 }
@@ -541,7 +600,10 @@ async fn accept_recv_init_syn(
     auth_link: &AuthenticatedPeerLink,
 ) -> IResult<AcceptInitSynOutput> {
     // Wait to read an InitSyn
-    let mut messages = link.read_transport_message().await.map_err(|e| (e, None))?;
+    let mut messages = link
+        .read_transport_message(manager)
+        .await
+        .map_err(|e| (e, None))?;
     if messages.len() != 1 {
         let e = format!(
             "Received multiple messages instead of a single InitSyn on {}: {:?}",
@@ -622,6 +684,7 @@ async fn accept_recv_init_syn(
         pid: init_syn.pid,
         sn_resolution: init_syn.sn_resolution,
         is_qos: init_syn.is_qos,
+        batch_size: init_syn.batch_size,
         init_ack_attachment: attachment_from_config(&auth.properties).ok(),
         auth_transport: auth.transport,
     };
@@ -640,6 +703,11 @@ async fn accept_send_init_ack(
 ) -> IResult<AcceptInitAckOutput> {
     // Compute the minimum SN Resolution
     let agreed_sn_resolution = manager.config.sn_resolution.min(input.sn_resolution);
+    // Agree on a large batch size only if both peers support it on this link
+    let agreed_batch_size = match (large_batch_size(manager, link), input.batch_size) {
+        (Some(local), Some(remote)) if remote > u16::MAX as u32 => Some(local.min(remote)),
+        _ => None,
+    };
 
     // Create and encode the cookie
     let mut wbuf = WBuf::new(64, false);
@@ -648,6 +716,7 @@ async fn accept_send_init_ack(
         pid: input.pid,
         sn_resolution: agreed_sn_resolution,
         is_qos: input.is_qos,
+        batch_size: link_batch_size(manager, agreed_batch_size),
         nonce: zasynclock!(manager.prng).gen_range(0..agreed_sn_resolution),
     };
     wbuf.write_cookie(&cookie);
@@ -678,6 +747,7 @@ async fn accept_send_init_ack(
         apid,
         sn_resolution,
         input.is_qos,
+        agreed_batch_size,
         cookie,
         input.init_ack_attachment,
     );
@@ -709,7 +779,10 @@ async fn accept_recv_open_syn(
     input: AcceptInitAckOutput,
 ) -> IResult<AcceptOpenSynOutput> {
     // Wait to read an OpenSyn
-    let mut messages = link.read_transport_message().await.map_err(|e| (e, None))?;
+    let mut messages = link
+        .read_transport_message(manager)
+        .await
+        .map_err(|e| (e, None))?;
     if messages.len() != 1 {
         let e = format!(
             "Received multiple messages instead of a single OpenSyn on {}: {:?}",
@@ -830,6 +903,7 @@ struct AcceptInitTransportOutput {
     transport: TransportUnicast,
     initial_sn: ZInt,
     lease: Duration,
+    batch_size: u32,
     open_ack_attachment: Option<Attachment>,
 }
 async fn accept_init_transport(
@@ -912,6 +986,7 @@ async fn accept_init_transport(
         transport,
         initial_sn: open_ack_initial_sn,
         lease: input.lease,
+        batch_size: input.cookie.batch_size,
         open_ack_attachment: input.open_ack_attachment,
     };
     Ok(output)
@@ -921,6 +996,7 @@ async fn accept_init_transport(
 struct AcceptOpenAckOutput {
     transport: TransportUnicast,
     lease: Duration,
+    batch_size: u32,
 }
 async fn accept_send_open_ack(
     manager: &TransportManager,
@@ -941,6 +1017,7 @@ async fn accept_send_open_ack(
     let output = AcceptOpenAckOutput {
         transport: input.transport,
         lease: input.lease,
+        batch_size: input.batch_size,
     };
     Ok(output)
 }
@@ -968,7 +1045,7 @@ async fn accept_finalize_transport(
         //       transport lease.
         let keep_alive = manager.config.unicast.keep_alive.min(input.lease / 4);
         // Start the TX loop
-        let _ = transport.start_tx(link, keep_alive, manager.config.batch_size)?;
+        let _ = transport.start_tx(link, keep_alive, input.batch_size)?;
 
         // Assign a callback if the transport is new
         loop {
@@ -1008,7 +1085,7 @@ async fn accept_finalize_transport(
         }
 
         // Start the RX loop
-        let _ = transport.start_rx(link, input.lease)?;
+        let _ = transport.start_rx(link, input.lease, input.batch_size)?;
     }
     drop(a_guard);
 
@@ -11,8 +11,11 @@
 // Contributors:
 //   ADLINK janu team, <janu@adlink-labs.tech>
 //
+use super::common::batch::{streamed_length_bytes, SerializationBatch};
+use super::common::rx_pool::RxBufferPools;
 use super::common::{conduit::TransportConduitTx, pipeline::TransmissionPipeline};
-use super::protocol::core::Priority;
+use super::super::defaults::ZN_LINK_PING_INTERVAL;
+use super::protocol::core::{Channel, Priority, ZInt};
 use super::protocol::io::{ZBuf, ZSlice};
 use super::protocol::proto::TransportMessage;
 use super::transport::TransportUnicastInner;
@@ -22,10 +25,10 @@ use crate::net::link::LinkUnicast;
 use async_std::prelude::*;
 use async_std::task;
 use async_std::task::JoinHandle;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
-use janu_util::collections::RecyclingObjectPool;
+use std::time::{Duration, Instant};
 use janu_util::core::{ZError, ZErrorKind, ZResult};
 use janu_util::sync::Signal;
 use janu_util::zerror;
@@ -43,10 +46,70 @@ pub(super) struct TransportLinkUnicast {
     active_rx: Arc<AtomicBool>,
     signal_rx: Signal,
     handle_rx: Option<Arc<JoinHandle<()>>>,
+    // The round-trip time estimation
+    rtt: LinkRtt,
+}
+
+/// Round-trip time estimation of a link based on Ping/Pong messages.
+/// The Ping hash carries the send time expressed in microseconds since the link epoch,
+/// hence no state needs to be kept for the outstanding Pings.
+#[derive(Clone)]
+pub(super) struct LinkRtt {
+    epoch: Instant,
+    // Whether the Pings are sent, shared by all the links of a transport
+    enabled: Arc<AtomicBool>,
+    // Smoothed RTT in microseconds, 0 if not yet measured
+    srtt: Arc<AtomicU64>,
+}
+
+impl LinkRtt {
+    fn new(enabled: Arc<AtomicBool>) -> LinkRtt {
+        LinkRtt {
+            epoch: Instant::now(),
+            enabled,
+            srtt: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire)
+    }
+
+    fn make_ping(&self) -> TransportMessage {
+        let hash = self.epoch.elapsed().as_micros() as ZInt;
+        TransportMessage::make_ping(hash, None)
+    }
+
+    pub(super) fn pong(&self, hash: ZInt) {
+        let now = self.epoch.elapsed().as_micros() as u64;
+        if let Some(sample) = now.checked_sub(hash) {
+            self.sample(sample);
+        }
+    }
+
+    fn sample(&self, sample: u64) {
+        let sample = sample.max(1);
+        // Exponentially weighted moving average as per RFC 6298
+        let srtt = self.srtt.load(Ordering::Acquire);
+        let srtt = if srtt == 0 {
+            sample
+        } else {
+            (7 * srtt + sample) / 8
+        };
+        self.srtt.store(srtt.max(1), Ordering::Release);
+    }
+
+    pub(super) fn get(&self) -> Option<Duration> {
+        match self.srtt.load(Ordering::Acquire) {
+            0 => None,
+            srtt => Some(Duration::from_micros(srtt)),
+        }
+    }
 }
 
 impl TransportLinkUnicast {
     pub(super) fn new(transport: TransportUnicastInner, link: LinkUnicast) -> TransportLinkUnicast {
+        let rtt = LinkRtt::new(transport.rtt_enabled.clone());
         TransportLinkUnicast {
             transport,
             inner: link,
@@ -55,6 +118,7 @@ impl TransportLinkUnicast {
             active_rx: Arc::new(AtomicBool::new(false)),
             signal_rx: Signal::new(),
             handle_rx: None,
+            rtt,
         }
     }
 }
@@ -65,6 +129,11 @@ impl TransportLinkUnicast {
         &self.inner
     }
 
+    #[inline]
+    pub(super) fn get_rtt(&self) -> &LinkRtt {
+        &self.rtt
+    }
+
     #[inline]
     pub(super) fn get_pipeline(&self) -> Option<Arc<TransmissionPipeline>> {
         self.pipeline.clone()
@@ -73,13 +142,20 @@ impl TransportLinkUnicast {
     pub(super) fn start_tx(
         &mut self,
         keep_alive: Duration,
-        batch_size: u16,
+        batch_size: u32,
         conduit_tx: Arc<[TransportConduitTx]>,
     ) {
         if self.handle_tx.is_none() {
+            // A batch size larger than 65_535 bytes is only provided for streamed
+            // links when it has been agreed with the peer, cap it to the MTU otherwise
+            let batch_size = if batch_size > u16::MAX as u32 {
+                batch_size
+            } else {
+                batch_size.min(self.inner.get_mtu() as u32)
+            };
             // The pipeline
             let pipeline = Arc::new(TransmissionPipeline::new(
-                batch_size.min(self.inner.get_mtu()),
+                batch_size,
                 self.inner.is_streamed(),
                 conduit_tx,
             ));
@@ -88,11 +164,13 @@ impl TransportLinkUnicast {
             // Spawn the TX task
             let c_link = self.inner.clone();
             let c_transport = self.transport.clone();
+            let c_rtt = self.rtt.clone();
             let handle = task::spawn(async move {
                 let res = tx_task(
                     pipeline,
                     c_link.clone(),
                     keep_alive,
+                    c_rtt,
                     #[cfg(feature = "stats")]
                     c_transport.stats.clone(),
                 )
@@ -114,7 +192,7 @@ impl TransportLinkUnicast {
         }
     }
 
-    pub(super) fn start_rx(&mut self, lease: Duration) {
+    pub(super) fn start_rx(&mut self, lease: Duration, batch_size: u32) {
         if self.handle_rx.is_none() {
             self.active_rx.store(true, Ordering::Release);
             // Spawn the RX task
@@ -122,7 +200,7 @@ impl TransportLinkUnicast {
             let c_transport = self.transport.clone();
             let c_signal = self.signal_rx.clone();
             let c_active = self.active_rx.clone();
-            let c_rx_buff_size = self.transport.manager.config.link_rx_buff_size;
+            let c_rx_pools = self.transport.manager.rx_pools.clone();
 
             let handle = task::spawn(async move {
                 // Start the consume task
@@ -132,7 +210,8 @@ impl TransportLinkUnicast {
                     lease,
                     c_signal.clone(),
                     c_active.clone(),
-                    c_rx_buff_size,
+                    c_rx_pools,
+                    batch_size,
                 )
                 .await;
                 c_active.store(false, Ordering::Release);
@@ -175,24 +254,49 @@ impl TransportLinkUnicast {
 /*************************************/
 /*              TASKS                */
 /*************************************/
+// The channel of a batch pulled from the given priority queue of the pipeline
+fn batch_channel(batch: &SerializationBatch, priority: usize) -> Channel {
+    Channel {
+        priority: Priority::try_from(priority as u8).unwrap_or_default(),
+        reliability: batch.reliability(),
+    }
+}
+
 async fn tx_task(
     pipeline: Arc<TransmissionPipeline>,
     link: LinkUnicast,
     keep_alive: Duration,
+    rtt: LinkRtt,
     #[cfg(feature = "stats")] stats: TransportUnicastStatsInner,
 ) -> ZResult<()> {
+    // Periodically send a Ping to measure the round-trip time of the link, if enabled
+    let ping_interval = Duration::from_millis(*ZN_LINK_PING_INTERVAL);
+    let mut last_ping: Option<Instant> = None;
     loop {
+        if rtt.is_enabled() {
+            let ping = match last_ping {
+                Some(t) => t.elapsed() >= ping_interval,
+                None => true,
+            };
+            if ping {
+                pipeline.push_transport_message(rtt.make_ping(), Priority::Control);
+                last_ping = Some(Instant::now());
+            }
+        }
+
         match pipeline.pull().timeout(keep_alive).await {
             Ok(res) => match res {
                 Some((batch, priority)) => {
                     // Send the buffer on the link
-                    let bytes = batch.as_bytes();
-                    let _ = link.write_all(bytes).await?;
+                    let bytes = batch.as_ioslices();
+                    let _ = link
+                        .write_batch(&bytes, batch_channel(&batch, priority))
+                        .await?;
 
                     #[cfg(feature = "stats")]
                     {
                         stats.inc_tx_msgs(batch.stats.t_msgs);
-                        stats.inc_tx_bytes(bytes.len());
+                        stats.inc_tx_bytes(bytes.iter().map(|b| b.len()).sum());
                     }
 
                     // Reinsert the batch into the queue
@@ -211,9 +315,9 @@ async fn tx_task(
 
     // Drain the transmission pipeline and write remaining bytes on the wire
     let mut batches = pipeline.drain();
-    for (b, _) in batches.drain(..) {
+    for (b, priority) in batches.drain(..) {
         let _ = link
-            .write_all(b.as_bytes())
+            .write_batch(&b.as_ioslices(), batch_channel(&b, priority))
             .timeout(keep_alive)
             .await
             .map_err(|_| {
@@ -237,18 +341,28 @@ async fn rx_task_stream(
     lease: Duration,
     signal: Signal,
     active: Arc<AtomicBool>,
-    rx_buff_size: usize,
+    rx_pools: Arc<RxBufferPools>,
+    batch_size: u32,
 ) -> ZResult<()> {
     enum Action {
         Read(usize),
         Stop,
     }
 
-    async fn read(link: &LinkUnicast, buffer: &mut [u8]) -> ZResult<Action> {
-        // 16 bits for reading the batch length
-        let mut length = [0u8, 0u8];
-        link.read_exact(&mut length).await?;
-        let n = u16::from_le_bytes(length) as usize;
+    async fn read(link: &LinkUnicast, buffer: &mut [u8], length_bytes: usize) -> ZResult<Action> {
+        // 16 bits (or 32 bits for large batches) for reading the batch length
+        let mut length = [0u8, 0u8, 0u8, 0u8];
+        link.read_exact(&mut length[..length_bytes]).await?;
+        let n = u32::from_le_bytes(length) as usize;
+        if n > buffer.len() {
+            let e = format!(
+                "{}: batch of {} bytes exceeds the maximum of {} bytes",
+                link,
+                n,
+                buffer.len()
+            );
+            return zerror!(ZErrorKind::IoError { descr: e });
+        }
         link.read_exact(&mut buffer[0..n]).await?;
         Ok(Action::Read(n))
     }
@@ -260,19 +374,24 @@ async fn rx_task_stream(
 
     // The ZBuf to read a message batch onto
     let mut zbuf = ZBuf::new();
-    // The pool of buffers
-    let mtu = link.get_mtu() as usize;
-    let n = 1 + (rx_buff_size / mtu);
-    let pool = RecyclingObjectPool::new(n, || vec![0u8; mtu].into_boxed_slice());
+    // The length of the batches is encoded on 32 bits if a large batch size has been agreed
+    let length_bytes = streamed_length_bytes(batch_size);
+    // The pool of buffers reserved for this link
+    let size = if length_bytes == 4 {
+        batch_size as usize
+    } else {
+        link.get_mtu() as usize
+    };
+    let pool = rx_pools.reserve(size);
     while active.load(Ordering::Acquire) {
         // Clear the ZBuf
         zbuf.clear();
 
         // Retrieve one buffer
-        let mut buffer = pool.try_take().unwrap_or_else(|| pool.alloc());
+        let mut buffer = pool.take();
 
         // Async read from the underlying link
-        let action = read(&link, &mut buffer)
+        let action = read(&link, &mut buffer, length_bytes)
             .race(stop(signal.clone()))
             .timeout(lease)
             .await
@@ -285,7 +404,7 @@ async fn rx_task_stream(
                 zbuf.add_zslice(ZSlice::new(buffer.into(), 0, n));
 
                 #[cfg(feature = "stats")]
-                transport.stats.inc_rx_bytes(2 + n); // Account for the batch len encoding (16 bits)
+                transport.stats.inc_rx_bytes(length_bytes + n); // Account for the batch len encoding
 
                 while zbuf.can_read() {
                     match zbuf.read_transport_message() {
@@ -314,7 +433,7 @@ async fn rx_task_dgram(
     lease: Duration,
     signal: Signal,
     active: Arc<AtomicBool>,
-    rx_buff_size: usize,
+    rx_pools: Arc<RxBufferPools>,
 ) -> ZResult<()> {
     enum Action {
         Read(usize),
@@ -333,15 +452,13 @@ async fn rx_task_dgram(
 
     // The ZBuf to read a message batch onto
     let mut zbuf = ZBuf::new();
-    // The pool of buffers
-    let mtu = link.get_mtu() as usize;
-    let n = 1 + (rx_buff_size / mtu);
-    let pool = RecyclingObjectPool::new(n, || vec![0u8; mtu].into_boxed_slice());
+    // The pool of buffers reserved for this link
+    let pool = rx_pools.reserve(link.get_mtu() as usize);
     while active.load(Ordering::Acquire) {
         // Clear the zbuf
         zbuf.clear();
         // Retrieve one buffer
-        let mut buffer = pool.try_take().unwrap_or_else(|| pool.alloc());
+        let mut buffer = pool.take();
 
         // Async read from the underlying link
         let action = read(&link, &mut buffer)
@@ -394,11 +511,46 @@ async fn rx_task(
     lease: Duration,
     signal: Signal,
     active: Arc<AtomicBool>,
-    rx_buff_size: usize,
+    rx_pools: Arc<RxBufferPools>,
+    batch_size: u32,
 ) -> ZResult<()> {
     if link.is_streamed() {
-        rx_task_stream(link, transport, lease, signal, active, rx_buff_size).await
+        rx_task_stream(link, transport, lease, signal, active, rx_pools, batch_size).await
     } else {
-        rx_task_dgram(link, transport, lease, signal, active, rx_buff_size).await
+        rx_task_dgram(link, transport, lease, signal, active, rx_pools).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LinkRtt;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn link_rtt_smoothing() {
+        let rtt = LinkRtt::new(Arc::new(AtomicBool::new(false)));
+        assert_eq!(rtt.get(), None);
+
+        // The first sample initializes the estimation
+        rtt.sample(800);
+        assert_eq!(rtt.get(), Some(Duration::from_micros(800)));
+
+        // The following ones are weighted by 1/8
+        rtt.sample(1_600);
+        assert_eq!(rtt.get(), Some(Duration::from_micros(900)));
+        rtt.sample(100);
+        assert_eq!(rtt.get(), Some(Duration::from_micros(800)));
+
+        // A null sample still counts as a measurement
+        let rtt = LinkRtt::new(Arc::new(AtomicBool::new(false)));
+        rtt.sample(0);
+        assert_eq!(rtt.get(), Some(Duration::from_micros(1)));
+
+        // A Pong carrying a time in the future is discarded
+        let rtt = LinkRtt::new(Arc::new(AtomicBool::new(false)));
+        rtt.pong(u64::MAX);
+        assert_eq!(rtt.get(), None);
     }
 }
@@ -209,6 +209,8 @@ pub struct TransportManagerStateUnicast {
     pub(super) incoming: AsyncArc<AsyncMutex<HashMap<LinkUnicast, Option<Vec<u8>>>>>,
     // Established listeners
     pub(super) protocols: Arc<Mutex<HashMap<LocatorProtocol, LinkManagerUnicast>>>,
+    // Link protocols registered by the application
+    pub(super) link_protocols: Arc<Mutex<HashMap<String, LinkProtocolUnicast>>>,
     // Established transports
     pub(super) transports: Arc<Mutex<HashMap<PeerId, Arc<TransportUnicastInner>>>>,
 }
@@ -219,6 +221,7 @@ impl Default for TransportManagerStateUnicast {
             opened: AsyncArc::new(AsyncMutex::new(HashMap::new())),
             incoming: AsyncArc::new(AsyncMutex::new(HashMap::new())),
             protocols: Arc::new(Mutex::new(HashMap::new())),
+            link_protocols: Arc::new(Mutex::new(HashMap::new())),
             transports: Arc::new(Mutex::new(HashMap::new())),
         }
     }
@@ -231,6 +234,92 @@ pub(super) struct Opened {
 }
 
 impl TransportManager {
+    /*************************************/
+    /*           LINK PROTOCOL           */
+    /*************************************/
+    /// Registers a link protocol that is not built in janu. The protocol name
+    /// can not be the one of a built-in or of an already registered protocol.
+    pub fn register_link_protocol_unicast(&self, protocol: LinkProtocolUnicast) -> ZResult<()> {
+        let name = protocol.name().to_string();
+        match format!("{}{}", name, PROTO_SEPARATOR).parse::<LocatorAddress>() {
+            Ok(LocatorAddress::Custom(_)) => {}
+            _ => {
+                let e = format!("Invalid or built-in link protocol name: {}", name);
+                log::trace!("{}", e);
+                return zerror!(ZErrorKind::InvalidLocator { descr: e });
+            }
+        }
+
+        let mut guard = zlock!(self.state.unicast.link_protocols);
+        if guard.contains_key(&name) {
+            let e = format!("Link protocol already registered: {}", name);
+            log::trace!("{}", e);
+            return zerror!(ZErrorKind::InvalidLocator { descr: e });
+        }
+        guard.insert(name, protocol);
+        Ok(())
+    }
+
+    /// Unregisters a link protocol. A protocol can not be unregistered while
+    /// it has listeners. The links already established with the protocol are
+    /// not affected.
+    pub fn unregister_link_protocol_unicast(&self, name: &str) -> ZResult<()> {
+        let mut protocols = zlock!(self.state.unicast.protocols);
+        let mut link_protocols = zlock!(self.state.unicast.link_protocols);
+        if !link_protocols.contains_key(name) {
+            return zerror!(ZErrorKind::InvalidLocator {
+                descr: format!("Link protocol not registered: {}", name)
+            });
+        }
+
+        // Evict the link manager of the protocol, if any
+        let protocol = LocatorProtocol::Custom(name.to_string());
+        if let Some(manager) = protocols.get(&protocol) {
+            let listeners = manager.get_listeners();
+            if !listeners.is_empty() {
+                let e = format!("Link protocol {} has listeners: {:?}", name, listeners);
+                log::trace!("{}", e);
+                return zerror!(ZErrorKind::InvalidLocator { descr: e });
+            }
+        }
+        protocols.remove(&protocol);
+        link_protocols.remove(name);
+        Ok(())
+    }
+
+    pub(crate) fn get_link_protocol_unicast(&self, name: &str) -> ZResult<LinkProtocolUnicast> {
+        match zlock!(self.state.unicast.link_protocols).get(name) {
+            Some(protocol) => Ok(protocol.clone()),
+            None => zerror!(ZErrorKind::InvalidLocator {
+                descr: format!(
+                    "Unknown link protocol: {}. It is neither built in nor registered.",
+                    name
+                )
+            }),
+        }
+    }
+
+    // Check the endpoint address and fill and merge the endpoint configuration
+    fn prepare_endpoint_unicast(&self, endpoint: &mut EndPoint) -> ZResult<()> {
+        let config = match &endpoint.locator.address {
+            LocatorAddress::Custom(locator) => {
+                let protocol = self.get_link_protocol_unicast(locator.protocol())?;
+                protocol.check_address(locator.address())?;
+                protocol.config()
+            }
+            address => self.config.endpoint.get(&address.get_proto()).cloned(),
+        };
+        if let Some(mut config) = config {
+            if let Some(ec) = endpoint.config.as_ref() {
+                for (k, v) in ec.iter() {
+                    config.insert(k.clone(), v.clone());
+                }
+            }
+            endpoint.config = Some(Arc::new(config));
+        }
+        Ok(())
+    }
+
     /*************************************/
     /*            LINK MANAGER           */
     /*************************************/
@@ -271,25 +360,8 @@ impl TransportManager {
     /*              LISTENER             */
     /*************************************/
     pub async fn add_listener_unicast(&self, mut endpoint: EndPoint) -> ZResult<Locator> {
+        self.prepare_endpoint_unicast(&mut endpoint)?;
         let manager = self.new_link_manager_unicast(&endpoint.locator.address.get_proto())?;
-        // Fill and merge the endpoint configuration
-        if let Some(config) = self
-            .config
-            .endpoint
-            .get(&endpoint.locator.address.get_proto())
-        {
-            let config = match endpoint.config.as_ref() {
-                Some(ec) => {
-                    let mut config = config.clone();
-                    for (k, v) in ec.iter() {
-                        config.insert(k.clone(), v.clone());
-                    }
-                    config
-                }
-                None => config.clone(),
-            };
-            endpoint.config = Some(Arc::new(config));
-        };
         manager.new_listener(endpoint).await
     }
 
@@ -414,26 +486,10 @@ impl TransportManager {
             });
         }
 
+        // Check the endpoint and fill its configuration
+        self.prepare_endpoint_unicast(&mut endpoint)?;
         // Automatically create a new link manager for the protocol if it does not exist
         let manager = self.new_link_manager_unicast(&endpoint.locator.address.get_proto())?;
-        // Fill and merge the endpoint configuration
-        if let Some(config) = self
-            .config
-            .endpoint
-            .get(&endpoint.locator.address.get_proto())
-        {
-            let config = match endpoint.config.as_ref() {
-                Some(ec) => {
-                    let mut config = config.clone();
-                    for (k, v) in ec.iter() {
-                        config.insert(k.clone(), v.clone());
-                    }
-                    config
-                }
-                None => config.clone(),
-            };
-            endpoint.config = Some(Arc::new(config));
-        };
 
         // Create a new link associated by calling the Link Manager
         let link = manager.new_link(endpoint).await?;
@@ -469,7 +525,8 @@ impl TransportManager {
         Ok(())
     }
 
-    pub(crate) async fn handle_new_link_unicast(&self, link: LinkUnicast) {
+    /// Handles a new link accepted by the listener of a link manager.
+    pub async fn handle_new_link_unicast(&self, link: LinkUnicast) {
         let mut guard = zasynclock!(self.state.unicast.incoming);
         if guard.len() >= self.config.unicast.open_pending {
             // We reached the limit of concurrent incoming transport, this means two things:
@@ -28,6 +28,7 @@ use crate::net::link::Link;
 pub use manager::*;
 use std::fmt;
 use std::sync::{Arc, Weak};
+use std::time::Duration;
 use transport::TransportUnicastInner;
 use janu_util::core::{ZError, ZErrorKind, ZResult};
 use janu_util::zerror2;
@@ -100,6 +101,24 @@ impl TransportUnicast {
         Ok(transport.is_qos())
     }
 
+    /// Starts measuring the round-trip time of the links of the transport by periodically
+    /// sending Ping messages on them.
+    #[inline(always)]
+    pub fn enable_rtt(&self) -> ZResult<()> {
+        let transport = self.get_transport()?;
+        transport.enable_rtt();
+        Ok(())
+    }
+
+    /// Returns the smoothed round-trip time of the transport, i.e. the lowest one among
+    /// its links. Returns `None` if no round-trip time has been measured yet
+    /// or if it is not measured, see [`enable_rtt`](TransportUnicast::enable_rtt).
+    #[inline(always)]
+    pub fn get_rtt(&self) -> ZResult<Option<Duration>> {
+        let transport = self.get_transport()?;
+        Ok(transport.get_rtt())
+    }
+
     #[inline(always)]
     pub fn get_callback(&self) -> ZResult<Option<Arc<dyn TransportPeerEventHandler>>> {
         let transport = self.get_transport()?;
@@ -184,6 +203,14 @@ impl TransportUnicast {
     }
 }
 
+#[cfg(test)]
+impl TransportUnicast {
+    // A handler of a transport that has been closed
+    pub(crate) fn closed() -> TransportUnicast {
+        TransportUnicast(Weak::new())
+    }
+}
+
 impl From<&Arc<TransportUnicastInner>> for TransportUnicast {
     fn from(s: &Arc<TransportUnicastInner>) -> TransportUnicast {
         TransportUnicast(Arc::downgrade(s))
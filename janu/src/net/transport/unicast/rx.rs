@@ -14,7 +14,8 @@
 use super::common::conduit::TransportChannelRx;
 use super::protocol::core::{PeerId, Priority, Reliability, ZInt};
 use super::protocol::proto::{
-    Close, Frame, FramePayload, KeepAlive, TransportBody, TransportMessage, JanuMessage,
+    Close, Frame, FramePayload, KeepAlive, Ping, Pong, TransportBody, TransportMessage,
+    JanuMessage,
 };
 use super::transport::TransportUnicastInner;
 use crate::net::link::LinkUnicast;
@@ -33,7 +34,7 @@ impl TransportUnicastInner {
         match callback.as_ref() {
             Some(callback) => {
                 #[cfg(feature = "zero-copy")]
-                let _ = msg.map_to_shmbuf(self.manager.shmr.clone())?;
+                let _ = msg.map_to_shmbuf(self.manager.shmr.clone(), false)?;
                 callback.handle_message(msg)
             }
             None => {
@@ -138,6 +139,28 @@ impl TransportUnicastInner {
         }
     }
 
+    fn handle_ping(&self, link: &LinkUnicast, hash: ZInt) -> ZResult<()> {
+        // Reply with a Pong on the same link the Ping has been received from
+        let guard = zread!(self.links);
+        if let Some(pipeline) = guard
+            .iter()
+            .find(|l| l.get_link() == link)
+            .and_then(|l| l.get_pipeline())
+        {
+            let pong = TransportMessage::make_pong(hash, None);
+            pipeline.push_transport_message(pong, Priority::Control);
+        }
+        Ok(())
+    }
+
+    fn handle_pong(&self, link: &LinkUnicast, hash: ZInt) -> ZResult<()> {
+        let guard = zread!(self.links);
+        if let Some(l) = guard.iter().find(|l| l.get_link() == link) {
+            l.get_rtt().pong(hash);
+        }
+        Ok(())
+    }
+
     pub(super) fn receive_message(&self, msg: TransportMessage, link: &LinkUnicast) -> ZResult<()> {
         log::trace!("Received: {:?}", msg);
         // Process the received message
@@ -172,6 +195,8 @@ impl TransportUnicastInner {
                 link_only,
             }) => self.handle_close(link, pid, reason, link_only),
             TransportBody::KeepAlive(KeepAlive { .. }) => Ok(()),
+            TransportBody::Ping(Ping { hash }) => self.handle_ping(link, hash),
+            TransportBody::Pong(Pong { hash }) => self.handle_pong(link, hash),
             _ => {
                 log::debug!(
                     "Transport: {}. Message handling not implemented: {:?}",
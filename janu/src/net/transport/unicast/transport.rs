@@ -18,12 +18,15 @@ use super::common::{
 };
 use super::link::TransportLinkUnicast;
 use super::protocol::core::{ConduitSn, PeerId, Priority, WhatAmI, ZInt};
+#[cfg(feature = "zero-copy")]
+use super::protocol::io::SharedMemoryRelease;
 use super::protocol::proto::{TransportMessage, JanuMessage};
 use crate::net::link::{Link, LinkUnicast};
 use async_std::sync::{Arc as AsyncArc, Mutex as AsyncMutex, MutexGuard as AsyncMutexGuard};
 use std::convert::TryInto;
 #[cfg(feature = "stats")]
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use janu_util::core::{ZError, ZErrorKind, ZResult};
@@ -131,6 +134,8 @@ pub(crate) struct TransportUnicastInner {
     pub(super) alive: AsyncArc<AsyncMutex<bool>>,
     // The transport can do shm
     pub(super) is_shm: bool,
+    // The round-trip time of the links is measured
+    pub(super) rtt_enabled: Arc<AtomicBool>,
     // Transport statistics
     #[cfg(feature = "stats")]
     pub(super) stats: TransportUnicastStatsInner,
@@ -205,6 +210,7 @@ impl TransportUnicastInner {
             callback: Arc::new(RwLock::new(None)),
             alive: AsyncArc::new(AsyncMutex::new(true)),
             is_shm: config.is_shm,
+            rtt_enabled: Arc::new(AtomicBool::new(false)),
             #[cfg(feature = "stats")]
             stats: TransportUnicastStatsInner::default(),
         }
@@ -294,7 +300,7 @@ impl TransportUnicastInner {
         &self,
         link: &LinkUnicast,
         keep_alive: Duration,
-        batch_size: u16,
+        batch_size: u32,
     ) -> ZResult<()> {
         let mut guard = zwrite!(self.links);
         match zlinkgetmut!(guard, link) {
@@ -326,11 +332,16 @@ impl TransportUnicastInner {
         }
     }
 
-    pub(super) fn start_rx(&self, link: &LinkUnicast, lease: Duration) -> ZResult<()> {
+    pub(super) fn start_rx(
+        &self,
+        link: &LinkUnicast,
+        lease: Duration,
+        batch_size: u32,
+    ) -> ZResult<()> {
         let mut guard = zwrite!(self.links);
         match zlinkgetmut!(guard, link) {
             Some(l) => {
-                l.start_rx(lease);
+                l.start_rx(lease, batch_size);
                 Ok(())
             }
             None => {
@@ -421,6 +432,17 @@ impl TransportUnicastInner {
         self.conduit_tx.len() > 1
     }
 
+    pub(crate) fn enable_rtt(&self) {
+        self.rtt_enabled.store(true, Ordering::Release);
+    }
+
+    pub(crate) fn get_rtt(&self) -> Option<Duration> {
+        zread!(self.links)
+            .iter()
+            .filter_map(|l| l.get_rtt().get())
+            .min()
+    }
+
     pub(crate) fn get_callback(&self) -> Option<Arc<dyn TransportPeerEventHandler>> {
         zread!(self.callback).clone()
     }
@@ -487,9 +509,9 @@ impl TransportUnicastInner {
     #[cfg(feature = "zero-copy")]
     pub(crate) fn schedule(&self, mut message: JanuMessage) {
         let res = if self.is_shm {
-            message.map_to_shminfo()
+            message.map_to_shminfo(&mut SharedMemoryRelease::Reader)
         } else {
-            message.map_to_shmbuf(self.manager.shmr.clone())
+            message.map_to_shmbuf(self.manager.shmr.clone(), false)
         };
         if let Err(e) = res {
             log::trace!("Failed SHM conversion: {}", e);
@@ -64,6 +64,12 @@ pub use super::protocol::core::QueryConsolidation;
 /// The kind of congestion control.
 pub use super::protocol::core::CongestionControl;
 
+/// The channel (priority and reliability) on which the data is sent.
+pub use super::protocol::core::Channel;
+
+/// The priority of the data.
+pub use super::protocol::core::Priority;
+
 /// The kind of reliability.
 pub use super::protocol::core::Reliability;
 
@@ -98,7 +104,8 @@ pub use janu_util::sync::ZReady;
 /// ```
 /// # use janu::net::protocol::io::ZBuf;
 /// # use janu::net::protocol::proto::DataInfo;
-/// # let sample = janu::net::Sample { res_name: "".to_string(), payload: ZBuf::new(), data_info: None };
+/// # use janu::net::{Channel, CongestionControl};
+/// # let sample = janu::net::Sample { res_name: "".to_string(), payload: ZBuf::new(), data_info: None, channel: Channel::default(), congestion_control: CongestionControl::default() };
 /// if let Some(info) = sample.data_info {
 ///     match info.timestamp {
 ///         Some(ts) => println!("Sample's timestamp: {}", ts),
@@ -130,6 +137,12 @@ pub struct Sample {
     pub res_name: String,
     pub payload: ZBuf,
     pub data_info: Option<DataInfo>,
+    /// The priority and reliability with which the value was sent.
+    /// It is ignored when replying to a query.
+    pub channel: Channel,
+    /// The congestion control with which the value was sent.
+    /// It is ignored when replying to a query.
+    pub congestion_control: CongestionControl,
 }
 
 impl Sample {
@@ -17,6 +17,8 @@ use crate::workspace::ChangeKind;
 use crate::Properties;
 use janu_util::core::{ZError, ZErrorKind, ZResult};
 use janu_util::{zerror, zerror2};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
 /// A user value that is associated with a [Path](super::Path) in janu.
 #[derive(Clone, Debug)]
@@ -42,6 +44,24 @@ pub enum Value {
     /// An Float value.  
     /// Note: this is equivalent to `Raw(APP_FLOAT, buf)` where buf contains the float encoded as a String
     Float(f64),
+    /// A CBOR value, kept in its encoded form until deserialized with [`Value::deserialize`].  
+    /// Note: this is equivalent to `Raw(APP_CBOR, buf)` where buf contains the CBOR encoded data
+    Cbor(ZBuf),
+    /// A MessagePack value, kept in its encoded form until deserialized with [`Value::deserialize`].  
+    /// Note: this is equivalent to `Raw(APP_MSGPACK, buf)` where buf contains the MessagePack encoded data
+    MsgPack(ZBuf),
+    /// A 64-bit signed integer value in native binary form.  
+    /// Note: this is equivalent to `Raw(APP_INT64, buf)` where buf contains the integer as 8 little-endian bytes
+    Int64(i64),
+    /// A 64-bit float value in native binary form.  
+    /// Note: this is equivalent to `Raw(APP_FLOAT64, buf)` where buf contains the float as 8 little-endian bytes
+    Float64(f64),
+    /// An array of 64-bit signed integers in native binary form.  
+    /// Note: this is equivalent to `Raw(APP_INT64_ARRAY, buf)` where buf contains the integers as contiguous 8 little-endian bytes
+    Int64Array(Vec<i64>),
+    /// An array of 64-bit floats in native binary form.  
+    /// Note: this is equivalent to `Raw(APP_FLOAT64_ARRAY, buf)` where buf contains the floats as contiguous 8 little-endian bytes
+    Float64Array(Vec<f64>),
 }
 
 impl Value {
@@ -59,6 +79,12 @@ impl Value {
             Json(_) => APP_JSON,
             Integer(_) => APP_INTEGER,
             Float(_) => APP_FLOAT,
+            Cbor(_) => APP_CBOR,
+            MsgPack(_) => APP_MSGPACK,
+            Int64(_) => APP_INT64,
+            Float64(_) => APP_FLOAT64,
+            Int64Array(_) => APP_INT64_ARRAY,
+            Float64Array(_) => APP_FLOAT64_ARRAY,
         }
     }
 
@@ -93,6 +119,18 @@ impl Value {
             Json(s) => (APP_JSON, ZBuf::from(s.as_bytes())),
             Integer(i) => (APP_INTEGER, ZBuf::from(i.to_string().as_bytes())),
             Float(f) => (APP_FLOAT, ZBuf::from(f.to_string().as_bytes())),
+            Cbor(buf) => (APP_CBOR, buf),
+            MsgPack(buf) => (APP_MSGPACK, buf),
+            Int64(i) => (APP_INT64, ZBuf::from(&i.to_le_bytes()[..])),
+            Float64(f) => (APP_FLOAT64, ZBuf::from(&f.to_le_bytes()[..])),
+            Int64Array(v) => (
+                APP_INT64_ARRAY,
+                ZBuf::from(v.iter().flat_map(|i| i.to_le_bytes()).collect::<Vec<u8>>()),
+            ),
+            Float64Array(v) => (
+                APP_FLOAT64_ARRAY,
+                ZBuf::from(v.iter().flat_map(|f| f.to_le_bytes()).collect::<Vec<u8>>()),
+            ),
         }
     }
 
@@ -187,6 +225,18 @@ impl Value {
                     })
                 })
                 .map(Float),
+            APP_CBOR => Ok(Cbor(payload)),
+            APP_MSGPACK => Ok(MsgPack(payload)),
+            APP_INT64 => read_le_words(encoding, &payload)
+                .and_then(|v| single_word(encoding, v))
+                .map(|w| Int64(i64::from_le_bytes(w))),
+            APP_FLOAT64 => read_le_words(encoding, &payload)
+                .and_then(|v| single_word(encoding, v))
+                .map(|w| Float64(f64::from_le_bytes(w))),
+            APP_INT64_ARRAY => read_le_words(encoding, &payload)
+                .map(|v| Int64Array(v.into_iter().map(i64::from_le_bytes).collect())),
+            APP_FLOAT64_ARRAY => read_le_words(encoding, &payload)
+                .map(|v| Float64Array(v.into_iter().map(f64::from_le_bytes).collect())),
             _ => Ok(Raw(encoding, payload)),
         }
     }
@@ -226,6 +276,19 @@ impl Value {
             Json(s) => (APP_JSON, false, s),
             Integer(i) => (APP_INTEGER, false, i.to_string()),
             Float(f) => (APP_FLOAT, false, f.to_string()),
+            Cbor(buf) => (APP_CBOR, true, base64::encode(buf.to_vec())),
+            MsgPack(buf) => (APP_MSGPACK, true, base64::encode(buf.to_vec())),
+            Int64(i) => (APP_INT64, false, i.to_string()),
+            Float64(f) => (APP_FLOAT64, false, f.to_string()),
+            Int64Array(v) => (APP_INT64_ARRAY, false, serde_json::json!(v).to_string()),
+            Float64Array(v) => (
+                APP_FLOAT64_ARRAY,
+                false,
+                v.into_iter()
+                    .map(float64_to_json)
+                    .collect::<serde_json::Value>()
+                    .to_string(),
+            ),
         }
     }
 
@@ -280,24 +343,109 @@ impl Value {
                     e
                 )
             }),
-            _ => {
-                if base64 {
-                    match base64::decode(s) {
-                        Ok(bytes) => Ok(Raw(encoding, bytes.into())),
-                        Err(e) => zerror!(ZErrorKind::ValueDecodingFailed {
-                            descr: format!(
-                                "Failed to decode base64 Value with encoding {} : {}",
-                                encoding, e
-                            )
-                        }),
-                    }
-                } else {
-                    Ok(Raw(encoding, s.as_bytes().into()))
-                }
-            }
+            APP_CBOR => bytes_from_string(encoding, base64, s).map(Cbor),
+            APP_MSGPACK => bytes_from_string(encoding, base64, s).map(MsgPack),
+            APP_INT64 => s.parse::<i64>().map(Int64).map_err(|e| {
+                zerror2!(
+                    ZErrorKind::ValueDecodingFailed {
+                        descr: "Failed to decode an Int64 Value".to_string()
+                    },
+                    e
+                )
+            }),
+            APP_FLOAT64 => s.parse::<f64>().map(Float64).map_err(|e| {
+                zerror2!(
+                    ZErrorKind::ValueDecodingFailed {
+                        descr: "Failed to decode a Float64 Value".to_string()
+                    },
+                    e
+                )
+            }),
+            APP_INT64_ARRAY => serde_json::from_str::<Vec<i64>>(&s)
+                .map(Int64Array)
+                .map_err(|e| {
+                    zerror2!(
+                        ZErrorKind::ValueDecodingFailed {
+                            descr: "Failed to decode an Int64Array Value".to_string()
+                        },
+                        e
+                    )
+                }),
+            APP_FLOAT64_ARRAY => match serde_json::from_str::<Vec<serde_json::Value>>(&s) {
+                Ok(v) => v
+                    .iter()
+                    .map(float64_from_json)
+                    .collect::<ZResult<Vec<f64>>>()
+                    .map(Float64Array),
+                Err(e) => zerror!(
+                    ZErrorKind::ValueDecodingFailed {
+                        descr: "Failed to decode a Float64Array Value".to_string()
+                    },
+                    e
+                ),
+            },
+            _ => bytes_from_string(encoding, base64, s).map(|buf| Raw(encoding, buf)),
         }
     }
 
+    /// Serializes any serde-serializable data into a Value of the given encoding.
+    /// Supported encodings are [`APP_JSON`] (resulting in a [`Value::Json`]),
+    /// [`APP_CBOR`] with the `cbor` feature (resulting in a [`Value::Cbor`]) and
+    /// [`APP_MSGPACK`] with the `msgpack` feature (resulting in a [`Value::MsgPack`]
+    /// where structs are encoded as maps, i.e. with their field names).
+    pub fn serialize<T: Serialize>(encoding: ZInt, data: &T) -> ZResult<Value> {
+        let res = match encoding {
+            #[cfg(feature = "cbor")]
+            APP_CBOR => {
+                let mut bytes = vec![];
+                ciborium::ser::into_writer(data, &mut bytes)
+                    .map(|_| Value::Cbor(bytes.into()))
+                    .map_err(|e| e.to_string())
+            }
+            #[cfg(feature = "msgpack")]
+            APP_MSGPACK => rmp_serde::to_vec_named(data)
+                .map(|bytes| Value::MsgPack(bytes.into()))
+                .map_err(|e| e.to_string()),
+            APP_JSON => serde_json::to_string(data)
+                .map(Value::Json)
+                .map_err(|e| e.to_string()),
+            _ => Err(format!("unsupported encoding {}", to_string(encoding))),
+        };
+        res.map_err(|e| {
+            zerror2!(ZErrorKind::ValueEncodingFailed {
+                descr: format!("Failed to serialize Value: {}", e)
+            })
+        })
+    }
+
+    /// Deserializes the Value into any serde-deserializable type.
+    /// This is supported for JSON Values, and for CBOR and MessagePack Values
+    /// with the `cbor` and `msgpack` features, including [`Value::Raw`] with
+    /// one of those encodings.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> ZResult<T> {
+        use Value::*;
+        let res = match self {
+            #[cfg(feature = "cbor")]
+            Cbor(buf) | Raw(APP_CBOR, buf) => {
+                ciborium::de::from_reader(&buf.contiguous()[..]).map_err(|e| e.to_string())
+            }
+            #[cfg(feature = "msgpack")]
+            MsgPack(buf) | Raw(APP_MSGPACK, buf) => {
+                rmp_serde::from_read_ref(&buf.contiguous()).map_err(|e| e.to_string())
+            }
+            Json(s) => serde_json::from_str(s).map_err(|e| e.to_string()),
+            Raw(APP_JSON, buf) | Raw(TEXT_JSON, buf) => {
+                serde_json::from_slice(&buf.contiguous()).map_err(|e| e.to_string())
+            }
+            _ => Err(format!("unsupported encoding {}", self.encoding_descr())),
+        };
+        res.map_err(|e| {
+            zerror2!(ZErrorKind::ValueDecodingFailed {
+                descr: format!("Failed to deserialize Value: {}", e)
+            })
+        })
+    }
+
     /// Convert the payload from a [`Sample`] into a [`Value`].
     /// If the Sample's kind is DELETE, `Ok(None)` is returned.
     /// Otherwise, if decode_value is `true` the payload is decoded as a typed [`Value`].
@@ -314,13 +462,93 @@ impl Value {
         if kind == ChangeKind::Delete {
             Ok(None)
         } else if decode_value {
-            Ok(Some(Value::decode(encoding, sample.payload.clone())?))
+            Ok(Some(Value::decode(
+                encoding,
+                sample.payload.clone(),
+            )?))
         } else {
             Ok(Some(Value::Raw(encoding, sample.payload.clone())))
         }
     }
 }
 
+fn read_le_words(encoding: ZInt, payload: &ZBuf) -> ZResult<Vec<[u8; 8]>> {
+    let bytes = payload.contiguous();
+    let chunks = bytes.chunks_exact(8);
+    if !chunks.remainder().is_empty() {
+        return zerror!(ZErrorKind::ValueDecodingFailed {
+            descr: format!(
+                "Failed to decode Value with encoding {}: length {} is not a multiple of 8",
+                encoding,
+                bytes.len()
+            )
+        });
+    }
+    Ok(chunks
+        .map(|c| {
+            let mut w = [0u8; 8];
+            w.copy_from_slice(c);
+            w
+        })
+        .collect())
+}
+
+fn single_word(encoding: ZInt, words: Vec<[u8; 8]>) -> ZResult<[u8; 8]> {
+    match words.as_slice() {
+        [w] => Ok(*w),
+        _ => zerror!(ZErrorKind::ValueDecodingFailed {
+            descr: format!(
+                "Failed to decode Value with encoding {}: expected 8 bytes, got {}",
+                encoding,
+                words.len() * 8
+            )
+        }),
+    }
+}
+
+// JSON has no representation for non-finite numbers: they are rendered
+// as the strings "NaN", "inf" and "-inf" (as produced by f64::to_string).
+fn float64_to_json(f: f64) -> serde_json::Value {
+    if f.is_finite() {
+        serde_json::json!(f)
+    } else {
+        serde_json::json!(f.to_string())
+    }
+}
+
+fn float64_from_json(js: &serde_json::Value) -> ZResult<f64> {
+    let f = match js {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.parse::<f64>().ok().filter(|f| !f.is_finite()),
+        _ => None,
+    };
+    match f {
+        Some(f) => Ok(f),
+        None => zerror!(ZErrorKind::ValueDecodingFailed {
+            descr: format!(
+                "Failed to decode a Float64Array Value: invalid element {}",
+                js
+            )
+        }),
+    }
+}
+
+fn bytes_from_string(encoding: ZInt, base64: bool, s: String) -> ZResult<ZBuf> {
+    if base64 {
+        match base64::decode(s) {
+            Ok(bytes) => Ok(bytes.into()),
+            Err(e) => zerror!(ZErrorKind::ValueDecodingFailed {
+                descr: format!(
+                    "Failed to decode base64 Value with encoding {} : {}",
+                    encoding, e
+                )
+            }),
+        }
+    } else {
+        Ok(s.as_bytes().into())
+    }
+}
+
 impl From<ZBuf> for Value {
     fn from(buf: ZBuf) -> Self {
         Value::Raw(APP_OCTET_STREAM, buf)
@@ -380,3 +608,27 @@ impl From<f64> for Value {
         Value::Float(f)
     }
 }
+
+impl From<Vec<i64>> for Value {
+    fn from(v: Vec<i64>) -> Self {
+        Value::Int64Array(v)
+    }
+}
+
+impl From<&[i64]> for Value {
+    fn from(v: &[i64]) -> Self {
+        Value::Int64Array(v.to_vec())
+    }
+}
+
+impl From<Vec<f64>> for Value {
+    fn from(v: Vec<f64>) -> Self {
+        Value::Float64Array(v)
+    }
+}
+
+impl From<&[f64]> for Value {
+    fn from(v: &[f64]) -> Self {
+        Value::Float64Array(v.to_vec())
+    }
+}
@@ -13,7 +13,7 @@
 //
 use crate::net::queryable::EVAL;
 use crate::net::{
-    data_kind, encoding, CallbackSubscriber, CongestionControl, DataInfo, Query,
+    data_kind, encoding, CallbackSubscriber, Channel, CongestionControl, DataInfo, Query,
     QueryConsolidation, QueryTarget, Queryable, Receiver, RecvError, RecvTimeoutError, Reliability,
     RepliesSender, Reply, ReplyReceiver, ResKey, Sample, SampleReceiver, Session, SubInfo, SubMode,
     Subscriber, TryRecvError, ZBuf, ZFuture, ZInt,
@@ -570,6 +570,8 @@ impl Change {
             res_name: self.path.to_string(),
             payload,
             data_info: Some(info),
+            channel: Channel::default(),
+            congestion_control: CongestionControl::default(),
         }
     }
 }
@@ -608,6 +610,8 @@ fn path_value_to_sample(path: Path, value: Value) -> Sample {
         res_name: path.to_string(),
         payload,
         data_info: Some(info),
+        channel: Channel::default(),
+        congestion_control: CongestionControl::default(),
     }
 }
 
@@ -0,0 +1,185 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+#[cfg(feature = "transport_tcp")]
+mod tests {
+    use async_std::prelude::*;
+    use async_std::task;
+    use clap::App;
+    use serde_json::Value;
+    use std::time::Duration;
+    use janu::net::info::ZN_INFO_PID_KEY;
+    use janu::net::plugins::PluginsManager;
+    use janu::net::queryable::EVAL;
+    use janu::net::runtime::{AdminSpace, Runtime};
+    use janu::net::*;
+    use janu_util::properties::config::*;
+    use janu_util::LibLoader;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+    const SLEEP: Duration = Duration::from_millis(100);
+
+    const LOCATOR: &str = "tcp/127.0.0.1:17510";
+    const SUB_KEY: &str = "/test/admin/sub";
+    const QABL_KEY: &str = "/test/admin/qabl";
+
+    fn config(mode: &str) -> ConfigProperties {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, mode.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        if mode == "router" {
+            config.insert(ZN_LISTENER_KEY, LOCATOR.to_string());
+        } else {
+            config.insert(ZN_PEER_KEY, LOCATOR.to_string());
+        }
+        config
+    }
+
+    async fn router() -> Runtime {
+        let runtime = Runtime::new(0, config("router"), None).await.unwrap();
+        let (plugins, _) = PluginsManager::builder()
+            .into_dynamic(LibLoader::new::<&str>(&[], false))
+            .get_requirements();
+        let args = App::new("adminspace").get_matches_from(vec!["adminspace"]);
+        let (handles, _) = plugins.start(&(runtime.clone(), args));
+        AdminSpace::start(&runtime, handles, String::new()).await;
+        runtime
+    }
+
+    async fn get(session: &Session, path: &str) -> Value {
+        let mut replies = session
+            .query(
+                &path.into(),
+                "",
+                QueryTarget::default(),
+                QueryConsolidation::default(),
+            )
+            .await
+            .unwrap();
+        let reply = replies.next().timeout(TIMEOUT).await.unwrap().unwrap();
+        assert_eq!(reply.data.res_name, path);
+        serde_json::from_slice(&reply.data.payload.to_vec()).unwrap()
+    }
+
+    // Returns the entry of `resources` for `key` once it is reported by the admin space
+    async fn resource(session: &Session, path: &str, key: &str) -> Value {
+        let check = async {
+            loop {
+                let json = get(session, path).await;
+                if let Some(res) = json["resources"]
+                    .as_array()
+                    .and_then(|resources| resources.iter().find(|res| res["key"] == key))
+                {
+                    return res.clone();
+                }
+                task::sleep(SLEEP).await;
+            }
+        };
+        check.timeout(TIMEOUT).await.unwrap()
+    }
+
+    #[test]
+    fn adminspace_routing() {
+        task::block_on(async {
+            let router = router().await;
+            let session = open(config("client")).await.unwrap();
+            let pid = session.info().await.get(&ZN_INFO_PID_KEY).unwrap().clone();
+            let root = format!("/@/router/{}/routing", router.get_pid_str());
+
+            let sub_info = SubInfo {
+                reliability: Reliability::Reliable,
+                mode: SubMode::Push,
+                period: None,
+            };
+            let sub = session
+                .declare_subscriber(&SUB_KEY.into(), &sub_info)
+                .await
+                .unwrap();
+            let qabl = session
+                .declare_queryable(&QABL_KEY.into(), EVAL)
+                .await
+                .unwrap();
+
+            // routing/subscribers
+            let res = resource(&session, &format!("{}/subscribers", root), SUB_KEY).await;
+            assert_eq!(
+                res["router_subs"],
+                serde_json::json!([router.get_pid_str()])
+            );
+            assert_eq!(res["peer_subs"], serde_json::json!([router.get_pid_str()]));
+            let client_subs = res["client_subs"].as_array().unwrap();
+            assert_eq!(client_subs.len(), 1);
+            assert!(client_subs[0]["face"].is_u64());
+            assert_eq!(client_subs[0]["mode"], "Push");
+            assert_eq!(client_subs[0]["reliability"], "Reliable");
+            assert!(client_subs[0]["period"].is_null());
+            let face_id = client_subs[0]["face"].clone();
+            let json = get(&session, &format!("{}/subscribers", root)).await;
+            assert!(json["router_subs"]
+                .as_array()
+                .unwrap()
+                .contains(&SUB_KEY.into()));
+            assert!(json["peer_subs"].is_array());
+
+            // routing/queryables
+            let res = resource(&session, &format!("{}/queryables", root), QABL_KEY).await;
+            assert_eq!(
+                res["router_qabls"],
+                serde_json::json!([{"pid": router.get_pid_str(), "kind": EVAL}])
+            );
+            assert_eq!(res["peer_qabls"], res["router_qabls"]);
+            assert_eq!(
+                res["client_qabls"],
+                serde_json::json!([{"face": face_id, "kind": EVAL}])
+            );
+            let json = get(&session, &format!("{}/queryables", root)).await;
+            assert!(json["router_qabls"]
+                .as_array()
+                .unwrap()
+                .contains(&QABL_KEY.into()));
+            assert!(json["peer_qabls"].is_array());
+
+            // routing/faces
+            let json = get(&session, &format!("{}/faces", root)).await;
+            let face = json
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|face| face["id"] == face_id)
+                .unwrap();
+            assert_eq!(face["pid"], pid.as_str());
+            assert_eq!(face["whatami"], "Client");
+            assert!(face["link_id"].is_u64());
+            assert_eq!(
+                face["subscribers"],
+                serde_json::json!([{
+                    "key": SUB_KEY,
+                    "mode": "Push",
+                    "reliability": "Reliable",
+                    "period": null,
+                }])
+            );
+            assert_eq!(
+                face["queryables"],
+                serde_json::json!([{"key": QABL_KEY, "kind": EVAL}])
+            );
+            assert!(face["local_subscribers"].is_array());
+            assert!(face["local_queryables"].is_array());
+
+            sub.undeclare().await.unwrap();
+            qabl.undeclare().await.unwrap();
+            session.close().await.unwrap();
+            router.close().await.unwrap();
+        });
+    }
+}
@@ -0,0 +1,247 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use janu::net::link::Locator;
+use janu::net::protocol::core::whatami;
+use janu::net::runtime::advertisement::{AddressRange, LocatorFamily, LocatorsAdvertisement};
+use janu::net::runtime::Runtime;
+use janu_util::properties::config::*;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(100);
+const SETTLE: Duration = Duration::from_secs(3);
+
+fn locators(s: &[&str]) -> Vec<Locator> {
+    s.iter().map(|l| l.parse().unwrap()).collect()
+}
+
+fn advertisement(key: u64, value: &str) -> LocatorsAdvertisement {
+    let mut config = ConfigProperties::default();
+    config.insert(key, value.to_string());
+    LocatorsAdvertisement::from_config(&config)
+}
+
+#[test]
+fn address_range() {
+    let range = AddressRange::parse("10.0.0.0/8").unwrap();
+    assert!(range.contains(&"10.1.2.3".parse().unwrap()));
+    assert!(!range.contains(&"11.1.2.3".parse().unwrap()));
+    assert!(!range.contains(&"::1".parse().unwrap()));
+
+    let range = AddressRange::parse("192.168.1.128/25").unwrap();
+    assert!(range.contains(&"192.168.1.200".parse().unwrap()));
+    assert!(!range.contains(&"192.168.1.100".parse().unwrap()));
+
+    let range = AddressRange::parse("fe80::/10").unwrap();
+    assert!(range.contains(&"fe80::1".parse().unwrap()));
+    assert!(!range.contains(&"2001:db8::1".parse().unwrap()));
+
+    let range = AddressRange::parse("127.0.0.1").unwrap();
+    assert!(range.contains(&"127.0.0.1".parse().unwrap()));
+    assert!(!range.contains(&"127.0.0.2".parse().unwrap()));
+
+    assert!(AddressRange::parse("10.0.0.0/33").is_none());
+    assert!(AddressRange::parse("eth0").is_none());
+}
+
+#[test]
+fn locator_family() {
+    let family = |l: &str| LocatorFamily::of(&l.parse().unwrap());
+    assert_eq!(family("tcp/127.0.0.1:7447"), Some(LocatorFamily::Ipv4));
+    assert_eq!(family("udp/[::1]:7447"), Some(LocatorFamily::Ipv6));
+    assert_eq!(family("tcp/localhost:7447"), None);
+}
+
+#[test]
+fn advertised_locators() {
+    let listeners = locators(&[
+        "tcp/192.168.1.10:7447",
+        "tcp/10.0.0.2:7447",
+        "udp/192.168.1.10:7447",
+    ]);
+
+    // Nothing configured
+    let adv = LocatorsAdvertisement::default();
+    assert_eq!(adv.locators(&listeners, whatami::PEER), listeners);
+
+    // External locators replace the locators of the listener on all interfaces
+    let adv = advertisement(
+        ZN_ADVERTISED_LOCATORS_KEY,
+        "tcp/0.0.0.0:7447=tcp/203.0.113.1:7447, invalid",
+    );
+    assert_eq!(
+        adv.locators(&listeners, whatami::PEER),
+        locators(&["tcp/203.0.113.1:7447", "udp/192.168.1.10:7447"])
+    );
+
+    // External locators replace the locators of the listener on one interface
+    let adv = advertisement(
+        ZN_ADVERTISED_LOCATORS_KEY,
+        "tcp/10.0.0.2:7447=tcp/203.0.113.1:7447",
+    );
+    assert_eq!(
+        adv.locators(&listeners, whatami::PEER),
+        locators(&[
+            "tcp/192.168.1.10:7447",
+            "tcp/203.0.113.1:7447",
+            "udp/192.168.1.10:7447"
+        ])
+    );
+
+    // Excluded address ranges
+    let adv = advertisement(
+        ZN_ADVERTISED_LOCATORS_EXCLUDE_KEY,
+        "10.0.0.0/8,192.168.1.10",
+    );
+    assert!(adv.locators(&listeners, whatami::PEER).is_empty());
+    let adv = advertisement(ZN_ADVERTISED_LOCATORS_EXCLUDE_KEY, "10.0.0.0/8");
+    assert_eq!(
+        adv.locators(&listeners, whatami::PEER),
+        locators(&["tcp/192.168.1.10:7447", "udp/192.168.1.10:7447"])
+    );
+
+    // Families advertised per kind of node
+    let listeners = locators(&["tcp/192.168.1.10:7447", "tcp/[fd00::10]:7447"]);
+    let adv = advertisement(ZN_ADVERTISED_LOCATORS_FAMILIES_KEY, "ipv6=router|peer");
+    assert_eq!(adv.locators(&listeners, whatami::ROUTER), listeners);
+    assert_eq!(
+        adv.locators(&listeners, whatami::CLIENT),
+        locators(&["tcp/192.168.1.10:7447"])
+    );
+    // Scouting replies only contain the families advertised to all kinds of nodes
+    let all = whatami::ROUTER | whatami::PEER | whatami::CLIENT;
+    assert_eq!(
+        adv.locators(&listeners, all),
+        locators(&["tcp/192.168.1.10:7447"])
+    );
+    let adv = advertisement(ZN_ADVERTISED_LOCATORS_FAMILIES_KEY, "ipv4=,ipv6=client");
+    assert_eq!(
+        adv.locators(&listeners, whatami::CLIENT),
+        locators(&["tcp/[fd00::10]:7447"])
+    );
+
+    // The locators of other nodes are only filtered by family
+    let adv = advertisement(ZN_ADVERTISED_LOCATORS_EXCLUDE_KEY, "192.168.0.0/16");
+    assert_eq!(adv.filter(&listeners, whatami::PEER), listeners);
+    let adv = advertisement(ZN_ADVERTISED_LOCATORS_FAMILIES_KEY, "ipv6=router|peer");
+    assert_eq!(adv.filter(&listeners, whatami::PEER), listeners);
+    assert_eq!(
+        adv.filter(&listeners, whatami::CLIENT),
+        locators(&["tcp/192.168.1.10:7447"])
+    );
+}
+
+async fn peer_with(listener: &str, peer: Option<&str>, key: u64, value: Option<&str>) -> Runtime {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "peer".to_string());
+    config.insert(ZN_LISTENER_KEY, listener.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    if let Some(peer) = peer {
+        config.insert(ZN_PEER_KEY, peer.to_string());
+    }
+    if let Some(value) = value {
+        config.insert(key, value.to_string());
+    }
+    Runtime::new(0, config, None).await.unwrap()
+}
+
+async fn peer(listener: &str, peer: Option<&str>, exclude: Option<&str>) -> Runtime {
+    peer_with(listener, peer, ZN_ADVERTISED_LOCATORS_EXCLUDE_KEY, exclude).await
+}
+
+fn connected(r1: &Runtime, r2: &Runtime) -> bool {
+    r1.manager().get_transport(&r2.pid).is_some()
+}
+
+#[test]
+fn link_state_excluded_locators() {
+    task::block_on(async {
+        let a = peer("tcp/127.0.0.1:17480", None, None).await;
+        let b = peer(
+            "tcp/127.0.0.1:17481",
+            Some("tcp/127.0.0.1:17480"),
+            Some("127.0.0.0/8"),
+        )
+        .await;
+        let c = peer(
+            "tcp/127.0.0.1:17482",
+            Some("tcp/127.0.0.1:17480"),
+            Some("127.0.0.0/8"),
+        )
+        .await;
+
+        let check = async {
+            while !connected(&a, &b) || !connected(&a, &c) {
+                task::sleep(SLEEP).await;
+            }
+        };
+        check.timeout(TIMEOUT).await.unwrap();
+
+        // B and C don't advertise their locators in link-state messages
+        task::sleep(SETTLE).await;
+        assert!(!connected(&b, &c));
+        assert!(!connected(&c, &b));
+
+        for r in [a, b, c].iter() {
+            r.close().await.unwrap();
+        }
+    });
+}
+
+#[test]
+fn link_state_forwarded_locators() {
+    task::block_on(async {
+        // A only forwards the IPv4 locators of the other nodes to routers
+        let a = peer_with(
+            "tcp/127.0.0.1:17483",
+            None,
+            ZN_ADVERTISED_LOCATORS_FAMILIES_KEY,
+            Some("ipv4=router"),
+        )
+        .await;
+        let b = peer("tcp/127.0.0.1:17484", Some("tcp/127.0.0.1:17483"), None).await;
+        let c = peer(
+            "tcp/127.0.0.1:17485,tcp/[::1]:17485",
+            Some("tcp/127.0.0.1:17483"),
+            None,
+        )
+        .await;
+
+        // B and C only learn the IPv6 locator of C from A and connect with it
+        let check = async {
+            while !connected(&b, &c) || !connected(&c, &b) {
+                task::sleep(SLEEP).await;
+            }
+        };
+        check.timeout(TIMEOUT).await.unwrap();
+        for (r1, r2) in [(&b, &c), (&c, &b)] {
+            let links = r1
+                .manager()
+                .get_transport(&r2.pid)
+                .unwrap()
+                .get_links()
+                .unwrap();
+            assert!(!links.is_empty());
+            for link in links.iter() {
+                assert_eq!(LocatorFamily::of(&link.dst), Some(LocatorFamily::Ipv6));
+            }
+        }
+
+        for r in [a, b, c].iter() {
+            r.close().await.unwrap();
+        }
+    });
+}
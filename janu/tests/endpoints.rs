@@ -415,6 +415,81 @@ AXVFFIgCSluyrolaD6CWD9MqOex4YOfJR2bNxI7lFvuK4AwjyUJzT1U1HXib17mM
     task::block_on(run(&endpoints));
 }
 
+#[cfg(feature = "transport_ws")]
+#[test]
+fn endpoint_ws() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    // Define the locators
+    let endpoints: Vec<EndPoint> = vec![
+        "ws/127.0.0.1:9454".parse().unwrap(),
+        "ws/[::1]:9454".parse().unwrap(),
+        "ws/localhost:9455".parse().unwrap(),
+    ];
+    task::block_on(run(&endpoints));
+}
+
+#[cfg(feature = "transport_ws")]
+#[test]
+fn endpoint_wss_root_ca() {
+    use janu::net::link::ws::config::*;
+
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    // Generate a self-signed certificate for localhost, also used as root CA by the client
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let endpoint = |root_ca: String| {
+        let mut endpoint: EndPoint = "wss/localhost:9458".parse().unwrap();
+        let mut config = Properties::default();
+        config.insert(WSS_ROOT_CA_CERTIFICATE_RAW.to_string(), root_ca);
+        config.insert(
+            WSS_SERVER_PRIVATE_KEY_RAW.to_string(),
+            cert.serialize_private_key_pem(),
+        );
+        config.insert(
+            WSS_SERVER_CERTIFICATE_RAW.to_string(),
+            cert.serialize_pem().unwrap(),
+        );
+        endpoint.config = Some(Arc::new(config));
+        endpoint
+    };
+
+    let config = TransportManagerConfig::builder()
+        .whatami(whatami::PEER)
+        .pid(PeerId::new(1, [0u8; PeerId::MAX_SIZE]))
+        .build(Arc::new(SH));
+    let router = TransportManager::new(config);
+    let config = TransportManagerConfig::builder()
+        .whatami(whatami::PEER)
+        .pid(PeerId::new(1, [1u8; PeerId::MAX_SIZE]))
+        .build(Arc::new(SH));
+    let client = TransportManager::new(config);
+
+    task::block_on(async {
+        let valid = endpoint(cert.serialize_pem().unwrap());
+        router.add_listener(valid.clone()).await.unwrap();
+
+        // A root CA without any valid certificate is refused
+        let invalid = "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n";
+        let res = client.open_transport(endpoint(invalid.to_string())).await;
+        println!("Res: {:?}", res);
+        assert!(res.is_err());
+        let res = client
+            .open_transport(endpoint("no certificate".to_string()))
+            .await;
+        println!("Res: {:?}", res);
+        assert!(res.is_err());
+
+        let transport = client.open_transport(valid.clone()).await.unwrap();
+        transport.close().await.unwrap();
+        router.del_listener(&valid).await.unwrap();
+    });
+}
+
 #[cfg(feature = "transport_quic")]
 #[test]
 fn endpoint_quic() {
@@ -488,3 +563,178 @@ AXVFFIgCSluyrolaD6CWD9MqOex4YOfJR2bNxI7lFvuK4AwjyUJzT1U1HXib17mM
     let endpoints = vec![endpoint];
     task::block_on(run(&endpoints));
 }
+
+#[cfg(feature = "transport_mem")]
+#[test]
+fn endpoint_mem() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    // Define the locators
+    let endpoints: Vec<EndPoint> = vec![
+        "mem/janu-test-endpoint-0".parse().unwrap(),
+        "mem/janu-test-endpoint-1#mtu=1024;latency=1"
+            .parse()
+            .unwrap(),
+    ];
+    task::block_on(run(&endpoints));
+}
+
+#[cfg(all(feature = "transport_unixsock-dgram", target_os = "linux"))]
+#[test]
+fn endpoint_unix_dgram() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    // Remove the file if it still exists
+    let _ = std::fs::remove_file("janu-test-unix-dgram-0.sock");
+    // Define the locators
+    let endpoints: Vec<EndPoint> = vec![
+        "unixsock-dgram/janu-test-unix-dgram-0.sock"
+            .parse()
+            .unwrap(),
+        "unixsock-dgram/@janu-test-unix-dgram-1".parse().unwrap(),
+    ];
+    task::block_on(run(&endpoints));
+    let _ = std::fs::remove_file("janu-test-unix-dgram-0.sock");
+    let _ = std::fs::remove_file("janu-test-unix-dgram-0.sock.lock");
+}
+
+#[cfg(all(feature = "transport_unixsock-seqpacket", target_os = "linux"))]
+#[test]
+fn endpoint_unix_seqpacket() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    // Remove the file if it still exists
+    let _ = std::fs::remove_file("janu-test-unix-seqpacket-0.sock");
+    // Define the locators
+    let endpoints: Vec<EndPoint> = vec![
+        "unixsock-seqpacket/janu-test-unix-seqpacket-0.sock"
+            .parse()
+            .unwrap(),
+        "unixsock-seqpacket/@janu-test-unix-seqpacket-1"
+            .parse()
+            .unwrap(),
+    ];
+    task::block_on(run(&endpoints));
+    let _ = std::fs::remove_file("janu-test-unix-seqpacket-0.sock");
+    let _ = std::fs::remove_file("janu-test-unix-seqpacket-0.sock.lock");
+}
+
+#[cfg(all(feature = "transport_unixsock-seqpacket", target_os = "linux"))]
+#[test]
+fn endpoint_unix_seqpacket_permissions() {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    let path = "janu-test-unix-seqpacket-2.sock";
+    let _ = std::fs::remove_file(path);
+
+    let config = TransportManagerConfig::builder()
+        .whatami(whatami::PEER)
+        .pid(PeerId::new(1, [0u8; PeerId::MAX_SIZE]))
+        .build(Arc::new(SH));
+    let sm = TransportManager::new(config);
+
+    task::block_on(async {
+        // The current user and group can always be set by the owner of the file
+        let uid = nix::unistd::getuid();
+        let gid = nix::unistd::getgid();
+        let endpoint: EndPoint = format!(
+            "unixsock-seqpacket/{}#mode=600;owner={};group={}",
+            path, uid, gid
+        )
+        .parse()
+        .unwrap();
+        let res = sm.add_listener(endpoint.clone()).await;
+        println!("Res: {:?}", res);
+        assert!(res.is_ok());
+
+        let metadata = std::fs::metadata(path).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        assert_eq!(metadata.uid(), uid.as_raw());
+        assert_eq!(metadata.gid(), gid.as_raw());
+
+        let res = sm.del_listener(&endpoint).await;
+        assert!(res.is_ok());
+        // The socket file is removed with the listener
+        assert!(std::fs::metadata(path).is_err());
+
+        // The mode is not restricted by the umask
+        let endpoint: EndPoint = format!("unixsock-seqpacket/{}#mode=666", path)
+            .parse()
+            .unwrap();
+        let res = sm.add_listener(endpoint.clone()).await;
+        assert!(res.is_ok());
+        let metadata = std::fs::metadata(path).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o666);
+        let res = sm.del_listener(&endpoint).await;
+        assert!(res.is_ok());
+
+        // An invalid mode is refused before creating the socket file
+        let endpoint: EndPoint = format!("unixsock-seqpacket/{}#mode=abc", path)
+            .parse()
+            .unwrap();
+        let res = sm.add_listener(endpoint).await;
+        assert!(res.is_err());
+        assert!(std::fs::metadata(path).is_err());
+    });
+
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file(format!("{}.lock", path));
+}
+
+#[cfg(feature = "transport_tcp")]
+#[test]
+fn endpoint_tcp_socket_options() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    // Define the locators
+    let endpoints: Vec<EndPoint> = vec![
+        "tcp/127.0.0.1:9456#keepalive=30;sndbuf=65536;rcvbuf=65536;dscp=46"
+            .parse()
+            .unwrap(),
+        "tcp/[::1]:9456#tos=184;bind=::1".parse().unwrap(),
+    ];
+    task::block_on(run(&endpoints));
+
+    // Invalid socket options are refused
+    let config = TransportManagerConfig::builder()
+        .whatami(whatami::PEER)
+        .pid(PeerId::new(1, [0u8; PeerId::MAX_SIZE]))
+        .build(Arc::new(SH));
+    let sm = TransportManager::new(config);
+    let endpoints: Vec<EndPoint> = vec![
+        "tcp/127.0.0.1:9457#keepalive=abc".parse().unwrap(),
+        "tcp/127.0.0.1:9457#dscp=64".parse().unwrap(),
+        "tcp/127.0.0.1:9457#dscp=46;tos=184".parse().unwrap(),
+        "tcp/127.0.0.1:9457#sndbuf=-1".parse().unwrap(),
+        "tcp/127.0.0.1:9457#bind=localhost".parse().unwrap(),
+        "tcp/127.0.0.1:9457#iface=janu-none0".parse().unwrap(),
+    ];
+    task::block_on(async {
+        for e in endpoints.iter() {
+            println!("Add {}", e);
+            let res = sm.add_listener(e.clone()).await;
+            println!("Res: {:?}", res);
+            assert!(res.is_err());
+            let res = sm.open_transport(e.clone()).await;
+            assert!(res.is_err());
+        }
+
+        // The connection errors are reported when the socket is configured
+        let e: EndPoint = "tcp/127.0.0.1:9457#sndbuf=65536".parse().unwrap();
+        let res = sm.open_transport(e).await;
+        println!("Res: {:?}", res);
+        assert!(res.is_err());
+    });
+}
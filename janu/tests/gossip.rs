@@ -0,0 +1,147 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use janu::net::protocol::core::whatami;
+use janu::net::runtime::orchestrator::Autoconnect;
+use janu::net::runtime::Runtime;
+use janu_util::properties::config::*;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(100);
+
+async fn peer(listener: &str, peer: Option<&str>, protocols: Option<&str>) -> Runtime {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "peer".to_string());
+    config.insert(ZN_LISTENER_KEY, listener.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    if let Some(peer) = peer {
+        config.insert(ZN_PEER_KEY, peer.to_string());
+    }
+    if let Some(protocols) = protocols {
+        config.insert(ZN_AUTOCONNECT_PROTOCOLS_KEY, protocols.to_string());
+    }
+    Runtime::new(0, config, None).await.unwrap()
+}
+
+fn connected(r1: &Runtime, r2: &Runtime) -> bool {
+    r1.manager().get_transport(&r2.pid).is_some()
+}
+
+async fn connect(r1: &Runtime, r2: &Runtime) {
+    let check = async {
+        while !connected(r1, r2) || !connected(r2, r1) {
+            task::sleep(SLEEP).await;
+        }
+    };
+    check.timeout(TIMEOUT).await.unwrap();
+}
+
+async fn gossip_peers(ports: [u16; 3], protocols: Option<&str>) -> (Runtime, Runtime, Runtime) {
+    let locator = |port: u16| format!("tcp/127.0.0.1:{}", port);
+    // B and C also listen on udp
+    let locators = |port: u16| format!("tcp/127.0.0.1:{},udp/127.0.0.1:{}", port, port);
+    let a = peer(&locator(ports[0]), None, None).await;
+    let b = peer(&locators(ports[1]), Some(&locator(ports[0])), protocols).await;
+    let c = peer(&locators(ports[2]), Some(&locator(ports[0])), protocols).await;
+
+    // B and C are connected to A
+    let check = async {
+        while !connected(&a, &b) || !connected(&a, &c) {
+            task::sleep(SLEEP).await;
+        }
+    };
+    check.timeout(TIMEOUT).await.unwrap();
+    (a, b, c)
+}
+
+#[test]
+fn gossip_discovery() {
+    task::block_on(async {
+        let (a, b, c) = gossip_peers([17460, 17461, 17462], None).await;
+
+        // B and C learn each other's locators from A and connect to each other
+        connect(&b, &c).await;
+
+        // B and C connect to each other again once disconnected
+        b.manager()
+            .get_transport(&c.pid)
+            .unwrap()
+            .close()
+            .await
+            .unwrap();
+        connect(&b, &c).await;
+
+        for r in [a, b, c].iter() {
+            r.close().await.unwrap();
+        }
+    });
+}
+
+#[test]
+fn gossip_discovery_protocols() {
+    task::block_on(async {
+        let (a, b, c) = gossip_peers([17470, 17471, 17472], Some("udp")).await;
+
+        // B and C connect to each other with their udp locators only
+        connect(&b, &c).await;
+        for (r1, r2) in [(&b, &c), (&c, &b)] {
+            let links = r1
+                .manager()
+                .get_transport(&r2.pid)
+                .unwrap()
+                .get_links()
+                .unwrap();
+            assert!(!links.is_empty());
+            for link in links.iter() {
+                assert_eq!(link.dst.address.get_proto().to_string(), "udp");
+            }
+        }
+
+        for r in [a, b, c].iter() {
+            r.close().await.unwrap();
+        }
+    });
+}
+
+#[test]
+fn autoconnect_config() {
+    let mut config = ConfigProperties::default();
+    assert_eq!(
+        Autoconnect::from_config(&config, whatami::PEER).whatami(),
+        whatami::PEER | whatami::ROUTER
+    );
+    assert_eq!(
+        Autoconnect::from_config(&config, whatami::ROUTER).whatami(),
+        whatami::ROUTER
+    );
+
+    config.insert(
+        ZN_AUTOCONNECT_WHATAMI_KEY,
+        "peer, client,invalid".to_string(),
+    );
+    let autoconnect = Autoconnect::from_config(&config, whatami::ROUTER);
+    assert!(autoconnect.matches(whatami::PEER));
+    assert!(!autoconnect.matches(whatami::ROUTER));
+    assert!(!autoconnect.matches(whatami::CLIENT));
+
+    config.insert(ZN_AUTOCONNECT_PROTOCOLS_KEY, "TCP".to_string());
+    let autoconnect = Autoconnect::from_config(&config, whatami::PEER);
+    let locators = vec![
+        "tcp/127.0.0.1:7447".parse().unwrap(),
+        "udp/127.0.0.1:7447".parse().unwrap(),
+    ];
+    assert_eq!(autoconnect.locators(&locators), locators[..1].to_vec());
+}
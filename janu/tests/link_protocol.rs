@@ -0,0 +1,525 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use async_std::net::{SocketAddr, TcpListener, TcpStream};
+use async_std::prelude::*;
+use async_std::sync::Arc;
+use async_std::task;
+use async_trait::async_trait;
+use std::any::Any;
+use std::collections::HashMap;
+use std::net::Shutdown;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use janu::net::link::{
+    EndPoint, Link, LinkManagerUnicast, LinkManagerUnicastTrait, LinkProtocolUnicastTrait,
+    LinkUnicast, LinkUnicastTrait, Locator, LocatorAddress, LocatorCustom,
+};
+use janu::net::protocol::core::{
+    whatami, Channel, CongestionControl, PeerId, Priority, Reliability, ResKey,
+};
+use janu::net::protocol::io::ZBuf;
+use janu::net::protocol::proto::JanuMessage;
+use janu::net::runtime::Runtime;
+use janu::net::transport::{
+    TransportEventHandler, TransportManager, TransportManagerConfig, TransportMulticast,
+    TransportMulticastEventHandler, TransportPeer, TransportPeerEventHandler, TransportUnicast,
+};
+use janu_util::core::{ZError, ZErrorKind, ZResult};
+use janu_util::properties::config::*;
+use janu_util::properties::Properties;
+use janu_util::{zasync_executor_init, zerror, zerror2};
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+const SLEEP: Duration = Duration::from_secs(1);
+const SLEEP_COUNT: Duration = Duration::from_millis(10);
+
+const MSG_COUNT: usize = 100;
+const MSG_SIZE: usize = 1_024;
+
+const PROTOCOL: &str = "tcpx";
+const CONFIG_KEY: &str = "tcpx_config";
+
+/*************************************/
+/*          LINK PROTOCOL            */
+/*************************************/
+// A third-party link protocol on top of TCP
+fn locator(addr: SocketAddr) -> Locator {
+    Locator {
+        address: LocatorAddress::Custom(LocatorCustom::new(PROTOCOL, &addr.to_string())),
+        metadata: None,
+    }
+}
+
+fn socket_addr(endpoint: &EndPoint) -> ZResult<SocketAddr> {
+    match &endpoint.locator.address {
+        LocatorAddress::Custom(locator) => locator.address().parse().map_err(|_| {
+            zerror2!(ZErrorKind::InvalidLocator {
+                descr: locator.address().to_string()
+            })
+        }),
+        address => zerror!(ZErrorKind::InvalidLocator {
+            descr: address.to_string()
+        }),
+    }
+}
+
+fn io_error(e: std::io::Error) -> ZError {
+    zerror2!(ZErrorKind::IoError {
+        descr: e.to_string()
+    })
+}
+
+struct LinkUnicastTcpx {
+    socket: TcpStream,
+    src_addr: SocketAddr,
+    dst_addr: SocketAddr,
+}
+
+#[async_trait]
+impl LinkUnicastTrait for LinkUnicastTcpx {
+    fn get_mtu(&self) -> u16 {
+        u16::MAX
+    }
+
+    fn get_src(&self) -> Locator {
+        locator(self.src_addr)
+    }
+
+    fn get_dst(&self) -> Locator {
+        locator(self.dst_addr)
+    }
+
+    fn is_reliable(&self) -> bool {
+        true
+    }
+
+    fn is_streamed(&self) -> bool {
+        true
+    }
+
+    async fn write(&self, buffer: &[u8]) -> ZResult<usize> {
+        (&self.socket).write(buffer).await.map_err(io_error)
+    }
+
+    async fn write_all(&self, buffer: &[u8]) -> ZResult<()> {
+        (&self.socket).write_all(buffer).await.map_err(io_error)
+    }
+
+    async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
+        (&self.socket).read(buffer).await.map_err(io_error)
+    }
+
+    async fn read_exact(&self, buffer: &mut [u8]) -> ZResult<()> {
+        (&self.socket).read_exact(buffer).await.map_err(io_error)
+    }
+
+    async fn close(&self) -> ZResult<()> {
+        self.socket.shutdown(Shutdown::Both).map_err(io_error)
+    }
+}
+
+fn new_link(socket: TcpStream) -> ZResult<LinkUnicast> {
+    let link = LinkUnicastTcpx {
+        src_addr: socket.local_addr().map_err(io_error)?,
+        dst_addr: socket.peer_addr().map_err(io_error)?,
+        socket,
+    };
+    let link: Arc<dyn LinkUnicastTrait> = Arc::new(link);
+    Ok(link.into())
+}
+
+struct LinkManagerUnicastTcpx {
+    manager: TransportManager,
+    listeners: Mutex<HashMap<SocketAddr, (EndPoint, task::JoinHandle<()>)>>,
+}
+
+#[async_trait]
+impl LinkManagerUnicastTrait for LinkManagerUnicastTcpx {
+    async fn new_link(&self, endpoint: EndPoint) -> ZResult<LinkUnicast> {
+        let addr = socket_addr(&endpoint)?;
+        let socket = TcpStream::connect(addr).await.map_err(io_error)?;
+        new_link(socket)
+    }
+
+    async fn new_listener(&self, mut endpoint: EndPoint) -> ZResult<Locator> {
+        let socket = TcpListener::bind(socket_addr(&endpoint)?)
+            .await
+            .map_err(io_error)?;
+        let addr = socket.local_addr().map_err(io_error)?;
+        endpoint.locator = locator(addr);
+
+        let manager = self.manager.clone();
+        let handle = task::spawn(async move {
+            while let Ok((stream, _)) = socket.accept().await {
+                if let Ok(link) = new_link(stream) {
+                    manager.handle_new_link_unicast(link).await;
+                }
+            }
+        });
+        let locator = endpoint.locator.clone();
+        self.listeners
+            .lock()
+            .unwrap()
+            .insert(addr, (endpoint, handle));
+        Ok(locator)
+    }
+
+    async fn del_listener(&self, endpoint: &EndPoint) -> ZResult<()> {
+        let addr = socket_addr(endpoint)?;
+        let listener = self.listeners.lock().unwrap().remove(&addr);
+        match listener {
+            Some((_, handle)) => {
+                handle.cancel().await;
+                Ok(())
+            }
+            None => zerror!(ZErrorKind::InvalidLocator {
+                descr: endpoint.to_string()
+            }),
+        }
+    }
+
+    fn get_listeners(&self) -> Vec<EndPoint> {
+        let guard = self.listeners.lock().unwrap();
+        guard.values().map(|(e, _)| e.clone()).collect()
+    }
+
+    fn get_locators(&self) -> Vec<Locator> {
+        let guard = self.listeners.lock().unwrap();
+        guard.keys().map(|addr| locator(*addr)).collect()
+    }
+}
+
+struct LinkProtocolUnicastTcpx;
+
+impl LinkProtocolUnicastTrait for LinkProtocolUnicastTcpx {
+    fn name(&self) -> &str {
+        PROTOCOL
+    }
+
+    fn check_address(&self, address: &str) -> ZResult<()> {
+        address.parse::<SocketAddr>().map(|_| ()).map_err(|e| {
+            zerror2!(ZErrorKind::InvalidLocator {
+                descr: e.to_string()
+            })
+        })
+    }
+
+    fn config(&self) -> Option<Properties> {
+        let mut config = Properties::default();
+        config.insert(CONFIG_KEY.to_string(), "protocol".to_string());
+        Some(config)
+    }
+
+    fn new_link_manager(&self, manager: TransportManager) -> LinkManagerUnicast {
+        Arc::new(LinkManagerUnicastTcpx {
+            manager,
+            listeners: Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+/*************************************/
+/*            TRANSPORT              */
+/*************************************/
+// Transport Handler for the router
+#[derive(Default)]
+struct SHRouter {
+    count: Arc<AtomicUsize>,
+}
+
+impl TransportEventHandler for SHRouter {
+    fn new_unicast(
+        &self,
+        _peer: TransportPeer,
+        _transport: TransportUnicast,
+    ) -> ZResult<Arc<dyn TransportPeerEventHandler>> {
+        Ok(Arc::new(SCPeer {
+            count: self.count.clone(),
+        }))
+    }
+
+    fn new_multicast(
+        &self,
+        _transport: TransportMulticast,
+    ) -> ZResult<Arc<dyn TransportMulticastEventHandler>> {
+        panic!();
+    }
+}
+
+// Transport Handler for the client
+struct SHClient;
+
+impl TransportEventHandler for SHClient {
+    fn new_unicast(
+        &self,
+        _peer: TransportPeer,
+        _transport: TransportUnicast,
+    ) -> ZResult<Arc<dyn TransportPeerEventHandler>> {
+        Ok(Arc::new(SCPeer::default()))
+    }
+
+    fn new_multicast(
+        &self,
+        _transport: TransportMulticast,
+    ) -> ZResult<Arc<dyn TransportMulticastEventHandler>> {
+        panic!();
+    }
+}
+
+// Transport Callback counting the received messages
+#[derive(Default)]
+struct SCPeer {
+    count: Arc<AtomicUsize>,
+}
+
+impl TransportPeerEventHandler for SCPeer {
+    fn handle_message(&self, _message: JanuMessage) -> ZResult<()> {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn new_link(&self, _link: Link) {}
+    fn del_link(&self, _link: Link) {}
+    fn closing(&self) {}
+    fn closed(&self) {}
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn transport_manager(id: u8, handler: Arc<dyn TransportEventHandler>) -> TransportManager {
+    let config = TransportManagerConfig::builder()
+        .whatami(whatami::PEER)
+        .pid(PeerId::new(1, [id; PeerId::MAX_SIZE]))
+        .build(handler);
+    TransportManager::new(config)
+}
+
+#[test]
+fn link_protocol_registration() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    task::block_on(async {
+        let manager = transport_manager(0, Arc::new(SHClient));
+
+        // Locators of unknown protocols are parsed but can not be used
+        let endpoint: EndPoint = "tcpx/127.0.0.1:13470".parse().unwrap();
+        assert!(manager.add_listener(endpoint.clone()).await.is_err());
+        assert!(manager.open_transport(endpoint.clone()).await.is_err());
+        assert!("0tcpx/127.0.0.1:13470".parse::<EndPoint>().is_err());
+        // Whereas the locators of the built-in protocols whose feature is disabled are not
+        #[cfg(not(feature = "transport_mem"))]
+        assert!("mem/janu-test-link-protocol".parse::<EndPoint>().is_err());
+
+        // Built-in protocols can not be registered
+        struct LinkProtocolUnicastTcp;
+        impl LinkProtocolUnicastTrait for LinkProtocolUnicastTcp {
+            fn name(&self) -> &str {
+                "tcp"
+            }
+            fn check_address(&self, _address: &str) -> ZResult<()> {
+                Ok(())
+            }
+            fn new_link_manager(&self, manager: TransportManager) -> LinkManagerUnicast {
+                LinkProtocolUnicastTcpx.new_link_manager(manager)
+            }
+        }
+        assert!(manager
+            .register_link_protocol_unicast(Arc::new(LinkProtocolUnicastTcp))
+            .is_err());
+
+        // Register the protocol only once
+        let protocol = Arc::new(LinkProtocolUnicastTcpx);
+        manager
+            .register_link_protocol_unicast(protocol.clone())
+            .unwrap();
+        assert!(manager.register_link_protocol_unicast(protocol).is_err());
+
+        // The address is checked by the protocol
+        let invalid: EndPoint = "tcpx/localhost".parse().unwrap();
+        assert!(manager.add_listener(invalid).await.is_err());
+
+        // The endpoint configuration is merged with the one of the protocol
+        let mut config = Properties::default();
+        config.insert("key".to_string(), "endpoint".to_string());
+        let mut endpoint = endpoint;
+        endpoint.config = Some(Arc::new(config));
+        let locator = manager.add_listener(endpoint.clone()).await.unwrap();
+        assert_eq!(locator.to_string(), "tcpx/127.0.0.1:13470");
+        assert_eq!(manager.get_locators(), vec![locator]);
+        let listeners = manager.get_listeners();
+        let config = listeners[0].config.as_ref().unwrap();
+        assert_eq!(config.get(CONFIG_KEY).unwrap(), "protocol");
+        assert_eq!(config.get("key").unwrap(), "endpoint");
+        manager.del_listener(&endpoint).await.unwrap();
+
+        // Once unregistered the protocol can not be used anymore
+        manager.unregister_link_protocol_unicast(PROTOCOL).unwrap();
+        assert!(manager.unregister_link_protocol_unicast(PROTOCOL).is_err());
+        assert!(manager.add_listener(endpoint).await.is_err());
+    });
+}
+
+#[test]
+fn link_protocol_config() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    task::block_on(async {
+        // The locators of the unregistered protocols are rejected in the configuration
+        for key in [ZN_LISTENER_KEY, ZN_PEER_KEY].iter() {
+            let mut config = ConfigProperties::default();
+            config.insert(ZN_MODE_KEY, "peer".to_string());
+            config.insert(ZN_LISTENER_KEY, "".to_string());
+            config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+            config.insert(*key, "tcpx/127.0.0.1:13472".to_string());
+            assert!(Runtime::new(0, config, None).await.is_err());
+        }
+    });
+}
+
+#[test]
+fn link_protocol_reregistration() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    // Counts the link managers created for the protocol
+    struct LinkProtocolUnicastCount(Arc<AtomicUsize>);
+    impl LinkProtocolUnicastTrait for LinkProtocolUnicastCount {
+        fn name(&self) -> &str {
+            PROTOCOL
+        }
+        fn check_address(&self, address: &str) -> ZResult<()> {
+            LinkProtocolUnicastTcpx.check_address(address)
+        }
+        fn new_link_manager(&self, manager: TransportManager) -> LinkManagerUnicast {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            LinkProtocolUnicastTcpx.new_link_manager(manager)
+        }
+    }
+
+    task::block_on(async {
+        let manager = transport_manager(0, Arc::new(SHClient));
+        let endpoint: EndPoint = "tcpx/127.0.0.1:13472".parse().unwrap();
+
+        // Opening a transport creates a link manager for the protocol
+        let first = Arc::new(AtomicUsize::new(0));
+        manager
+            .register_link_protocol_unicast(Arc::new(LinkProtocolUnicastCount(first.clone())))
+            .unwrap();
+        assert!(manager.open_transport(endpoint.clone()).await.is_err());
+        assert_eq!(first.load(Ordering::SeqCst), 1);
+
+        // A protocol with listeners can not be unregistered
+        let _ = manager.add_listener(endpoint.clone()).await.unwrap();
+        assert!(manager.unregister_link_protocol_unicast(PROTOCOL).is_err());
+        manager.del_listener(&endpoint).await.unwrap();
+        assert!(manager.open_transport(endpoint.clone()).await.is_err());
+        assert_eq!(first.load(Ordering::SeqCst), 2);
+
+        // The link manager of an unregistered protocol is not used anymore
+        manager.unregister_link_protocol_unicast(PROTOCOL).unwrap();
+        let second = Arc::new(AtomicUsize::new(0));
+        manager
+            .register_link_protocol_unicast(Arc::new(LinkProtocolUnicastCount(second.clone())))
+            .unwrap();
+        assert!(manager.open_transport(endpoint).await.is_err());
+        assert_eq!(first.load(Ordering::SeqCst), 2);
+        assert_eq!(second.load(Ordering::SeqCst), 1);
+    });
+}
+
+#[test]
+fn link_protocol_transport() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    task::block_on(async {
+        let endpoint: EndPoint = "tcpx/127.0.0.1:13471".parse().unwrap();
+
+        // Create the router and the client transport managers
+        let router_handler = Arc::new(SHRouter::default());
+        let router_manager = transport_manager(1, router_handler.clone());
+        router_manager
+            .register_link_protocol_unicast(Arc::new(LinkProtocolUnicastTcpx))
+            .unwrap();
+        let client_manager = transport_manager(0, Arc::new(SHClient));
+        client_manager
+            .register_link_protocol_unicast(Arc::new(LinkProtocolUnicastTcpx))
+            .unwrap();
+
+        // Open a transport on the third-party link protocol
+        let _ = router_manager
+            .add_listener(endpoint.clone())
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        let client_transport = client_manager
+            .open_transport(endpoint.clone())
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        let links = client_transport.get_links().unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].dst.to_string(), endpoint.locator.to_string());
+
+        // Send the messages
+        let message = JanuMessage::make_data(
+            ResKey::RName("/test".to_string()),
+            ZBuf::from(vec![0u8; MSG_SIZE]),
+            Channel {
+                priority: Priority::default(),
+                reliability: Reliability::Reliable,
+            },
+            CongestionControl::Block,
+            None,
+            None,
+            None,
+            None,
+        );
+        for _ in 0..MSG_COUNT {
+            client_transport.schedule(message.clone()).unwrap();
+        }
+        let check = async {
+            while router_handler.count.load(Ordering::SeqCst) != MSG_COUNT {
+                task::sleep(SLEEP_COUNT).await;
+            }
+        };
+        check.timeout(TIMEOUT).await.unwrap();
+
+        // Close the transport and the listener
+        client_transport
+            .close()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        task::sleep(SLEEP).await;
+        router_manager
+            .del_listener(&endpoint)
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+    });
+}
@@ -175,11 +175,19 @@ fn gen_query_target() -> QueryTarget {
 }
 
 fn gen_target() -> Target {
-    let num: u8 = thread_rng().gen_range(0..4);
+    let num: u8 = thread_rng().gen_range(0..6);
     match num {
         0 => Target::BestMatching,
         1 => Target::Complete { n: 3 },
         2 => Target::All,
+        3 => Target::Balanced {
+            n: 1,
+            strategy: BalancingStrategy::RoundRobin,
+        },
+        4 => Target::Balanced {
+            n: 2,
+            strategy: BalancingStrategy::LeastPending,
+        },
         _ => Target::None,
     }
 }
@@ -311,21 +319,25 @@ fn codec_init() {
         let is_qos = [true, false];
         let wami = [whatami::ROUTER, whatami::CLIENT];
         let sn_resolution = [SEQ_NUM_RES, gen!(ZInt)];
+        let batch_size = [None, Some(gen!(u32)), Some(u32::MAX)];
         let attachment = [None, Some(gen_attachment())];
 
         for q in is_qos.iter() {
             for w in wami.iter() {
                 for s in sn_resolution.iter() {
-                    for a in attachment.iter() {
-                        let msg = TransportMessage::make_init_syn(
-                            gen!(u8),
-                            *w,
-                            gen_pid(),
-                            *s,
-                            *q,
-                            a.clone(),
-                        );
-                        test_write_read_transport_message(msg);
+                    for b in batch_size.iter() {
+                        for a in attachment.iter() {
+                            let msg = TransportMessage::make_init_syn(
+                                gen!(u8),
+                                *w,
+                                gen_pid(),
+                                *s,
+                                *q,
+                                *b,
+                                a.clone(),
+                            );
+                            test_write_read_transport_message(msg);
+                        }
                     }
                 }
             }
@@ -335,16 +347,19 @@ fn codec_init() {
         for q in is_qos.iter() {
             for w in wami.iter() {
                 for s in sn_resolution.iter() {
-                    for a in attachment.iter() {
-                        let msg = TransportMessage::make_init_ack(
-                            *w,
-                            gen_pid(),
-                            *s,
-                            *q,
-                            gen_buffer(64).into(),
-                            a.clone(),
-                        );
-                        test_write_read_transport_message(msg);
+                    for b in batch_size.iter() {
+                        for a in attachment.iter() {
+                            let msg = TransportMessage::make_init_ack(
+                                *w,
+                                gen_pid(),
+                                *s,
+                                *q,
+                                *b,
+                                gen_buffer(64).into(),
+                                a.clone(),
+                            );
+                            test_write_read_transport_message(msg);
+                        }
                     }
                 }
             }
@@ -837,3 +852,67 @@ fn codec_query() {
         }
     }
 }
+
+#[test]
+fn codec_link_state_list() {
+    for _ in 0..NUM_ITER {
+        let pid = [None, Some(gen_pid())];
+        let whatami = [None, Some(gen!(ZInt))];
+        let locators = [None, Some(vec!["tcp/127.0.0.1:7447".parse().unwrap()])];
+        let accept_costs = [false, true];
+
+        let mut link_states = vec![];
+        for p in pid.iter() {
+            for w in whatami.iter() {
+                for l in locators.iter() {
+                    for a in accept_costs.iter() {
+                        let links: Vec<ZInt> = (0..gen!(u8) % 4).map(|_| gen!(ZInt)).collect();
+                        let costs = option_gen!(links.iter().map(|_| gen!(ZInt)).collect());
+                        link_states.push(LinkState {
+                            psid: gen!(ZInt),
+                            sn: gen!(ZInt),
+                            pid: *p,
+                            whatami: *w,
+                            locators: l.clone(),
+                            links,
+                            costs,
+                            accept_costs: *a,
+                        });
+                    }
+                }
+            }
+        }
+        let msg = JanuMessage::make_link_state_list(link_states, None);
+        test_write_read_janu_message(msg);
+    }
+}
+
+#[test]
+fn codec_link_state_accept_costs() {
+    // Accepting costs does not add any field, so that the link states of the nodes
+    // accepting costs can be decoded by the nodes unaware of them
+    let link_state = LinkState {
+        psid: 1,
+        sn: 2,
+        pid: Some(gen_pid()),
+        whatami: Some(whatami::ROUTER),
+        locators: None,
+        links: vec![3, 4],
+        costs: None,
+        accept_costs: false,
+    };
+    let mut accepting = link_state.clone();
+    accepting.accept_costs = true;
+
+    let mut buf1 = WBuf::new(64, false);
+    buf1.write_janu_message(&mut JanuMessage::make_link_state_list(
+        vec![link_state],
+        None,
+    ));
+    let mut buf2 = WBuf::new(64, false);
+    buf2.write_janu_message(&mut JanuMessage::make_link_state_list(
+        vec![accepting],
+        None,
+    ));
+    assert_eq!(buf1.len(), buf2.len());
+}
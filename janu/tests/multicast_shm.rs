@@ -0,0 +1,310 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+
+#[cfg(all(feature = "zero-copy", feature = "transport_udp", unix))]
+mod tests {
+    use async_std::prelude::*;
+    use async_std::sync::Arc;
+    use async_std::task;
+    use std::any::Any;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use janu::net::link::{EndPoint, Link};
+    use janu::net::protocol::core::{
+        whatami, Channel, CongestionControl, PeerId, Priority, Reliability, ResKey,
+    };
+    use janu::net::protocol::io::{SharedMemoryManager, ZBuf};
+    use janu::net::protocol::proto::{Data, JanuBody, JanuMessage};
+    use janu::net::transport::{
+        TransportEventHandler, TransportManager, TransportManagerConfig,
+        TransportManagerConfigMulticast, TransportMulticast, TransportMulticastEventHandler,
+        TransportPeer, TransportPeerEventHandler, TransportUnicast,
+    };
+    use janu_util::core::ZResult;
+    use janu_util::properties::config::ConfigProperties;
+    use janu_util::zasync_executor_init;
+
+    const TIMEOUT: Duration = Duration::from_secs(60);
+    const SLEEP: Duration = Duration::from_secs(1);
+    const SLEEP_COUNT: Duration = Duration::from_millis(10);
+    const USLEEP: Duration = Duration::from_micros(100);
+
+    const MSG_COUNT: usize = 100;
+    const MSG_SIZE: usize = 1_024;
+
+    // Transport Handler for the peers
+    struct SHPeer {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl Default for SHPeer {
+        fn default() -> Self {
+            Self {
+                count: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+    }
+
+    impl SHPeer {
+        fn get_count(&self) -> usize {
+            self.count.load(Ordering::SeqCst)
+        }
+    }
+
+    impl TransportEventHandler for SHPeer {
+        fn new_unicast(
+            &self,
+            _peer: TransportPeer,
+            _transport: TransportUnicast,
+        ) -> ZResult<Arc<dyn TransportPeerEventHandler>> {
+            panic!();
+        }
+
+        fn new_multicast(
+            &self,
+            _transport: TransportMulticast,
+        ) -> ZResult<Arc<dyn TransportMulticastEventHandler>> {
+            let arc = Arc::new(SCPeer::new(self.count.clone()));
+            Ok(arc)
+        }
+    }
+
+    // Transport Callback for the peers
+    pub struct SCPeer {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl SCPeer {
+        pub fn new(count: Arc<AtomicUsize>) -> Self {
+            Self { count }
+        }
+    }
+
+    impl TransportMulticastEventHandler for SCPeer {
+        fn new_peer(&self, peer: TransportPeer) -> ZResult<Arc<dyn TransportPeerEventHandler>> {
+            assert!(peer.is_shm);
+            Ok(Arc::new(SCPeer {
+                count: self.count.clone(),
+            }))
+        }
+        fn closing(&self) {}
+        fn closed(&self) {}
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    impl TransportPeerEventHandler for SCPeer {
+        fn handle_message(&self, message: JanuMessage) -> ZResult<()> {
+            let payload = match message.body {
+                JanuBody::Data(Data { payload, .. }) => payload.contiguous(),
+                _ => panic!("Unsolicited message"),
+            };
+            assert_eq!(payload.len(), MSG_SIZE);
+
+            let mut count_bytes = [0u8; 8];
+            count_bytes.copy_from_slice(&payload[0..8]);
+            let msg_count = u64::from_le_bytes(count_bytes) as usize;
+            let sex_count = self.count.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(msg_count, sex_count);
+
+            Ok(())
+        }
+
+        fn new_link(&self, _link: Link) {}
+        fn del_link(&self, _link: Link) {}
+        fn closing(&self) {}
+        fn closed(&self) {}
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    async fn run(endpoint: &EndPoint) {
+        // Define peer01 and peer02 IDs
+        let peer01_id = PeerId::new(1, [0u8; PeerId::MAX_SIZE]);
+        let peer02_id = PeerId::new(1, [1u8; PeerId::MAX_SIZE]);
+
+        // Create the SharedMemoryManager
+        let mut shm01 =
+            SharedMemoryManager::new("peer_mcast_shm01".to_string(), 2 * MSG_SIZE).unwrap();
+
+        // Create the peer01 transport manager with shared memory enabled
+        let peer01_handler = Arc::new(SHPeer::default());
+        let config = TransportManagerConfig::builder()
+            .pid(peer01_id)
+            .whatami(whatami::PEER)
+            .multicast(TransportManagerConfigMulticast::builder().shm(true).build())
+            .build(peer01_handler.clone());
+        let peer01_manager = TransportManager::new(config);
+
+        // Create the peer02 transport manager with shared memory enabled
+        let peer02_handler = Arc::new(SHPeer::default());
+        let config = TransportManagerConfig::builder()
+            .pid(peer02_id)
+            .whatami(whatami::PEER)
+            .multicast(TransportManagerConfigMulticast::builder().shm(true).build())
+            .build(peer02_handler.clone());
+        let peer02_manager = TransportManager::new(config);
+
+        // Open the multicast transports
+        println!("Transport Multicast SHM [1a]");
+        let _ = peer01_manager
+            .open_transport_multicast(endpoint.clone())
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        println!("Transport Multicast SHM [1b]");
+        let _ = peer02_manager
+            .open_transport_multicast(endpoint.clone())
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Wait for peer 01 and 02 to join each other
+        println!("Transport Multicast SHM [2a]");
+        let peer01_transport = peer01_manager
+            .get_transport_multicast(&endpoint.locator)
+            .unwrap();
+        let peer02_transport = peer02_manager
+            .get_transport_multicast(&endpoint.locator)
+            .unwrap();
+        let count = async {
+            while peer01_transport.get_peers().unwrap().is_empty()
+                || peer02_transport.get_peers().unwrap().is_empty()
+            {
+                task::sleep(SLEEP_COUNT).await;
+            }
+        };
+        count.timeout(TIMEOUT).await.unwrap();
+        assert!(peer01_transport.is_shm().unwrap());
+        assert!(peer02_transport.is_shm().unwrap());
+
+        // Send the messages
+        println!("Transport Multicast SHM [3a]");
+        for msg_count in 0..MSG_COUNT {
+            let mut sbuf = async {
+                loop {
+                    match shm01.alloc(MSG_SIZE) {
+                        Some(sbuf) => break sbuf,
+                        None => task::sleep(USLEEP).await,
+                    }
+                }
+            }
+            .timeout(TIMEOUT)
+            .await
+            .unwrap();
+
+            let bs = unsafe { sbuf.as_mut_slice() };
+            bs[0..8].copy_from_slice(&msg_count.to_le_bytes());
+
+            let key = ResKey::RName("/test".to_string());
+            let payload: ZBuf = sbuf.into();
+            let channel = Channel {
+                priority: Priority::default(),
+                reliability: Reliability::BestEffort,
+            };
+            let message = JanuMessage::make_data(
+                key,
+                payload,
+                channel,
+                CongestionControl::Block,
+                None,
+                None,
+                None,
+                None,
+            );
+            peer01_transport.schedule(message).unwrap();
+        }
+
+        // Wait for the messages to arrive to the other side
+        println!("Transport Multicast SHM [3b]");
+        let count = async {
+            while peer02_handler.get_count() != MSG_COUNT {
+                task::sleep(SLEEP_COUNT).await;
+            }
+        };
+        count.timeout(TIMEOUT).await.unwrap();
+
+        // All the buffers are released by the receiver and by the sender once their
+        // retention expired
+        println!("Transport Multicast SHM [4a]");
+        let released = async {
+            loop {
+                shm01.garbage_collect();
+                if shm01.stats().iter().all(|s| s.busy_chunks == 0) {
+                    break;
+                }
+                task::sleep(SLEEP_COUNT).await;
+            }
+        };
+        released.timeout(TIMEOUT).await.unwrap();
+
+        // Close the transports
+        println!("Transport Multicast SHM [5a]");
+        peer01_transport
+            .close()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        peer02_transport
+            .close()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Wait a little bit
+        task::sleep(SLEEP).await;
+    }
+
+    #[cfg(feature = "transport_udp")]
+    #[test]
+    fn transport_multicast_udp_shm() {
+        task::block_on(async {
+            zasync_executor_init!();
+        });
+
+        let endpoint: EndPoint = "udp/224.0.0.224:17447".parse().unwrap();
+        task::block_on(run(&endpoint));
+    }
+
+    #[test]
+    fn transport_multicast_shm_config() {
+        task::block_on(async {
+            let is_shm = |config: &str| {
+                let properties = ConfigProperties::from(config);
+                async move {
+                    TransportManagerConfigMulticast::builder()
+                        .from_config(&properties)
+                        .await
+                        .unwrap()
+                        .build()
+                        .is_shm
+                }
+            };
+
+            // Shared memory is not used over multicast unless explicitly enabled
+            assert!(!is_shm("").await);
+            assert!(!is_shm("shm=true").await);
+            assert!(is_shm("shm_multicast=true").await);
+            assert!(!is_shm("shm=false;shm_multicast=true").await);
+        });
+    }
+}
@@ -0,0 +1,159 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+#[cfg(feature = "transport_tcp")]
+mod tests {
+    use async_std::prelude::*;
+    use async_std::task;
+    use clap::App;
+    use std::time::Duration;
+    use janu::net::plugins::PluginsManager;
+    use janu::net::runtime::{AdminSpace, Runtime};
+    use janu::net::*;
+    use janu_util::properties::config::*;
+    use janu_util::LibLoader;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+    const SLEEP: Duration = Duration::from_millis(100);
+
+    const LOCATOR_A: &str = "tcp/127.0.0.1:17500";
+    const LOCATOR_B: &str = "tcp/127.0.0.1:17501";
+    const LOCATOR_C: &str = "tcp/127.0.0.1:17502";
+    const KEY: &str = "/test/periodic/a";
+
+    fn config(mode: &str, listener: Option<&str>, peer: Option<&str>) -> ConfigProperties {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, mode.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        if let Some(listener) = listener {
+            config.insert(ZN_LISTENER_KEY, listener.to_string());
+        }
+        if let Some(peer) = peer {
+            config.insert(ZN_PEER_KEY, peer.to_string());
+        }
+        config
+    }
+
+    async fn router(listener: &str, peer: Option<&str>) -> Runtime {
+        let runtime = Runtime::new(0, config("router", Some(listener), peer), None)
+            .await
+            .unwrap();
+        let (plugins, _) = PluginsManager::builder()
+            .into_dynamic(LibLoader::new::<&str>(&[], false))
+            .get_requirements();
+        let args = App::new("routing_periodic").get_matches_from(vec!["routing_periodic"]);
+        let (handles, _) = plugins.start(&(runtime.clone(), args));
+        AdminSpace::start(&runtime, handles, String::new()).await;
+        runtime
+    }
+
+    async fn route(session: &Session, pid: &str) -> Option<serde_json::Value> {
+        let mut replies = session
+            .query(
+                &format!("/@/router/{}/routing/route", pid).into(),
+                &format!("?(key={})", KEY),
+                QueryTarget::default(),
+                QueryConsolidation::default(),
+            )
+            .await
+            .unwrap();
+        let reply = replies.next().await?;
+        serde_json::from_slice(&reply.data.payload.to_vec()).ok()
+    }
+
+    // Declares a periodic subscriber and checks that the data published on router A
+    // is sampled at A instead of being pushed, and that the samples still get delivered
+    async fn check_sampled(router_a: &Runtime, publisher: &Session, subscriber: &Session) {
+        let sub_info = SubInfo {
+            reliability: Reliability::Reliable,
+            mode: SubMode::Push,
+            period: Some(Period {
+                origin: 0,
+                period: 50,
+                duration: 0,
+            }),
+        };
+        let mut sub = subscriber
+            .declare_subscriber(&KEY.into(), &sub_info)
+            .await
+            .unwrap();
+
+        let pid_a = router_a.get_pid_str();
+        let check = async {
+            loop {
+                if let Some(json) = route(publisher, &pid_a).await {
+                    let data = &json["data"];
+                    if data["route"].as_array().map(Vec::is_empty) == Some(true)
+                        && data["pull_caches"].as_array().map(Vec::len) == Some(1)
+                    {
+                        break;
+                    }
+                }
+                task::sleep(SLEEP).await;
+            }
+        };
+        check.timeout(TIMEOUT).await.unwrap();
+
+        let check = async {
+            loop {
+                publisher
+                    .write(&KEY.into(), vec![0u8; 8].into())
+                    .await
+                    .unwrap();
+                if sub.receiver().next().timeout(SLEEP).await.is_ok() {
+                    break;
+                }
+            }
+        };
+        check.timeout(TIMEOUT).await.unwrap();
+
+        sub.undeclare().await.unwrap();
+    }
+
+    #[test]
+    fn routing_periodic_sampled_upstream() {
+        task::block_on(async {
+            // A publisher on router A and a periodic subscriber on router B
+            let router_a = router(LOCATOR_A, None).await;
+            let router_b = router(LOCATOR_B, Some(LOCATOR_A)).await;
+            let publisher = open(config("client", None, Some(LOCATOR_A))).await.unwrap();
+            let subscriber = open(config("client", None, Some(LOCATOR_B))).await.unwrap();
+
+            check_sampled(&router_a, &publisher, &subscriber).await;
+
+            for session in [publisher, subscriber] {
+                session.close().await.unwrap();
+            }
+            for runtime in [router_b, router_a] {
+                runtime.close().await.unwrap();
+            }
+        });
+    }
+
+    #[test]
+    fn routing_periodic_sampled_for_peer() {
+        task::block_on(async {
+            // A publisher on router A and a periodic subscriber in a peer connected to A
+            let router_a = router(LOCATOR_C, None).await;
+            let publisher = open(config("client", None, Some(LOCATOR_C))).await.unwrap();
+            let subscriber = open(config("peer", None, Some(LOCATOR_C))).await.unwrap();
+
+            check_sampled(&router_a, &publisher, &subscriber).await;
+
+            for session in [publisher, subscriber] {
+                session.close().await.unwrap();
+            }
+            router_a.close().await.unwrap();
+        });
+    }
+}
@@ -0,0 +1,116 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+#[cfg(all(feature = "zero-copy", feature = "transport_tcp"))]
+mod tests {
+    use async_std::prelude::*;
+    use async_std::task;
+    use std::time::Duration;
+    use janu::net::protocol::io::ZSliceKind;
+    use janu::net::*;
+    use janu_util::properties::config::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+    const SLEEP: Duration = Duration::from_millis(100);
+    const SETTLE: Duration = Duration::from_secs(1);
+
+    const LOCATOR: &str = "tcp/127.0.0.1:17490";
+    const MSG_COUNT: usize = 16;
+    const MSG_SIZE: usize = 1_024;
+
+    fn config(mode: &str, shm: bool) -> ConfigProperties {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, mode.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        config.insert(ZN_SHM_KEY, shm.to_string());
+        if mode == "router" {
+            config.insert(ZN_LISTENER_KEY, LOCATOR.to_string());
+        } else {
+            config.insert(ZN_PEER_KEY, LOCATOR.to_string());
+        }
+        config
+    }
+
+    async fn receive(sub: &mut Subscriber<'_>, kind: ZSliceKind) {
+        for msg_count in 0..MSG_COUNT {
+            let sample = sub
+                .receiver()
+                .next()
+                .timeout(TIMEOUT)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(sample.payload.len(), MSG_SIZE);
+            assert_eq!(sample.payload.zslices_num(), 1);
+            assert_eq!(sample.payload.get_zslice(0).unwrap().get_kind(), kind);
+            let payload = sample.payload.to_vec();
+            assert_eq!(payload[0..8], msg_count.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn routing_shm_forward_by_reference() {
+        task::block_on(async {
+            // The router and the publisher are capable of shared memory
+            let _router = open(config("router", true)).await.unwrap();
+            let publisher = open(config("client", true)).await.unwrap();
+            // A subscriber on the same host capable of shared memory and one that is not
+            let shm_client = open(config("client", true)).await.unwrap();
+            let net_client = open(config("client", false)).await.unwrap();
+
+            let sub_info = SubInfo {
+                reliability: Reliability::Reliable,
+                mode: SubMode::Push,
+                period: None,
+            };
+            let reskey: ResKey = "/test/shm".into();
+            let mut shm_sub = shm_client
+                .declare_subscriber(&reskey, &sub_info)
+                .await
+                .unwrap();
+            let mut net_sub = net_client
+                .declare_subscriber(&reskey, &sub_info)
+                .await
+                .unwrap();
+            task::sleep(SETTLE).await;
+
+            // The samples are held by the subscribers until all of them have been written
+            let mut shm =
+                SharedMemoryManager::new("routing_shm".to_string(), 2 * MSG_COUNT * MSG_SIZE)
+                    .unwrap();
+            for msg_count in 0..MSG_COUNT {
+                let mut sbuf = shm.alloc(MSG_SIZE).unwrap();
+                let bs = unsafe { sbuf.as_mut_slice() };
+                bs[0..8].copy_from_slice(&msg_count.to_le_bytes());
+                publisher.write(&reskey, sbuf.into()).await.unwrap();
+            }
+
+            // The router forwards the buffers by reference to the subscriber capable of
+            // shared memory and falls back to copying them to the other one
+            receive(&mut shm_sub, ZSliceKind::Shm).await;
+            receive(&mut net_sub, ZSliceKind::Net).await;
+
+            // Every hop released the buffers
+            let released = async {
+                loop {
+                    shm.garbage_collect();
+                    if shm.stats().iter().all(|s| s.busy_chunks == 0) {
+                        break;
+                    }
+                    task::sleep(SLEEP).await;
+                }
+            };
+            released.timeout(TIMEOUT).await.unwrap();
+        });
+    }
+}
@@ -13,14 +13,20 @@
 //
 use async_std::sync::Arc;
 use std::convert::TryInto;
+use std::time::Duration;
 use uhlc::HLC;
 use janu::net::protocol::core::rname::intersect;
 use janu::net::protocol::core::{
-    whatami, Channel, CongestionControl, PeerId, QueryConsolidation, QueryTarget, Reliability,
-    ResKey, SubInfo, SubMode, ZInt,
+    queryable, whatami, BalancingStrategy, Channel, CongestionControl, PeerId, Period,
+    QueryConsolidation, QueryTarget, Reliability, ResKey, SubInfo, SubMode, Target, ZInt,
 };
 use janu::net::protocol::io::ZBuf;
 use janu::net::protocol::proto::{DataInfo, RoutingContext};
+use janu::net::routing::egress::{EgressLimit, EgressPolicy};
+use janu::net::routing::election::{
+    self, Candidate, ClosestElection, HashElection, HomeElection, RouterElection,
+};
+use janu::net::routing::face::FaceState;
 use janu::net::routing::router::*;
 use janu::net::transport::{DummyPrimitives, Primitives};
 use janu_util::zlock;
@@ -334,6 +340,9 @@ fn clean_test() {
 
 pub struct ClientPrimitives {
     data: std::sync::Mutex<Option<ResKey>>,
+    data_count: std::sync::atomic::AtomicUsize,
+    queries: std::sync::Mutex<Vec<(ZInt, QueryTarget)>>,
+    finals: std::sync::atomic::AtomicUsize,
     mapping: std::sync::Mutex<std::collections::HashMap<ZInt, String>>,
 }
 
@@ -341,6 +350,9 @@ impl ClientPrimitives {
     pub fn new() -> ClientPrimitives {
         ClientPrimitives {
             data: std::sync::Mutex::new(None),
+            data_count: std::sync::atomic::AtomicUsize::new(0),
+            queries: std::sync::Mutex::new(vec![]),
+            finals: std::sync::atomic::AtomicUsize::new(0),
             mapping: std::sync::Mutex::new(std::collections::HashMap::new()),
         }
     }
@@ -376,6 +388,18 @@ impl ClientPrimitives {
             .map(|data| self.get_name(data))
     }
 
+    fn get_data_count(&self) -> usize {
+        self.data_count.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    fn get_queries(&self) -> Vec<(ZInt, QueryTarget)> {
+        self.queries.lock().unwrap().clone()
+    }
+
+    fn get_finals_count(&self) -> usize {
+        self.finals.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
     #[allow(dead_code)]
     fn get_last_key(&self) -> Option<ResKey> {
         self.data.lock().unwrap().as_ref().cloned()
@@ -423,17 +447,20 @@ impl Primitives for ClientPrimitives {
         _routing_context: Option<RoutingContext>,
     ) {
         *zlock!(self.data) = Some(reskey.clone());
+        self.data_count
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
     }
 
     fn send_query(
         &self,
         _reskey: &ResKey,
         _predicate: &str,
-        _qid: ZInt,
-        _target: QueryTarget,
+        qid: ZInt,
+        target: QueryTarget,
         _consolidation: QueryConsolidation,
         _routing_context: Option<RoutingContext>,
     ) {
+        zlock!(self.queries).push((qid, target));
     }
 
     fn send_reply_data(
@@ -446,7 +473,10 @@ impl Primitives for ClientPrimitives {
         _payload: ZBuf,
     ) {
     }
-    fn send_reply_final(&self, _qid: ZInt) {}
+    fn send_reply_final(&self, _qid: ZInt) {
+        self.finals
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
 
     fn send_pull(
         &self,
@@ -690,3 +720,557 @@ fn client_test() {
     // mapping strategy check
     // assert_eq!(primitives2.get_last_key().unwrap(), ResKey::RIdWithSuffix(31, "/z2_pub1".to_string()));
 }
+
+#[test]
+fn periodic_subscription_test() {
+    let router = Router::new(
+        PeerId::new(0, [0; 16]),
+        whatami::CLIENT,
+        Some(Arc::new(HLC::default())),
+    );
+    let sub_info = |origin: ZInt, period: ZInt| SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        period: Some(Period {
+            origin,
+            period,
+            duration: 0,
+        }),
+    };
+    let sub_key = ResKey::RName("/test/periodic/**".to_string());
+
+    let primitives0 = Arc::new(ClientPrimitives::new());
+    let face0 = router.new_primitives(primitives0);
+
+    // The sampling task of this subscription does not start during the test:
+    // its sampled values are flushed by pulls instead
+    let primitives1 = Arc::new(ClientPrimitives::new());
+    let face1 = router.new_primitives(primitives1.clone());
+    face1.decl_subscriber(&sub_key, &sub_info(3_600_000, 100), None);
+
+    let publish = |name: &str| {
+        face0.send_data(
+            &ResKey::RName(name.to_string()),
+            ZBuf::new(),
+            Channel::default(),
+            CongestionControl::default(),
+            None,
+            None,
+        )
+    };
+
+    // Only the latest value of each key gets delivered
+    for _ in 0..10 {
+        publish("/test/periodic/a");
+    }
+    publish("/test/periodic/b");
+    assert_eq!(primitives1.get_data_count(), 0);
+    face1.send_pull(true, &sub_key, 0, &None);
+    assert_eq!(primitives1.get_data_count(), 2);
+
+    for _ in 0..10 {
+        publish("/test/periodic/a");
+    }
+    face1.send_pull(true, &sub_key, 1, &None);
+    assert_eq!(primitives1.get_data_count(), 3);
+    assert_eq!(primitives1.get_last_name().unwrap(), "/test/periodic/a");
+    face1.send_pull(true, &sub_key, 2, &None);
+    assert_eq!(primitives1.get_data_count(), 3);
+
+    // Nothing gets cached once the subscription is undeclared
+    face1.forget_subscriber(&sub_key, None);
+    publish("/test/periodic/a");
+    face1.send_pull(true, &sub_key, 3, &None);
+    assert_eq!(primitives1.get_data_count(), 3);
+
+    // The sampling task delivers at most one value per key and per period
+    const PERIOD: ZInt = 20;
+    let primitives2 = Arc::new(ClientPrimitives::new());
+    let face2 = router.new_primitives(primitives2.clone());
+    face2.decl_subscriber(&sub_key, &sub_info(0, PERIOD), None);
+    let start = std::time::Instant::now();
+    while primitives2.get_data_count() < 3 {
+        assert!(start.elapsed() < Duration::from_secs(10));
+        publish("/test/periodic/a");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    let elapsed = start.elapsed().as_millis() as usize;
+    assert!(primitives2.get_data_count() <= elapsed / PERIOD as usize + 1);
+    face2.forget_subscriber(&sub_key, None);
+
+    // The data is delivered as it is received during the delivery window
+    let primitives3 = Arc::new(ClientPrimitives::new());
+    let face3 = router.new_primitives(primitives3.clone());
+    let window = SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        period: Some(Period {
+            origin: 0,
+            period: 3_600_000,
+            duration: 3_600_000,
+        }),
+    };
+    face3.decl_subscriber(&sub_key, &window, None);
+    let start = std::time::Instant::now();
+    while primitives3.get_data_count() == 0 {
+        assert!(start.elapsed() < Duration::from_secs(10));
+        publish("/test/periodic/a");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    let count = primitives3.get_data_count();
+    for _ in 0..10 {
+        publish("/test/periodic/a");
+    }
+    assert_eq!(primitives3.get_data_count(), count + 10);
+    face3.forget_subscriber(&sub_key, None);
+}
+
+#[test]
+fn egress_policies_test() {
+    let policies = EgressPolicy::parse_list(
+        "/test/rate/**=10msg/s, /test/bw/**=10B/s@client, /test/other=1msg/s@router, invalid=1B/s, /test/x=1",
+    );
+    assert_eq!(policies.len(), 3);
+    assert_eq!(policies[0].limit, EgressLimit::Rate(10.0));
+    assert_eq!(policies[1].limit, EgressLimit::Bandwidth(10));
+
+    let router = Router::new(
+        PeerId::new(0, [0; 16]),
+        whatami::CLIENT,
+        Some(Arc::new(HLC::default())),
+    );
+    router.init_egress_policies(policies.clone());
+    let sub_info = SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        period: None,
+    };
+
+    let primitives0 = Arc::new(ClientPrimitives::new());
+    let face0 = router.new_primitives(primitives0);
+
+    let primitives1 = Arc::new(ClientPrimitives::new());
+    let face1 = router.new_primitives(primitives1.clone());
+    face1.decl_subscriber(&ResKey::RName("/test/**".to_string()), &sub_info, None);
+
+    let publish = |name: &str, payload: &[u8]| {
+        face0.send_data(
+            &ResKey::RName(name.to_string()),
+            ZBuf::from(payload),
+            Channel::default(),
+            CongestionControl::default(),
+            None,
+            None,
+        )
+    };
+
+    // The first message goes through, the latest one is delayed and the others are dropped
+    let start = std::time::Instant::now();
+    for _ in 0..10 {
+        publish("/test/rate/a", &[0]);
+    }
+    assert_eq!(primitives1.get_data_count(), 1);
+    while primitives1.get_data_count() < 2 {
+        assert!(start.elapsed() < Duration::from_secs(10));
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert_eq!(policies[0].forwarded(), 2);
+    assert_eq!(policies[0].dropped(), 8);
+
+    // Messages exceeding the bandwidth are dropped
+    for _ in 0..4 {
+        publish("/test/bw/a", &[0; 4]);
+    }
+    assert_eq!(primitives1.get_data_count(), 4);
+    assert_eq!(policies[1].forwarded(), 2);
+    assert_eq!(policies[1].dropped(), 2);
+
+    // Policies only apply to their key expressions and faces
+    for _ in 0..4 {
+        publish("/test/other", &[0]);
+    }
+    assert_eq!(primitives1.get_data_count(), 8);
+    assert_eq!(policies[2].forwarded(), 0);
+    assert_eq!(policies[2].dropped(), 0);
+}
+
+#[test]
+fn egress_policies_combined_test() {
+    let policies = EgressPolicy::parse_list("/test/**=20msg/s, /test/**=8B/s");
+    let router = Router::new(
+        PeerId::new(0, [0; 16]),
+        whatami::CLIENT,
+        Some(Arc::new(HLC::default())),
+    );
+    router.init_egress_policies(policies.clone());
+    let sub_info = SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        period: None,
+    };
+
+    let primitives0 = Arc::new(ClientPrimitives::new());
+    let face0 = router.new_primitives(primitives0);
+
+    let primitives1 = Arc::new(ClientPrimitives::new());
+    let face1 = router.new_primitives(primitives1.clone());
+    face1.decl_subscriber(&ResKey::RName("/test/**".to_string()), &sub_info, None);
+
+    let publish = |name: &str| {
+        face0.send_data(
+            &ResKey::RName(name.to_string()),
+            ZBuf::from(&[0u8; 4][..]),
+            Channel::default(),
+            CongestionControl::default(),
+            None,
+            None,
+        )
+    };
+
+    // A message delayed by the rate doesn't consume bandwidth
+    publish("/test/a");
+    publish("/test/a");
+    publish("/test/b");
+    assert_eq!(primitives1.get_data_count(), 2);
+    assert_eq!(primitives1.get_last_name().unwrap(), "/test/b");
+    assert_eq!(policies[1].forwarded(), 2);
+
+    // The delayed message is dropped once the bandwidth is exhausted
+    let start = std::time::Instant::now();
+    while policies[1].dropped() < 1 {
+        assert!(start.elapsed() < Duration::from_secs(10));
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(primitives1.get_data_count(), 2);
+    assert_eq!(policies[0].forwarded(), 2);
+    assert_eq!(policies[0].dropped(), 0);
+    assert_eq!(policies[1].forwarded(), 2);
+}
+
+#[test]
+fn router_election_test() {
+    let routers: Vec<PeerId> = (1..=4).map(|i| PeerId::new(1, [i; 16])).collect();
+    let candidates = |costs: &[Option<ZInt>]| -> Vec<Candidate> {
+        routers
+            .iter()
+            .zip(costs.iter())
+            .map(|(pid, cost)| Candidate { pid, cost: *cost })
+            .collect()
+    };
+    let all = candidates(&[None; 4]);
+    let names: Vec<String> = (0..32).map(|i| format!("/test/{}", i)).collect();
+
+    // The hash election is deterministic and only reassigns the resources
+    // of the routers leaving
+    let hash = HashElection;
+    let reversed: Vec<Candidate> = all.iter().rev().copied().collect();
+    let mut elected = vec![];
+    for name in &names {
+        let pid = hash.elect(name, &all);
+        assert_eq!(pid, hash.elect(name, &reversed));
+        elected.push(pid);
+    }
+    assert!(routers.iter().all(|pid| elected.contains(&pid)));
+    let leaving = &routers[0];
+    let remaining: Vec<Candidate> = all.iter().filter(|c| c.pid != leaving).copied().collect();
+    for (name, pid) in names.iter().zip(elected.iter()) {
+        if *pid != leaving {
+            assert_eq!(hash.elect(name, &remaining), *pid);
+        }
+    }
+
+    // The closest election elects the router closest to the source
+    let closest = ClosestElection;
+    let costs = candidates(&[Some(30), None, Some(15), Some(20)]);
+    for name in &names {
+        assert_eq!(closest.elect(name, &costs), &routers[2]);
+    }
+    // Routers at the same cost are elected by id, whatever the order of the candidates
+    let tied = candidates(&[None, Some(10), Some(20), Some(10)]);
+    let tied_reversed: Vec<Candidate> = tied.iter().rev().copied().collect();
+    for name in &names {
+        assert_eq!(closest.elect(name, &tied), &routers[1]);
+        assert_eq!(closest.elect(name, &tied_reversed), &routers[1]);
+    }
+    // Routers with unknown costs are elected as with the hash election
+    for name in &names {
+        assert_eq!(closest.elect(name, &all), hash.elect(name, &all));
+    }
+
+    // The home election elects the first matching home if it is a candidate
+    let homes = format!(
+        "/test/1=0303030303030303, /test/**={}, invalid, /x=zz",
+        routers[1]
+    );
+    let home = HomeElection::parse_list(&homes);
+    assert_eq!(home.homes().len(), 2);
+    assert_eq!(home.elect("/test/1", &all), hash.elect("/test/1", &all));
+    assert_eq!(home.elect("/test/2", &all), &routers[1]);
+    assert_eq!(home.elect("/other", &all), hash.elect("/other", &all));
+    let without_home: Vec<Candidate> = all
+        .iter()
+        .filter(|c| *c.pid != routers[1])
+        .copied()
+        .collect();
+    assert_eq!(
+        home.elect("/test/2", &without_home),
+        hash.elect("/test/2", &without_home)
+    );
+
+    assert_eq!(
+        election::from_config("closest", None).unwrap().name(),
+        "closest"
+    );
+    assert_eq!(
+        election::from_config("home", Some(&homes)).unwrap().name(),
+        "home"
+    );
+    assert!(election::from_config("random", None).is_err());
+}
+
+#[test]
+fn balanced_queries_test() {
+    let router = Router::new(
+        PeerId::new(0, [0; 16]),
+        whatami::CLIENT,
+        Some(Arc::new(HLC::default())),
+    );
+    let reskey = ResKey::RName("/test/eval".to_string());
+
+    let querier_primitives = Arc::new(ClientPrimitives::new());
+    let querier = router.new_primitives(querier_primitives.clone());
+
+    let mut primitives = vec![];
+    let mut faces = vec![];
+    for _ in 0..3 {
+        let p = Arc::new(ClientPrimitives::new());
+        let face = router.new_primitives(p.clone());
+        face.decl_queryable(&reskey, queryable::EVAL, None);
+        primitives.push(p);
+        faces.push(face);
+    }
+    let counts = || -> Vec<usize> { primitives.iter().map(|p| p.get_queries().len()).collect() };
+    let mut replied = [0; 3];
+    let mut reply = |i: usize| {
+        let queries = primitives[i].get_queries();
+        for (qid, _) in &queries[replied[i]..] {
+            faces[i].send_reply_final(*qid);
+        }
+        replied[i] = queries.len();
+    };
+    let query = |qid: ZInt, n: ZInt, strategy: BalancingStrategy| {
+        querier.send_query(
+            &reskey,
+            "",
+            qid,
+            QueryTarget {
+                kind: queryable::ALL_KINDS,
+                target: Target::Balanced { n, strategy },
+            },
+            QueryConsolidation::default(),
+            None,
+        )
+    };
+
+    // Round robin: each query is routed to exactly one queryable, in turn
+    for qid in 0..6 {
+        query(qid, 1, BalancingStrategy::RoundRobin);
+    }
+    assert_eq!(counts(), vec![2, 2, 2]);
+    assert_eq!(querier_primitives.get_finals_count(), 0);
+
+    // Least pending: queries are routed to the queryables with the fewest pending queries
+    reply(0);
+    reply(1);
+    assert_eq!(querier_primitives.get_finals_count(), 4);
+    query(6, 1, BalancingStrategy::LeastPending);
+    query(7, 1, BalancingStrategy::LeastPending);
+    assert_eq!(counts(), vec![3, 3, 2]);
+
+    // Complete { n } semantics: a query is routed to n distinct queryables
+    let before = counts();
+    query(8, 2, BalancingStrategy::RoundRobin);
+    let after = counts();
+    assert_eq!((0..3).filter(|i| after[*i] == before[*i] + 1).count(), 2);
+    for i in (0..3).filter(|i| after[*i] > before[*i]) {
+        assert_eq!(
+            primitives[i].get_queries().last().unwrap().1.target,
+            Target::Balanced {
+                n: 1,
+                strategy: BalancingStrategy::RoundRobin
+            }
+        );
+    }
+    for i in 0..3 {
+        reply(i);
+    }
+    assert_eq!(querier_primitives.get_finals_count(), 9);
+
+    // A query pending on a queryable closing before replying is routed to another one
+    let before = counts();
+    query(9, 1, BalancingStrategy::RoundRobin);
+    let after = counts();
+    let chosen = (0..3).find(|i| after[*i] > before[*i]).unwrap();
+    faces[chosen].send_close();
+    let retried = counts();
+    let other = (0..3).find(|i| retried[*i] > after[*i]).unwrap();
+    assert_ne!(other, chosen);
+    assert_eq!(querier_primitives.get_finals_count(), 9);
+
+    // The query is finalized once the other queryable replies
+    reply(other);
+    assert_eq!(querier_primitives.get_finals_count(), 10);
+}
+
+#[test]
+fn balanced_queries_rotation_test() {
+    let router = Router::new(
+        PeerId::new(0, [0; 16]),
+        whatami::CLIENT,
+        Some(Arc::new(HLC::default())),
+    );
+    let qabl_key = ResKey::RName("/test/*".to_string());
+
+    // The querying session declares a queryable too
+    let querier_primitives = Arc::new(ClientPrimitives::new());
+    let querier = router.new_primitives(querier_primitives.clone());
+    querier.decl_queryable(&qabl_key, queryable::EVAL, None);
+    let other_primitives = Arc::new(ClientPrimitives::new());
+    let other = router.new_primitives(other_primitives.clone());
+    other.decl_queryable(&qabl_key, queryable::EVAL, None);
+    let primitives = [querier_primitives.clone(), other_primitives];
+    let faces = [querier.clone(), other];
+
+    let counts = || -> Vec<usize> { primitives.iter().map(|p| p.get_queries().len()).collect() };
+    let query = |name: &str, qid: ZInt| -> usize {
+        let before = counts();
+        querier.send_query(
+            &ResKey::RName(name.to_string()),
+            "",
+            qid,
+            QueryTarget {
+                kind: queryable::ALL_KINDS,
+                target: Target::Balanced {
+                    n: 1,
+                    strategy: BalancingStrategy::RoundRobin,
+                },
+            },
+            QueryConsolidation::default(),
+            None,
+        );
+        let after = counts();
+        assert_eq!(
+            after.iter().sum::<usize>(),
+            before.iter().sum::<usize>() + 1
+        );
+        (0..2).find(|i| after[*i] > before[*i]).unwrap()
+    };
+
+    // The queries on each key expression are routed in turn to the matching
+    // queryables, including the one of the querying session
+    let mut chosen_a = vec![];
+    let mut chosen_b = vec![];
+    for qid in 0..2 {
+        chosen_a.push(query("/test/a", 2 * qid));
+        chosen_b.push(query("/test/b", 2 * qid + 1));
+    }
+    chosen_a.sort_unstable();
+    chosen_b.sort_unstable();
+    assert_eq!(chosen_a, vec![0, 1]);
+    assert_eq!(chosen_b, vec![0, 1]);
+    for (face, primitives) in faces.iter().zip(primitives.iter()) {
+        for (qid, _) in primitives.get_queries() {
+            face.send_reply_final(qid);
+        }
+    }
+    assert_eq!(querier_primitives.get_finals_count(), 4);
+
+    // A query pending on a queryable undeclared before replying is routed to another one
+    let chosen = query("/test/a", 4);
+    let before = counts();
+    faces[chosen].forget_queryable(&qabl_key, None);
+    let after = counts();
+    assert_eq!(after[1 - chosen], before[1 - chosen] + 1);
+    assert_eq!(after[chosen], before[chosen]);
+
+    // The late final reply of the undeclared queryable does not finalize the query
+    let (qid, _) = *primitives[chosen].get_queries().last().unwrap();
+    faces[chosen].send_reply_final(qid);
+    assert_eq!(querier_primitives.get_finals_count(), 4);
+    let (qid, _) = *primitives[1 - chosen].get_queries().last().unwrap();
+    faces[1 - chosen].send_reply_final(qid);
+    assert_eq!(querier_primitives.get_finals_count(), 5);
+}
+
+#[test]
+fn balanced_queries_from_router_test() {
+    let mut tables = Tables::new(
+        PeerId::new(0, [0; 16]),
+        whatami::CLIENT,
+        Some(Arc::new(HLC::default())),
+    );
+
+    // The router and a local session both declare a queryable
+    let router_primitives = Arc::new(ClientPrimitives::new());
+    let router = tables
+        .open_face(
+            PeerId::new(1, [1; 16]),
+            whatami::ROUTER,
+            router_primitives.clone(),
+        )
+        .upgrade()
+        .unwrap();
+    let session_primitives = Arc::new(ClientPrimitives::new());
+    let session = tables
+        .open_face(
+            PeerId::new(0, [0; 16]),
+            whatami::CLIENT,
+            session_primitives.clone(),
+        )
+        .upgrade()
+        .unwrap();
+    for face in [&router, &session].iter() {
+        declare_client_queryable(
+            &mut tables,
+            &mut (*face).clone(),
+            0,
+            "/test/eval",
+            queryable::EVAL,
+        );
+    }
+    let query = |tables: &mut Tables, face: &Arc<FaceState>, qid: ZInt| {
+        route_query(
+            tables,
+            face,
+            0,
+            "/test/eval",
+            "",
+            qid,
+            QueryTarget {
+                kind: queryable::ALL_KINDS,
+                target: Target::Balanced {
+                    n: 1,
+                    strategy: BalancingStrategy::RoundRobin,
+                },
+            },
+            QueryConsolidation::default(),
+            None,
+        )
+    };
+
+    // The queries of the router were balanced by the router with its own
+    // queryables, hence they are never routed back to it
+    for qid in 0..2 {
+        query(&mut tables, &router, qid);
+    }
+    assert_eq!(router_primitives.get_queries().len(), 0);
+    assert_eq!(session_primitives.get_queries().len(), 2);
+
+    // Whereas the queries of the local session are balanced with its own queryable
+    for qid in 0..2 {
+        query(&mut tables, &session, qid);
+    }
+    assert_eq!(router_primitives.get_queries().len(), 1);
+    assert_eq!(session_primitives.get_queries().len(), 3);
+}
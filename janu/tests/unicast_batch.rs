@@ -0,0 +1,278 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::sync::Arc;
+use async_std::task;
+use std::any::Any;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use janu::net::link::{EndPoint, Link};
+use janu::net::protocol::core::{
+    whatami, Channel, CongestionControl, PeerId, Priority, Reliability, ResKey,
+};
+use janu::net::protocol::io::ZBuf;
+use janu::net::protocol::proto::JanuMessage;
+use janu::net::transport::{
+    DummyTransportEventHandler, TransportEventHandler, TransportManager, TransportManagerConfig,
+    TransportMulticast, TransportMulticastEventHandler, TransportPeer, TransportPeerEventHandler,
+    TransportUnicast,
+};
+use janu_util::core::ZResult;
+use janu_util::zasync_executor_init;
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+const SLEEP: Duration = Duration::from_secs(1);
+const SLEEP_COUNT: Duration = Duration::from_millis(10);
+
+// A handful of messages larger than the 65_535 bytes of the legacy batches
+const MSG_COUNT: usize = 5;
+const MSG_SIZE: usize = 131_072;
+
+const BATCH_SIZE_LEGACY: u32 = 65_535;
+const BATCH_SIZE_LARGE: u32 = 4 * 1_048_576;
+
+// Transport Handler for the router
+struct SHRouter {
+    count: Arc<AtomicUsize>,
+}
+
+impl Default for SHRouter {
+    fn default() -> Self {
+        Self {
+            count: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+impl SHRouter {
+    fn get_count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+}
+
+impl TransportEventHandler for SHRouter {
+    fn new_unicast(
+        &self,
+        _peer: TransportPeer,
+        _transport: TransportUnicast,
+    ) -> ZResult<Arc<dyn TransportPeerEventHandler>> {
+        let arc = Arc::new(SCRouter::new(self.count.clone()));
+        Ok(arc)
+    }
+
+    fn new_multicast(
+        &self,
+        _transport: TransportMulticast,
+    ) -> ZResult<Arc<dyn TransportMulticastEventHandler>> {
+        panic!();
+    }
+}
+
+// Transport Callback for the router
+pub struct SCRouter {
+    count: Arc<AtomicUsize>,
+}
+
+impl SCRouter {
+    pub fn new(count: Arc<AtomicUsize>) -> Self {
+        Self { count }
+    }
+}
+
+impl TransportPeerEventHandler for SCRouter {
+    fn handle_message(&self, _message: JanuMessage) -> ZResult<()> {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn new_link(&self, _link: Link) {}
+    fn del_link(&self, _link: Link) {}
+    fn closing(&self) {}
+    fn closed(&self) {}
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+async fn run(endpoint: &EndPoint, router_batch: u32, client_batch: u32, defrag_buff_size: usize) {
+    // Define client and router IDs
+    let client_id = PeerId::new(1, [0u8; PeerId::MAX_SIZE]);
+    let router_id = PeerId::new(1, [1u8; PeerId::MAX_SIZE]);
+
+    // Create the router transport manager
+    let router_handler = Arc::new(SHRouter::default());
+    let config = TransportManagerConfig::builder()
+        .pid(router_id)
+        .whatami(whatami::ROUTER)
+        .batch_size(router_batch)
+        .defrag_buff_size(defrag_buff_size)
+        .build(router_handler.clone());
+    let router_manager = TransportManager::new(config);
+
+    // Create the client transport manager
+    let config = TransportManagerConfig::builder()
+        .whatami(whatami::CLIENT)
+        .pid(client_id)
+        .batch_size(client_batch)
+        .build(Arc::new(DummyTransportEventHandler));
+    let client_manager = TransportManager::new(config);
+
+    // Create the listener on the router
+    println!("Add locator: {}", endpoint);
+    let _ = router_manager
+        .add_listener(endpoint.clone())
+        .timeout(TIMEOUT)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Open transport -> This should be accepted
+    println!(
+        "Opening transport with {} (router batch: {}, client batch: {})",
+        endpoint, router_batch, client_batch
+    );
+    let _ = client_manager
+        .open_transport(endpoint.clone())
+        .timeout(TIMEOUT)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let client_transport = client_manager.get_transport(&router_id).unwrap();
+
+    // Create the message to send
+    let key = ResKey::RName("/test".to_string());
+    let payload = ZBuf::from(vec![0u8; MSG_SIZE]);
+    let channel = Channel {
+        priority: Priority::default(),
+        reliability: Reliability::Reliable,
+    };
+    let message = JanuMessage::make_data(
+        key,
+        payload,
+        channel,
+        CongestionControl::Block,
+        None,
+        None,
+        None,
+        None,
+    );
+
+    println!("Sending {} messages of {} bytes", MSG_COUNT, MSG_SIZE);
+    for _ in 0..MSG_COUNT {
+        client_transport.schedule(message.clone()).unwrap();
+    }
+
+    let count = async {
+        while router_handler.get_count() != MSG_COUNT {
+            task::sleep(SLEEP_COUNT).await;
+        }
+    };
+    count.timeout(TIMEOUT).await.unwrap();
+
+    // Close the transport
+    println!("Closing transport with {}", endpoint);
+    client_transport
+        .close()
+        .timeout(TIMEOUT)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Wait a little bit
+    task::sleep(SLEEP).await;
+
+    // Stop the locators on the manager
+    println!("Del locator: {}", endpoint);
+    router_manager
+        .del_listener(endpoint)
+        .timeout(TIMEOUT)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Wait a little bit
+    task::sleep(SLEEP).await;
+}
+
+#[cfg(feature = "transport_tcp")]
+#[test]
+fn transport_unicast_large_batch_tcp() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    let endpoint: EndPoint = "tcp/127.0.0.1:16447".parse().unwrap();
+    task::block_on(async {
+        // Large batches are agreed: messages are not fragmented and hence
+        // they are received despite the small defragmentation buffer
+        run(&endpoint, BATCH_SIZE_LARGE, BATCH_SIZE_LARGE, 65_535).await;
+        run(&endpoint, BATCH_SIZE_LARGE, 2 * MSG_SIZE as u32, 65_535).await;
+        // One of the peers does not use large batches: messages are fragmented
+        run(&endpoint, BATCH_SIZE_LEGACY, BATCH_SIZE_LARGE, 2 * MSG_SIZE).await;
+        run(&endpoint, BATCH_SIZE_LARGE, BATCH_SIZE_LEGACY, 2 * MSG_SIZE).await;
+    });
+}
+
+#[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
+#[test]
+fn transport_unicast_large_batch_unix() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    let f1 = "janu-test-unix-socket-large-batch.sock";
+    let _ = std::fs::remove_file(f1);
+    let endpoint: EndPoint = format!("unixsock-stream/{}", f1).parse().unwrap();
+    task::block_on(async {
+        run(&endpoint, BATCH_SIZE_LARGE, BATCH_SIZE_LARGE, 65_535).await;
+        run(&endpoint, BATCH_SIZE_LEGACY, BATCH_SIZE_LARGE, 2 * MSG_SIZE).await;
+    });
+    let _ = std::fs::remove_file(f1);
+    let _ = std::fs::remove_file(format!("{}.lock", f1));
+}
+
+#[cfg(feature = "transport_quic")]
+#[test]
+fn transport_unicast_large_batch_quic() {
+    use janu::net::link::quic::config::*;
+    use janu_util::properties::Properties;
+
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    // Generate a self-signed certificate for localhost, also used as root CA by the client
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let mut endpoint: EndPoint = "quic/localhost:16448".parse().unwrap();
+    let mut config = Properties::default();
+    config.insert(
+        TLS_ROOT_CA_CERTIFICATE_RAW.to_string(),
+        cert.serialize_pem().unwrap(),
+    );
+    config.insert(
+        TLS_SERVER_PRIVATE_KEY_RAW.to_string(),
+        cert.serialize_private_key_pem(),
+    );
+    config.insert(
+        TLS_SERVER_CERTIFICATE_RAW.to_string(),
+        cert.serialize_pem().unwrap(),
+    );
+    endpoint.config = Some(Arc::new(config));
+    task::block_on(async {
+        run(&endpoint, BATCH_SIZE_LARGE, BATCH_SIZE_LARGE, 65_535).await;
+        run(&endpoint, BATCH_SIZE_LEGACY, BATCH_SIZE_LARGE, 2 * MSG_SIZE).await;
+    });
+}
@@ -0,0 +1,351 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+#[cfg(feature = "transport_mem")]
+mod tests {
+    use async_std::prelude::*;
+    use async_std::sync::Arc;
+    use async_std::task;
+    use std::any::Any;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+    use janu::net::link::{EndPoint, Link};
+    use janu::net::protocol::core::{
+        whatami, Channel, CongestionControl, PeerId, Priority, Reliability, ResKey,
+    };
+    use janu::net::protocol::io::ZBuf;
+    use janu::net::protocol::proto::{Data, JanuBody, JanuMessage};
+    use janu::net::transport::{
+        TransportEventHandler, TransportManager, TransportManagerConfig, TransportMulticast,
+        TransportMulticastEventHandler, TransportPeer, TransportPeerEventHandler, TransportUnicast,
+    };
+    use janu_util::core::ZResult;
+    use janu_util::zasync_executor_init;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const TIMEOUT: Duration = Duration::from_secs(60);
+    const SLEEP: Duration = Duration::from_secs(1);
+    const SLEEP_COUNT: Duration = Duration::from_millis(10);
+    const SLEEP_SEND: Duration = Duration::from_millis(5);
+
+    const MSG_COUNT: usize = 100;
+    const MSG_SIZE: usize = 1_024;
+
+    // Transport Handler for the router
+    #[derive(Default)]
+    struct SHRouter {
+        count: Arc<AtomicUsize>,
+        bytes: Arc<AtomicUsize>,
+    }
+
+    impl TransportEventHandler for SHRouter {
+        fn new_unicast(
+            &self,
+            _peer: TransportPeer,
+            _transport: TransportUnicast,
+        ) -> ZResult<Arc<dyn TransportPeerEventHandler>> {
+            Ok(Arc::new(SCPeer {
+                count: self.count.clone(),
+                bytes: self.bytes.clone(),
+            }))
+        }
+
+        fn new_multicast(
+            &self,
+            _transport: TransportMulticast,
+        ) -> ZResult<Arc<dyn TransportMulticastEventHandler>> {
+            panic!();
+        }
+    }
+
+    // Transport Handler for the client
+    struct SHClient;
+
+    impl TransportEventHandler for SHClient {
+        fn new_unicast(
+            &self,
+            _peer: TransportPeer,
+            _transport: TransportUnicast,
+        ) -> ZResult<Arc<dyn TransportPeerEventHandler>> {
+            Ok(Arc::new(SCPeer::default()))
+        }
+
+        fn new_multicast(
+            &self,
+            _transport: TransportMulticast,
+        ) -> ZResult<Arc<dyn TransportMulticastEventHandler>> {
+            panic!();
+        }
+    }
+
+    // Transport Callback counting the received messages and payload bytes
+    #[derive(Default)]
+    struct SCPeer {
+        count: Arc<AtomicUsize>,
+        bytes: Arc<AtomicUsize>,
+    }
+
+    impl TransportPeerEventHandler for SCPeer {
+        fn handle_message(&self, message: JanuMessage) -> ZResult<()> {
+            if let JanuBody::Data(Data { payload, .. }) = &message.body {
+                self.bytes.fetch_add(payload.len(), Ordering::SeqCst);
+            }
+            self.count.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn new_link(&self, _link: Link) {}
+        fn del_link(&self, _link: Link) {}
+        fn closing(&self) {}
+        fn closed(&self) {}
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    fn transport_manager(id: u8, handler: Arc<dyn TransportEventHandler>) -> TransportManager {
+        let config = TransportManagerConfig::builder()
+            .whatami(whatami::PEER)
+            .pid(PeerId::new(1, [id; PeerId::MAX_SIZE]))
+            .build(handler);
+        TransportManager::new(config)
+    }
+
+    fn message(size: usize, reliability: Reliability) -> JanuMessage {
+        JanuMessage::make_data(
+            ResKey::RName("/test".to_string()),
+            ZBuf::from(vec![0u8; size]),
+            Channel {
+                priority: Priority::default(),
+                reliability,
+            },
+            CongestionControl::Block,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    async fn wait_count(handler: &SHRouter, count: usize) {
+        let check = async {
+            while handler.count.load(Ordering::SeqCst) < count {
+                task::sleep(SLEEP_COUNT).await;
+            }
+        };
+        check.timeout(TIMEOUT).await.unwrap();
+    }
+
+    struct MemRun {
+        router_manager: TransportManager,
+        router_handler: Arc<SHRouter>,
+        client_transport: TransportUnicast,
+        listen: EndPoint,
+    }
+
+    async fn open(listen: &str, connect: &str) -> MemRun {
+        let listen: EndPoint = listen.parse().unwrap();
+        let connect: EndPoint = connect.parse().unwrap();
+
+        let router_handler = Arc::new(SHRouter::default());
+        let router_manager = transport_manager(1, router_handler.clone());
+        let client_manager = transport_manager(0, Arc::new(SHClient));
+
+        let _ = router_manager
+            .add_listener(listen.clone())
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        let client_transport = client_manager
+            .open_transport(connect)
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+
+        MemRun {
+            router_manager,
+            router_handler,
+            client_transport,
+            listen,
+        }
+    }
+
+    async fn close(run: MemRun) {
+        run.client_transport
+            .close()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        task::sleep(SLEEP).await;
+        run.router_manager
+            .del_listener(&run.listen)
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn transport_mem_config() {
+        task::block_on(async {
+            zasync_executor_init!();
+        });
+
+        task::block_on(async {
+            let manager = transport_manager(0, Arc::new(SHClient));
+
+            // No listener to rendezvous with
+            let endpoint: EndPoint = "mem/janu-test-mem-config".parse().unwrap();
+            assert!(manager.open_transport(endpoint.clone()).await.is_err());
+
+            // Invalid configurations
+            for config in ["loss=200", "mtu=0", "latency=-1", "bandwidth=0"].iter() {
+                let invalid: EndPoint = format!("{}#{}", endpoint, config).parse().unwrap();
+                assert!(manager.add_listener(invalid).await.is_err());
+            }
+
+            // Names are unique in the process
+            let other = transport_manager(1, Arc::new(SHClient));
+            manager.add_listener(endpoint.clone()).await.unwrap();
+            assert!(other.add_listener(endpoint.clone()).await.is_err());
+            manager.del_listener(&endpoint).await.unwrap();
+            other.add_listener(endpoint.clone()).await.unwrap();
+            other.del_listener(&endpoint).await.unwrap();
+        });
+    }
+
+    #[test]
+    fn transport_mem_mtu() {
+        task::block_on(async {
+            zasync_executor_init!();
+        });
+
+        task::block_on(async {
+            // The smallest MTU applies and large messages are fragmented
+            let run = open(
+                "mem/janu-test-mem-mtu#mtu=512",
+                "mem/janu-test-mem-mtu#mtu=1024",
+            )
+            .await;
+            let links = run.client_transport.get_links().unwrap();
+            assert_eq!(links.len(), 1);
+            assert_eq!(links[0].mtu, 512);
+            assert!(links[0].is_reliable);
+
+            let size = 16 * MSG_SIZE;
+            for _ in 0..MSG_COUNT {
+                run.client_transport
+                    .schedule(message(size, Reliability::Reliable))
+                    .unwrap();
+            }
+            wait_count(&run.router_handler, MSG_COUNT).await;
+            assert_eq!(
+                run.router_handler.bytes.load(Ordering::SeqCst),
+                MSG_COUNT * size
+            );
+            close(run).await;
+        });
+    }
+
+    #[test]
+    fn transport_mem_loss() {
+        task::block_on(async {
+            zasync_executor_init!();
+        });
+
+        task::block_on(async {
+            // Pick a seed that does not drop the InitSyn and OpenSyn messages
+            let loss = 30.0;
+            let seed = (0..)
+                .find(|seed| {
+                    let mut rng = StdRng::seed_from_u64(*seed);
+                    (0..2).all(|_| rng.gen::<f64>() * 100.0 >= loss)
+                })
+                .unwrap();
+            let run = open(
+                "mem/janu-test-mem-loss",
+                &format!("mem/janu-test-mem-loss#loss={};seed={}", loss, seed),
+            )
+            .await;
+            let links = run.client_transport.get_links().unwrap();
+            assert!(!links[0].is_reliable);
+
+            for _ in 0..MSG_COUNT {
+                run.client_transport
+                    .schedule(message(MSG_SIZE, Reliability::BestEffort))
+                    .unwrap();
+                // Send one message per batch
+                task::sleep(SLEEP_SEND).await;
+            }
+            task::sleep(SLEEP).await;
+            let received = run.router_handler.count.load(Ordering::SeqCst);
+            println!(
+                "Received {}/{} messages over a lossy link",
+                received, MSG_COUNT
+            );
+            assert!(received > 0 && received < MSG_COUNT);
+            close(run).await;
+        });
+    }
+
+    #[test]
+    fn transport_mem_latency_bandwidth() {
+        task::block_on(async {
+            zasync_executor_init!();
+        });
+
+        task::block_on(async {
+            let latency = Duration::from_millis(100);
+            let bandwidth = MSG_COUNT * MSG_SIZE;
+
+            // Opening the transport takes at least two round trips
+            let now = Instant::now();
+            let run = open(
+                "mem/janu-test-mem-latency#latency=100",
+                &format!(
+                    "mem/janu-test-mem-latency#latency=100;bandwidth={}",
+                    bandwidth
+                ),
+            )
+            .await;
+            assert!(now.elapsed() >= 4 * latency);
+
+            // A single message takes at least the latency
+            let now = Instant::now();
+            run.client_transport
+                .schedule(message(MSG_SIZE, Reliability::Reliable))
+                .unwrap();
+            wait_count(&run.router_handler, 1).await;
+            assert!(now.elapsed() >= latency);
+
+            // Sending twice the bandwidth takes at least two seconds
+            let now = Instant::now();
+            let count = 1 + 2 * MSG_COUNT;
+            for _ in 1..count {
+                run.client_transport
+                    .schedule(message(MSG_SIZE, Reliability::Reliable))
+                    .unwrap();
+            }
+            wait_count(&run.router_handler, count).await;
+            let elapsed = now.elapsed();
+            println!("Sent {} bytes in {:?}", (count - 1) * MSG_SIZE, elapsed);
+            assert!(elapsed >= 2 * SLEEP);
+            close(run).await;
+        });
+    }
+}
@@ -658,3 +658,14 @@ tOzot3pwe+3SJtpk90xAQrABEO0Zh2unrC8i83ySfg==
 
     task::block_on(openclose_transport(&endpoint));
 }
+
+#[cfg(feature = "transport_mem")]
+#[test]
+fn openclose_mem_only() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    let endpoint: EndPoint = "mem/janu-test-openclose".parse().unwrap();
+    task::block_on(openclose_transport(&endpoint));
+}
@@ -0,0 +1,334 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+#![cfg(feature = "transport_quic")]
+use async_std::prelude::*;
+use async_std::sync::Arc;
+use async_std::task;
+use std::any::Any;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use janu::net::link::quic::config::*;
+use janu::net::link::{EndPoint, Link};
+use janu::net::protocol::core::{
+    whatami, Channel, CongestionControl, PeerId, Priority, Reliability, ResKey,
+};
+use janu::net::protocol::io::ZBuf;
+use janu::net::protocol::proto::JanuMessage;
+use janu::net::transport::{
+    TransportEventHandler, TransportManager, TransportManagerConfig, TransportMulticast,
+    TransportMulticastEventHandler, TransportPeer, TransportPeerEventHandler, TransportUnicast,
+};
+use janu_util::core::ZResult;
+use janu_util::properties::Properties;
+use janu_util::zasync_executor_init;
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+const SLEEP: Duration = Duration::from_secs(1);
+const SLEEP_COUNT: Duration = Duration::from_millis(10);
+
+const BACKGROUND_COUNT: usize = 256;
+const BACKGROUND_SIZE: usize = 60_000;
+const REAL_TIME_COUNT: usize = 8;
+const BEST_EFFORT_COUNT: usize = 100;
+
+// Transport Handler for the router
+#[derive(Default)]
+struct SHRouter {
+    // The number of messages received per priority
+    count: Arc<[AtomicUsize; Priority::NUM]>,
+    // The number of background messages received when each real-time message arrived
+    real_time: Arc<Mutex<Vec<usize>>>,
+}
+
+impl TransportEventHandler for SHRouter {
+    fn new_unicast(
+        &self,
+        _peer: TransportPeer,
+        _transport: TransportUnicast,
+    ) -> ZResult<Arc<dyn TransportPeerEventHandler>> {
+        Ok(Arc::new(SCRouter {
+            count: self.count.clone(),
+            real_time: self.real_time.clone(),
+        }))
+    }
+
+    fn new_multicast(
+        &self,
+        _transport: TransportMulticast,
+    ) -> ZResult<Arc<dyn TransportMulticastEventHandler>> {
+        panic!();
+    }
+}
+
+impl SHRouter {
+    fn get_count(&self, priority: Priority) -> usize {
+        self.count[priority as usize].load(Ordering::SeqCst)
+    }
+}
+
+// Transport Callback for the router
+struct SCRouter {
+    count: Arc<[AtomicUsize; Priority::NUM]>,
+    real_time: Arc<Mutex<Vec<usize>>>,
+}
+
+impl TransportPeerEventHandler for SCRouter {
+    fn handle_message(&self, message: JanuMessage) -> ZResult<()> {
+        let priority = message.channel.priority;
+        if priority == Priority::RealTime {
+            let background = self.count[Priority::Background as usize].load(Ordering::SeqCst);
+            self.real_time.lock().unwrap().push(background);
+        }
+        self.count[priority as usize].fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn new_link(&self, _link: Link) {}
+    fn del_link(&self, _link: Link) {}
+    fn closing(&self) {}
+    fn closed(&self) {}
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// Transport Handler for the client
+struct SHClient;
+
+impl TransportEventHandler for SHClient {
+    fn new_unicast(
+        &self,
+        _peer: TransportPeer,
+        _transport: TransportUnicast,
+    ) -> ZResult<Arc<dyn TransportPeerEventHandler>> {
+        Ok(Arc::new(SCClient))
+    }
+
+    fn new_multicast(
+        &self,
+        _transport: TransportMulticast,
+    ) -> ZResult<Arc<dyn TransportMulticastEventHandler>> {
+        panic!();
+    }
+}
+
+// Transport Callback for the client
+struct SCClient;
+
+impl TransportPeerEventHandler for SCClient {
+    fn handle_message(&self, _message: JanuMessage) -> ZResult<()> {
+        Ok(())
+    }
+
+    fn new_link(&self, _link: Link) {}
+    fn del_link(&self, _link: Link) {}
+    fn closing(&self) {}
+    fn closed(&self) {}
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn endpoint(port: u16) -> EndPoint {
+    // Generate a self-signed certificate for localhost, also used as root CA by the client
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let mut endpoint: EndPoint = format!("quic/localhost:{}", port).parse().unwrap();
+    let mut config = Properties::default();
+    config.insert(
+        TLS_ROOT_CA_CERTIFICATE_RAW.to_string(),
+        cert.serialize_pem().unwrap(),
+    );
+    config.insert(
+        TLS_SERVER_PRIVATE_KEY_RAW.to_string(),
+        cert.serialize_private_key_pem(),
+    );
+    config.insert(
+        TLS_SERVER_CERTIFICATE_RAW.to_string(),
+        cert.serialize_pem().unwrap(),
+    );
+    endpoint.config = Some(Arc::new(config));
+    endpoint
+}
+
+async fn open_transport(
+    endpoint: &EndPoint,
+) -> (TransportManager, Arc<SHRouter>, TransportUnicast) {
+    // Define client and router IDs
+    let client_id = PeerId::new(1, [0u8; PeerId::MAX_SIZE]);
+    let router_id = PeerId::new(1, [1u8; PeerId::MAX_SIZE]);
+
+    // Create the router transport manager
+    let router_handler = Arc::new(SHRouter::default());
+    let config = TransportManagerConfig::builder()
+        .whatami(whatami::ROUTER)
+        .pid(router_id)
+        .build(router_handler.clone());
+    let router_manager = TransportManager::new(config);
+
+    // Create the client transport manager
+    let config = TransportManagerConfig::builder()
+        .whatami(whatami::CLIENT)
+        .pid(client_id)
+        .build(Arc::new(SHClient));
+    let client_manager = TransportManager::new(config);
+
+    let _ = router_manager
+        .add_listener(endpoint.clone())
+        .timeout(TIMEOUT)
+        .await
+        .unwrap()
+        .unwrap();
+    let client_transport = client_manager
+        .open_transport(endpoint.clone())
+        .timeout(TIMEOUT)
+        .await
+        .unwrap()
+        .unwrap();
+
+    (router_manager, router_handler, client_transport)
+}
+
+async fn close_transport(
+    router_manager: TransportManager,
+    client_transport: TransportUnicast,
+    endpoint: &EndPoint,
+) {
+    client_transport
+        .close()
+        .timeout(TIMEOUT)
+        .await
+        .unwrap()
+        .unwrap();
+    task::sleep(SLEEP).await;
+    router_manager
+        .del_listener(endpoint)
+        .timeout(TIMEOUT)
+        .await
+        .unwrap()
+        .unwrap();
+    task::sleep(SLEEP).await;
+}
+
+fn message(priority: Priority, reliability: Reliability, size: usize) -> JanuMessage {
+    JanuMessage::make_data(
+        ResKey::RName("/test".to_string()),
+        ZBuf::from(vec![0u8; size]),
+        Channel {
+            priority,
+            reliability,
+        },
+        CongestionControl::Block,
+        None,
+        None,
+        None,
+        None,
+    )
+}
+
+#[test]
+fn transport_quic_real_time_overtakes_background() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    task::block_on(async {
+        let endpoint = endpoint(13460);
+        let (router_manager, router_handler, client_transport) = open_transport(&endpoint).await;
+
+        // Saturate the background stream
+        let c_transport = client_transport.clone();
+        let background = task::spawn_blocking(move || {
+            let msg = message(Priority::Background, Reliability::Reliable, BACKGROUND_SIZE);
+            for _ in 0..BACKGROUND_COUNT {
+                c_transport.schedule(msg.clone()).unwrap();
+            }
+        });
+        let check = async {
+            while router_handler.get_count(Priority::Background) < BACKGROUND_COUNT / 8 {
+                task::sleep(SLEEP_COUNT).await;
+            }
+        };
+        check.timeout(TIMEOUT).await.unwrap();
+
+        // Send the real-time messages while the background messages are flowing
+        for _ in 0..REAL_TIME_COUNT {
+            let msg = message(Priority::RealTime, Reliability::Reliable, 8);
+            client_transport.schedule(msg).unwrap();
+            task::sleep(SLEEP_COUNT).await;
+        }
+
+        background.timeout(TIMEOUT).await.unwrap();
+        let check = async {
+            while router_handler.get_count(Priority::Background) < BACKGROUND_COUNT {
+                task::sleep(SLEEP_COUNT).await;
+            }
+        };
+        check.timeout(TIMEOUT).await.unwrap();
+
+        // The real-time messages have been delivered before the background backlog
+        let real_time = router_handler.real_time.lock().unwrap().clone();
+        println!("Background messages received before real-time ones: {:?}", real_time);
+        assert_eq!(real_time.len(), REAL_TIME_COUNT);
+        assert!(real_time.iter().all(|b| *b < BACKGROUND_COUNT / 2));
+
+        close_transport(router_manager, client_transport, &endpoint).await;
+    });
+}
+
+#[test]
+fn transport_quic_best_effort_datagrams() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    task::block_on(async {
+        let endpoint = endpoint(13461);
+        let (router_manager, router_handler, client_transport) = open_transport(&endpoint).await;
+
+        // Small best effort batches fit in a datagram
+        for _ in 0..BEST_EFFORT_COUNT {
+            let msg = message(Priority::Data, Reliability::BestEffort, 8);
+            client_transport.schedule(msg).unwrap();
+            task::sleep(SLEEP_COUNT).await;
+        }
+        let check = async {
+            while router_handler.get_count(Priority::Data) < BEST_EFFORT_COUNT {
+                task::sleep(SLEEP_COUNT).await;
+            }
+        };
+        check.timeout(TIMEOUT).await.unwrap();
+
+        // Large best effort batches fall back to the stream of their priority.
+        // Best effort messages may be dropped under load, hence only check
+        // that they are still delivered.
+        for _ in 0..BEST_EFFORT_COUNT {
+            let msg = message(Priority::Data, Reliability::BestEffort, BACKGROUND_SIZE);
+            client_transport.schedule(msg).unwrap();
+            task::sleep(SLEEP_COUNT).await;
+        }
+        let check = async {
+            while router_handler.get_count(Priority::Data) == BEST_EFFORT_COUNT {
+                task::sleep(SLEEP_COUNT).await;
+            }
+        };
+        check.timeout(TIMEOUT).await.unwrap();
+        task::sleep(SLEEP).await;
+        assert!(router_handler.get_count(Priority::Data) <= 2 * BEST_EFFORT_COUNT);
+
+        close_transport(router_manager, client_transport, &endpoint).await;
+    });
+}
@@ -0,0 +1,128 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::sync::Arc;
+use async_std::task;
+use std::time::Duration;
+use janu::net::link::EndPoint;
+use janu::net::protocol::core::{whatami, PeerId};
+use janu::net::transport::{
+    DummyTransportPeerEventHandler, TransportEventHandler, TransportManager,
+    TransportManagerConfig, TransportManagerConfigUnicast, TransportMulticast,
+    TransportMulticastEventHandler, TransportPeer, TransportPeerEventHandler, TransportUnicast,
+};
+use janu_util::core::ZResult;
+use janu_util::zasync_executor_init;
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+const SLEEP: Duration = Duration::from_millis(100);
+const KEEP_ALIVE: Duration = Duration::from_millis(100);
+
+struct SHRtt;
+
+impl TransportEventHandler for SHRtt {
+    fn new_unicast(
+        &self,
+        _peer: TransportPeer,
+        _transport: TransportUnicast,
+    ) -> ZResult<Arc<dyn TransportPeerEventHandler>> {
+        Ok(Arc::new(DummyTransportPeerEventHandler))
+    }
+
+    fn new_multicast(
+        &self,
+        _transport: TransportMulticast,
+    ) -> ZResult<Arc<dyn TransportMulticastEventHandler>> {
+        panic!();
+    }
+}
+
+fn make_manager(id: u8, whatami: whatami::Type) -> TransportManager {
+    let config = TransportManagerConfig::builder()
+        .whatami(whatami)
+        .pid(PeerId::new(1, [id; PeerId::MAX_SIZE]))
+        .unicast(
+            TransportManagerConfigUnicast::builder()
+                .keep_alive(KEEP_ALIVE)
+                .build(),
+        )
+        .build(Arc::new(SHRtt));
+    TransportManager::new(config)
+}
+
+async fn rtt_transport(endpoint: &EndPoint) {
+    let router_manager = make_manager(0, whatami::ROUTER);
+    let client_manager = make_manager(1, whatami::CLIENT);
+
+    println!("Transport RTT [1a]");
+    let _ = router_manager
+        .add_listener(endpoint.clone())
+        .timeout(TIMEOUT)
+        .await
+        .unwrap()
+        .unwrap();
+    let transport = client_manager
+        .open_transport(endpoint.clone())
+        .timeout(TIMEOUT)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // No Ping is sent until the round-trip time measurement is enabled
+    println!("Transport RTT [2a]");
+    task::sleep(10 * KEEP_ALIVE).await;
+    assert!(transport.get_rtt().unwrap().is_none());
+
+    // The round-trip time gets measured on both sides by the periodic Ping/Pong
+    println!("Transport RTT [2b]");
+    transport.enable_rtt().unwrap();
+    let check = async {
+        loop {
+            let router_rtt = router_manager.get_transports().first().and_then(|t| {
+                t.enable_rtt().unwrap();
+                t.get_rtt().unwrap()
+            });
+            if let (Some(client_rtt), Some(router_rtt)) = (transport.get_rtt().unwrap(), router_rtt)
+            {
+                break (client_rtt, router_rtt);
+            }
+            task::sleep(SLEEP).await;
+        }
+    };
+    let (client_rtt, router_rtt) = check.timeout(TIMEOUT).await.unwrap();
+    println!("Transport RTT [2c]: {:?} {:?}", client_rtt, router_rtt);
+    assert!(client_rtt < TIMEOUT);
+    assert!(router_rtt < TIMEOUT);
+
+    println!("Transport RTT [3a]");
+    transport.close().timeout(TIMEOUT).await.unwrap().unwrap();
+    router_manager
+        .del_listener(endpoint)
+        .timeout(TIMEOUT)
+        .await
+        .unwrap()
+        .unwrap();
+    task::sleep(SLEEP).await;
+}
+
+#[cfg(feature = "transport_tcp")]
+#[test]
+fn transport_tcp_rtt() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    let endpoint: EndPoint = "tcp/127.0.0.1:15447".parse().unwrap();
+    task::block_on(rtt_transport(&endpoint));
+}
@@ -0,0 +1,323 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+#[cfg(all(feature = "transport_serial", target_family = "unix"))]
+mod tests {
+    use async_std::prelude::*;
+    use async_std::sync::Arc;
+    use async_std::task;
+    use nix::fcntl::OFlag;
+    use nix::pty::{self, PtyMaster};
+    use std::any::Any;
+    use std::os::unix::io::{AsRawFd, RawFd};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::time::Duration;
+    use janu::net::link::{EndPoint, Link};
+    use janu::net::protocol::core::{
+        whatami, Channel, CongestionControl, PeerId, Priority, Reliability, ResKey,
+    };
+    use janu::net::protocol::io::ZBuf;
+    use janu::net::protocol::proto::JanuMessage;
+    use janu::net::transport::{
+        TransportEventHandler, TransportManager, TransportManagerConfig, TransportMulticast,
+        TransportMulticastEventHandler, TransportPeer, TransportPeerEventHandler, TransportUnicast,
+    };
+    use janu_util::core::ZResult;
+    use janu_util::zasync_executor_init;
+
+    const TIMEOUT: Duration = Duration::from_secs(60);
+    const SLEEP: Duration = Duration::from_secs(1);
+    const SLEEP_COUNT: Duration = Duration::from_millis(10);
+    const SLEEP_SEND: Duration = Duration::from_millis(5);
+
+    const MSG_COUNT: usize = 100;
+    const MSG_SIZE: usize = 256;
+    // Corrupt one byte every CORRUPT_PERIOD bytes crossing the serial line
+    const CORRUPT_PERIOD: usize = 1_000;
+
+    /*************************************/
+    /*          SERIAL LINE              */
+    /*************************************/
+    // Emulate a serial line with two pseudo-terminals whose master sides are
+    // bridged together: what is written on one slave is read on the other one.
+    struct SerialLine {
+        path_a: String,
+        path_b: String,
+        corrupt: Arc<AtomicBool>,
+    }
+
+    fn open_pty() -> (PtyMaster, String) {
+        let master = pty::posix_openpt(OFlag::O_RDWR | OFlag::O_NOCTTY).unwrap();
+        pty::grantpt(&master).unwrap();
+        pty::unlockpt(&master).unwrap();
+        let path = pty::ptsname_r(&master).unwrap();
+        (master, path)
+    }
+
+    fn bridge(src: RawFd, dst: RawFd, corrupt: Arc<AtomicBool>) {
+        let mut buffer = [0u8; 1_024];
+        let mut count: usize = 0;
+        loop {
+            // Reading from the master fails until the slave is opened
+            let n = match nix::unistd::read(src, &mut buffer) {
+                Ok(n) if n > 0 => n,
+                _ => {
+                    std::thread::sleep(SLEEP_COUNT);
+                    continue;
+                }
+            };
+            if corrupt.load(Ordering::Acquire) {
+                for b in buffer[..n].iter_mut() {
+                    count += 1;
+                    if count == CORRUPT_PERIOD {
+                        count = 0;
+                        *b ^= 0x5a;
+                    }
+                }
+            }
+            let mut written = 0;
+            while written < n {
+                match nix::unistd::write(dst, &buffer[written..n]) {
+                    Ok(w) => written += w,
+                    Err(_) => std::thread::sleep(SLEEP_COUNT),
+                }
+            }
+        }
+    }
+
+    fn serial_line() -> SerialLine {
+        let (master_a, path_a) = open_pty();
+        let (master_b, path_b) = open_pty();
+        let corrupt = Arc::new(AtomicBool::new(false));
+
+        let (fd_a, fd_b) = (master_a.as_raw_fd(), master_b.as_raw_fd());
+        let c_corrupt = corrupt.clone();
+        std::thread::spawn(move || bridge(fd_a, fd_b, c_corrupt));
+        let c_corrupt = corrupt.clone();
+        std::thread::spawn(move || bridge(fd_b, fd_a, c_corrupt));
+        // Keep the masters open for the whole test
+        std::mem::forget(master_a);
+        std::mem::forget(master_b);
+
+        SerialLine {
+            path_a,
+            path_b,
+            corrupt,
+        }
+    }
+
+    /*************************************/
+    /*            TRANSPORT              */
+    /*************************************/
+    // Transport Handler for the router
+    #[derive(Default)]
+    struct SHRouter {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl TransportEventHandler for SHRouter {
+        fn new_unicast(
+            &self,
+            _peer: TransportPeer,
+            _transport: TransportUnicast,
+        ) -> ZResult<Arc<dyn TransportPeerEventHandler>> {
+            Ok(Arc::new(SCPeer {
+                count: self.count.clone(),
+            }))
+        }
+
+        fn new_multicast(
+            &self,
+            _transport: TransportMulticast,
+        ) -> ZResult<Arc<dyn TransportMulticastEventHandler>> {
+            panic!();
+        }
+    }
+
+    // Transport Handler for the client
+    struct SHClient;
+
+    impl TransportEventHandler for SHClient {
+        fn new_unicast(
+            &self,
+            _peer: TransportPeer,
+            _transport: TransportUnicast,
+        ) -> ZResult<Arc<dyn TransportPeerEventHandler>> {
+            Ok(Arc::new(SCPeer::default()))
+        }
+
+        fn new_multicast(
+            &self,
+            _transport: TransportMulticast,
+        ) -> ZResult<Arc<dyn TransportMulticastEventHandler>> {
+            panic!();
+        }
+    }
+
+    // Transport Callback counting the received messages
+    #[derive(Default)]
+    struct SCPeer {
+        count: Arc<AtomicUsize>,
+    }
+
+    impl TransportPeerEventHandler for SCPeer {
+        fn handle_message(&self, _message: JanuMessage) -> ZResult<()> {
+            self.count.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn new_link(&self, _link: Link) {}
+        fn del_link(&self, _link: Link) {}
+        fn closing(&self) {}
+        fn closed(&self) {}
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    fn transport_manager(id: u8, handler: Arc<dyn TransportEventHandler>) -> TransportManager {
+        let config = TransportManagerConfig::builder()
+            .whatami(whatami::PEER)
+            .pid(PeerId::new(1, [id; PeerId::MAX_SIZE]))
+            .build(handler);
+        TransportManager::new(config)
+    }
+
+    async fn send(transport: &TransportUnicast) {
+        let message = JanuMessage::make_data(
+            ResKey::RName("/test".to_string()),
+            ZBuf::from(vec![0u8; MSG_SIZE]),
+            Channel {
+                priority: Priority::default(),
+                reliability: Reliability::BestEffort,
+            },
+            CongestionControl::Block,
+            None,
+            None,
+            None,
+            None,
+        );
+        for _ in 0..MSG_COUNT {
+            transport.schedule(message.clone()).unwrap();
+            // Send one message per frame
+            task::sleep(SLEEP_SEND).await;
+        }
+    }
+
+    #[test]
+    fn transport_unicast_serial() {
+        task::block_on(async {
+            zasync_executor_init!();
+        });
+
+        task::block_on(async {
+            let line = serial_line();
+            let listen: EndPoint = format!("serial/{}#baudrate=115200", line.path_a)
+                .parse()
+                .unwrap();
+            let connect: EndPoint = format!("serial/{}#baudrate=115200", line.path_b)
+                .parse()
+                .unwrap();
+
+            // Create the router and the client transport managers
+            let router_handler = Arc::new(SHRouter::default());
+            let router_manager = transport_manager(1, router_handler.clone());
+            let client_manager = transport_manager(0, Arc::new(SHClient));
+
+            let locator = router_manager
+                .add_listener(listen.clone())
+                .timeout(TIMEOUT)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(locator, listen.locator);
+
+            // Invalid baud rates are rejected
+            let invalid: EndPoint = format!("serial/{}#baudrate=1234", line.path_b)
+                .parse()
+                .unwrap();
+            assert!(client_manager.open_transport(invalid).await.is_err());
+
+            let client_transport = client_manager
+                .open_transport(connect.clone())
+                .timeout(TIMEOUT)
+                .await
+                .unwrap()
+                .unwrap();
+            let links = client_transport.get_links().unwrap();
+            assert_eq!(links.len(), 1);
+            assert!(!links[0].is_reliable);
+            assert!(!links[0].is_streamed);
+
+            // All the messages cross a clean serial line
+            send(&client_transport).await;
+            let check = async {
+                while router_handler.count.load(Ordering::SeqCst) != MSG_COUNT {
+                    task::sleep(SLEEP_COUNT).await;
+                }
+            };
+            check.timeout(TIMEOUT).await.unwrap();
+
+            // Corrupted frames are dropped without breaking the link
+            line.corrupt.store(true, Ordering::Release);
+            send(&client_transport).await;
+            task::sleep(SLEEP).await;
+            line.corrupt.store(false, Ordering::Release);
+            let received = router_handler.count.load(Ordering::SeqCst) - MSG_COUNT;
+            println!(
+                "Received {}/{} messages over a noisy line",
+                received, MSG_COUNT
+            );
+            assert!(received > 0 && received < MSG_COUNT);
+
+            send(&client_transport).await;
+            let check = async {
+                while router_handler.count.load(Ordering::SeqCst) != 2 * MSG_COUNT + received {
+                    task::sleep(SLEEP_COUNT).await;
+                }
+            };
+            check.timeout(TIMEOUT).await.unwrap();
+
+            // Close the transport: the listener is ready for a new transport
+            client_transport
+                .close()
+                .timeout(TIMEOUT)
+                .await
+                .unwrap()
+                .unwrap();
+            task::sleep(SLEEP).await;
+
+            let client_transport = client_manager
+                .open_transport(connect.clone())
+                .timeout(TIMEOUT)
+                .await
+                .unwrap()
+                .unwrap();
+            client_transport
+                .close()
+                .timeout(TIMEOUT)
+                .await
+                .unwrap()
+                .unwrap();
+            task::sleep(SLEEP).await;
+
+            router_manager
+                .del_listener(&listen)
+                .timeout(TIMEOUT)
+                .await
+                .unwrap()
+                .unwrap();
+        });
+    }
+}
@@ -791,3 +791,223 @@ tOzot3pwe+3SJtpk90xAQrABEO0Zh2unrC8i83ySfg==
     let endpoints = vec![endpoint];
     task::block_on(run(&endpoints, &channel, &MSG_SIZE_ALL));
 }
+
+#[cfg(feature = "transport_ws")]
+#[test]
+fn transport_unicast_ws_only() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    // Define the locators
+    let endpoints: Vec<EndPoint> = vec![
+        "ws/127.0.0.1:10453".parse().unwrap(),
+        "ws/[::1]:10453".parse().unwrap(),
+    ];
+    // Define the reliability and congestion control
+    let channel = [
+        Channel {
+            priority: Priority::default(),
+            reliability: Reliability::Reliable,
+        },
+        Channel {
+            priority: Priority::default(),
+            reliability: Reliability::BestEffort,
+        },
+        Channel {
+            priority: Priority::RealTime,
+            reliability: Reliability::Reliable,
+        },
+        Channel {
+            priority: Priority::RealTime,
+            reliability: Reliability::BestEffort,
+        },
+    ];
+    // Run
+    task::block_on(run(&endpoints, &channel, &MSG_SIZE_ALL));
+}
+
+#[cfg(feature = "transport_ws")]
+#[test]
+fn transport_unicast_wss_only() {
+    use janu::net::link::ws::config::*;
+
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    // Generate a self-signed certificate for localhost, also used as root CA by the client
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+
+    // Define the locator
+    let mut endpoint: EndPoint = "wss/localhost:10454".parse().unwrap();
+    let mut config = Properties::default();
+    config.insert(
+        WSS_ROOT_CA_CERTIFICATE_RAW.to_string(),
+        cert.serialize_pem().unwrap(),
+    );
+    config.insert(
+        WSS_SERVER_PRIVATE_KEY_RAW.to_string(),
+        cert.serialize_private_key_pem(),
+    );
+    config.insert(
+        WSS_SERVER_CERTIFICATE_RAW.to_string(),
+        cert.serialize_pem().unwrap(),
+    );
+    endpoint.config = Some(Arc::new(config));
+
+    // Define the reliability and congestion control
+    let channel = [
+        Channel {
+            priority: Priority::default(),
+            reliability: Reliability::Reliable,
+        },
+        Channel {
+            priority: Priority::default(),
+            reliability: Reliability::BestEffort,
+        },
+        Channel {
+            priority: Priority::RealTime,
+            reliability: Reliability::Reliable,
+        },
+        Channel {
+            priority: Priority::RealTime,
+            reliability: Reliability::BestEffort,
+        },
+    ];
+    // Run
+    let endpoints = vec![endpoint];
+    task::block_on(run(&endpoints, &channel, &MSG_SIZE_ALL));
+}
+
+#[cfg(feature = "transport_mem")]
+#[test]
+fn transport_unicast_mem_only() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    // Define the locators
+    let endpoints: Vec<EndPoint> = vec![
+        "mem/janu-test-transport-0".parse().unwrap(),
+        "mem/janu-test-transport-1".parse().unwrap(),
+    ];
+    // Define the reliability and congestion control
+    let channel = [
+        Channel {
+            priority: Priority::default(),
+            reliability: Reliability::Reliable,
+        },
+        Channel {
+            priority: Priority::default(),
+            reliability: Reliability::BestEffort,
+        },
+        Channel {
+            priority: Priority::RealTime,
+            reliability: Reliability::Reliable,
+        },
+        Channel {
+            priority: Priority::RealTime,
+            reliability: Reliability::BestEffort,
+        },
+    ];
+    // Run
+    task::block_on(run(&endpoints, &channel, &MSG_SIZE_ALL));
+}
+
+#[cfg(all(feature = "transport_unixsock-dgram", target_os = "linux"))]
+#[test]
+fn transport_unicast_unix_dgram_only() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    let _ = std::fs::remove_file("janu-test-unix-dgram-5.sock");
+    // Define the locators
+    let endpoints: Vec<EndPoint> = vec![
+        "unixsock-dgram/janu-test-unix-dgram-5.sock"
+            .parse()
+            .unwrap(),
+        "unixsock-dgram/@janu-test-unix-dgram-6".parse().unwrap(),
+    ];
+    // Define the reliability and congestion control
+    let channel = [
+        Channel {
+            priority: Priority::default(),
+            reliability: Reliability::Reliable,
+        },
+        Channel {
+            priority: Priority::RealTime,
+            reliability: Reliability::BestEffort,
+        },
+    ];
+    // Run
+    task::block_on(run(&endpoints, &channel, &MSG_SIZE_ALL));
+    let _ = std::fs::remove_file("janu-test-unix-dgram-5.sock");
+    let _ = std::fs::remove_file("janu-test-unix-dgram-5.sock.lock");
+}
+
+#[cfg(all(feature = "transport_unixsock-seqpacket", target_os = "linux"))]
+#[test]
+fn transport_unicast_unix_seqpacket_only() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    let _ = std::fs::remove_file("janu-test-unix-seqpacket-5.sock");
+    // Define the locators
+    let endpoints: Vec<EndPoint> = vec![
+        "unixsock-seqpacket/janu-test-unix-seqpacket-5.sock"
+            .parse()
+            .unwrap(),
+        "unixsock-seqpacket/@janu-test-unix-seqpacket-6"
+            .parse()
+            .unwrap(),
+    ];
+    // Define the reliability and congestion control
+    let channel = [
+        Channel {
+            priority: Priority::default(),
+            reliability: Reliability::Reliable,
+        },
+        Channel {
+            priority: Priority::RealTime,
+            reliability: Reliability::BestEffort,
+        },
+    ];
+    // Run
+    task::block_on(run(&endpoints, &channel, &MSG_SIZE_ALL));
+    let _ = std::fs::remove_file("janu-test-unix-seqpacket-5.sock");
+    let _ = std::fs::remove_file("janu-test-unix-seqpacket-5.sock.lock");
+}
+
+#[cfg(all(feature = "transport_tcp", target_os = "linux"))]
+#[test]
+fn transport_unicast_tcp_socket_options() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    // Define the locators, the bind address is only used by the connecting side
+    let endpoints: Vec<EndPoint> = vec![
+        "tcp/127.0.0.1:10455#keepalive=10;keepalive_interval=5;keepalive_retries=3;sndbuf=1048576;rcvbuf=1048576;dscp=46;bind=127.0.0.1"
+            .parse()
+            .unwrap(),
+        "tcp/[::1]:10455#keepalive=10;tos=32;bind=[::1]:0"
+            .parse()
+            .unwrap(),
+    ];
+    // Define the reliability and congestion control
+    let channel = [
+        Channel {
+            priority: Priority::default(),
+            reliability: Reliability::Reliable,
+        },
+        Channel {
+            priority: Priority::RealTime,
+            reliability: Reliability::BestEffort,
+        },
+    ];
+    // Run
+    task::block_on(run(&endpoints, &channel, &MSG_SIZE_NOFRAG));
+}
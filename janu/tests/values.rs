@@ -0,0 +1,156 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use janu::net::encoding::*;
+use janu::net::ZInt;
+use janu::Value;
+use std::collections::HashMap;
+
+type Data = (String, u32, Vec<f64>, HashMap<String, i64>);
+
+fn data() -> Data {
+    let mut map = HashMap::new();
+    map.insert("x".to_string(), -1);
+    map.insert("y".to_string(), 42);
+    ("sensor".to_string(), 7, vec![0.1, 2.5, -3.75], map)
+}
+
+fn check_round_trip(value: Value, encoding: ZInt) {
+    assert_eq!(value.encoding(), encoding);
+
+    // Through the wire encoding
+    let (e, buf) = value.clone().encode();
+    assert_eq!(e, encoding);
+    let decoded = Value::decode(e, buf).unwrap();
+    assert_eq!(decoded.encoding(), encoding);
+    assert_eq!(decoded.deserialize::<Data>().unwrap(), data());
+
+    // Through the string encoding
+    let (e, base64, s) = value.encode_to_string();
+    let decoded = Value::decode_from_string(e, base64, s).unwrap();
+    assert_eq!(decoded.encoding(), encoding);
+    assert_eq!(decoded.deserialize::<Data>().unwrap(), data());
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn value_cbor() {
+    check_round_trip(Value::serialize(APP_CBOR, &data()).unwrap(), APP_CBOR);
+    assert_eq!(to_string(APP_CBOR), "application/cbor");
+    assert_eq!(from_str("application/cbor").unwrap(), APP_CBOR);
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn value_msgpack() {
+    check_round_trip(Value::serialize(APP_MSGPACK, &data()).unwrap(), APP_MSGPACK);
+    assert_eq!(to_string(APP_MSGPACK), "application/msgpack");
+    assert_eq!(from_str("application/msgpack").unwrap(), APP_MSGPACK);
+}
+
+#[test]
+fn value_json() {
+    check_round_trip(Value::serialize(APP_JSON, &data()).unwrap(), APP_JSON);
+}
+
+#[test]
+fn value_encode_decode_unsupported() {
+    assert!(Value::serialize(TEXT_PLAIN, &data()).is_err());
+    let value = Value::from("not structured");
+    assert!(value.deserialize::<Data>().is_err());
+}
+
+fn check_numeric_round_trip(value: Value, encoding: ZInt, wire_len: usize) {
+    assert_eq!(value.encoding(), encoding);
+
+    let (e, buf) = value.clone().encode();
+    assert_eq!(e, encoding);
+    assert_eq!(buf.len(), wire_len);
+    let decoded = Value::decode(e, buf).unwrap();
+    assert_eq!(format!("{:?}", decoded), format!("{:?}", value));
+
+    let (e, base64, s) = value.clone().encode_to_string();
+    assert!(!base64);
+    let decoded = Value::decode_from_string(e, base64, s).unwrap();
+    assert_eq!(format!("{:?}", decoded), format!("{:?}", value));
+}
+
+#[test]
+fn value_int64() {
+    check_numeric_round_trip(Value::Int64(i64::MIN), APP_INT64, 8);
+    check_numeric_round_trip(Value::from(vec![-1i64, 0, i64::MAX]), APP_INT64_ARRAY, 24);
+    check_numeric_round_trip(Value::from(Vec::<i64>::new()), APP_INT64_ARRAY, 0);
+    assert_eq!(from_str("application/int64").unwrap(), APP_INT64);
+    assert_eq!(
+        from_str("application/int64-array").unwrap(),
+        APP_INT64_ARRAY
+    );
+}
+
+#[test]
+fn value_float64() {
+    check_numeric_round_trip(Value::Float64(0.1 + 0.2), APP_FLOAT64, 8);
+    check_numeric_round_trip(
+        Value::from(vec![0.1f64, -2.5e-300, f64::MAX]),
+        APP_FLOAT64_ARRAY,
+        24,
+    );
+    assert_eq!(from_str("application/float64").unwrap(), APP_FLOAT64);
+    assert_eq!(
+        from_str("application/float64-array").unwrap(),
+        APP_FLOAT64_ARRAY
+    );
+
+    // The bit pattern is preserved on the wire
+    let (e, buf) = Value::Float64(f64::NAN).encode();
+    match Value::decode(e, buf).unwrap() {
+        Value::Float64(f) => assert!(f.is_nan()),
+        v => panic!("Unexpected value: {:?}", v),
+    }
+}
+
+#[test]
+fn value_float64_non_finite_string() {
+    let floats = vec![f64::INFINITY, f64::NEG_INFINITY, f64::NAN, 1.5];
+    let (e, base64, s) = Value::from(floats).encode_to_string();
+    assert_eq!(s, r#"["inf","-inf","NaN",1.5]"#);
+    match Value::decode_from_string(e, base64, s).unwrap() {
+        Value::Float64Array(v) => {
+            assert_eq!(v[0], f64::INFINITY);
+            assert_eq!(v[1], f64::NEG_INFINITY);
+            assert!(v[2].is_nan());
+            assert_eq!(v[3], 1.5);
+        }
+        v => panic!("Unexpected value: {:?}", v),
+    }
+
+    for f in &[f64::INFINITY, f64::NEG_INFINITY] {
+        let (e, base64, s) = Value::Float64(*f).encode_to_string();
+        match Value::decode_from_string(e, base64, s).unwrap() {
+            Value::Float64(d) => assert_eq!(d, *f),
+            v => panic!("Unexpected value: {:?}", v),
+        }
+    }
+
+    // Only non-finite values may be given as strings
+    for s in &["[null]", r#"["1.5"]"#, r#"["foo"]"#] {
+        assert!(Value::decode_from_string(APP_FLOAT64_ARRAY, false, s.to_string()).is_err());
+    }
+}
+
+#[test]
+fn value_numeric_invalid_length() {
+    assert!(Value::decode(APP_INT64, vec![0u8; 4].into()).is_err());
+    assert!(Value::decode(APP_FLOAT64, vec![0u8; 16].into()).is_err());
+    assert!(Value::decode(APP_INT64_ARRAY, vec![0u8; 9].into()).is_err());
+}
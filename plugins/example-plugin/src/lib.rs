@@ -116,6 +116,8 @@ async fn run(runtime: Runtime, selector: ResKey, flag: Arc<AtomicBool>) {
                             res_name: rname.clone(),
                             payload: data.clone(),
                             data_info: data_info.clone(),
+                            channel: Channel::default(),
+                            congestion_control: CongestionControl::default(),
                         }).await;
                     }
                 }
@@ -0,0 +1,517 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use async_std::sync::Arc;
+use clap::{Arg, ArgMatches};
+use futures::prelude::*;
+use log::{debug, info, warn};
+use runtime::Runtime;
+use std::collections::{BTreeSet, VecDeque};
+use std::convert::TryFrom;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use janu::net::protocol::core::Timestamp;
+use janu::net::queryable::{EVAL, STORAGE};
+use janu::net::utils::resource_name;
+use janu::net::*;
+use janu::{Path, PathExpr, Selector};
+use janu_plugin_trait::prelude::*;
+use janu_util::properties::config::*;
+use janu_util::{zerror2, zlock};
+
+/// The property of the forwarded queries' predicates listing the bridges that forwarded them.
+const PROP_BRIDGES: &str = "bridges";
+
+/// How long the samples tagged by another source are remembered once forwarded.
+const FORWARDED_EXPIRY: Duration = Duration::from_secs(10);
+
+/// A mapping between a key prefix of the local domain and a key prefix of the remote domain.
+#[derive(Debug, Clone)]
+struct Mapping {
+    local: Path,
+    remote: Path,
+}
+
+impl Mapping {
+    /// Parses a mapping with the format `<local_prefix>=<remote_prefix>`.
+    fn parse(s: &str) -> ZResult<Mapping> {
+        let (local, remote) = s.split_once('=').ok_or_else(|| {
+            zerror2!(ZErrorKind::Other {
+                descr: format!(
+                    "Invalid bridge mapping '{}': expected <local_prefix>=<remote_prefix>",
+                    s
+                )
+            })
+        })?;
+        let local = Path::try_from(local.trim().trim_end_matches('/'))?;
+        let remote = Path::try_from(remote.trim().trim_end_matches('/'))?;
+        if local.is_relative() || remote.is_relative() {
+            return Err(zerror2!(ZErrorKind::Other {
+                descr: format!("Invalid bridge mapping '{}': prefixes must be absolute", s)
+            }));
+        }
+        Ok(Mapping { local, remote })
+    }
+}
+
+/// The timestamps of the samples tagged by another source recently forwarded by a bridge.
+#[derive(Default)]
+struct Forwarded {
+    timestamps: BTreeSet<Timestamp>,
+    expiry: VecDeque<(Instant, Timestamp)>,
+}
+
+impl Forwarded {
+    /// Records the timestamp of a forwarded sample.
+    /// Returns `false` if a sample with the same timestamp was already forwarded.
+    fn insert(&mut self, timestamp: Timestamp, now: Instant) -> bool {
+        while let Some((instant, expired)) = self.expiry.front() {
+            if now.duration_since(*instant) < FORWARDED_EXPIRY {
+                break;
+            }
+            self.timestamps.remove(expired);
+            self.expiry.pop_front();
+        }
+        if self.timestamps.insert(timestamp) {
+            self.expiry.push_back((now, timestamp));
+            true
+        } else {
+            false
+        }
+    }
+}
+
+pub struct BridgePlugin {}
+
+janu_plugin_trait::declare_plugin!(BridgePlugin);
+
+impl Plugin for BridgePlugin {
+    type Requirements = Vec<Arg<'static, 'static>>;
+    type StartArgs = (Runtime, ArgMatches<'static>);
+    fn compatibility() -> janu_plugin_trait::PluginId {
+        janu_plugin_trait::PluginId {
+            uid: "janu-plugin-bridge",
+        }
+    }
+
+    fn get_requirements() -> Self::Requirements {
+        vec![
+            Arg::from_usage(
+                "--bridge-remote=[LOCATOR]... \
+                'A locator of the remote janu domain to bridge with. \
+                Repeat this option to specify several locators'",
+            ),
+            Arg::from_usage(
+                "--bridge-remote-mode=[MODE] \
+                'The mode of the session opened in the remote domain'",
+            )
+            .possible_values(&["client", "peer"])
+            .default_value("client"),
+            Arg::from_usage(
+                "--bridge-map=[MAPPING]... \
+                'A mapping of a local key prefix to a remote key prefix, \
+                with the format <local_prefix>=<remote_prefix>. \
+                Repeat this option to specify several mappings'",
+            ),
+        ]
+    }
+
+    fn start(
+        (runtime, args): &Self::StartArgs,
+    ) -> Result<Box<dyn std::any::Any + Send + Sync>, Box<dyn std::error::Error>> {
+        let remotes: Vec<String> = match args.values_of("bridge-remote") {
+            Some(values) => values.map(String::from).collect(),
+            None => {
+                return Err(Box::new(zerror2!(ZErrorKind::Other {
+                    descr: "bridge-remote is a mandatory option for BridgePlugin".into()
+                })))
+            }
+        };
+        let mappings = match args.values_of("bridge-map") {
+            Some(values) => values
+                .map(Mapping::parse)
+                .collect::<ZResult<Vec<Mapping>>>()?,
+            None => {
+                return Err(Box::new(zerror2!(ZErrorKind::Other {
+                    descr: "bridge-map is a mandatory option for BridgePlugin".into()
+                })))
+            }
+        };
+        let mode = args.value_of("bridge-remote-mode").unwrap().to_string();
+        async_std::task::spawn(run(runtime.clone(), remotes, mode, mappings));
+        Ok(Box::new(()))
+    }
+}
+
+async fn run(runtime: Runtime, remotes: Vec<String>, mode: String, mappings: Vec<Mapping>) {
+    // Try to initiate login.
+    // Required in case of dynamic lib, otherwise no logs.
+    // But cannot be done twice in case of static link.
+    let _ = env_logger::try_init();
+
+    // The samples and queries forwarded by this bridge are tagged with the router's id
+    // so that they are not forwarded back when they loop through other bridges.
+    let tag = runtime.pid;
+    let forwarded = Arc::new(Mutex::new(Forwarded::default()));
+
+    // Local routing is disabled on both sessions so that the samples and queries
+    // forwarded by one side of a mapping are not received by the other side.
+    let local = Arc::new(Session::init(runtime, false, vec![], vec![]).await);
+
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, mode);
+    config.insert(ZN_PEER_KEY, remotes.join(","));
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    config.insert(ZN_LOCAL_ROUTING_KEY, ZN_FALSE.to_string());
+    debug!("Open bridge session with {:?}", config);
+    let remote = match open(config).await {
+        Ok(session) => Arc::new(session),
+        Err(e) => {
+            log::error!("Unable to open the bridge remote session: {}", e);
+            return;
+        }
+    };
+
+    for mapping in mappings {
+        info!("Bridge {} <-> {}", mapping.local, mapping.remote);
+        async_std::task::spawn(mirror_data(
+            local.clone(),
+            mapping.local.clone(),
+            remote.clone(),
+            mapping.remote.clone(),
+            tag,
+            forwarded.clone(),
+        ));
+        async_std::task::spawn(mirror_data(
+            remote.clone(),
+            mapping.remote.clone(),
+            local.clone(),
+            mapping.local.clone(),
+            tag,
+            forwarded.clone(),
+        ));
+        async_std::task::spawn(mirror_queries(
+            local.clone(),
+            mapping.local.clone(),
+            remote.clone(),
+            mapping.remote.clone(),
+            tag,
+        ));
+        async_std::task::spawn(mirror_queries(
+            remote.clone(),
+            mapping.remote.clone(),
+            local.clone(),
+            mapping.local.clone(),
+            tag,
+        ));
+    }
+}
+
+/// Replaces the prefix `from` of `name` with `to`.
+/// Returns `None` if `name` doesn't start with the `from` segments.
+fn map_name(name: &str, from: &Path, to: &Path) -> Option<String> {
+    let rest = Path::try_from(name).ok()?.strip_prefix(from)?;
+    if rest.as_str().is_empty() {
+        Some(to.to_string())
+    } else if rest.is_relative() {
+        None
+    } else {
+        Some(rest.with_prefix(to).to_string())
+    }
+}
+
+/// Replaces the prefix `from` of the key expression `expr` with `to`.
+/// Returns `None` if `expr` doesn't start with the `from` segments.
+fn map_expr(expr: &str, from: &Path, to: &Path) -> Option<String> {
+    let rest = PathExpr::try_from(expr).ok()?.strip_prefix(from)?;
+    if rest.as_str().is_empty() {
+        Some(to.to_string())
+    } else if rest.is_relative() {
+        None
+    } else {
+        Some(rest.with_prefix(to).to_string())
+    }
+}
+
+/// Returns the predicate of a query forwarded by the bridge with id `tag`,
+/// or `None` if the query was already forwarded by this bridge.
+fn tag_predicate(predicate: &str, tag: &PeerId) -> Option<String> {
+    let mut selector = Selector::try_from(format!("/{}", predicate)).ok()?;
+    let tag = tag.to_string();
+    let bridges = match selector.properties.get(PROP_BRIDGES) {
+        Some(bridges) if bridges.split(',').any(|bridge| bridge == tag) => return None,
+        Some(bridges) => format!("{},{}", bridges, tag),
+        None => tag,
+    };
+    selector
+        .properties
+        .insert(PROP_BRIDGES.to_string(), bridges);
+    Some(format!(
+        "?{}({}){}",
+        selector.filter.unwrap_or_default(),
+        selector.properties,
+        selector
+            .fragment
+            .map(|fragment| format!("[{}]", fragment))
+            .unwrap_or_default()
+    ))
+}
+
+/// Forwards the samples published under `src_prefix` in `src` to `dst` under `dst_prefix`.
+async fn mirror_data(
+    src: Arc<Session>,
+    src_prefix: Path,
+    dst: Arc<Session>,
+    dst_prefix: Path,
+    tag: PeerId,
+    forwarded: Arc<Mutex<Forwarded>>,
+) {
+    let selector = format!("{}/**", src_prefix);
+    let sub_info = SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        period: None,
+    };
+    debug!("Declaring Subscriber on {}", selector);
+    let mut sub = match src
+        .declare_subscriber(&selector.as_str().into(), &sub_info)
+        .await
+    {
+        Ok(sub) => sub,
+        Err(e) => {
+            log::error!("Unable to declare bridge subscriber on {}: {}", selector, e);
+            return;
+        }
+    };
+
+    while let Some(sample) = sub.receiver().next().await {
+        let mut info = sample.data_info.unwrap_or_else(DataInfo::new);
+        match &info.source_id {
+            Some(source_id) if *source_id == tag => {
+                debug!(
+                    "Ignore sample '{}' forwarded by this bridge",
+                    sample.res_name
+                );
+                continue;
+            }
+            // The samples tagged by another source keep their tag: this bridge
+            // remembers them by timestamp to not forward them again
+            Some(source_id) => {
+                if info.timestamp.is_none() {
+                    info.timestamp = src
+                        .hlc()
+                        .or_else(|| dst.hlc())
+                        .map(|hlc| hlc.new_timestamp());
+                }
+                match info.timestamp {
+                    Some(timestamp) => {
+                        if !zlock!(forwarded).insert(timestamp, Instant::now()) {
+                            debug!(
+                                "Ignore sample '{}' already forwarded by this bridge",
+                                sample.res_name
+                            );
+                            continue;
+                        }
+                    }
+                    None => {
+                        warn!(
+                            "Unable to forward sample '{}' tagged by {} without timestamp",
+                            sample.res_name, source_id
+                        );
+                        continue;
+                    }
+                }
+            }
+            None => info.source_id = Some(tag),
+        }
+        let name = match map_name(&sample.res_name, &src_prefix, &dst_prefix) {
+            Some(name) => name,
+            None => continue,
+        };
+        debug!("Forward sample '{}' as '{}'", sample.res_name, name);
+        // The sample is forwarded with the priority and congestion control it was sent with
+        if let Err(e) = dst
+            .write_with_info(
+                &name.as_str().into(),
+                sample.payload,
+                info,
+                sample.channel,
+                sample.congestion_control,
+            )
+            .await
+        {
+            warn!("Unable to forward sample '{}': {}", name, e);
+        }
+    }
+}
+
+/// Answers the queries received on `src_prefix` in `src` with the replies
+/// to the same queries issued in `dst` under `dst_prefix`.
+async fn mirror_queries(
+    src: Arc<Session>,
+    src_prefix: Path,
+    dst: Arc<Session>,
+    dst_prefix: Path,
+    tag: PeerId,
+) {
+    let selector = format!("{}/**", src_prefix);
+    debug!("Declaring Queryable on {}", selector);
+    let mut queryable = match src
+        .declare_queryable(&selector.as_str().into(), STORAGE | EVAL)
+        .await
+    {
+        Ok(queryable) => queryable,
+        Err(e) => {
+            log::error!("Unable to declare bridge queryable on {}: {}", selector, e);
+            return;
+        }
+    };
+
+    while let Some(query) = queryable.receiver().next().await {
+        let dst = dst.clone();
+        let src_prefix = src_prefix.clone();
+        let dst_prefix = dst_prefix.clone();
+        async_std::task::spawn(async move {
+            let predicate = match tag_predicate(&query.predicate, &tag) {
+                Some(predicate) => predicate,
+                None => {
+                    debug!(
+                        "Ignore query '{}{}' forwarded by this bridge",
+                        query.res_name, query.predicate
+                    );
+                    return;
+                }
+            };
+            // If the query can't be mapped (e.g. '/**'), query the whole mapped
+            // prefix and only keep the replies matching the original query.
+            let (expr, filter) = match map_expr(&query.res_name, &src_prefix, &dst_prefix) {
+                Some(expr) => (expr, false),
+                None => (format!("{}/**", dst_prefix), true),
+            };
+            debug!(
+                "Forward query '{}{}' as '{}{}'",
+                query.res_name, query.predicate, expr, predicate
+            );
+            let mut replies = match dst
+                .query(
+                    &expr.as_str().into(),
+                    &predicate,
+                    QueryTarget::default(),
+                    QueryConsolidation::default(),
+                )
+                .await
+            {
+                Ok(replies) => replies,
+                Err(e) => {
+                    warn!("Unable to forward query '{}': {}", expr, e);
+                    return;
+                }
+            };
+            while let Some(reply) = replies.next().await {
+                let name = match map_name(&reply.data.res_name, &dst_prefix, &src_prefix) {
+                    Some(name) => name,
+                    None => continue,
+                };
+                if filter && !resource_name::intersect(&query.res_name, &name) {
+                    continue;
+                }
+                query
+                    .reply_async(Sample {
+                        res_name: name,
+                        payload: reply.data.payload,
+                        data_info: reply.data.data_info,
+                        channel: Channel::default(),
+                        congestion_control: CongestionControl::default(),
+                    })
+                    .await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(s: &str) -> Path {
+        Path::try_from(s).unwrap()
+    }
+
+    #[test]
+    fn bridge_map_name() {
+        let (from, to) = (path("/a/b"), path("/x"));
+        assert_eq!(map_name("/a/b", &from, &to), Some("/x".to_string()));
+        assert_eq!(map_name("/a/b/c/d", &from, &to), Some("/x/c/d".to_string()));
+        assert_eq!(map_name("/a/bc", &from, &to), None);
+        assert_eq!(map_name("/a", &from, &to), None);
+        assert_eq!(map_name("/z/a/b", &from, &to), None);
+    }
+
+    #[test]
+    fn bridge_map_expr() {
+        let (from, to) = (path("/a/b"), path("/x"));
+        assert_eq!(map_expr("/a/b", &from, &to), Some("/x".to_string()));
+        assert_eq!(map_expr("/a/b/**", &from, &to), Some("/x/**".to_string()));
+        assert_eq!(map_expr("/a/b/*/c", &from, &to), Some("/x/*/c".to_string()));
+        assert_eq!(map_expr("/a/bc/**", &from, &to), None);
+        assert_eq!(map_expr("/a/**", &from, &to), None);
+        assert_eq!(map_expr("/**", &from, &to), None);
+    }
+
+    #[test]
+    fn bridge_tag_predicate() {
+        let (tag1, tag2) = (PeerId::new(1, [1; 16]), PeerId::new(1, [2; 16]));
+        let selector = |predicate: &str| Selector::try_from(format!("/{}", predicate)).unwrap();
+
+        let predicate = tag_predicate("", &tag1).unwrap();
+        assert_eq!(
+            selector(&predicate).properties.get(PROP_BRIDGES),
+            Some(&tag1.to_string())
+        );
+        assert_eq!(tag_predicate(&predicate, &tag1), None);
+
+        let predicate = tag_predicate(&predicate, &tag2).unwrap();
+        assert_eq!(
+            selector(&predicate).properties.get(PROP_BRIDGES),
+            Some(&format!("{},{}", tag1, tag2))
+        );
+        assert_eq!(tag_predicate(&predicate, &tag1), None);
+        assert_eq!(tag_predicate(&predicate, &tag2), None);
+
+        // The other parts of the predicate are kept
+        let predicate = tag_predicate("?x>1(starttime=now()-1h)[a]", &tag1).unwrap();
+        let tagged = selector(&predicate);
+        assert_eq!(tagged.filter, Some("x>1".to_string()));
+        assert_eq!(
+            tagged.properties.get("starttime"),
+            Some(&"now()-1h".to_string())
+        );
+        assert_eq!(tagged.fragment, Some("a".to_string()));
+    }
+
+    #[test]
+    fn bridge_forwarded() {
+        let hlc = uhlc::HLC::default();
+        let (ts1, ts2) = (hlc.new_timestamp(), hlc.new_timestamp());
+        let now = Instant::now();
+        let mut forwarded = Forwarded::default();
+        assert!(forwarded.insert(ts1, now));
+        assert!(!forwarded.insert(ts1, now));
+        assert!(forwarded.insert(ts2, now + FORWARDED_EXPIRY / 2));
+
+        // The timestamps are forgotten once expired
+        assert!(forwarded.insert(ts1, now + FORWARDED_EXPIRY));
+        assert!(!forwarded.insert(ts2, now + FORWARDED_EXPIRY));
+        assert_eq!(forwarded.timestamps.len(), 2);
+    }
+}
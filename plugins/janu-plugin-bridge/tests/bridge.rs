@@ -0,0 +1,243 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK janu team, <janu@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::task;
+use clap::App;
+use std::time::Duration;
+use janu::net::queryable::{self, EVAL};
+use janu::net::runtime::Runtime;
+use janu::net::*;
+use janu_plugin_trait::prelude::*;
+use janu_util::properties::config::*;
+use zplugin_bridge::BridgePlugin;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(100);
+
+const LOCATOR_1: &str = "tcp/127.0.0.1:18447";
+const LOCATOR_2: &str = "tcp/127.0.0.1:18448";
+
+fn config(mode: &str, locator: &str) -> ConfigProperties {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, mode.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    if mode == "router" {
+        config.insert(ZN_LISTENER_KEY, locator.to_string());
+        config.insert(ZN_ADD_TIMESTAMP_KEY, ZN_TRUE.to_string());
+    } else {
+        config.insert(ZN_PEER_KEY, locator.to_string());
+    }
+    config
+}
+
+// Starts a bridge from the domain of `runtime` to the domain of the `remote` router
+fn bridge(runtime: &Runtime, remote: &str, mapping: &str) {
+    let args = App::new("bridge")
+        .args(&BridgePlugin::get_requirements())
+        .get_matches_from(vec![
+            "bridge",
+            "--bridge-remote",
+            remote,
+            "--bridge-map",
+            mapping,
+        ]);
+    BridgePlugin::start(&(runtime.clone(), args)).unwrap();
+}
+
+#[test]
+fn bridge_loops() {
+    task::block_on(async {
+        // Two bridges between the same prefixes of two domains
+        let router1 = Runtime::new(0, config("router", LOCATOR_1), None)
+            .await
+            .unwrap();
+        let router2 = Runtime::new(0, config("router", LOCATOR_2), None)
+            .await
+            .unwrap();
+        bridge(&router1, LOCATOR_2, "/d1=/d2");
+        bridge(&router2, LOCATOR_1, "/d2=/d1");
+        let client1 = open(config("client", LOCATOR_1)).await.unwrap();
+        let client2 = open(config("client", LOCATOR_2)).await.unwrap();
+
+        let sub_info = SubInfo {
+            reliability: Reliability::Reliable,
+            mode: SubMode::Push,
+            period: None,
+        };
+        let mut sub1 = client1
+            .declare_subscriber(&"/d1/**".into(), &sub_info)
+            .await
+            .unwrap();
+        let mut sub = client2
+            .declare_subscriber(&"/d2/**".into(), &sub_info)
+            .await
+            .unwrap();
+        let mut qabl = client2
+            .declare_queryable(&"/d2/q/**".into(), EVAL)
+            .await
+            .unwrap();
+
+        // Waits for both bridges to forward the samples published by `client`
+        async fn ready(client: &Session, sub: &mut Subscriber<'_>, from: &str, to: &str) {
+            let check = async {
+                for i in 0.. {
+                    let name = format!("{}/ready/{}", from, i);
+                    client
+                        .write(&name.as_str().into(), vec![0u8].into())
+                        .await
+                        .unwrap();
+                    let mut count = 0;
+                    while let Ok(Some(sample)) = sub.receiver().next().timeout(SLEEP).await {
+                        if sample.res_name == name.replace(from, to) {
+                            count += 1;
+                        }
+                    }
+                    if count == 2 {
+                        break;
+                    }
+                }
+            };
+            check.timeout(TIMEOUT).await.unwrap();
+        }
+        ready(&client1, &mut sub, "/d1", "/d2").await;
+        ready(&client2, &mut sub1, "/d2", "/d1").await;
+
+        // Counts the samples received with `name` until the sample published
+        // with `marker` in the first domain is forwarded to the second domain
+        async fn count(
+            client: &Session,
+            sub: &mut Subscriber<'_>,
+            name: &str,
+            marker: &str,
+        ) -> usize {
+            let mut count = 0;
+            let check = async {
+                loop {
+                    client
+                        .write(&marker.into(), vec![0u8].into())
+                        .await
+                        .unwrap();
+                    while let Ok(Some(sample)) = sub.receiver().next().timeout(SLEEP).await {
+                        if sample.res_name == marker.replace("/d1", "/d2") {
+                            return;
+                        } else if sample.res_name == name {
+                            count += 1;
+                        }
+                    }
+                }
+            };
+            check.timeout(TIMEOUT).await.unwrap();
+            count
+        }
+
+        // A sample tagged by another source is forwarded at most once by each bridge,
+        // with the priority and congestion control it was sent with
+        let mut info = DataInfo::new();
+        info.source_id = Some(PeerId::new(1, [7; 16]));
+        let channel = Channel {
+            priority: Priority::InteractiveHigh,
+            reliability: Reliability::Reliable,
+        };
+        client1
+            .write_with_info(
+                &"/d1/tagged".into(),
+                vec![0u8].into(),
+                info,
+                channel,
+                CongestionControl::Drop,
+            )
+            .await
+            .unwrap();
+        let check = async {
+            loop {
+                let sample = sub.receiver().next().await.unwrap();
+                if sample.res_name == "/d2/tagged" {
+                    break sample;
+                }
+            }
+        };
+        let sample = check.timeout(TIMEOUT).await.unwrap();
+        assert_eq!(sample.channel, channel);
+        assert_eq!(sample.congestion_control, CongestionControl::Drop);
+        assert!(count(&client1, &mut sub, "/d2/tagged", "/d1/marker1").await <= 1);
+        assert_eq!(
+            count(&client1, &mut sub, "/d2/tagged", "/d1/marker2").await,
+            0
+        );
+
+        // Queries only routed to the bridges complete
+        let check = async {
+            let mut replies = client1
+                .query(
+                    &"/d1/none/**".into(),
+                    "",
+                    QueryTarget {
+                        kind: queryable::ALL_KINDS,
+                        target: Target::All,
+                    },
+                    QueryConsolidation::none(),
+                )
+                .await
+                .unwrap();
+            replies.next().await
+        };
+        assert!(check.timeout(TIMEOUT).await.unwrap().is_none());
+
+        // Queries are forwarded with the tags of the bridges and answered
+        let answer = async {
+            while let Some(query) = qabl.receiver().next().await {
+                assert!(query.predicate.contains("bridges="));
+                query
+                    .reply_async(Sample {
+                        res_name: "/d2/q/x".to_string(),
+                        payload: vec![0u8].into(),
+                        data_info: None,
+                        channel: Channel::default(),
+                        congestion_control: CongestionControl::default(),
+                    })
+                    .await;
+            }
+            vec![]
+        };
+        let check = async {
+            let mut replies = client1
+                .query(
+                    &"/d1/q/**".into(),
+                    "",
+                    QueryTarget::default(),
+                    QueryConsolidation::none(),
+                )
+                .await
+                .unwrap();
+            let mut names = vec![];
+            while let Some(reply) = replies.next().await {
+                names.push(reply.data.res_name);
+            }
+            names
+        };
+        let names = check.race(answer).timeout(TIMEOUT).await.unwrap();
+        assert!(!names.is_empty());
+        assert!(names.iter().all(|name| name == "/d1/q/x"));
+
+        sub.undeclare().await.unwrap();
+        sub1.undeclare().await.unwrap();
+        qabl.undeclare().await.unwrap();
+        for session in [client1, client2] {
+            session.close().await.unwrap();
+        }
+        for runtime in [router1, router2] {
+            runtime.close().await.unwrap();
+        }
+    });
+}
@@ -56,6 +56,8 @@ async fn main() {
                         res_name: path.to_string(),
                         payload: HTML.as_bytes().into(),
                         data_info: None,
+                        channel: Channel::default(),
+                        congestion_control: CongestionControl::default(),
                     })
                     .await;
             }),
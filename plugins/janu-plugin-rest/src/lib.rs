@@ -79,6 +79,16 @@ fn value_to_json(value: Value) -> String {
         Json(s) => s,
         Integer(i) => format!(r#"{}"#, i),
         Float(f) => format!(r#"{}"#, f),
+        Cbor(ref buf) | MsgPack(ref buf) => match value.deserialize::<serde_json::Value>() {
+            Ok(js) => js.to_string(),
+            Err(_) => format!(r#""{}""#, base64::encode(buf.to_vec())),
+        },
+        Int64(i) => format!(r#"{}"#, i),
+        // Non-finite floats are rendered as JSON strings
+        Float64(f) if !f.is_finite() => format!(r#""{}""#, f),
+        Float64(f) => serde_json::json!(f).to_string(),
+        Int64Array(v) => serde_json::json!(v).to_string(),
+        Float64Array(_) => value.encode_to_string().2,
     }
 }
 